@@ -0,0 +1,405 @@
+"""Generate pyzalea.pyi from the Rust sources.
+
+Reads the #[pyclass], #[pymethods], #[pyfunction] and create_exception!
+items in src/ and writes a stub with their signatures, types and doc
+comments. maturin ships pyzalea.pyi (and a py.typed marker) in the wheel.
+
+    python scripts/stubgen.py          # rewrite pyzalea.pyi
+    python scripts/stubgen.py --check  # fail if it's out of date (for CI)
+"""
+
+import argparse
+import difflib
+import re
+import sys
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+SRC = ROOT / "src"
+STUB = ROOT / "pyzalea.pyi"
+
+SIMPLE = {
+    "bool": "bool",
+    "str": "str",
+    "String": "str",
+    "PathBuf": "str | os.PathLike[str]",
+    "Path": "str | os.PathLike[str]",
+    "f32": "float",
+    "f64": "float",
+    "PyAny": "Any",
+    "PyObject": "Any",
+    "PyTuple": "tuple[Any, ...]",
+    "PyDict": "dict[str, Any]",
+    "PyList": "list[Any]",
+    "PyModule": "Any",
+    "PyArray1": "numpy.ndarray",
+    "PyArray2": "numpy.ndarray",
+    "PyArray3": "numpy.ndarray",
+    # FromPyObject types of our own
+    "ActionInput": "FighterAction | numpy.ndarray | list[float]",
+}
+INTS = {"i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize"}
+# wrappers that are the type they wrap, as far as Python is concerned
+TRANSPARENT = {"PyResult", "Py", "PyRef", "PyRefMut", "Bound", "Borrowed", "Box", "Arc", "Mutex"}
+LIST = {"Vec", "VecDeque", "HashSet"}
+
+
+def split_top(text, sep=","):
+    """split on `sep` outside of <>, (), [] and string literals"""
+    parts, depth, current, quoted = [], 0, "", False
+    for ch in text:
+        if ch == '"':
+            quoted = not quoted
+        elif not quoted and ch in "<([":
+            depth += 1
+        elif not quoted and ch in ">)]":
+            depth -= 1
+        if ch == sep and depth == 0 and not quoted:
+            parts.append(current.strip())
+            current = ""
+        else:
+            current += ch
+    if current.strip():
+        parts.append(current.strip())
+    return parts
+
+
+class Types:
+    def __init__(self, classes):
+        # rust struct name -> python class name
+        self.classes = classes
+
+    def py(self, rust, owner=None):
+        t = " ".join(rust.split())
+        t = re.sub(r"^&\s*('\w+\s+)?(mut\s+)?", "", t)
+        t = re.sub(r"'\w+\s*,?\s*", "", t).strip()
+        t = t.replace("dyn ", "")
+        if not t:
+            return "Any"
+        if t.startswith("(") and t.endswith(")"):
+            items = split_top(t[1:-1])
+            if not items:
+                return "None"
+            return "tuple[" + ", ".join(self.py(i, owner) for i in items) + "]"
+        if t == "[u8]":
+            return "bytes"
+        if t.startswith("[") and t.endswith("]"):
+            inner = t[1:-1].split(";")[0]
+            return f"list[{self.py(inner, owner)}]"
+        match = re.match(r"^([\w:]+)\s*(?:<(.*)>)?$", t)
+        if not match:
+            return "Any"
+        name = match.group(1).split("::")[-1]
+        args = [a for a in split_top(match.group(2) or "") if a]
+        if name == "Self" and owner:
+            return owner
+        if name in TRANSPARENT:
+            return self.py(args[-1], owner) if args else "Any"
+        if name == "Option":
+            inner = self.py(args[0], owner)
+            return inner if inner.endswith("| None") or inner == "Any" else f"{inner} | None"
+        if name in LIST:
+            return f"list[{self.py(args[0], owner)}]"
+        if name in ("HashMap", "BTreeMap"):
+            return f"dict[{self.py(args[0], owner)}, {self.py(args[1], owner)}]"
+        if name in INTS:
+            return "int"
+        if name in SIMPLE:
+            return SIMPLE[name]
+        if name in self.classes:
+            return self.classes[name]
+        return "Any"
+
+
+def docs(lines):
+    text = "\n".join(l.strip()[3:].strip() for l in lines)
+    return text.strip()
+
+
+def docstring(text, indent):
+    text = text.replace("\\", "\\\\").replace('"""', '\\"\\"\\"')
+    if "\n" not in text:
+        # a quote right next to the closing """ would end the string early
+        return f'{indent}"""{text}"""' if not text.endswith('"') else f'{indent}"""{text} """'
+    lines = "\n".join(f"{indent}{l}" if l else "" for l in text.split("\n"))
+    return f'{indent}"""\n{lines}\n{indent}"""'
+
+
+def default(value):
+    value = value.strip()
+    if value in ("None", "true", "false"):
+        return {"true": "True", "false": "False"}.get(value, value)
+    value = re.sub(r'^("[^"]*")\.to_string\(\)$', r"\1", value)
+    if re.fullmatch(r"-?\d+(\.\d+)?", value) or re.fullmatch(r'"[^"]*"', value):
+        return value
+    return "..."
+
+
+def signature_params(fn):
+    """(name, type, default) for the python-visible params of a parsed fn"""
+    params = {}
+    order = []
+    for raw in split_top(fn["params"]):
+        raw = raw.strip()
+        if ":" not in raw:
+            continue  # self, &self, &mut self
+        name, rust = raw.split(":", 1)
+        name = name.replace("mut ", "").strip()
+        rust = rust.strip()
+        if name in ("slf", "py") or rust.startswith("Python"):
+            continue
+        params[name] = rust
+        order.append(name)
+    out = []
+    if fn["signature"] is None:
+        return [(n, params[n], None) for n in order]
+    for item in split_top(fn["signature"]):
+        if item in ("*", "/"):
+            out.append((item, None, None))
+            continue
+        if "=" in item:
+            name, value = item.split("=", 1)
+            out.append((name.strip(), params.get(name.strip()), value))
+        else:
+            bare = item.lstrip("*")
+            out.append((item, params.get(bare), None))
+    return out
+
+
+def parse_fn(text, start):
+    """the fn starting at `start` (at "fn "), up to its body"""
+    match = re.compile(r"fn\s+(\w+)\s*(<[^(]*>)?\s*\(").match(text, start)
+    if not match:
+        return None, start
+    i, depth = match.end(), 1
+    while depth:
+        depth += {"(": 1, ")": -1}.get(text[i], 0)
+        i += 1
+    params = text[match.end() : i - 1]
+    rest = re.compile(r"\s*(->\s*([^{;]*?))?\s*(where[^{]*)?[{;]").match(text, i)
+    ret = rest.group(2).strip() if rest and rest.group(2) else None
+    return {"name": match.group(1), "params": params, "ret": ret}, (rest.end() if rest else i)
+
+
+def items(text):
+    """(attributes, doc lines, fn) for every fn in a block, in order"""
+    attrs, doc = [], []
+    lines = text.split("\n")
+    offsets, pos = [], 0
+    for line in lines:
+        offsets.append(pos)
+        pos += len(line) + 1
+    n = 0
+    while n < len(lines):
+        line = lines[n].strip()
+        if line.startswith("///"):
+            doc.append(line)
+        elif line.startswith("#["):
+            attr = line
+            while attr.count("[") > attr.count("]") and n + 1 < len(lines):
+                n += 1
+                attr += " " + lines[n].strip()
+            attrs.append(attr)
+        elif re.match(r"(pub(\([\w:]+\))?\s+)?(async\s+)?fn\s", line):
+            start = offsets[n] + lines[n].index("fn ")
+            fn, end = parse_fn(text, start)
+            if fn:
+                fn["async"] = " async " in " " + line
+                yield attrs, doc, fn
+                while n + 1 < len(lines) and offsets[n + 1] < end:
+                    n += 1
+            attrs, doc = [], []
+            # skip the body, which may start and end on this line
+            head = text[start : offsets[n] + len(lines[n])]
+            depth = head.count("{") - head.count("}")
+            while depth > 0 and n + 1 < len(lines):
+                n += 1
+                depth += lines[n].count("{") - lines[n].count("}")
+        elif line and not line.startswith("//"):
+            attrs, doc = [], []
+        n += 1
+
+
+def attr_signature(attrs):
+    for attr in attrs:
+        match = re.search(r"signature\s*=\s*\((.*)\)\s*\)\s*\]", attr)
+        if match:
+            return match.group(1)
+    return None
+
+
+def collect():
+    sources = {p: p.read_text() for p in sorted(SRC.rglob("*.rs"))}
+    structs = {}  # rust name -> (python name, doc, fields, order)
+    for path, text in sources.items():
+        for match in re.finditer(r"((?:[ \t]*///.*\n)*)[ \t]*#\[pyclass([^\]]*)\]\s*(?:#\[[^\]]*\]\s*)*pub\s+(struct|enum)\s+(\w+)", text):
+            doc, args, kind, rust = match.groups()
+            name = re.search(r'name\s*=\s*"(\w+)"', args)
+            fields = []
+            if kind == "struct":
+                body = text[match.end() : text.index("\n}", match.end())]
+                field_doc, field_attrs = [], []
+                for line in body.split("\n"):
+                    line = line.strip()
+                    if line.startswith("///"):
+                        field_doc.append(line)
+                    elif line.startswith("#["):
+                        field_attrs.append(line)
+                    elif ":" in line and not line.startswith("//"):
+                        fname, ftype = line.split(":", 1)
+                        fname = fname.replace("pub(crate)", "").replace("pub", "").strip()
+                        ftype = ftype.split("//")[0].strip().rstrip(",")
+                        get = any("pyo3(get" in a for a in field_attrs)
+                        settable = any(re.search(r"pyo3\(.*\bset\b", a) for a in field_attrs)
+                        if get:
+                            fields.append((fname, ftype, settable, docs(field_doc)))
+                        field_doc, field_attrs = [], []
+            structs[rust] = {
+                "name": name.group(1) if name else rust,
+                "doc": docs(doc.strip().split("\n")) if doc.strip() else "",
+                "fields": fields,
+                "methods": [],
+                "kind": kind,
+            }
+    functions, exceptions = [], []
+    for path, text in sources.items():
+        for match in re.finditer(r"#\[pymethods\]\s*impl\s+(\w+)\s*\{", text):
+            depth, i = 1, match.end()
+            while depth:
+                depth += {"{": 1, "}": -1}.get(text[i], 0)
+                i += 1
+            if match.group(1) in structs:
+                structs[match.group(1)]["methods"].extend(items(text[match.end() : i - 1]))
+        for attrs, doc, fn in items(text):
+            if any(a.startswith("#[pyfunction") for a in attrs):
+                functions.append((attrs, doc, fn))
+        for match in re.finditer(r"create_exception!\(\s*\w+\s*,\s*(\w+)\s*,\s*([\w:]+)\s*\)", text):
+            base = match.group(2).split("::")[-1]
+            exceptions.append((match.group(1), {"PyException": "Exception", "PyRuntimeError": "RuntimeError"}.get(base, base)))
+    return structs, functions, exceptions
+
+
+def render_fn(types, attrs, doc, fn, owner=None, indent=""):
+    sig = signature_params(dict(fn, signature=attr_signature(attrs)))
+    name = fn["name"]
+    is_new = any(a.startswith("#[new") for a in attrs)
+    is_static = any(a.startswith("#[staticmethod") for a in attrs)
+    is_class = any(a.startswith("#[classmethod") for a in attrs)
+    params = []
+    if owner and not is_static:
+        params.append("cls" if is_class else "self")
+    for pname, rust, value in sig:
+        if pname in ("*", "/"):
+            params.append(pname)
+            continue
+        if pname.startswith("**"):
+            params.append(f"{pname}: Any")
+            continue
+        if pname.startswith("*"):
+            params.append(f"{pname}: Any")
+            continue
+        ptype = types.py(rust, owner) if rust else "Any"
+        params.append(f"{pname}: {ptype}" + (f" = {default(value)}" if value is not None else ""))
+    if is_new:
+        name, ret = "__init__", "None"
+    else:
+        ret = types.py(fn["ret"], owner) if fn["ret"] else "None"
+        if fn["async"] or name.endswith("_async"):
+            ret = f"Awaitable[{ret if fn['async'] else 'Any'}]"
+    out = []
+    if is_static:
+        out.append(f"{indent}@staticmethod")
+    if is_class:
+        out.append(f"{indent}@classmethod")
+    text = docs(doc)
+    head = f"{indent}def {name}({', '.join(params)}) -> {ret}:"
+    if text:
+        out.append(head)
+        out.append(docstring(text, indent + "    "))
+    else:
+        out.append(head + " ...")
+    return "\n".join(out)
+
+
+def render_property(name, ptype, settable, doc, indent="    "):
+    out = [f"{indent}@property"]
+    if doc:
+        out.append(f"{indent}def {name}(self) -> {ptype}:")
+        out.append(docstring(doc, indent + "    "))
+    else:
+        out.append(f"{indent}def {name}(self) -> {ptype}: ...")
+    if settable:
+        out.append(f"{indent}@{name}.setter")
+        out.append(f"{indent}def {name}(self, value: {ptype}) -> None: ...")
+    return "\n".join(out)
+
+
+def render(structs, functions, exceptions):
+    types = Types({rust: s["name"] for rust, s in structs.items()})
+    out = [
+        "# generated by scripts/stubgen.py from src/, don't edit by hand",
+        "",
+        "import os",
+        "from typing import Any, Awaitable",
+        "",
+        "import numpy",
+        "",
+    ]
+    for name, base in exceptions:
+        out.append(f"class {name}({base}): ...")
+    out.append("")
+    for attrs, doc, fn in functions:
+        out.append(render_fn(types, attrs, doc, fn))
+    for rust, s in sorted(structs.items(), key=lambda kv: kv[1]["name"]):
+        owner = s["name"]
+        out.append("")
+        out.append(f"class {owner}:")
+        if s["doc"]:
+            out.append(docstring(s["doc"], "    "))
+        body = []
+        seen = set()
+        getters, setters = {}, set()
+        for attrs, doc, fn in s["methods"]:
+            getter = next((a for a in attrs if a.startswith("#[getter")), None)
+            setter = next((a for a in attrs if a.startswith("#[setter")), None)
+            direct = lambda a: re.search(r"\((\w+)\)", a)
+            if getter:
+                pname = direct(getter).group(1) if direct(getter) else re.sub(r"^get_", "", fn["name"])
+                getters[pname] = (types.py(fn["ret"], owner) if fn["ret"] else "None", docs(doc))
+            elif setter:
+                pname = direct(setter).group(1) if direct(setter) else re.sub(r"^set_", "", fn["name"])
+                setters.add(pname)
+        for fname, ftype, settable, doc in s["fields"]:
+            if fname not in getters:
+                getters[fname] = (types.py(ftype, owner), doc)
+                if settable:
+                    setters.add(fname)
+        for pname, (ptype, doc) in getters.items():
+            body.append(render_property(pname, ptype, pname in setters, doc))
+            seen.add(pname)
+        for attrs, doc, fn in s["methods"]:
+            if any(a.startswith(("#[getter", "#[setter")) for a in attrs):
+                continue
+            body.append(render_fn(types, attrs, doc, fn, owner=owner, indent="    "))
+        out.extend(body if body else ["    ..."])
+    return "\n".join(out) + "\n"
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--check", action="store_true", help="exit 1 if pyzalea.pyi is out of date")
+    args = parser.parse_args()
+    stub = render(*collect())
+    if args.check:
+        current = STUB.read_text() if STUB.exists() else ""
+        if current != stub:
+            sys.stdout.writelines(difflib.unified_diff(current.splitlines(True), stub.splitlines(True), "pyzalea.pyi", "generated"))
+            return 1
+        return 0
+    STUB.write_text(stub)
+    print(f"wrote {STUB.relative_to(ROOT)}")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -2,9 +2,27 @@
 //!
 //! No network/server for speed
 
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PyTuple};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::config;
+use crate::dlpack::ObservationTensor;
+use crate::effects::Effect;
+use crate::loadout::{Loadout, FIST};
+use crate::metrics;
+use crate::opponents::{Opponent, OpponentKind};
+use crate::recorder::ACTION_NAMES;
+use crate::rewards::{RewardBreakdown, RewardConfig, RewardFn, StepEvents};
+use crate::terrain::{Block, Terrain, TERRAIN_HEIGHT};
 
-/// combat constants (1.21)
+/// combat constants (1.21), the defaults of ArenaConstants and Kit
 const ATTACK_RANGE: f64 = 3.0;
 const ATTACK_COOLDOWN_TICKS: u32 = 10; // 0.5 seconds @ 20 TPS
 const SPRINT_CRIT_MULTIPLIER: f64 = 1.5;
@@ -23,8 +41,1295 @@ const SPRINT_SPEED: f64 = 0.13;
 const JUMP_VELOCITY: f64 = 0.42;
 const GRAVITY: f64 = 0.08;
 const DRAG: f64 = 0.98;
-const KNOCKBACK_HORIZONTAL: f64 = 0.4;
-const KNOCKBACK_VERTICAL: f64 = 0.36;
+const KNOCKBACK_HORIZONTAL: f64 = 0.4; // a hit's strength, also added to vy
+const KNOCKBACK_VERTICAL: f64 = 0.4; // the most vy a hit leaves
+const SPRINT_KNOCKBACK: f64 = 0.5; // per level, sprinting is one more
+const SPRINT_HIT_SLOWDOWN: f64 = 0.6; // of the attacker's velocity after a knockback level hit
+
+// bow constants (1.21)
+const ARROW_SPEED: f64 = 3.0; // blocks per tick at full draw
+const ARROW_GRAVITY: f64 = 0.05;
+const ARROW_DRAG: f64 = 0.99;
+const ARROW_DAMAGE: f64 = 2.0; // per block per tick of speed, rounded up
+const BOW_FULL_DRAW_TICKS: u32 = 20;
+const BOW_MIN_POWER: f64 = 0.1; // released earlier than this, nothing's shot
+const USE_SPEED_MULTIPLIER: f64 = 0.2; // walking while drawing or blocking
+const ARROW_LIFETIME: u32 = 200;
+const EYE_HEIGHT: f64 = 1.62;
+// fighter hitbox
+const HALF_WIDTH: f64 = 0.3;
+const HEIGHT: f64 = 1.8;
+const SAFE_FALL_DISTANCE: f64 = 3.0; // blocks, each one past it is a point of damage
+const VOID_DEPTH: f64 = 4.0; // a fighter this far below a platform mode floor has lost
+
+// water and lava (LivingEntity.travel's fluid branch, 1.21)
+const FLUID_SPEED_MULTIPLIER: f64 = 0.2;
+const WATER_DRAG: f64 = 0.8;
+const LAVA_DRAG: f64 = 0.5;
+const FLUID_GRAVITY_MULTIPLIER: f64 = 0.25;
+const SWIM_UP: f64 = 0.04; // a tick while jump is held
+const LAVA_DAMAGE: f64 = 4.0;
+const LAVA_INTERVAL: u32 = 10; // ticks between lava's hurts, the invulnerability
+const LAVA_FIRE_TICKS: u32 = 300; // on fire for 15 seconds after leaving it
+const FIRE_DAMAGE: f64 = 1.0;
+const FIRE_INTERVAL: u32 = 20;
+const HAZARD_RADIUS: f64 = 4.0; // how far the observation looks for water and lava
+
+// ender pearl constants (1.21)
+const PEARL_SPEED: f64 = 1.5;
+const PEARL_GRAVITY: f64 = 0.03;
+const PEARL_DRAG: f64 = 0.99;
+const PEARL_DAMAGE: f64 = 5.0; // to the thrower on landing, armor doesn't help
+const PEARL_COOLDOWN_TICKS: u32 = 20;
+
+// the potion effects the arena knows, Fighter.effects is in this order
+const EFFECTS: [&str; 6] = ["speed", "slowness", "strength", "weakness", "regeneration", "poison"];
+const SPEED_PER_LEVEL: f64 = 0.2;
+const SLOWNESS_PER_LEVEL: f64 = 0.15;
+const STRENGTH_PER_LEVEL: f64 = 3.0;
+const WEAKNESS_PER_LEVEL: f64 = 4.0;
+const REGENERATION_INTERVAL: u32 = 50; // ticks per heart point at level I, halved each level
+const POISON_INTERVAL: u32 = 25; // same, for damage that stops at 1 health
+
+// splash potion constants (1.21)
+const POTION_SPEED: f64 = 0.5;
+const POTION_PITCH_OFFSET: f64 = -20.0; // thrown a bit upwards
+const POTION_GRAVITY: f64 = 0.05;
+const POTION_DRAG: f64 = 0.99;
+const POTION_COOLDOWN_TICKS: u32 = 4; // vanilla's right-click delay
+const SPLASH_RADIUS: f64 = 4.0;
+const SPLASH_HEIGHT: f64 = 2.0;
+const SPLASH_MIN_TICKS: u32 = 20; // shorter than this after the falloff, it isn't applied
+const INSTANT_AMOUNT: f64 = 6.0; // harming damage at level I, doubled each level. healing is 4
+
+// fishing rod constants (1.21)
+const HOOK_BASE_SPEED: f64 = 0.6; // plus half the aim's length, ~1.1 straight ahead
+const HOOK_GRAVITY: f64 = 0.03;
+const HOOK_DRAG: f64 = 0.92;
+const HOOK_MAX_DISTANCE: f64 = 32.0; // the line snaps further out
+const HOOK_PULL: f64 = 0.1; // of the distance to the caster, added to the velocity on a reel
+const ROD_COOLDOWN_TICKS: u32 = 4; // vanilla's right-click delay
+
+// shield constants (1.21)
+pub const SHIELD_DELAY_TICKS: u32 = 5; // raised this long before it blocks
+const SHIELD_DISABLE_TICKS: u32 = 100; // after an axe hit
+const SHIELD_KNOCKBACK_MULTIPLIER: f64 = 0.5; // of a frontal hit, the damage is all blocked
+
+const SPAWN_DISTANCE: f64 = 6.0;
+const MAX_ARENA_SIZE: f64 = 1024.0; // blocks a side, bounds the terrain grid
+
+// 1.8 combat constants, for CombatVersion::Legacy
+const LEGACY_KNOCKBACK_HORIZONTAL: f64 = 0.4;
+const LEGACY_KNOCKBACK_VERTICAL: f64 = 0.4; // also the most it can leave vy at
+const LEGACY_SPRINT_KNOCKBACK: (f64, f64) = (0.5, 0.1); // extra per level, horizontal and vertical
+const LEGACY_INVULNERABLE_TICKS: u32 = 10; // after a hit, later ones don't land
+const LEGACY_ARMOR_PER_POINT: f64 = 0.04;
+const LEGACY_SHARPNESS_PER_LEVEL: f64 = 1.25;
+
+/// which game's melee rules a FastArena follows, `combat_version` in
+/// `[arena.constants]` and ArenaConfig
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum CombatVersion {
+    /// the attack cooldown, shields and axes
+    #[default]
+    #[serde(rename = "1.21")]
+    Modern,
+    /// no cooldown but 10 ticks of invulnerability after a hit, sprint
+    /// knockback, and 1.8's weapons, armor and sharpness
+    #[serde(rename = "1.8")]
+    Legacy,
+}
+
+impl CombatVersion {
+    pub fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "1.21" => Ok(Self::Modern),
+            "1.8" => Ok(Self::Legacy),
+            _ => Err(PyValueError::new_err(format!("unknown combat_version {:?}, expected \"1.21\" or \"1.8\"", name))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Modern => "1.21",
+            Self::Legacy => "1.8",
+        }
+    }
+}
+
+/// sumo's platform when ArenaConfig.platform_size isn't set
+const SUMO_PLATFORM_SIZE: f64 = 10.0;
+
+/// what a FastArena episode is about, `mode` in `[arena]` and ArenaConfig
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArenaMode {
+    /// the regular fight to the death
+    #[default]
+    Fight,
+    /// empty hands and no damage on a platform, knocking the other off wins
+    Sumo,
+}
+
+impl ArenaMode {
+    pub fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "fight" => Ok(Self::Fight),
+            "sumo" => Ok(Self::Sumo),
+            _ => Err(PyValueError::new_err(format!("unknown mode {:?}, expected \"fight\" or \"sumo\"", name))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fight => "fight",
+            Self::Sumo => "sumo",
+        }
+    }
+
+    /// the platform a FastArena in this mode gets, sumo needs one
+    fn platform(self, platform_size: Option<f64>) -> Option<f64> {
+        match self {
+            Self::Fight => platform_size,
+            Self::Sumo => platform_size.or(Some(SUMO_PLATFORM_SIZE)),
+        }
+    }
+}
+
+/// simulation constants, `[arena.constants]` in a config file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArenaConstants {
+    pub attack_range: f64,
+    pub attack_cooldown_ticks: u32,
+    pub sprint_crit_multiplier: f64,
+    pub max_health: f64,
+    pub max_food: f64,
+    pub food_heal_threshold: f64,
+    pub food_heal_amount: f64,
+    pub food_per_steak: f64,
+    pub eat_ticks: u32,
+    pub walk_speed: f64,
+    pub sprint_speed: f64,
+    pub jump_velocity: f64,
+    pub gravity: f64,
+    pub drag: f64,
+    pub knockback_horizontal: f64,
+    pub knockback_vertical: f64,
+    pub sprint_knockback: f64,
+    pub combat_version: CombatVersion,
+}
+
+impl Default for ArenaConstants {
+    fn default() -> Self {
+        Self {
+            attack_range: ATTACK_RANGE,
+            attack_cooldown_ticks: ATTACK_COOLDOWN_TICKS,
+            sprint_crit_multiplier: SPRINT_CRIT_MULTIPLIER,
+            max_health: MAX_HEALTH,
+            max_food: MAX_FOOD,
+            food_heal_threshold: FOOD_HEAL_THRESHOLD,
+            food_heal_amount: FOOD_HEAL_AMOUNT,
+            food_per_steak: FOOD_PER_STEAK,
+            eat_ticks: EAT_TICKS,
+            walk_speed: WALK_SPEED,
+            sprint_speed: SPRINT_SPEED,
+            jump_velocity: JUMP_VELOCITY,
+            gravity: GRAVITY,
+            drag: DRAG,
+            knockback_horizontal: KNOCKBACK_HORIZONTAL,
+            knockback_vertical: KNOCKBACK_VERTICAL,
+            sprint_knockback: SPRINT_KNOCKBACK,
+            combat_version: CombatVersion::Modern,
+        }
+    }
+}
+
+impl ArenaConstants {
+    /// ArenaConfig and config files both go through this, every constant
+    /// divides or integrates something in the sim or get_obs()
+    pub fn validate(&self) -> PyResult<()> {
+        for (name, value) in [
+            ("attack_range", self.attack_range),
+            ("max_health", self.max_health),
+            ("max_food", self.max_food),
+        ] {
+            check_positive(name, value)?;
+        }
+        if self.eat_ticks == 0 {
+            return Err(PyValueError::new_err("eat_ticks must be positive"));
+        }
+        for (name, value) in [
+            ("sprint_crit_multiplier", self.sprint_crit_multiplier),
+            ("food_heal_threshold", self.food_heal_threshold),
+            ("food_heal_amount", self.food_heal_amount),
+            ("food_per_steak", self.food_per_steak),
+            ("walk_speed", self.walk_speed),
+            ("sprint_speed", self.sprint_speed),
+            ("jump_velocity", self.jump_velocity),
+            ("gravity", self.gravity),
+            ("knockback_horizontal", self.knockback_horizontal),
+            ("knockback_vertical", self.knockback_vertical),
+            ("sprint_knockback", self.sprint_knockback),
+        ] {
+            check_not_negative(name, value)?;
+        }
+        check_fraction("drag", self.drag)
+    }
+}
+
+/// what a fighter spawns with, `[kits.<name>]` in a config file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Kit {
+    /// damage of a hit before armor
+    pub weapon_damage: f64,
+    /// fraction of incoming damage the armor takes away
+    pub armor_reduction: f64,
+    /// vanilla's armor points, reducing each hit by its damage instead of by
+    /// armor_reduction. a Loadout sets these
+    pub armor_points: Option<f64>,
+    pub armor_toughness: f64,
+    /// ArenaConstants.attack_cooldown_ticks if None
+    pub attack_cooldown_ticks: Option<u32>,
+    pub steaks: u32,
+    /// for the bow, none by default
+    pub arrows: u32,
+    pub shield: bool,
+    /// the weapon is an axe, hits on a raised shield disable it
+    pub disables_shields: bool,
+    /// ender pearls, none by default
+    pub pearls: u32,
+    /// potion effects a fighter spawns with
+    pub effects: Vec<Effect>,
+    /// splash potions to throw, all of them `potion`
+    pub potions: u32,
+    pub potion: Option<Effect>,
+    pub rod: bool,
+    /// the weapon's sharpness level
+    pub sharpness: u32,
+    /// the weapon is a sword, which sweeps. the default iron sword does
+    pub sword: bool,
+    /// the weapon's knockback level
+    pub knockback: u32,
+}
+
+impl Default for Kit {
+    fn default() -> Self {
+        Self {
+            weapon_damage: BASE_DAMAGE_IRON_SWORD,
+            armor_reduction: DIAMOND_ARMOR_REDUCTION,
+            armor_points: None,
+            armor_toughness: 0.0,
+            attack_cooldown_ticks: None,
+            steaks: 64,
+            arrows: 0,
+            shield: false,
+            disables_shields: false,
+            pearls: 0,
+            effects: vec![],
+            potions: 0,
+            potion: None,
+            rod: false,
+            sharpness: 0,
+            sword: true,
+            knockback: 0,
+        }
+    }
+}
+
+impl Kit {
+    /// see ArenaConstants::validate()
+    pub fn validate(&self) -> PyResult<()> {
+        check_not_negative("weapon_damage", self.weapon_damage)?;
+        check_fraction("armor_reduction", self.armor_reduction)?;
+        if let Some(points) = self.armor_points {
+            check_not_negative("armor_points", points)?;
+        }
+        check_not_negative("armor_toughness", self.armor_toughness)?;
+        for effect in &self.effects {
+            effect_index(&effect.name)?;
+        }
+        self.potion.as_ref().map_or(Ok(()), check_potion)
+    }
+
+    fn cooldown_ticks(&self, constants: &ArenaConstants) -> u32 {
+        match constants.combat_version {
+            CombatVersion::Modern => self.attack_cooldown_ticks.unwrap_or(constants.attack_cooldown_ticks),
+            CombatVersion::Legacy => 0,
+        }
+    }
+
+    /// this kit's armor behind an empty hand, what melee uses in sumo
+    fn empty_handed(&self) -> Kit {
+        let (damage, cooldown, _) = FIST;
+        Kit {
+            weapon_damage: damage,
+            armor_reduction: self.armor_reduction,
+            armor_points: self.armor_points,
+            armor_toughness: self.armor_toughness,
+            attack_cooldown_ticks: Some(cooldown),
+            sword: false,
+            ..Kit::default()
+        }
+    }
+
+    /// extra melee damage from sharpness
+    fn sharpness_damage(&self, version: CombatVersion) -> f64 {
+        match (version, self.sharpness) {
+            (_, 0) => 0.0,
+            (CombatVersion::Modern, level) => 0.5 * level as f64 + 0.5,
+            (CombatVersion::Legacy, level) => LEGACY_SHARPNESS_PER_LEVEL * level as f64,
+        }
+    }
+
+    /// what's left of a hit of `damage` after this kit's armor
+    pub(crate) fn after_armor(&self, damage: f64, version: CombatVersion) -> f64 {
+        let Some(points) = self.armor_points else {
+            return damage * (1.0 - self.armor_reduction);
+        };
+        if version == CombatVersion::Legacy {
+            // 1.8's EntityLivingBase.applyArmorCalculations, toughness came later
+            return damage * (1.0 - points.min(25.0) * LEGACY_ARMOR_PER_POINT);
+        }
+        // CombatRules.getDamageAfterAbsorb
+        let effective = (points - damage / (2.0 + self.armor_toughness / 4.0)).clamp(points * 0.2, 20.0);
+        damage * (1.0 - effective / 25.0)
+    }
+}
+
+/// EFFECTS index of an effect, "minecraft:" is optional
+fn effect_position(name: &str) -> Option<usize> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    EFFECTS.iter().position(|e| *e == name)
+}
+
+/// effect_position, or an error naming the effects there are
+pub fn effect_index(name: &str) -> PyResult<usize> {
+    effect_position(name).ok_or_else(|| {
+        PyValueError::new_err(format!("the arena has no {:?} effect, expected one of {}", name, EFFECTS.join(", ")))
+    })
+}
+
+/// `effects` as Fighter.effects, leaving out the ones the arena doesn't have
+pub fn effect_slots(effects: &[Effect]) -> [ActiveEffect; EFFECTS.len()] {
+    let mut slots = [ActiveEffect::default(); EFFECTS.len()];
+    for effect in effects {
+        if let Some(i) = effect_position(&effect.name) {
+            slots[i] = ActiveEffect::of(effect);
+        }
+    }
+    slots
+}
+
+/// what a splash potion does
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Splash {
+    Harming,
+    Healing,
+    /// EFFECTS index
+    Effect(usize),
+}
+
+fn splash_kind(name: &str) -> Option<Splash> {
+    match name.strip_prefix("minecraft:").unwrap_or(name) {
+        "harming" | "instant_damage" => Some(Splash::Harming),
+        "healing" | "instant_health" => Some(Splash::Healing),
+        name => effect_position(name).map(Splash::Effect),
+    }
+}
+
+/// checks a splash potion's effect, "harming" and "healing" are the instant ones
+pub fn check_potion(effect: &Effect) -> PyResult<()> {
+    if splash_kind(&effect.name).is_none() {
+        return Err(PyValueError::new_err(format!(
+            "the arena has no {:?} potion, expected harming, healing or one of {}",
+            effect.name,
+            EFFECTS.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// the Block for FastArena.set_fluid()'s `fluid`, air for None
+fn fluid_block(fluid: Option<&str>) -> PyResult<Block> {
+    let Some(name) = fluid else {
+        return Ok(Block::Air);
+    };
+    Block::fluid(name).ok_or_else(|| PyValueError::new_err(format!("unknown fluid {:?}, expected \"water\" or \"lava\"", name)))
+}
+
+/// an effect on a fighter, level 0 when it isn't active
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ActiveEffect {
+    /// amplifier + 1
+    pub level: u32,
+    /// u32::MAX if it doesn't run out
+    pub ticks: u32,
+    /// ticks it has been on for, what an infinite effect's intervals count
+    pub elapsed: u32,
+}
+
+impl ActiveEffect {
+    fn of(effect: &Effect) -> Self {
+        match effect.duration {
+            0 => Self::default(),
+            duration => Self {
+                level: effect.amplifier + 1,
+                ticks: u32::try_from(duration).unwrap_or(u32::MAX),
+                elapsed: 0,
+            },
+        }
+    }
+
+    /// whether an effect that acts every `interval` ticks at level I, twice
+    /// as often each level up, acts this tick. timed effects go by the ticks
+    /// left like vanilla, infinite ones by the ticks since they started
+    fn pulses(&self, interval: u32) -> bool {
+        let interval = (interval >> (self.level - 1).min(31)).max(1);
+        match self.ticks {
+            u32::MAX => self.elapsed.wrapping_add(1).is_multiple_of(interval),
+            ticks => ticks.is_multiple_of(interval),
+        }
+    }
+}
+
+/// every tunable number of a FastArena, for FastArena(config=...) without a
+/// config file. the kit values go to both fighters
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ArenaConfig {
+    pub constants: ArenaConstants,
+    /// both fighters'
+    pub kit: Kit,
+    pub floor_y: f64,
+    pub platform_size: Option<f64>,
+    pub mode: ArenaMode,
+}
+
+impl ArenaConfig {
+    /// what new() and every setter check
+    fn validate(&self) -> PyResult<()> {
+        self.constants.validate()?;
+        self.kit.validate()?;
+        check_finite("floor_y", self.floor_y)?;
+        check_platform(self.platform_size)
+    }
+
+    /// a setter's change, kept only if the config is still valid
+    fn update(&mut self, change: impl FnOnce(&mut Self)) -> PyResult<()> {
+        let mut config = self.clone();
+        change(&mut config);
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    /// onto a fresh arena, before it's reset
+    fn apply(&self, arena: &mut FastArena) {
+        arena.constants = self.constants.clone();
+        arena.kits = [self.kit.clone(), self.kit.clone()];
+        arena.floor_y = self.floor_y;
+        arena.platform_size = self.mode.platform(self.platform_size);
+        arena.mode = self.mode;
+    }
+}
+
+#[pymethods]
+impl ArenaConfig {
+    #[new]
+    #[pyo3(signature = (
+        attack_range=ATTACK_RANGE,
+        attack_cooldown_ticks=ATTACK_COOLDOWN_TICKS,
+        sprint_crit_multiplier=SPRINT_CRIT_MULTIPLIER,
+        weapon_damage=BASE_DAMAGE_IRON_SWORD,
+        armor_reduction=DIAMOND_ARMOR_REDUCTION,
+        steaks=64,
+        arrows=0,
+        shield=false,
+        disables_shields=false,
+        pearls=0,
+        potions=0,
+        potion=None,
+        rod=false,
+        sharpness=0,
+        knockback=0,
+        max_health=MAX_HEALTH,
+        max_food=MAX_FOOD,
+        food_heal_threshold=FOOD_HEAL_THRESHOLD,
+        food_heal_amount=FOOD_HEAL_AMOUNT,
+        food_per_steak=FOOD_PER_STEAK,
+        eat_ticks=EAT_TICKS,
+        walk_speed=WALK_SPEED,
+        sprint_speed=SPRINT_SPEED,
+        jump_velocity=JUMP_VELOCITY,
+        gravity=GRAVITY,
+        drag=DRAG,
+        knockback_horizontal=KNOCKBACK_HORIZONTAL,
+        knockback_vertical=KNOCKBACK_VERTICAL,
+        sprint_knockback=SPRINT_KNOCKBACK,
+        floor_y=0.0,
+        platform_size=None,
+        combat_version="1.21",
+        mode="fight",
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        attack_range: f64,
+        attack_cooldown_ticks: u32,
+        sprint_crit_multiplier: f64,
+        weapon_damage: f64,
+        armor_reduction: f64,
+        steaks: u32,
+        arrows: u32,
+        shield: bool,
+        disables_shields: bool,
+        pearls: u32,
+        potions: u32,
+        potion: Option<Effect>,
+        rod: bool,
+        sharpness: u32,
+        knockback: u32,
+        max_health: f64,
+        max_food: f64,
+        food_heal_threshold: f64,
+        food_heal_amount: f64,
+        food_per_steak: f64,
+        eat_ticks: u32,
+        walk_speed: f64,
+        sprint_speed: f64,
+        jump_velocity: f64,
+        gravity: f64,
+        drag: f64,
+        knockback_horizontal: f64,
+        knockback_vertical: f64,
+        sprint_knockback: f64,
+        floor_y: f64,
+        platform_size: Option<f64>,
+        combat_version: &str,
+        mode: &str,
+    ) -> PyResult<Self> {
+        let config = Self {
+            constants: ArenaConstants {
+                attack_range,
+                attack_cooldown_ticks,
+                sprint_crit_multiplier,
+                max_health,
+                max_food,
+                food_heal_threshold,
+                food_heal_amount,
+                food_per_steak,
+                eat_ticks,
+                walk_speed,
+                sprint_speed,
+                jump_velocity,
+                gravity,
+                drag,
+                knockback_horizontal,
+                knockback_vertical,
+                sprint_knockback,
+                combat_version: CombatVersion::parse(combat_version)?,
+            },
+            kit: Kit {
+                weapon_damage,
+                armor_reduction,
+                steaks,
+                arrows,
+                shield,
+                disables_shields,
+                pearls,
+                potions,
+                potion,
+                rod,
+                sharpness,
+                knockback,
+                ..Kit::default()
+            },
+            floor_y,
+            platform_size,
+            mode: ArenaMode::parse(mode)?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ArenaConfig(attack_range={}, attack_cooldown_ticks={}, sprint_crit_multiplier={}, weapon_damage={}, \
+             armor_reduction={}, steaks={}, arrows={}, shield={}, disables_shields={}, pearls={}, potions={}, potion={}, rod={}, sharpness={}, knockback={}, max_health={}, max_food={}, food_heal_threshold={}, food_heal_amount={}, \
+             food_per_steak={}, eat_ticks={}, walk_speed={}, sprint_speed={}, jump_velocity={}, gravity={}, drag={}, \
+             knockback_horizontal={}, knockback_vertical={}, sprint_knockback={}, floor_y={}, platform_size={}, combat_version={:?}, mode={:?})",
+            self.constants.attack_range,
+            self.constants.attack_cooldown_ticks,
+            self.constants.sprint_crit_multiplier,
+            self.kit.weapon_damage,
+            self.kit.armor_reduction,
+            self.kit.steaks,
+            self.kit.arrows,
+            if self.kit.shield { "True" } else { "False" },
+            if self.kit.disables_shields { "True" } else { "False" },
+            self.kit.pearls,
+            self.kit.potions,
+            self.kit.potion.as_ref().map_or("None".to_string(), |p| p.__repr__()),
+            if self.kit.rod { "True" } else { "False" },
+            self.kit.sharpness,
+            self.kit.knockback,
+            self.constants.max_health,
+            self.constants.max_food,
+            self.constants.food_heal_threshold,
+            self.constants.food_heal_amount,
+            self.constants.food_per_steak,
+            self.constants.eat_ticks,
+            self.constants.walk_speed,
+            self.constants.sprint_speed,
+            self.constants.jump_velocity,
+            self.constants.gravity,
+            self.constants.drag,
+            self.constants.knockback_horizontal,
+            self.constants.knockback_vertical,
+            self.constants.sprint_knockback,
+            self.floor_y,
+            self.platform_size.map_or("None".to_string(), |size| size.to_string()),
+            self.constants.combat_version.name(),
+            self.mode.name(),
+        )
+    }
+
+    #[getter]
+    fn attack_range(&self) -> f64 {
+        self.constants.attack_range
+    }
+
+    #[setter]
+    fn set_attack_range(&mut self, attack_range: f64) -> PyResult<()> {
+        self.update(|config| config.constants.attack_range = attack_range)
+    }
+
+    #[getter]
+    fn attack_cooldown_ticks(&self) -> u32 {
+        self.constants.attack_cooldown_ticks
+    }
+
+    #[setter]
+    fn set_attack_cooldown_ticks(&mut self, attack_cooldown_ticks: u32) -> PyResult<()> {
+        self.update(|config| config.constants.attack_cooldown_ticks = attack_cooldown_ticks)
+    }
+
+    #[getter]
+    fn sprint_crit_multiplier(&self) -> f64 {
+        self.constants.sprint_crit_multiplier
+    }
+
+    #[setter]
+    fn set_sprint_crit_multiplier(&mut self, sprint_crit_multiplier: f64) -> PyResult<()> {
+        self.update(|config| config.constants.sprint_crit_multiplier = sprint_crit_multiplier)
+    }
+
+    /// damage of a hit before armor
+    #[getter]
+    fn weapon_damage(&self) -> f64 {
+        self.kit.weapon_damage
+    }
+
+    #[setter]
+    fn set_weapon_damage(&mut self, weapon_damage: f64) -> PyResult<()> {
+        self.update(|config| config.kit.weapon_damage = weapon_damage)
+    }
+
+    /// fraction of incoming damage the armor takes away
+    #[getter]
+    fn armor_reduction(&self) -> f64 {
+        self.kit.armor_reduction
+    }
+
+    #[setter]
+    fn set_armor_reduction(&mut self, armor_reduction: f64) -> PyResult<()> {
+        self.update(|config| config.kit.armor_reduction = armor_reduction)
+    }
+
+    #[getter]
+    fn steaks(&self) -> u32 {
+        self.kit.steaks
+    }
+
+    #[setter]
+    fn set_steaks(&mut self, steaks: u32) -> PyResult<()> {
+        self.update(|config| config.kit.steaks = steaks)
+    }
+
+    #[getter]
+    fn arrows(&self) -> u32 {
+        self.kit.arrows
+    }
+
+    #[setter]
+    fn set_arrows(&mut self, arrows: u32) -> PyResult<()> {
+        self.update(|config| config.kit.arrows = arrows)
+    }
+
+    #[getter]
+    fn shield(&self) -> bool {
+        self.kit.shield
+    }
+
+    #[setter]
+    fn set_shield(&mut self, shield: bool) -> PyResult<()> {
+        self.update(|config| config.kit.shield = shield)
+    }
+
+    #[getter]
+    fn disables_shields(&self) -> bool {
+        self.kit.disables_shields
+    }
+
+    #[setter]
+    fn set_disables_shields(&mut self, disables_shields: bool) -> PyResult<()> {
+        self.update(|config| config.kit.disables_shields = disables_shields)
+    }
+
+    #[getter]
+    fn pearls(&self) -> u32 {
+        self.kit.pearls
+    }
+
+    #[setter]
+    fn set_pearls(&mut self, pearls: u32) -> PyResult<()> {
+        self.update(|config| config.kit.pearls = pearls)
+    }
+
+    /// splash potions, all of them `potion`
+    #[getter]
+    fn potions(&self) -> u32 {
+        self.kit.potions
+    }
+
+    #[setter]
+    fn set_potions(&mut self, potions: u32) -> PyResult<()> {
+        self.update(|config| config.kit.potions = potions)
+    }
+
+    #[getter]
+    fn potion(&self) -> Option<Effect> {
+        self.kit.potion.clone()
+    }
+
+    #[setter]
+    fn set_potion(&mut self, potion: Option<Effect>) -> PyResult<()> {
+        self.update(|config| config.kit.potion = potion)
+    }
+
+    #[getter]
+    fn rod(&self) -> bool {
+        self.kit.rod
+    }
+
+    #[setter]
+    fn set_rod(&mut self, rod: bool) -> PyResult<()> {
+        self.update(|config| config.kit.rod = rod)
+    }
+
+    #[getter]
+    fn sharpness(&self) -> u32 {
+        self.kit.sharpness
+    }
+
+    #[setter]
+    fn set_sharpness(&mut self, sharpness: u32) -> PyResult<()> {
+        self.update(|config| config.kit.sharpness = sharpness)
+    }
+
+    /// the weapon's knockback level
+    #[getter]
+    fn knockback(&self) -> u32 {
+        self.kit.knockback
+    }
+
+    #[setter]
+    fn set_knockback(&mut self, knockback: u32) -> PyResult<()> {
+        self.update(|config| config.kit.knockback = knockback)
+    }
+
+    #[getter]
+    fn max_health(&self) -> f64 {
+        self.constants.max_health
+    }
+
+    #[setter]
+    fn set_max_health(&mut self, max_health: f64) -> PyResult<()> {
+        self.update(|config| config.constants.max_health = max_health)
+    }
+
+    #[getter]
+    fn max_food(&self) -> f64 {
+        self.constants.max_food
+    }
+
+    #[setter]
+    fn set_max_food(&mut self, max_food: f64) -> PyResult<()> {
+        self.update(|config| config.constants.max_food = max_food)
+    }
+
+    #[getter]
+    fn food_heal_threshold(&self) -> f64 {
+        self.constants.food_heal_threshold
+    }
+
+    #[setter]
+    fn set_food_heal_threshold(&mut self, food_heal_threshold: f64) -> PyResult<()> {
+        self.update(|config| config.constants.food_heal_threshold = food_heal_threshold)
+    }
+
+    #[getter]
+    fn food_heal_amount(&self) -> f64 {
+        self.constants.food_heal_amount
+    }
+
+    #[setter]
+    fn set_food_heal_amount(&mut self, food_heal_amount: f64) -> PyResult<()> {
+        self.update(|config| config.constants.food_heal_amount = food_heal_amount)
+    }
+
+    #[getter]
+    fn food_per_steak(&self) -> f64 {
+        self.constants.food_per_steak
+    }
+
+    #[setter]
+    fn set_food_per_steak(&mut self, food_per_steak: f64) -> PyResult<()> {
+        self.update(|config| config.constants.food_per_steak = food_per_steak)
+    }
+
+    #[getter]
+    fn eat_ticks(&self) -> u32 {
+        self.constants.eat_ticks
+    }
+
+    #[setter]
+    fn set_eat_ticks(&mut self, eat_ticks: u32) -> PyResult<()> {
+        self.update(|config| config.constants.eat_ticks = eat_ticks)
+    }
+
+    /// blocks per tick
+    #[getter]
+    fn walk_speed(&self) -> f64 {
+        self.constants.walk_speed
+    }
+
+    #[setter]
+    fn set_walk_speed(&mut self, walk_speed: f64) -> PyResult<()> {
+        self.update(|config| config.constants.walk_speed = walk_speed)
+    }
+
+    #[getter]
+    fn sprint_speed(&self) -> f64 {
+        self.constants.sprint_speed
+    }
+
+    #[setter]
+    fn set_sprint_speed(&mut self, sprint_speed: f64) -> PyResult<()> {
+        self.update(|config| config.constants.sprint_speed = sprint_speed)
+    }
+
+    #[getter]
+    fn jump_velocity(&self) -> f64 {
+        self.constants.jump_velocity
+    }
+
+    #[setter]
+    fn set_jump_velocity(&mut self, jump_velocity: f64) -> PyResult<()> {
+        self.update(|config| config.constants.jump_velocity = jump_velocity)
+    }
+
+    #[getter]
+    fn gravity(&self) -> f64 {
+        self.constants.gravity
+    }
+
+    #[setter]
+    fn set_gravity(&mut self, gravity: f64) -> PyResult<()> {
+        self.update(|config| config.constants.gravity = gravity)
+    }
+
+    /// horizontal velocity kept each tick
+    #[getter]
+    fn drag(&self) -> f64 {
+        self.constants.drag
+    }
+
+    #[setter]
+    fn set_drag(&mut self, drag: f64) -> PyResult<()> {
+        self.update(|config| config.constants.drag = drag)
+    }
+
+    /// a hit's knockback strength, blocks per tick
+    #[getter]
+    fn knockback_horizontal(&self) -> f64 {
+        self.constants.knockback_horizontal
+    }
+
+    #[setter]
+    fn set_knockback_horizontal(&mut self, knockback_horizontal: f64) -> PyResult<()> {
+        self.update(|config| config.constants.knockback_horizontal = knockback_horizontal)
+    }
+
+    /// the most upward velocity a hit leaves
+    #[getter]
+    fn knockback_vertical(&self) -> f64 {
+        self.constants.knockback_vertical
+    }
+
+    #[setter]
+    fn set_knockback_vertical(&mut self, knockback_vertical: f64) -> PyResult<()> {
+        self.update(|config| config.constants.knockback_vertical = knockback_vertical)
+    }
+
+    /// the extra strength of each knockback level, sprinting adds one
+    #[getter]
+    fn sprint_knockback(&self) -> f64 {
+        self.constants.sprint_knockback
+    }
+
+    #[setter]
+    fn set_sprint_knockback(&mut self, sprint_knockback: f64) -> PyResult<()> {
+        self.update(|config| config.constants.sprint_knockback = sprint_knockback)
+    }
+
+    #[getter]
+    fn floor_y(&self) -> f64 {
+        self.floor_y
+    }
+
+    #[setter]
+    fn set_floor_y(&mut self, floor_y: f64) -> PyResult<()> {
+        self.update(|config| config.floor_y = floor_y)
+    }
+
+    /// side of a platform with the void around it, None for a floor everywhere
+    #[getter]
+    fn platform_size(&self) -> Option<f64> {
+        self.platform_size
+    }
+
+    #[setter]
+    fn set_platform_size(&mut self, platform_size: Option<f64>) -> PyResult<()> {
+        self.update(|config| config.platform_size = platform_size)
+    }
+
+    /// "1.21" or "1.8"
+    #[getter]
+    fn combat_version(&self) -> &'static str {
+        self.constants.combat_version.name()
+    }
+
+    #[setter]
+    fn set_combat_version(&mut self, combat_version: &str) -> PyResult<()> {
+        let combat_version = CombatVersion::parse(combat_version)?;
+        self.update(|config| config.constants.combat_version = combat_version)
+    }
+
+    /// "fight" or "sumo"
+    #[getter]
+    fn mode(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    #[setter]
+    fn set_mode(&mut self, mode: &str) -> PyResult<()> {
+        let mode = ArenaMode::parse(mode)?;
+        self.update(|config| config.mode = mode)
+    }
+}
+
+fn check_finite(name: &str, value: f64) -> PyResult<()> {
+    if !value.is_finite() {
+        return Err(PyValueError::new_err(format!("{} must be finite", name)));
+    }
+    Ok(())
+}
+
+fn check_positive(name: &str, value: f64) -> PyResult<()> {
+    if !value.is_finite() || value <= 0.0 {
+        return Err(PyValueError::new_err(format!("{} must be positive", name)));
+    }
+    Ok(())
+}
+
+fn check_not_negative(name: &str, value: f64) -> PyResult<()> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(PyValueError::new_err(format!("{} must be finite and not negative", name)));
+    }
+    Ok(())
+}
+
+fn check_fraction(name: &str, value: f64) -> PyResult<()> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(PyValueError::new_err(format!("{} must be in [0, 1]", name)));
+    }
+    Ok(())
+}
+
+fn check_arena_size(arena_size: f64) -> PyResult<()> {
+    check_positive("arena_size", arena_size)?;
+    if arena_size > MAX_ARENA_SIZE {
+        return Err(PyValueError::new_err(format!("arena_size must be at most {}", MAX_ARENA_SIZE)));
+    }
+    Ok(())
+}
+
+fn check_platform(platform_size: Option<f64>) -> PyResult<()> {
+    if platform_size.is_some_and(|size| size.is_nan() || size <= 0.0) {
+        return Err(PyValueError::new_err("platform_size must be positive"));
+    }
+    Ok(())
+}
+
+/// `[arena]` of a config file
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ArenaSection {
+    size: f64,
+    max_ticks: u32,
+    spawn_distance: f64,
+    /// kit name for both fighters
+    kit: Option<String>,
+    /// kit names for fighter 1 and 2
+    kits: Option<[String; 2]>,
+    /// see FastArena.platform_size
+    platform_size: Option<f64>,
+    mode: ArenaMode,
+    /// see FastArena.set_opponent()
+    opponent: Option<OpponentKind>,
+    /// see FastArena.set_heights()
+    heights: Option<Vec<Vec<u32>>>,
+    /// FastArena.fill_fluid() boxes, after the heights
+    fluids: Vec<FluidFill>,
+    constants: ArenaConstants,
+    rewards: RewardConfig,
+}
+
+/// one of `[arena]`'s fluids, like { fluid = "lava", from = [-2, 0, -2], to = [2, 0, 2] }
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FluidFill {
+    fluid: String,
+    from: [i32; 3],
+    to: [i32; 3],
+}
+
+impl Default for ArenaSection {
+    fn default() -> Self {
+        Self {
+            size: 32.0,
+            max_ticks: 2400,
+            spawn_distance: SPAWN_DISTANCE,
+            kit: None,
+            kits: None,
+            platform_size: None,
+            mode: ArenaMode::Fight,
+            opponent: None,
+            heights: None,
+            fluids: vec![],
+            constants: ArenaConstants::default(),
+            rewards: RewardConfig::default(),
+        }
+    }
+}
+
+/// the parts of a config file an arena reads, the rest (like `[bot]`) is
+/// left to connect_from_config()
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ArenaFile {
+    arena: ArenaSection,
+    kits: HashMap<String, Kit>,
+}
+
+impl ArenaFile {
+    fn kit(&self, name: &str) -> PyResult<Kit> {
+        self.kits.get(name).cloned().ok_or_else(|| {
+            let mut names: Vec<_> = self.kits.keys().map(String::as_str).collect();
+            names.sort();
+            PyValueError::new_err(format!("unknown kit {:?}, the file has [{}]", name, names.join(", ")))
+        })
+        .and_then(|kit| {
+            kit.validate()?;
+            Ok(kit)
+        })
+    }
+
+    fn build(&self) -> PyResult<FastArena> {
+        let section = &self.arena;
+        let kits = match (&section.kit, &section.kits) {
+            (Some(_), Some(_)) => return Err(PyValueError::new_err("set arena.kit or arena.kits, not both")),
+            (Some(kit), None) => [self.kit(kit)?, self.kit(kit)?],
+            (None, Some([kit1, kit2])) => [self.kit(kit1)?, self.kit(kit2)?],
+            (None, None) => [Kit::default(), Kit::default()],
+        };
+        if !(section.size.is_finite() && section.size > 0.0) || section.max_ticks == 0 {
+            return Err(PyValueError::new_err("arena.size and arena.max_ticks must be positive"));
+        }
+        check_positive("arena.spawn_distance", section.spawn_distance)?;
+        check_platform(section.platform_size)?;
+        section.constants.validate()?;
+        let mut arena = FastArena::new(section.size, section.max_ticks, None, Some(section.rewards.clone()))?;
+        arena.constants = section.constants.clone();
+        arena.kits = kits;
+        arena.spawn_distance = section.spawn_distance;
+        arena.platform_size = section.mode.platform(section.platform_size);
+        arena.mode = section.mode;
+        arena.opponent = section.opponent.map(|kind| Opponent::new(kind, 0));
+        if let Some(heights) = &section.heights {
+            arena.set_heights(heights.clone())?;
+        }
+        for fill in &section.fluids {
+            arena.put_blocks(fill.from.into(), fill.to.into(), fluid_block(Some(&fill.fluid))?)?;
+        }
+        arena.reset(None, None, None);
+        Ok(arena)
+    }
+}
+
+/// length of get_obs1()/get_obs2()
+pub const OBS_SIZE: usize = 110;
+
+/// (name, low, high) of each get_obs1() feature, in order. unbounded ones
+/// are infinite (health goes below 0 on a killing blow), the terrain ones are
+/// ground_<dx>_<dz> with m for minus
+pub const OBS_FEATURES: [(&str, f64, f64); OBS_SIZE] = [
+    // My state (13)
+    ("x", f64::NEG_INFINITY, f64::INFINITY),
+    ("y", f64::NEG_INFINITY, f64::INFINITY),
+    ("z", f64::NEG_INFINITY, f64::INFINITY),
+    ("vx", f64::NEG_INFINITY, f64::INFINITY),
+    ("vy", f64::NEG_INFINITY, f64::INFINITY),
+    ("vz", f64::NEG_INFINITY, f64::INFINITY),
+    ("health", f64::NEG_INFINITY, 1.0),
+    ("food", 0.0, 1.0),
+    ("cooldown", 0.0, 1.0),
+    ("yaw", -1.0, 1.0),
+    ("pitch", -1.0, 1.0),
+    ("on_ground", 0.0, 1.0),
+    ("sprinting", 0.0, 1.0),
+    // Enemy state (10)
+    ("enemy_dx", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_dy", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_dz", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_distance", 0.0, 1.0),
+    ("enemy_health", f64::NEG_INFINITY, 1.0),
+    ("enemy_yaw_to_me", -1.0, 1.0),
+    ("enemy_vx", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_vy", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_vz", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_visible", 0.0, 1.0),
+    // Combat state (4)
+    ("damage_dealt", 0.0, f64::INFINITY),
+    ("damage_taken", 0.0, f64::INFINITY),
+    ("hits_landed", 0.0, f64::INFINITY),
+    ("hits_taken", 0.0, f64::INFINITY),
+    // Eating state (4)
+    ("eating", 0.0, 1.0),
+    ("eating_progress", 0.0, 1.0),
+    ("enemy_eating", 0.0, 1.0),
+    ("steaks", 0.0, f64::INFINITY),
+    // Ranged state (7)
+    ("arrows", 0.0, f64::INFINITY),
+    ("bow_power", 0.0, 1.0),
+    ("enemy_bow_power", 0.0, 1.0),
+    ("incoming_arrows", 0.0, 1.0),
+    ("arrow_dx", f64::NEG_INFINITY, f64::INFINITY),
+    ("arrow_dy", f64::NEG_INFINITY, f64::INFINITY),
+    ("arrow_dz", f64::NEG_INFINITY, f64::INFINITY),
+    // Shield state (4)
+    ("blocking", 0.0, 1.0),
+    ("enemy_blocking", 0.0, 1.0),
+    ("shield_disabled", 0.0, 1.0),
+    ("enemy_shield_disabled", 0.0, 1.0),
+    // Pearls (7)
+    ("pearls", 0.0, f64::INFINITY),
+    ("enemy_pearls", 0.0, f64::INFINITY),
+    ("pearl_cooldown", 0.0, 1.0),
+    ("pearl_in_flight", 0.0, 1.0),
+    ("enemy_pearl_dx", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_pearl_dy", f64::NEG_INFINITY, f64::INFINITY),
+    ("enemy_pearl_dz", f64::NEG_INFINITY, f64::INFINITY),
+    // Effects (10)
+    ("speed", 0.0, f64::INFINITY),
+    ("slowness", 0.0, f64::INFINITY),
+    ("strength", 0.0, f64::INFINITY),
+    ("weakness", 0.0, f64::INFINITY),
+    ("regeneration", 0.0, f64::INFINITY),
+    ("enemy_speed", 0.0, f64::INFINITY),
+    ("enemy_slowness", 0.0, f64::INFINITY),
+    ("enemy_strength", 0.0, f64::INFINITY),
+    ("enemy_weakness", 0.0, f64::INFINITY),
+    ("enemy_regeneration", 0.0, f64::INFINITY),
+    // Splash potions (7)
+    ("potions", 0.0, f64::INFINITY),
+    ("enemy_potions", 0.0, f64::INFINITY),
+    ("poison", 0.0, f64::INFINITY),
+    ("enemy_poison", 0.0, f64::INFINITY),
+    ("potion_dx", f64::NEG_INFINITY, f64::INFINITY),
+    ("potion_dy", f64::NEG_INFINITY, f64::INFINITY),
+    ("potion_dz", f64::NEG_INFINITY, f64::INFINITY),
+    // Fishing rod (7)
+    ("rod_cooldown", 0.0, 1.0),
+    ("hook_out", 0.0, 1.0),
+    ("hook_hooked", 0.0, 1.0),
+    ("hooked", 0.0, 1.0),
+    ("hook_dx", f64::NEG_INFINITY, f64::INFINITY),
+    ("hook_dy", f64::NEG_INFINITY, f64::INFINITY),
+    ("hook_dz", f64::NEG_INFINITY, f64::INFINITY),
+    // Platform (3)
+    ("platform_size", 0.0, f64::INFINITY),
+    ("edge_distance", 0.0, f64::INFINITY),
+    ("enemy_edge_distance", 0.0, f64::INFINITY),
+    // Terrain (26)
+    ("ground_m2_m2", -1.0, 1.0),
+    ("ground_m2_m1", -1.0, 1.0),
+    ("ground_m2_0", -1.0, 1.0),
+    ("ground_m2_1", -1.0, 1.0),
+    ("ground_m2_2", -1.0, 1.0),
+    ("ground_m1_m2", -1.0, 1.0),
+    ("ground_m1_m1", -1.0, 1.0),
+    ("ground_m1_0", -1.0, 1.0),
+    ("ground_m1_1", -1.0, 1.0),
+    ("ground_m1_2", -1.0, 1.0),
+    ("ground_0_m2", -1.0, 1.0),
+    ("ground_0_m1", -1.0, 1.0),
+    ("ground_0_0", -1.0, 1.0),
+    ("ground_0_1", -1.0, 1.0),
+    ("ground_0_2", -1.0, 1.0),
+    ("ground_1_m2", -1.0, 1.0),
+    ("ground_1_m1", -1.0, 1.0),
+    ("ground_1_0", -1.0, 1.0),
+    ("ground_1_1", -1.0, 1.0),
+    ("ground_1_2", -1.0, 1.0),
+    ("ground_2_m2", -1.0, 1.0),
+    ("ground_2_m1", -1.0, 1.0),
+    ("ground_2_0", -1.0, 1.0),
+    ("ground_2_1", -1.0, 1.0),
+    ("ground_2_2", -1.0, 1.0),
+    ("line_of_sight", 0.0, 1.0),
+    // Hazards (8)
+    ("in_water", 0.0, 1.0),
+    ("in_lava", 0.0, 1.0),
+    ("fire", 0.0, 1.0),
+    ("lava_closeness", 0.0, 1.0),
+    ("lava_dx", -1.0, 1.0),
+    ("lava_dy", -1.0, 1.0),
+    ("lava_dz", -1.0, 1.0),
+    ("water_closeness", 0.0, 1.0),
+];
+
+/// obs of fighter 1 or 2 as float32
+fn obs_f32(arena: &FastArena, agent: u8) -> PyResult<Vec<f32>> {
+    let obs = match agent {
+        1 => arena.get_obs1(),
+        2 => arena.get_obs2(),
+        _ => return Err(PyValueError::new_err(format!("agent must be 1 or 2, not {}", agent))),
+    };
+    Ok(obs.into_iter().map(|v| v as f32).collect())
+}
 
 #[pyclass]
 #[derive(Clone, Debug)]
@@ -58,6 +1363,18 @@ pub struct Fighter {
     pub food: f64,
     #[pyo3(get)]
     pub steaks: u32,
+    #[pyo3(get)]
+    pub arrows: u32,
+    #[pyo3(get)]
+    pub shield: bool,
+    #[pyo3(get)]
+    pub pearls: u32,
+    /// splash potions left
+    #[pyo3(get)]
+    pub potions: u32,
+    /// carries a fishing rod
+    #[pyo3(get)]
+    pub rod: bool,
 
     // Cooldowns
     #[pyo3(get)]
@@ -66,6 +1383,34 @@ pub struct Fighter {
     pub eating_ticks: u32,
     #[pyo3(get)]
     pub jump_cooldown: u32,  // prevent jump spam
+    /// ticks the bow has been drawn, 0 when it isn't
+    #[pyo3(get)]
+    pub draw_ticks: u32,
+    /// ticks the shield has been raised
+    #[pyo3(get)]
+    pub block_ticks: u32,
+    /// ticks until the shield works again after an axe hit
+    #[pyo3(get)]
+    pub shield_disabled: u32,
+    /// ticks until another pearl can be thrown
+    #[pyo3(get)]
+    pub pearl_cooldown: u32,
+    #[pyo3(get)]
+    pub potion_cooldown: u32,
+    /// ticks until the rod can be cast or reeled in again
+    #[pyo3(get)]
+    pub rod_cooldown: u32,
+    /// 1.8 combat's invulnerability after a melee hit
+    #[pyo3(get)]
+    pub hurt_ticks: u32,
+    /// blocks fallen since last on the ground
+    #[pyo3(get)]
+    pub fall_distance: f64,
+    /// ticks left burning, lava sets it and water puts it out
+    #[pyo3(get)]
+    pub fire_ticks: u32,
+    /// per EFFECTS entry
+    pub effects: [ActiveEffect; EFFECTS.len()],
 
     // Flags
     #[pyo3(get)]
@@ -74,94 +1419,600 @@ pub struct Fighter {
     pub sprinting: bool,
     #[pyo3(get)]
     pub eating: bool,
+    /// the shield is up and stopping frontal hits
+    #[pyo3(get)]
+    pub blocking: bool,
+    /// touching a water or lava block, as of the last move
+    #[pyo3(get)]
+    pub in_water: bool,
+    #[pyo3(get)]
+    pub in_lava: bool,
 
     // Stats for this episode
+    /// by hits, arrows and harming splashes, not falls, lava or poison
+    #[pyo3(get)]
+    pub damage_dealt: f64,
+    #[pyo3(get)]
+    pub damage_taken: f64,
+    #[pyo3(get)]
+    pub hits_landed: u32,
+    #[pyo3(get)]
+    pub hits_taken: u32,
+    /// hits that were sweep attacks
+    #[pyo3(get)]
+    pub sweeps: u32,
+    /// the last step's hit was a sweep attack
+    #[pyo3(get)]
+    pub swept: bool,
+
+    /// ArenaConstants.attack_cooldown_ticks, for cooldown_progress()
+    pub cooldown_ticks: u32,
+}
+
+impl Default for Fighter {
+    fn default() -> Self {
+        Self {
+            x: 0.0, y: 0.0, z: 0.0,
+            vx: 0.0, vy: 0.0, vz: 0.0,
+            yaw: 0.0, pitch: 0.0,
+            health: MAX_HEALTH,
+            food: MAX_FOOD,
+            steaks: 64,
+            arrows: 0,
+            shield: false,
+            pearls: 0,
+            potions: 0,
+            rod: false,
+            attack_cooldown: 0,
+            eating_ticks: 0,
+            jump_cooldown: 0,
+            draw_ticks: 0,
+            block_ticks: 0,
+            shield_disabled: 0,
+            pearl_cooldown: 0,
+            potion_cooldown: 0,
+            rod_cooldown: 0,
+            hurt_ticks: 0,
+            fall_distance: 0.0,
+            fire_ticks: 0,
+            effects: [ActiveEffect::default(); EFFECTS.len()],
+            on_ground: true,
+            sprinting: false,
+            eating: false,
+            blocking: false,
+            in_water: false,
+            in_lava: false,
+            damage_dealt: 0.0,
+            damage_taken: 0.0,
+            hits_landed: 0,
+            hits_taken: 0,
+            sweeps: 0,
+            swept: false,
+            cooldown_ticks: ATTACK_COOLDOWN_TICKS,
+        }
+    }
+}
+
+impl Fighter {
+    /// the hitbox's min and max corners
+    fn bounds(&self) -> ([f64; 3], [f64; 3]) {
+        (
+            [self.x - HALF_WIDTH, self.y, self.z - HALF_WIDTH],
+            [self.x + HALF_WIDTH, self.y + HEIGHT, self.z + HALF_WIDTH],
+        )
+    }
+
+    /// LivingEntity.knockback: halve the velocity and push `strength` away
+    /// from (x, z), which is relative to the fighter. `lift` also sends it up,
+    /// to at most `max_vy`
+    pub(crate) fn knock_back(&mut self, strength: f64, max_vy: f64, (x, z): (f64, f64), lift: bool) {
+        let length = x.hypot(z);
+        let (x, z) = if length > 1e-4 { (x / length, z / length) } else { (0.0, 0.0) };
+        self.vx = self.vx / 2.0 - x * strength;
+        self.vz = self.vz / 2.0 - z * strength;
+        if lift {
+            self.vy = (self.vy / 2.0 + strength).min(max_vy);
+        }
+    }
+
+    /// whether the shield is up between this fighter and (x, z)
+    pub(crate) fn blocks_from(&self, x: f64, z: f64) -> bool {
+        if !self.blocking {
+            return false;
+        }
+        // LivingEntity.isDamageSourceBlocked, in front is anywhere within 90 degrees
+        let yaw = self.yaw.to_radians();
+        let (dx, dz) = (x - self.x, z - self.z);
+        -yaw.sin() * dx + yaw.cos() * dz > 0.0
+    }
+
+    /// level of EFFECTS[effect], 0 without it
+    fn level(&self, effect: usize) -> f64 {
+        self.effects[effect].level as f64
+    }
+
+    /// of walking and sprinting, from speed and slowness
+    fn speed_multiplier(&self) -> f64 {
+        (1.0 + self.level(0) * SPEED_PER_LEVEL - self.level(1) * SLOWNESS_PER_LEVEL).max(0.0)
+    }
+
+    /// added to a melee hit, from strength and weakness
+    fn damage_bonus(&self) -> f64 {
+        self.level(2) * STRENGTH_PER_LEVEL - self.level(3) * WEAKNESS_PER_LEVEL
+    }
+}
+
+#[pymethods]
+impl Fighter {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// the active potion effects
+    #[getter]
+    fn effects(&self) -> Vec<Effect> {
+        let active = EFFECTS.iter().zip(self.effects).filter(|(_, effect)| effect.level > 0);
+        active
+            .map(|(name, effect)| Effect {
+                name: name.to_string(),
+                amplifier: effect.level - 1,
+                duration: if effect.ticks == u32::MAX { -1 } else { effect.ticks as i64 },
+                ambient: false,
+            })
+            .collect()
+    }
+
+    /// how far the bow is drawn, 0-1 (1 = full power)
+    fn bow_power(&self) -> f64 {
+        bow_power(self.draw_ticks)
+    }
+
+    /// attack cooldown as 0-1 (1 = ready)
+    fn cooldown_progress(&self) -> f64 {
+        if self.attack_cooldown == 0 {
+            1.0
+        } else {
+            1.0 - (self.attack_cooldown as f64 / self.cooldown_ticks.max(1) as f64)
+        }
+    }
+}
+
+/// Action input for a fighter
+#[pyclass]
+#[derive(Clone, Debug, Default)]
+pub struct FighterAction {
+    #[pyo3(get, set)]
+    pub forward: bool,
+    #[pyo3(get, set)]
+    pub backward: bool,
+    #[pyo3(get, set)]
+    pub left: bool,
+    #[pyo3(get, set)]
+    pub right: bool,
+    #[pyo3(get, set)]
+    pub jump: bool,
+    #[pyo3(get, set)]
+    pub sprint: bool,
+    #[pyo3(get, set)]
+    pub attack: bool,
+    #[pyo3(get, set)]
+    pub eat: bool,
+    #[pyo3(get, set)]
+    pub delta_yaw: f64,   // degrees
+    #[pyo3(get, set)]
+    pub delta_pitch: f64, // degrees
+    /// hold to draw the bow, let go to shoot
+    #[pyo3(get, set)]
+    pub draw: bool,
+    /// hold to raise the shield
+    #[pyo3(get, set)]
+    pub block: bool,
+    /// throw an ender pearl where the fighter looks
+    #[pyo3(get, set)]
+    pub pearl: bool,
+    /// throw a splash potion, the kit's kind
+    #[pyo3(get, set)]
+    pub potion: bool,
+    /// cast the fishing rod, or reel it in if the hook is out
+    #[pyo3(get, set)]
+    pub rod: bool,
+}
+
+/// power of a bow drawn for `ticks`, like vanilla's BowItem.getPowerForTime
+fn bow_power(ticks: u32) -> f64 {
+    let t = ticks as f64 / BOW_FULL_DRAW_TICKS as f64;
+    ((t * t + 2.0 * t) / 3.0).min(1.0)
+}
+
+/// an arrow in flight
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Arrow {
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    #[pyo3(get)]
+    pub vx: f64,
+    #[pyo3(get)]
+    pub vy: f64,
+    #[pyo3(get)]
+    pub vz: f64,
+    /// 1 or 2, who shot it
+    #[pyo3(get)]
+    pub owner: u8,
+    #[pyo3(get)]
+    pub ticks: u32,
+}
+
+impl Arrow {
+    fn speed(&self) -> f64 {
+        (self.vx * self.vx + self.vy * self.vy + self.vz * self.vz).sqrt()
+    }
+}
+
+/// an ender pearl in flight, its thrower teleports where it lands
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Pearl {
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    #[pyo3(get)]
+    pub vx: f64,
+    #[pyo3(get)]
+    pub vy: f64,
+    #[pyo3(get)]
+    pub vz: f64,
+    /// 1 or 2, who threw it
+    #[pyo3(get)]
+    pub owner: u8,
+}
+
+/// a splash potion in flight
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Potion {
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    #[pyo3(get)]
+    pub vx: f64,
+    #[pyo3(get)]
+    pub vy: f64,
+    #[pyo3(get)]
+    pub vz: f64,
+    /// 1 or 2, who threw it
+    #[pyo3(get)]
+    pub owner: u8,
+    /// what it splashes, at full strength
+    #[pyo3(get)]
+    pub effect: Effect,
+}
+
+/// a fishing rod's hook, out until it's reeled in
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Hook {
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    #[pyo3(get)]
+    pub vx: f64,
+    #[pyo3(get)]
+    pub vy: f64,
     #[pyo3(get)]
-    pub damage_dealt: f64,
+    pub vz: f64,
+    /// 1 or 2, whose rod it is
     #[pyo3(get)]
-    pub damage_taken: f64,
+    pub owner: u8,
+    /// caught on the other fighter, it follows them around
     #[pyo3(get)]
-    pub hits_landed: u32,
+    pub hooked: bool,
+    /// resting on the floor or a wall
     #[pyo3(get)]
-    pub hits_taken: u32,
+    pub in_ground: bool,
 }
 
-impl Default for Fighter {
-    fn default() -> Self {
-        Self {
-            x: 0.0, y: 0.0, z: 0.0,
-            vx: 0.0, vy: 0.0, vz: 0.0,
-            yaw: 0.0, pitch: 0.0,
-            health: MAX_HEALTH,
-            food: MAX_FOOD,
-            steaks: 64,
-            attack_cooldown: 0,
-            eating_ticks: 0,
-            jump_cooldown: 0,
-            on_ground: true,
-            sprinting: false,
-            eating: false,
-            damage_dealt: 0.0,
-            damage_taken: 0.0,
-            hits_landed: 0,
-            hits_taken: 0,
+/// where fighters and projectiles stop falling
+#[derive(Clone, Copy)]
+struct Floor<'a> {
+    y: f64,
+    /// half a side of the platform, None if the floor goes on forever
+    half_platform: Option<f64>,
+    /// the blocks standing on it
+    terrain: &'a Terrain,
+}
+
+impl<'a> Floor<'a> {
+    /// from FastArena's fields, so it doesn't hold on to all of it
+    fn new(y: f64, platform_size: Option<f64>, terrain: &'a Terrain) -> Self {
+        Self { y, half_platform: platform_size.map(|size| size / 2.0), terrain }
+    }
+
+    /// over the platform, or within `margin` of it
+    fn under(&self, x: f64, z: f64, margin: f64) -> bool {
+        self.half_platform.is_none_or(|half| x.abs() <= half + margin && z.abs() <= half + margin)
+    }
+
+    fn lands(&self, x: f64, y: f64, z: f64) -> bool {
+        y <= self.y && self.under(x, z, 0.0)
+    }
+
+    fn void(&self, y: f64) -> bool {
+        y < self.y - VOID_DEPTH
+    }
+
+    /// how much of a projectile's step `d` from `from` it flies before a
+    /// block stops it, 1 if none does
+    fn reach(&self, from: (f64, f64, f64), d: (f64, f64, f64)) -> f64 {
+        self.terrain.raycast(from, d).unwrap_or(1.0)
+    }
+}
+
+/// `d` scaled by `t`
+fn scaled(d: (f64, f64, f64), t: f64) -> (f64, f64, f64) {
+    (d.0 * t, d.1 * t, d.2 * t)
+}
+
+/// true if the segment from `from` to `from + d` passes through the fighter's
+/// hitbox, slab test
+fn segment_hits(fighter: &Fighter, from: (f64, f64, f64), d: (f64, f64, f64)) -> bool {
+    let bounds = [
+        (from.0, d.0, fighter.x - HALF_WIDTH, fighter.x + HALF_WIDTH),
+        (from.1, d.1, fighter.y, fighter.y + HEIGHT),
+        (from.2, d.2, fighter.z - HALF_WIDTH, fighter.z + HALF_WIDTH),
+    ];
+    let (mut enter, mut exit) = (0.0f64, 1.0f64);
+    for (start, delta, min, max) in bounds {
+        if delta.abs() < 1e-9 {
+            if start < min || start > max {
+                return false;
+            }
+            continue;
+        }
+        let (t1, t2) = ((min - start) / delta, (max - start) / delta);
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+        if enter > exit {
+            return false;
         }
     }
+    true
 }
 
 #[pymethods]
-impl Fighter {
+impl FighterAction {
     #[new]
     fn new() -> Self {
         Self::default()
     }
+}
 
-    /// attack cooldown as 0-1 (1 = ready)
-    fn cooldown_progress(&self) -> f64 {
-        if self.attack_cooldown == 0 {
-            1.0
+/// a FighterAction, or its fields' values in ACTION_NAMES order as a flat
+/// numpy array (float32 or float64) or a list of floats, for feeding a
+/// network's output straight in. flags are on above 0.5, the values after
+/// delta_pitch can be left out, and delta_yaw and delta_pitch must be finite
+pub struct ActionInput(pub FighterAction);
+
+/// flags in an ActionInput are on above this
+const ACTION_THRESHOLD: f64 = 0.5;
+
+impl<'py> FromPyObject<'py> for ActionInput {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(action) = ob.downcast::<FighterAction>() {
+            return Ok(Self(action.borrow().clone()));
+        }
+        let values: Vec<f64> = if ob.is_instance_of::<PyList>() || ob.is_instance_of::<PyTuple>() {
+            ob.extract()?
+        } else if let Ok(array) = ob.downcast::<PyArray1<f32>>() {
+            array.readonly().as_array().iter().map(|&v| v as f64).collect()
+        } else if let Ok(array) = ob.downcast::<PyArray1<f64>>() {
+            array.readonly().as_array().to_vec()
         } else {
-            1.0 - (self.attack_cooldown as f64 / ATTACK_COOLDOWN_TICKS as f64)
+            return Err(PyValueError::new_err(format!(
+                "expected a FighterAction or a 1-d array of floats, not {}",
+                ob.get_type().name()?
+            )));
+        };
+        FighterAction::from_values(&values).map(Self)
+    }
+}
+
+/// one FighterAction per row of a (count, ACTION_NAMES) float32 or float64
+/// numpy array, the rows as in ActionInput
+fn action_rows(actions: &Bound<'_, PyAny>, count: usize) -> PyResult<Vec<FighterAction>> {
+    let rows: Vec<Vec<f64>> = if let Ok(array) = actions.downcast::<PyArray2<f32>>() {
+        array.readonly().as_array().rows().into_iter().map(|row| row.iter().map(|&v| v as f64).collect()).collect()
+    } else if let Ok(array) = actions.downcast::<PyArray2<f64>>() {
+        array.readonly().as_array().rows().into_iter().map(|row| row.to_vec()).collect()
+    } else {
+        return Err(PyValueError::new_err(format!(
+            "expected a 2-d float32 or float64 array of actions, not {}",
+            actions.get_type().name()?
+        )));
+    };
+    rows_to_actions(&rows, count)
+}
+
+/// action_rows() once the array is read
+fn rows_to_actions(rows: &[Vec<f64>], count: usize) -> PyResult<Vec<FighterAction>> {
+    if rows.len() != count {
+        return Err(PyValueError::new_err(format!("{} rows of actions for {} arenas", rows.len(), count)));
+    }
+    rows.iter().map(|row| FighterAction::from_values(row)).collect()
+}
+
+impl FighterAction {
+    /// see ActionInput
+    pub fn from_values(values: &[f64]) -> PyResult<Self> {
+        // forward to delta_pitch
+        const REQUIRED: usize = 10;
+        if !(REQUIRED..=ACTION_NAMES.len()).contains(&values.len()) {
+            return Err(PyValueError::new_err(format!(
+                "an action array needs {} to {} values ({}), not {}",
+                REQUIRED,
+                ACTION_NAMES.len(),
+                ACTION_NAMES.join(", "),
+                values.len()
+            )));
+        }
+        for (name, value) in [("delta_yaw", values[8]), ("delta_pitch", values[9])] {
+            if !value.is_finite() {
+                return Err(PyValueError::new_err(format!("an action's {} must be finite, got {}", name, value)));
+            }
         }
+        let flag = |i: usize| values.get(i).is_some_and(|&v| v > ACTION_THRESHOLD);
+        Ok(Self {
+            forward: flag(0),
+            backward: flag(1),
+            left: flag(2),
+            right: flag(3),
+            jump: flag(4),
+            sprint: flag(5),
+            attack: flag(6),
+            eat: flag(7),
+            delta_yaw: values[8],
+            delta_pitch: values[9],
+            draw: flag(10),
+            block: flag(11),
+            pearl: flag(12),
+            potion: flag(13),
+            rod: flag(14),
+        })
     }
 }
 
-/// Action input for a fighter
+/// degrees a discrete turn or look moves
+const DISCRETE_TURN: f64 = 15.0;
+const DISCRETE_LOOK: f64 = 10.0;
+
+/// step_discrete()'s actions, by index
+pub const DISCRETE_ACTIONS: [&str; 19] = [
+    "idle",
+    "forward",
+    "sprint",
+    "backward",
+    "strafe_left",
+    "strafe_right",
+    "attack",
+    "sprint_attack",
+    "jump_crit",
+    "eat",
+    "turn_left",
+    "turn_right",
+    "look_up",
+    "look_down",
+    "draw",
+    "block",
+    "pearl",
+    "potion",
+    "rod",
+];
+
+/// the FighterAction of DISCRETE_ACTIONS[index]
+pub fn discrete_action(index: usize) -> PyResult<FighterAction> {
+    let none = FighterAction::default();
+    Ok(match index {
+        0 => none,
+        1 => FighterAction { forward: true, ..none },
+        2 => FighterAction { forward: true, sprint: true, ..none },
+        3 => FighterAction { backward: true, ..none },
+        4 => FighterAction { left: true, ..none },
+        5 => FighterAction { right: true, ..none },
+        6 => FighterAction { attack: true, ..none },
+        7 => FighterAction { forward: true, sprint: true, attack: true, ..none },
+        // the swing lands as a crit once the jump is falling, hold it for a
+        // few ticks
+        8 => FighterAction { forward: true, sprint: true, jump: true, attack: true, ..none },
+        9 => FighterAction { eat: true, ..none },
+        10 => FighterAction { delta_yaw: -DISCRETE_TURN, ..none },
+        11 => FighterAction { delta_yaw: DISCRETE_TURN, ..none },
+        12 => FighterAction { delta_pitch: -DISCRETE_LOOK, ..none },
+        13 => FighterAction { delta_pitch: DISCRETE_LOOK, ..none },
+        14 => FighterAction { draw: true, ..none },
+        15 => FighterAction { block: true, ..none },
+        16 => FighterAction { pearl: true, ..none },
+        17 => FighterAction { potion: true, ..none },
+        18 => FighterAction { rod: true, ..none },
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "discrete action {} out of range, there are {}",
+                index,
+                DISCRETE_ACTIONS.len()
+            )))
+        }
+    })
+}
+
+/// what a step did, see FastArena.step_result()
 #[pyclass]
-#[derive(Clone, Debug, Default)]
-pub struct FighterAction {
-    #[pyo3(get, set)]
-    pub forward: bool,
-    #[pyo3(get, set)]
-    pub backward: bool,
-    #[pyo3(get, set)]
-    pub left: bool,
-    #[pyo3(get, set)]
-    pub right: bool,
-    #[pyo3(get, set)]
-    pub jump: bool,
-    #[pyo3(get, set)]
-    pub sprint: bool,
-    #[pyo3(get, set)]
-    pub attack: bool,
-    #[pyo3(get, set)]
-    pub eat: bool,
-    #[pyo3(get, set)]
-    pub delta_yaw: f64,   // degrees
-    #[pyo3(get, set)]
-    pub delta_pitch: f64, // degrees
+#[derive(Clone, Debug)]
+pub struct StepResult {
+    #[pyo3(get)]
+    pub reward1: f64,
+    #[pyo3(get)]
+    pub reward2: f64,
+    /// a fighter died or fell in the void, there's no next state to
+    /// bootstrap from
+    #[pyo3(get)]
+    pub terminated: bool,
+    /// max_ticks ran out with both still up, the episode was cut short
+    #[pyo3(get)]
+    pub truncated: bool,
+    /// 0=none, 1=fighter1, 2=fighter2, -1=draw
+    #[pyo3(get)]
+    pub winner: i32,
+    /// "kill", "void", "timeout" or "draw", None before the end
+    #[pyo3(get)]
+    pub winner_reason: Option<&'static str>,
+    #[pyo3(get)]
+    pub tick: u32,
+    /// (name, fighter, amount) for everything that happened this tick: hit,
+    /// whiff, damage_dealt, damage_taken, healed, steak_eaten,
+    /// eating_interrupted and sweep
+    #[pyo3(get)]
+    pub events: Vec<(&'static str, u8, f64)>,
 }
 
 #[pymethods]
-impl FighterAction {
-    #[new]
-    fn new() -> Self {
-        Self::default()
+impl StepResult {
+    /// terminated or truncated
+    #[getter]
+    fn done(&self) -> bool {
+        self.terminated || self.truncated
+    }
+
+    fn __repr__(&self) -> String {
+        let bool_str = |b: bool| if b { "True" } else { "False" };
+        format!(
+            "StepResult(reward1={}, reward2={}, terminated={}, truncated={}, winner={}, tick={}, events={})",
+            self.reward1,
+            self.reward2,
+            bool_str(self.terminated),
+            bool_str(self.truncated),
+            self.winner,
+            self.tick,
+            self.events.len()
+        )
     }
 }
 
 /// Ultra-fast headless PvP arena
 #[pyclass]
+#[derive(Clone)]
 pub struct FastArena {
     pub fighter1: Fighter,
     pub fighter2: Fighter,
@@ -172,6 +2023,11 @@ pub struct FastArena {
     pub done: bool,
     #[pyo3(get)]
     pub winner: i32, // 0=none, 1=fighter1, 2=fighter2, -1=draw
+    /// how the episode ended: "kill" (0 health), "void", "timeout" (decided
+    /// on health) or "draw" (winner -1, a tie at the timeout or both out at
+    /// once), None before then
+    #[pyo3(get)]
+    pub winner_reason: Option<&'static str>,
 
     // Arena bounds
     pub min_x: f64,
@@ -179,13 +2035,116 @@ pub struct FastArena {
     pub min_z: f64,
     pub max_z: f64,
     pub floor_y: f64,
+    /// side of a square platform in the middle with the void around it, None
+    /// for a floor everywhere
+    #[pyo3(get)]
+    pub platform_size: Option<f64>,
+    /// blocks standing on the floor, see set_block()
+    pub terrain: Terrain,
 
     // Config
+    pub mode: ArenaMode,
     pub max_ticks: u32,
+    pub constants: ArenaConstants,
+    /// fighter 1's and fighter 2's
+    pub kits: [Kit; 2],
+    /// used by reset() without a distance
+    #[pyo3(get, set)]
+    pub spawn_distance: f64,
+    /// in flight, oldest first
+    #[pyo3(get)]
+    pub arrows: Vec<Arrow>,
+    #[pyo3(get)]
+    pub pearls: Vec<Pearl>,
+    #[pyo3(get)]
+    pub potions: Vec<Potion>,
+    /// at most one per fighter
+    #[pyo3(get)]
+    pub hooks: Vec<Hook>,
+    /// plays fighter 2 in step_vs_bot()
+    pub opponent: Option<Opponent>,
+    /// the weights of step()'s rewards
+    #[pyo3(get, set)]
+    pub rewards: RewardConfig,
+    /// see set_reward_fn()
+    pub reward_fn: Option<RewardFn>,
+    /// what the last step did
+    pub events: StepEvents,
+    /// and the terms of its rewards
+    pub breakdown: [RewardBreakdown; 2],
+    /// whether its episodes count in metrics(), off for pyzalea.bench()'s
+    pub metered: bool,
 }
 
 impl FastArena {
-    fn apply_movement(&self, fighter: &mut Fighter, action: &FighterAction) {
+    /// `block` everywhere from `a` to `b`, both corners included. nothing
+    /// changes if any of it is outside the grid
+    fn put_blocks(&mut self, a: (i32, i32, i32), b: (i32, i32, i32), block: Block) -> PyResult<()> {
+        let lo = (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2));
+        let hi = (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2));
+        // the grid is a box, so it holds the fill if it holds both corners
+        if let Some((x, y, z)) = [lo, hi].into_iter().find(|&(x, y, z)| self.terrain.get(x, y, z).is_none()) {
+            let (min_x, min_z, width, depth) = self.terrain.extent();
+            return Err(PyValueError::new_err(format!(
+                "block ({}, {}, {}) is outside the arena, x goes {}..{}, y 0..{} and z {}..{}",
+                x, y, z, min_x, min_x + width as i32, TERRAIN_HEIGHT, min_z, min_z + depth as i32
+            )));
+        }
+        for x in lo.0..=hi.0 {
+            for y in lo.1..=hi.1 {
+                for z in lo.2..=hi.2 {
+                    self.terrain.set(x, y, z, block);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn floor(&self) -> Floor<'_> {
+        Floor::new(self.floor_y, self.platform_size, &self.terrain)
+    }
+
+    /// whether each fighter is at 0 health or in the void, and so lost
+    fn out(&self) -> [bool; 2] {
+        [&self.fighter1, &self.fighter2].map(|fighter| fighter.health <= 0.0 || self.in_void(fighter))
+    }
+
+    /// far enough below a platform to have lost
+    pub(crate) fn in_void(&self, fighter: &Fighter) -> bool {
+        self.floor().void(fighter.y)
+    }
+
+    /// a fresh fighter with this arena's constants and `kit`
+    pub(crate) fn spawned(&self, kit: &Kit) -> Fighter {
+        if self.mode == ArenaMode::Sumo {
+            // empty handed, only the kit's effects
+            return Fighter {
+                y: self.floor_y,
+                health: self.constants.max_health,
+                food: self.constants.max_food,
+                steaks: 0,
+                effects: effect_slots(&kit.effects),
+                cooldown_ticks: kit.empty_handed().cooldown_ticks(&self.constants),
+                ..Fighter::default()
+            };
+        }
+        Fighter {
+            y: self.floor_y,
+            health: self.constants.max_health,
+            food: self.constants.max_food,
+            steaks: kit.steaks,
+            arrows: kit.arrows,
+            shield: kit.shield && self.constants.combat_version == CombatVersion::Modern,
+            pearls: kit.pearls,
+            potions: if kit.potion.is_some() { kit.potions } else { 0 },
+            rod: kit.rod,
+            effects: effect_slots(&kit.effects),
+            cooldown_ticks: kit.cooldown_ticks(&self.constants),
+            ..Fighter::default()
+        }
+    }
+
+    pub(crate) fn apply_movement(&self, fighter: &mut Fighter, action: &FighterAction) {
         fighter.yaw += action.delta_yaw;
         fighter.pitch = (fighter.pitch + action.delta_pitch).clamp(-90.0, 90.0);
 
@@ -225,8 +2184,18 @@ impl FastArena {
         }
 
         // Apply speed
-        fighter.sprinting = action.sprint && action.forward && fighter.food > 6.0;
-        let speed = if fighter.sprinting { SPRINT_SPEED } else { WALK_SPEED };
+        let using = fighter.draw_ticks > 0 || fighter.block_ticks > 0;
+        fighter.sprinting = action.sprint && action.forward && fighter.food > 6.0 && !using;
+        let c = &self.constants;
+        let in_fluid = fighter.in_water || fighter.in_lava;
+        let speed = if fighter.sprinting {
+            c.sprint_speed
+        } else if using {
+            c.walk_speed * USE_SPEED_MULTIPLIER
+        } else {
+            c.walk_speed
+        } * fighter.speed_multiplier()
+            * if in_fluid { FLUID_SPEED_MULTIPLIER } else { 1.0 };
 
         if !fighter.eating {
             fighter.vx += move_x * speed;
@@ -235,31 +2204,76 @@ impl FastArena {
 
         // Jump
         // Jump - requires on_ground, not eating, and cooldown ready
-        if action.jump && fighter.on_ground && !fighter.eating && fighter.jump_cooldown == 0 {
-            fighter.vy = JUMP_VELOCITY;
+        if action.jump && in_fluid {
+            // swimming up, no cooldown
+            fighter.vy += SWIM_UP;
+            fighter.on_ground = false;
+        } else if action.jump && fighter.on_ground && !fighter.eating && fighter.jump_cooldown == 0 {
+            fighter.vy = c.jump_velocity;
             fighter.on_ground = false;
             fighter.jump_cooldown = 10;  // ~0.5 sec cooldown after landing
         }
 
-        // Apply gravity
-        if !fighter.on_ground {
-            fighter.vy -= GRAVITY;
+        // Apply drag, fluids slow the fall too
+        let fluid_drag = if fighter.in_lava { LAVA_DRAG } else { WATER_DRAG };
+        if in_fluid {
+            fighter.vx *= fluid_drag;
+            fighter.vy *= fluid_drag;
+            fighter.vz *= fluid_drag;
+        } else {
+            fighter.vx *= c.drag;
+            fighter.vz *= c.drag;
         }
 
-        // Apply drag
-        fighter.vx *= DRAG;
-        fighter.vz *= DRAG;
-
-        // Update position
-        fighter.x += fighter.vx;
-        fighter.y += fighter.vy;
-        fighter.z += fighter.vz;
+        // Update position, an axis at a time so a block stops only that one
+        let y_before = fighter.y;
+        for axis in [1, 0, 2] {
+            let (lo, hi) = fighter.bounds();
+            let (position, velocity) = match axis {
+                0 => (&mut fighter.x, &mut fighter.vx),
+                1 => (&mut fighter.y, &mut fighter.vy),
+                _ => (&mut fighter.z, &mut fighter.vz),
+            };
+            let delta = self.terrain.clip(lo, hi, axis, *velocity);
+            *position += delta;
+            if delta != *velocity {
+                *velocity = 0.0;
+            }
+        }
+        fighter.fall_distance += (y_before - fighter.y).max(0.0);
+        let (lo, hi) = fighter.bounds();
+        let on_block = self.terrain.supports(lo, hi);
+        let fluids = self.terrain.fluids_in(lo, hi);
+        (fighter.in_water, fighter.in_lava) = (fluids.water, fluids.lava);
+        if fighter.in_water {
+            // water breaks any fall
+            fighter.fall_distance = 0.0;
+        }
 
-        // Floor collision
-        if fighter.y <= self.floor_y {
-            fighter.y = self.floor_y;
+        // Floor collision, off a platform there's only the void
+        let floor = self.floor();
+        let on_floor = fighter.y <= floor.y && y_before >= floor.y && floor.under(fighter.x, fighter.z, HALF_WIDTH);
+        if on_floor || on_block {
+            if on_floor {
+                // the fall stops at the floor, not past it
+                fighter.fall_distance -= floor.y - fighter.y;
+                fighter.y = floor.y;
+            }
             fighter.vy = 0.0;
             fighter.on_ground = true;
+            // LivingEntity.causeFallDamage, armor doesn't help
+            let damage = (fighter.fall_distance - SAFE_FALL_DISTANCE).ceil().max(0.0);
+            fighter.health -= damage;
+            fighter.damage_taken += damage;
+            fighter.fall_distance = 0.0;
+        } else {
+            fighter.on_ground = false;
+        }
+
+        // Apply gravity, after the move like LivingEntity.travel so a jump
+        // clears a block
+        if !fighter.on_ground {
+            fighter.vy -= c.gravity * if in_fluid { FLUID_GRAVITY_MULTIPLIER } else { 1.0 };
         }
 
         // Arena bounds
@@ -268,14 +2282,38 @@ impl FastArena {
     }
 
     fn try_attack(&mut self, attacker_idx: usize) -> bool {
+        let kits = (&self.kits[attacker_idx], &self.kits[1 - attacker_idx]);
         let (attacker, defender) = if attacker_idx == 0 {
             (&mut self.fighter1, &mut self.fighter2)
         } else {
             (&mut self.fighter2, &mut self.fighter1)
         };
+        Self::melee(&self.constants, &self.terrain, self.mode, kits, attacker, defender)
+    }
 
-        // Check cooldown
-        if attacker.attack_cooldown > 0 || attacker.eating {
+    /// `attacker` swinging at `defender`, with their kits, or empty handed in
+    /// sumo. true for a hit, or for knocking a shield down
+    pub(crate) fn melee(
+        c: &ArenaConstants,
+        terrain: &Terrain,
+        mode: ArenaMode,
+        (kit, defender_kit): (&Kit, &Kit),
+        attacker: &mut Fighter,
+        defender: &mut Fighter,
+    ) -> bool {
+        let hands;
+        let (kit, defender_kit) = if mode == ArenaMode::Sumo {
+            hands = (kit.empty_handed(), defender_kit.empty_handed());
+            (&hands.0, &hands.1)
+        } else {
+            (kit, defender_kit)
+        };
+        // Check cooldown, and the hands aren't busy eating, drawing or blocking
+        if attacker.attack_cooldown > 0 || attacker.eating || attacker.draw_ticks > 0 || attacker.block_ticks > 0 {
+            return false;
+        }
+        // Still invulnerable from the last one, only in 1.8
+        if defender.hurt_ticks > 0 {
             return false;
         }
 
@@ -285,7 +2323,11 @@ impl FastArena {
         let dz = defender.z - attacker.z;
         let dist = (dx*dx + dy*dy + dz*dz).sqrt();
 
-        if dist > ATTACK_RANGE {
+        if dist > c.attack_range {
+            return false;
+        }
+        // Through a wall, eye to eye
+        if terrain.raycast((attacker.x, attacker.y + EYE_HEIGHT, attacker.z), (dx, dy, dz)).is_some() {
             return false;
         }
 
@@ -298,16 +2340,48 @@ impl FastArena {
             return false;
         }
 
+        // Into a raised shield, an axe knocks it down for a while
+        if defender.blocks_from(attacker.x, attacker.z) {
+            attacker.attack_cooldown = kit.cooldown_ticks(c);
+            attacker.sprinting = false;
+            if !kit.disables_shields {
+                Self::blocked_knockback(defender, (attacker.x - defender.x, attacker.z - defender.z), c);
+                return false;
+            }
+            defender.shield_disabled = SHIELD_DISABLE_TICKS;
+            defender.block_ticks = 0;
+            defender.blocking = false;
+            return true;
+        }
+
         // Hit! Calculate damage
-        let mut damage = BASE_DAMAGE_IRON_SWORD;
+        let mut damage = (kit.weapon_damage + attacker.damage_bonus()).max(0.0);
+
+        // Player.attack sweeps with a sword on the ground, if it's neither a
+        // crit nor a sprint hit. the sweep reaches everyone else within a
+        // block of the defender, in a duel nobody else is there. sumo has no
+        // swords
+        let sweep = kit.sword
+            && mode != ArenaMode::Sumo
+            && attacker.on_ground
+            && !attacker.sprinting
+            && c.combat_version == CombatVersion::Modern;
+        if sweep {
+            attacker.sweeps += 1;
+            attacker.swept = true;
+        }
 
         // Sprint crit
         if attacker.sprinting && !attacker.on_ground {
-            damage *= SPRINT_CRIT_MULTIPLIER;
+            damage *= c.sprint_crit_multiplier;
+        }
+        damage += kit.sharpness_damage(c.combat_version);
+        if mode == ArenaMode::Sumo {
+            damage = 0.0;
         }
 
         // Armor reduction
-        damage *= 1.0 - DIAMOND_ARMOR_REDUCTION;
+        damage = defender_kit.after_armor(damage, c.combat_version);
 
         // Apply damage
         defender.health -= damage;
@@ -316,13 +2390,35 @@ impl FastArena {
 
         attacker.damage_dealt += damage;
         attacker.hits_landed += 1;
-        attacker.attack_cooldown = ATTACK_COOLDOWN_TICKS;
+        attacker.attack_cooldown = kit.cooldown_ticks(c);
 
-        // Knockback
+        // Knockback away from the attacker, then along its look for each
+        // knockback level, sprinting counts as one
         let kb_yaw = attacker.yaw.to_radians();
-        defender.vx += -kb_yaw.sin() * KNOCKBACK_HORIZONTAL;
-        defender.vz += kb_yaw.cos() * KNOCKBACK_HORIZONTAL;
-        defender.vy += KNOCKBACK_VERTICAL;
+        let away = (attacker.x - defender.x, attacker.z - defender.z);
+        let level = kit.knockback + attacker.sprinting as u32;
+        if c.combat_version == CombatVersion::Legacy {
+            // 1.8 lifts a defender in the air too, and adds the levels on top
+            defender.knock_back(LEGACY_KNOCKBACK_HORIZONTAL, LEGACY_KNOCKBACK_VERTICAL, away, true);
+            let (horizontal, vertical) = LEGACY_SPRINT_KNOCKBACK;
+            if level > 0 {
+                defender.vx += -kb_yaw.sin() * horizontal * level as f64;
+                defender.vz += kb_yaw.cos() * horizontal * level as f64;
+                defender.vy += vertical;
+            }
+            defender.hurt_ticks = LEGACY_INVULNERABLE_TICKS;
+        } else {
+            let lift = defender.on_ground;
+            defender.knock_back(c.knockback_horizontal, c.knockback_vertical, away, lift);
+            if level > 0 {
+                let strength = c.sprint_knockback * level as f64;
+                defender.knock_back(strength, c.knockback_vertical, (kb_yaw.sin(), -kb_yaw.cos()), lift);
+            }
+        }
+        if level > 0 {
+            attacker.vx *= SPRINT_HIT_SLOWDOWN;
+            attacker.vz *= SPRINT_HIT_SLOWDOWN;
+        }
         defender.on_ground = false;
 
         // Interrupt eating
@@ -335,10 +2431,96 @@ impl FastArena {
         true
     }
 
-    fn process_eating(&mut self, fighter: &mut Fighter, wants_eat: bool) {
-        if wants_eat && !fighter.eating && fighter.steaks > 0 && fighter.food < MAX_FOOD {
+    /// sumo's rewards before the win check: landing knockback, and keeping the
+    /// middle of the platform while the other is pushed to the edge
+    fn sumo_rewards(&self, hit1: bool, hit2: bool, whiff1: bool, whiff2: bool) -> [RewardBreakdown; 2] {
+        let w = &self.rewards;
+        let control = (self.edge_distance(&self.fighter1) - self.edge_distance(&self.fighter2)) * w.edge_control;
+        let mut parts = [RewardBreakdown { edge_control: control, time: w.time, ..RewardBreakdown::default() }; 2];
+        parts[1].edge_control = -control;
+        if hit1 { parts[0].hit = w.sumo_hit; }
+        if hit2 { parts[1].hit = w.sumo_hit; }
+        if whiff1 { parts[0].whiff = w.sumo_whiff; }
+        if whiff2 { parts[1].whiff = w.sumo_whiff; }
+        parts
+    }
+
+    /// blocks from the fighter to the nearest platform edge, 0 off it or
+    /// without a platform
+    fn edge_distance(&self, fighter: &Fighter) -> f64 {
+        self.platform_size.map_or(0.0, |size| (size / 2.0 - fighter.x.abs().max(fighter.z.abs())).max(0.0))
+    }
+
+    /// what gets through of a hit from in front of a shield
+    fn blocked_knockback(defender: &mut Fighter, away: (f64, f64), c: &ArenaConstants) {
+        let lift = defender.on_ground;
+        defender.knock_back(c.knockback_horizontal * SHIELD_KNOCKBACK_MULTIPLIER, c.knockback_vertical, away, lift);
+        defender.on_ground = false;
+    }
+
+    /// count the effects down, regeneration heals as it goes
+    pub(crate) fn tick_effects(&self, fighter: &mut Fighter) {
+        let regeneration = fighter.effects[4];
+        if regeneration.level > 0 && regeneration.pulses(REGENERATION_INTERVAL) {
+            fighter.health = (fighter.health + 1.0).min(self.constants.max_health);
+        }
+        let poison = fighter.effects[5];
+        if poison.level > 0 && poison.pulses(POISON_INTERVAL) && fighter.health > 1.0 {
+            fighter.health = (fighter.health - 1.0).max(1.0);
+            fighter.damage_taken += 1.0;
+        }
+        for effect in fighter.effects.iter_mut().filter(|effect| effect.level > 0) {
+            effect.elapsed = effect.elapsed.wrapping_add(1);
+            if effect.ticks != u32::MAX {
+                effect.ticks -= 1;
+                if effect.ticks == 0 {
+                    *effect = ActiveEffect::default();
+                }
+            }
+        }
+    }
+
+    /// lava's hurts and burning, through the kit's armor. water puts the
+    /// fire out
+    pub(crate) fn tick_hazards(&self, kit: &Kit, fighter: &mut Fighter) {
+        let mut damage = 0.0;
+        if fighter.in_water {
+            fighter.fire_ticks = 0;
+        } else if fighter.in_lava {
+            fighter.fire_ticks = LAVA_FIRE_TICKS;
+            if self.tick.is_multiple_of(LAVA_INTERVAL) {
+                damage += LAVA_DAMAGE;
+            }
+        } else if fighter.fire_ticks > 0 {
+            fighter.fire_ticks -= 1;
+            if fighter.fire_ticks.is_multiple_of(FIRE_INTERVAL) {
+                damage += FIRE_DAMAGE;
+            }
+        }
+        if damage > 0.0 {
+            let damage = kit.after_armor(damage, self.constants.combat_version);
+            fighter.health -= damage;
+            fighter.damage_taken += damage;
+        }
+    }
+
+    /// raise the shield while `block` is held, it blocks after SHIELD_DELAY_TICKS
+    pub(crate) fn process_shield(fighter: &mut Fighter, block: bool) {
+        fighter.shield_disabled = fighter.shield_disabled.saturating_sub(1);
+        let free = !fighter.eating && fighter.draw_ticks == 0;
+        if block && fighter.shield && fighter.shield_disabled == 0 && free {
+            fighter.block_ticks += 1;
+        } else {
+            fighter.block_ticks = 0;
+        }
+        fighter.blocking = fighter.block_ticks >= SHIELD_DELAY_TICKS;
+    }
+
+    pub(crate) fn process_eating(&self, fighter: &mut Fighter, wants_eat: bool) {
+        let c = &self.constants;
+        if wants_eat && !fighter.eating && fighter.draw_ticks == 0 && fighter.block_ticks == 0 && fighter.steaks > 0 && fighter.food < c.max_food {
             fighter.eating = true;
-            fighter.eating_ticks = EAT_TICKS;
+            fighter.eating_ticks = c.eat_ticks;
         }
 
         if fighter.eating {
@@ -348,68 +2530,418 @@ impl FastArena {
                 // Finished eating
                 fighter.eating = false;
                 fighter.steaks -= 1;
-                fighter.food = (fighter.food + FOOD_PER_STEAK).min(MAX_FOOD);
+                fighter.food = (fighter.food + c.food_per_steak).min(c.max_food);
             }
         }
 
         // Natural regen when food is high
-        if fighter.food >= FOOD_HEAL_THRESHOLD && fighter.health < MAX_HEALTH {
-            fighter.health = (fighter.health + FOOD_HEAL_AMOUNT * 0.05).min(MAX_HEALTH);
+        if fighter.food >= c.food_heal_threshold && fighter.health < c.max_health {
+            fighter.health = (fighter.health + c.food_heal_amount * 0.05).min(c.max_health);
             fighter.food -= 0.1; // Slow food drain during regen
         }
     }
-}
 
-#[pymethods]
-impl FastArena {
-    #[new]
-    #[pyo3(signature = (arena_size=32.0, max_ticks=2400))]
-    fn new(arena_size: f64, max_ticks: u32) -> Self {
-        let half = arena_size / 2.0;
-        Self {
-            fighter1: Fighter::default(),
-            fighter2: Fighter::default(),
-            tick: 0,
-            done: false,
-            winner: 0,
-            min_x: -half,
-            max_x: half,
-            min_z: -half,
-            max_z: half,
-            floor_y: 0.0,
-            max_ticks,
+    /// draw while `draw` is held, shoot on letting go
+    fn process_bow(fighter: &mut Fighter, owner: u8, draw: bool) -> Option<Arrow> {
+        if draw && fighter.arrows > 0 && !fighter.eating && fighter.block_ticks == 0 {
+            fighter.draw_ticks += 1;
+            return None;
+        }
+        let power = bow_power(fighter.draw_ticks);
+        fighter.draw_ticks = 0;
+        if power < BOW_MIN_POWER || fighter.arrows == 0 {
+            return None;
+        }
+        fighter.arrows -= 1;
+        let (yaw, pitch) = (fighter.yaw.to_radians(), fighter.pitch.to_radians());
+        let speed = power * ARROW_SPEED;
+        Some(Arrow {
+            x: fighter.x,
+            y: fighter.y + EYE_HEIGHT,
+            z: fighter.z,
+            vx: -yaw.sin() * pitch.cos() * speed + fighter.vx,
+            vy: -pitch.sin() * speed + if fighter.on_ground { 0.0 } else { fighter.vy },
+            vz: yaw.cos() * pitch.cos() * speed + fighter.vz,
+            owner,
+            ticks: 0,
+        })
+    }
+
+    /// throw a pearl if `pearl` is pressed, there's one left and the hands are free
+    fn process_pearl(fighter: &mut Fighter, owner: u8, pearl: bool) -> Option<Pearl> {
+        fighter.pearl_cooldown = fighter.pearl_cooldown.saturating_sub(1);
+        let busy = fighter.eating || fighter.draw_ticks > 0 || fighter.block_ticks > 0;
+        if !pearl || busy || fighter.pearls == 0 || fighter.pearl_cooldown > 0 {
+            return None;
+        }
+        fighter.pearls -= 1;
+        fighter.pearl_cooldown = PEARL_COOLDOWN_TICKS;
+        let (yaw, pitch) = (fighter.yaw.to_radians(), fighter.pitch.to_radians());
+        Some(Pearl {
+            x: fighter.x,
+            y: fighter.y + EYE_HEIGHT,
+            z: fighter.z,
+            vx: -yaw.sin() * pitch.cos() * PEARL_SPEED + fighter.vx,
+            vy: -pitch.sin() * PEARL_SPEED + if fighter.on_ground { 0.0 } else { fighter.vy },
+            vz: yaw.cos() * pitch.cos() * PEARL_SPEED + fighter.vz,
+            owner,
+        })
+    }
+
+    /// move every pearl a tick. one that hits the floor, a wall or the other
+    /// fighter teleports its thrower there
+    fn fly_pearls(&mut self) {
+        let floor = Floor::new(self.floor_y, self.platform_size, &self.terrain);
+        let mut pearls = std::mem::take(&mut self.pearls);
+        pearls.retain_mut(|pearl| {
+            if floor.void(pearl.y) {
+                return false;
+            }
+            let (thrower_idx, other) = if pearl.owner == 1 { (0, &self.fighter2) } else { (1, &self.fighter1) };
+            let from = (pearl.x, pearl.y, pearl.z);
+            let reach = floor.reach(from, (pearl.vx, pearl.vy, pearl.vz));
+            let d = scaled((pearl.vx, pearl.vy, pearl.vz), reach);
+            let landing = if segment_hits(other, from, d) {
+                Some((other.x, other.y, other.z))
+            } else {
+                let (x, y, z) = (pearl.x + d.0, pearl.y + d.1, pearl.z + d.2);
+                let inside = (self.min_x..=self.max_x).contains(&x) && (self.min_z..=self.max_z).contains(&z);
+                if reach < 1.0 || floor.lands(x, y, z) || !inside {
+                    Some((x.clamp(self.min_x, self.max_x), if floor.lands(x, y, z) { floor.y } else { y }, z.clamp(self.min_z, self.max_z)))
+                } else {
+                    (pearl.x, pearl.y, pearl.z) = (x, y, z);
+                    pearl.vx *= PEARL_DRAG;
+                    pearl.vy = pearl.vy * PEARL_DRAG - PEARL_GRAVITY;
+                    pearl.vz *= PEARL_DRAG;
+                    None
+                }
+            };
+            let Some((x, y, z)) = landing else {
+                return true;
+            };
+            let thrower = if thrower_idx == 0 { &mut self.fighter1 } else { &mut self.fighter2 };
+            (thrower.x, thrower.y, thrower.z) = (x, y, z);
+            (thrower.vx, thrower.vy, thrower.vz) = (0.0, 0.0, 0.0);
+            thrower.on_ground = floor.lands(x, y, z);
+            thrower.fall_distance = 0.0;
+            thrower.health -= PEARL_DAMAGE;
+            thrower.damage_taken += PEARL_DAMAGE;
+            false
+        });
+        self.pearls = pearls;
+    }
+
+    /// throw the kit's splash potion if `potion` is pressed and the hands are free
+    fn process_potion(&self, fighter: &mut Fighter, owner: u8, potion: bool) -> Option<Potion> {
+        fighter.potion_cooldown = fighter.potion_cooldown.saturating_sub(1);
+        let busy = fighter.eating || fighter.draw_ticks > 0 || fighter.block_ticks > 0;
+        if !potion || busy || fighter.potions == 0 || fighter.potion_cooldown > 0 {
+            return None;
+        }
+        let effect = self.kits[owner as usize - 1].potion.clone()?;
+        fighter.potions -= 1;
+        fighter.potion_cooldown = POTION_COOLDOWN_TICKS;
+        let (yaw, pitch) = (fighter.yaw.to_radians(), (fighter.pitch + POTION_PITCH_OFFSET).to_radians());
+        Some(Potion {
+            x: fighter.x,
+            y: fighter.y + EYE_HEIGHT,
+            z: fighter.z,
+            vx: -yaw.sin() * pitch.cos() * POTION_SPEED + fighter.vx,
+            vy: -pitch.sin() * POTION_SPEED + if fighter.on_ground { 0.0 } else { fighter.vy },
+            vz: yaw.cos() * pitch.cos() * POTION_SPEED + fighter.vz,
+            owner,
+            effect,
+        })
+    }
+
+    /// move every potion a tick, splashing where it hits the floor, a wall or
+    /// the other fighter
+    fn fly_potions(&mut self) {
+        // out of self while the splashes need all of it
+        let terrain = std::mem::take(&mut self.terrain);
+        let floor = Floor::new(self.floor_y, self.platform_size, &terrain);
+        let mut potions = std::mem::take(&mut self.potions);
+        potions.retain_mut(|potion| {
+            if floor.void(potion.y) {
+                return false;
+            }
+            let (other_idx, other) = if potion.owner == 1 { (1, &self.fighter2) } else { (0, &self.fighter1) };
+            let from = (potion.x, potion.y, potion.z);
+            let reach = floor.reach(from, (potion.vx, potion.vy, potion.vz));
+            let d = scaled((potion.vx, potion.vy, potion.vz), reach);
+            let (x, y, z) = (potion.x + d.0, potion.y + d.1, potion.z + d.2);
+            let inside = (self.min_x..=self.max_x).contains(&x) && (self.min_z..=self.max_z).contains(&z);
+            let impact = if segment_hits(other, from, d) {
+                Some(((other.x, other.y + HEIGHT / 2.0, other.z), Some(other_idx)))
+            } else if reach < 1.0 || floor.lands(x, y, z) || !inside {
+                let y = if floor.lands(x, y, z) { floor.y } else { y };
+                Some(((x.clamp(self.min_x, self.max_x), y, z.clamp(self.min_z, self.max_z)), None))
+            } else {
+                None
+            };
+            let Some((at, direct)) = impact else {
+                (potion.x, potion.y, potion.z) = (x, y, z);
+                potion.vx *= POTION_DRAG;
+                potion.vy = potion.vy * POTION_DRAG - POTION_GRAVITY;
+                potion.vz *= POTION_DRAG;
+                return true;
+            };
+            for idx in 0..2 {
+                self.splash(idx, potion.owner, &potion.effect, at, direct == Some(idx));
+            }
+            false
+        });
+        self.potions = potions;
+        self.terrain = terrain;
+    }
+
+    /// ThrownPotion.applySplash on one fighter: full strength on a direct
+    /// hit, fading out to nothing SPLASH_RADIUS away. harming the other
+    /// fighter counts as damage `owner` dealt
+    fn splash(&mut self, idx: usize, owner: u8, effect: &Effect, (x, y, z): (f64, f64, f64), direct: bool) {
+        let max_health = self.constants.max_health;
+        let (fighter, other) = if idx == 0 {
+            (&mut self.fighter1, &mut self.fighter2)
+        } else {
+            (&mut self.fighter2, &mut self.fighter1)
+        };
+        let (dx, dy, dz) = (fighter.x - x, fighter.y + EYE_HEIGHT - y, fighter.z - z);
+        let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+        if !direct && (distance >= SPLASH_RADIUS || dy.abs() > SPLASH_HEIGHT + HEIGHT) {
+            return;
+        }
+        let intensity = if direct { 1.0 } else { 1.0 - distance / SPLASH_RADIUS };
+        let doubling = 2f64.powi(effect.amplifier.min(31) as i32);
+        match splash_kind(&effect.name) {
+            Some(Splash::Harming) => {
+                let damage = (intensity * INSTANT_AMOUNT * doubling + 0.5).floor();
+                fighter.health -= damage;
+                fighter.damage_taken += damage;
+                fighter.eating = false;
+                fighter.eating_ticks = 0;
+                if owner as usize != idx + 1 {
+                    other.damage_dealt += damage;
+                }
+            }
+            Some(Splash::Healing) => {
+                let heal = (intensity * INSTANT_AMOUNT * 2.0 / 3.0 * doubling + 0.5).floor();
+                fighter.health = (fighter.health + heal).min(max_health);
+            }
+            Some(Splash::Effect(i)) => {
+                let ticks = match u32::try_from(effect.duration) {
+                    Ok(ticks) => (intensity * ticks as f64 + 0.5) as u32,
+                    Err(_) => u32::MAX,
+                };
+                let current = fighter.effects[i];
+                let level = effect.amplifier + 1;
+                // a stronger or longer effect isn't replaced
+                let stronger = current.level > level || (current.level == level && current.ticks >= ticks);
+                if ticks > SPLASH_MIN_TICKS && !stronger {
+                    fighter.effects[i] = ActiveEffect { level, ticks, elapsed: 0 };
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// cast or reel in fighter `idx`'s rod if `rod` is pressed and the hands
+    /// are free. reeling in a hooked fighter pulls them towards the caster
+    fn process_rod(&mut self, idx: usize, rod: bool) {
+        let owner = idx as u8 + 1;
+        let (fighter, other) = if idx == 0 {
+            (&mut self.fighter1, &mut self.fighter2)
+        } else {
+            (&mut self.fighter2, &mut self.fighter1)
+        };
+        fighter.rod_cooldown = fighter.rod_cooldown.saturating_sub(1);
+        let busy = fighter.eating || fighter.draw_ticks > 0 || fighter.block_ticks > 0;
+        if !rod || busy || !fighter.rod || fighter.rod_cooldown > 0 {
+            return;
         }
+        fighter.rod_cooldown = ROD_COOLDOWN_TICKS;
+        if let Some(i) = self.hooks.iter().position(|hook| hook.owner == owner) {
+            // FishingHook.pullEntity
+            if self.hooks.swap_remove(i).hooked {
+                other.vx += (fighter.x - other.x) * HOOK_PULL;
+                other.vy += (fighter.y - other.y) * HOOK_PULL;
+                other.vz += (fighter.z - other.z) * HOOK_PULL;
+                other.on_ground = false;
+            }
+            return;
+        }
+        // FishingHook's constructor, without the random spread
+        let (yaw, pitch) = (fighter.yaw.to_radians(), fighter.pitch.to_radians());
+        let (dx, dy, dz) = (-yaw.sin(), (-pitch.tan()).clamp(-5.0, 5.0), yaw.cos());
+        let length = (dx * dx + dy * dy + dz * dz).sqrt();
+        let speed = HOOK_BASE_SPEED / length + 0.5;
+        self.hooks.push(Hook {
+            x: fighter.x,
+            y: fighter.y + EYE_HEIGHT,
+            z: fighter.z,
+            vx: dx * speed,
+            vy: dy * speed,
+            vz: dz * speed,
+            owner,
+            hooked: false,
+            in_ground: false,
+        });
+    }
+
+    /// move every hook a tick. one that catches the other fighter knocks them
+    /// back like a hit without damage (1.8's rod) and then follows them
+    fn fly_hooks(&mut self) {
+        let c = &self.constants;
+        let floor = Floor::new(self.floor_y, self.platform_size, &self.terrain);
+        let mut hooks = std::mem::take(&mut self.hooks);
+        hooks.retain_mut(|hook| {
+            if floor.void(hook.y) {
+                return false;
+            }
+            let (caster, other) = if hook.owner == 1 {
+                (&self.fighter1, &mut self.fighter2)
+            } else {
+                (&self.fighter2, &mut self.fighter1)
+            };
+            let (dx, dy, dz) = (hook.x - caster.x, hook.y - caster.y, hook.z - caster.z);
+            if (dx * dx + dy * dy + dz * dz).sqrt() > HOOK_MAX_DISTANCE {
+                return false;
+            }
+            if hook.hooked {
+                (hook.x, hook.y, hook.z) = (other.x, other.y + HEIGHT * 0.8, other.z);
+                return true;
+            }
+            if hook.in_ground {
+                return true;
+            }
+            let from = (hook.x, hook.y, hook.z);
+            let reach = floor.reach(from, (hook.vx, hook.vy, hook.vz));
+            let d = scaled((hook.vx, hook.vy, hook.vz), reach);
+            if segment_hits(other, from, d) {
+                hook.hooked = true;
+                (hook.x, hook.y, hook.z) = (other.x, other.y + HEIGHT * 0.8, other.z);
+                if !other.blocks_from(caster.x, caster.z) {
+                    let lift = other.on_ground || c.combat_version == CombatVersion::Legacy;
+                    other.knock_back(c.knockback_horizontal, c.knockback_vertical, (caster.x - other.x, caster.z - other.z), lift);
+                    other.on_ground = false;
+                }
+                return true;
+            }
+            let (x, y, z) = (hook.x + d.0, hook.y + d.1, hook.z + d.2);
+            let inside = (self.min_x..=self.max_x).contains(&x) && (self.min_z..=self.max_z).contains(&z);
+            let lands = floor.lands(x, y, z);
+            (hook.x, hook.y, hook.z) = (x.clamp(self.min_x, self.max_x), if lands { floor.y } else { y }, z.clamp(self.min_z, self.max_z));
+            if lands || reach < 1.0 || !inside {
+                hook.in_ground = true;
+                (hook.vx, hook.vy, hook.vz) = (0.0, 0.0, 0.0);
+            } else {
+                hook.vx *= HOOK_DRAG;
+                hook.vy = hook.vy * HOOK_DRAG - HOOK_GRAVITY;
+                hook.vz *= HOOK_DRAG;
+            }
+            true
+        });
+        self.hooks = hooks;
+    }
+
+    /// move every arrow a tick, hitting whoever it passes through on the way
+    fn fly_arrows(&mut self) {
+        // out of self while the hits need all of it
+        let terrain = std::mem::take(&mut self.terrain);
+        let floor = Floor::new(self.floor_y, self.platform_size, &terrain);
+        let mut arrows = std::mem::take(&mut self.arrows);
+        arrows.retain_mut(|arrow| {
+            let target_idx = if arrow.owner == 1 { 1 } else { 0 };
+            let from = (arrow.x, arrow.y, arrow.z);
+            let reach = floor.reach(from, (arrow.vx, arrow.vy, arrow.vz));
+            let delta = scaled((arrow.vx, arrow.vy, arrow.vz), reach);
+            let target = if target_idx == 0 { &self.fighter1 } else { &self.fighter2 };
+            if segment_hits(target, from, delta) {
+                // a shield stops it outright
+                if !target.blocks_from(arrow.x, arrow.z) {
+                    let damage = (arrow.speed() * ARROW_DAMAGE).ceil();
+                    self.arrow_hit(target_idx, damage);
+                }
+                return false;
+            }
+            if reach < 1.0 {
+                return false;
+            }
+            arrow.x += arrow.vx;
+            arrow.y += arrow.vy;
+            arrow.z += arrow.vz;
+            arrow.vx *= ARROW_DRAG;
+            arrow.vy = arrow.vy * ARROW_DRAG - ARROW_GRAVITY;
+            arrow.vz *= ARROW_DRAG;
+            arrow.ticks += 1;
+            let inside = (self.min_x..=self.max_x).contains(&arrow.x) && (self.min_z..=self.max_z).contains(&arrow.z);
+            !floor.lands(arrow.x, arrow.y, arrow.z) && !floor.void(arrow.y) && inside && arrow.ticks < ARROW_LIFETIME
+        });
+        self.arrows = arrows;
+        self.terrain = terrain;
     }
 
-    /// Reset arena for new episode
-    fn reset(&mut self, spawn_distance: f64) {
-        // MC yaw: 0=+Z, 90=-X, -90=+X, 180=-Z
-        self.fighter1 = Fighter {
-            x: -spawn_distance / 2.0,
-            z: 0.0,
-            yaw: -90.0, // Facing +X (east, toward fighter2)
-            ..Fighter::default()
+    fn arrow_hit(&mut self, defender_idx: usize, damage: f64) {
+        let damage = self.kits[defender_idx].after_armor(damage, self.constants.combat_version);
+        let (attacker, defender) = if defender_idx == 0 {
+            (&mut self.fighter2, &mut self.fighter1)
+        } else {
+            (&mut self.fighter1, &mut self.fighter2)
         };
-        self.fighter2 = Fighter {
-            x: spawn_distance / 2.0,
-            z: 0.0,
-            yaw: 90.0, // Facing -X (west, toward fighter1)
-            ..Fighter::default()
+        defender.health -= damage;
+        defender.damage_taken += damage;
+        defender.hits_taken += 1;
+        attacker.damage_dealt += damage;
+        attacker.hits_landed += 1;
+        defender.eating = false;
+        defender.eating_ticks = 0;
+    }
+
+    /// the nearest arrow `me` could be hit by, relative to them
+    fn incoming_arrow(&self, me: &Fighter, owner: u8) -> Option<(f64, f64, f64)> {
+        self.arrows
+            .iter()
+            .filter(|arrow| arrow.owner == owner)
+            .map(|arrow| (arrow.x - me.x, arrow.y - me.y, arrow.z - me.z))
+            .min_by(|a, b| (a.0 * a.0 + a.1 * a.1 + a.2 * a.2).total_cmp(&(b.0 * b.0 + b.1 * b.1 + b.2 * b.2)))
+    }
+
+    /// step() from Rust, with FighterActions
+    pub fn step_actions(&mut self, py: Python<'_>, action1: &FighterAction, action2: &FighterAction) -> PyResult<(f64, f64, bool)> {
+        let rewards = self.simulate(action1, action2);
+        let Some(mut reward_fn) = self.reward_fn.take() else {
+            return Ok(rewards);
         };
-        self.tick = 0;
-        self.done = false;
-        self.winner = 0;
+        let end = (self.tick, self.done, self.winner, self.winner_reason);
+        let shaped = reward_fn.apply(py, &self.events, (rewards.0, rewards.1), end, || (self.get_obs1(), self.get_obs2()));
+        let add = reward_fn.add;
+        self.reward_fn = Some(reward_fn);
+        let (fn1, fn2) = shaped?;
+        for (parts, value) in self.breakdown.iter_mut().zip([fn1, fn2]) {
+            if !add {
+                *parts = RewardBreakdown::default();
+            }
+            parts.reward_fn = value;
+        }
+        Ok((self.breakdown[0].total(), self.breakdown[1].total(), self.done))
     }
 
-    /// Step the simulation by one tick
-    /// Returns: (reward1, reward2, done)
-    fn step(&mut self, action1: &FighterAction, action2: &FighterAction) -> (f64, f64, bool) {
+    /// step() without the Python reward function, so it runs without the
+    /// GIL: the shared-memory loop, the benchmarks and League.play()
+    pub fn simulate(&mut self, action1: &FighterAction, action2: &FighterAction) -> (f64, f64, bool) {
         if self.done {
+            self.events = StepEvents::default();
+            self.breakdown = [RewardBreakdown::default(); 2];
             return (0.0, 0.0, true);
         }
 
         let health1_before = self.fighter1.health;
         let health2_before = self.fighter2.health;
+        let dealt1_before = self.fighter1.damage_dealt;
+        let dealt2_before = self.fighter2.damage_dealt;
+        let taken1_before = self.fighter1.damage_taken;
+        let taken2_before = self.fighter2.damage_taken;
+        self.fighter1.swept = false;
+        self.fighter2.swept = false;
 
         // Track eating state before step
         let was_eating1 = self.fighter1.eating;
@@ -422,12 +2954,20 @@ impl FastArena {
         self.fighter2.attack_cooldown = self.fighter2.attack_cooldown.saturating_sub(1);
         self.fighter1.jump_cooldown = self.fighter1.jump_cooldown.saturating_sub(1);
         self.fighter2.jump_cooldown = self.fighter2.jump_cooldown.saturating_sub(1);
+        self.fighter1.hurt_ticks = self.fighter1.hurt_ticks.saturating_sub(1);
+        self.fighter2.hurt_ticks = self.fighter2.hurt_ticks.saturating_sub(1);
 
         // Movement (clone fighters for borrow checker)
         let mut f1 = self.fighter1.clone();
         let mut f2 = self.fighter2.clone();
+        self.tick_effects(&mut f1);
+        self.tick_effects(&mut f2);
         self.apply_movement(&mut f1, action1);
         self.apply_movement(&mut f2, action2);
+        self.tick_hazards(&self.kits[0], &mut f1);
+        self.tick_hazards(&self.kits[1], &mut f2);
+        Self::process_shield(&mut f1, action1.block);
+        Self::process_shield(&mut f2, action2.block);
         self.fighter1 = f1;
         self.fighter2 = f2;
 
@@ -444,9 +2984,26 @@ impl FastArena {
         let mut f2 = self.fighter2.clone();
         self.process_eating(&mut f1, action1.eat);
         self.process_eating(&mut f2, action2.eat);
+        let shot1 = Self::process_bow(&mut f1, 1, action1.draw);
+        let shot2 = Self::process_bow(&mut f2, 2, action2.draw);
+        let thrown1 = Self::process_pearl(&mut f1, 1, action1.pearl);
+        let thrown2 = Self::process_pearl(&mut f2, 2, action2.pearl);
+        let splashed1 = self.process_potion(&mut f1, 1, action1.potion);
+        let splashed2 = self.process_potion(&mut f2, 2, action2.potion);
         self.fighter1 = f1;
         self.fighter2 = f2;
 
+        // Projectiles, a new one flies from this tick on
+        self.fly_arrows();
+        self.arrows.extend(shot1.into_iter().chain(shot2));
+        self.fly_pearls();
+        self.pearls.extend(thrown1.into_iter().chain(thrown2));
+        self.fly_potions();
+        self.potions.extend(splashed1.into_iter().chain(splashed2));
+        self.fly_hooks();
+        self.process_rod(0, action1.rod);
+        self.process_rod(1, action2.rod);
+
         // Track eating completion
         let finished_eating1 = was_eating1 && !self.fighter1.eating && eating_ticks1_before == 1;
         let finished_eating2 = was_eating2 && !self.fighter2.eating && eating_ticks2_before == 1;
@@ -455,46 +3012,51 @@ impl FastArena {
 
         self.tick += 1;
 
-        // Calculate rewards
-        let damage1_dealt = health2_before - self.fighter2.health;
-        let damage2_dealt = health1_before - self.fighter1.health;
+        // Calculate rewards. only hits, arrows and harming splashes count as
+        // dealt, not a fall, the lava, poison or the other's own pearls
+        let damage1_taken = health1_before - self.fighter1.health;
+        let damage2_taken = health2_before - self.fighter2.health;
+        let damage1_dealt = self.fighter1.damage_dealt - dealt1_before;
+        let damage2_dealt = self.fighter2.damage_dealt - dealt2_before;
 
-        let mut reward1 = damage1_dealt * 0.5 - damage2_dealt * 0.3;
-        let mut reward2 = damage2_dealt * 0.5 - damage1_dealt * 0.3;
+        let w = &self.rewards;
+        let mut parts = [RewardBreakdown::default(); 2];
+        parts[0].damage = damage1_dealt * w.damage_dealt + damage1_taken * w.damage_taken;
+        parts[1].damage = damage2_dealt * w.damage_dealt + damage2_taken * w.damage_taken;
 
         // Hit bonus
-        if hit1 { reward1 += 0.2; }
-        if hit2 { reward2 += 0.2; }
+        if hit1 { parts[0].hit = w.hit; }
+        if hit2 { parts[1].hit = w.hit; }
 
         // Whiff penalty
-        if whiff1 { reward1 -= 0.05; }
-        if whiff2 { reward2 -= 0.05; }
+        if whiff1 { parts[0].whiff = w.whiff; }
+        if whiff2 { parts[1].whiff = w.whiff; }
 
         // No penalty for tactical jumps
-        if action1.jump && self.fighter1.jump_cooldown > 0 { reward1 -= 0.03; }
-        if action2.jump && self.fighter2.jump_cooldown > 0 { reward2 -= 0.03; }
+        if action1.jump && self.fighter1.jump_cooldown > 0 { parts[0].jump_spam = w.jump_spam; }
+        if action2.jump && self.fighter2.jump_cooldown > 0 { parts[1].jump_spam = w.jump_spam; }
 
         // Healing rewards
         let heal1 = (self.fighter1.health - health1_before).max(0.0);
         let heal2 = (self.fighter2.health - health2_before).max(0.0);
-        reward1 += heal1 * 0.3;  // Healing is valuable
-        reward2 += heal2 * 0.3;
+        parts[0].heal = heal1 * w.heal;  // Healing is valuable
+        parts[1].heal = heal2 * w.heal;
 
         // Reward for continuing to eat (each tick of progress)
         if self.fighter1.eating && was_eating1 {
-            reward1 += 0.02;  // Small reward for each tick of eating
+            parts[0].eat = w.eating;  // Small reward for each tick of eating
         }
         if self.fighter2.eating && was_eating2 {
-            reward2 += 0.02;
+            parts[1].eat = w.eating;
         }
 
         // Bonus for successfully finishing eating
-        if finished_eating1 { reward1 += 0.5; }
-        if finished_eating2 { reward2 += 0.5; }
+        if finished_eating1 { parts[0].eat = w.finish_eating; }
+        if finished_eating2 { parts[1].eat = w.finish_eating; }
 
         // Penalty for getting eating interrupted
-        if interrupted_eating1 { reward1 -= 0.3; }
-        if interrupted_eating2 { reward2 -= 0.3; }
+        if interrupted_eating1 { parts[0].eat = w.interrupted_eating; }
+        if interrupted_eating2 { parts[1].eat = w.interrupted_eating; }
 
         // Approach reward
         let dx = self.fighter2.x - self.fighter1.x;
@@ -502,65 +3064,282 @@ impl FastArena {
         let dist = (dx*dx + dz*dz).sqrt();
 
         // Proximity reward (peaks at attack range ~3 blocks)
-        if dist < 10.0 {
-            let closeness_reward = (10.0 - dist) * 0.01;  // Max 0.1 per tick when very close
-            reward1 += closeness_reward;
-            reward2 += closeness_reward;
+        if dist < w.proximity_range {
+            let closeness_reward = (w.proximity_range - dist) * w.proximity;  // Max 0.1 per tick when very close
+            parts[0].proximity = closeness_reward;
+            parts[1].proximity = closeness_reward;
         }
 
         // Small time penalty
-        reward1 -= 0.001;
-        reward2 -= 0.001;
+        parts[0].time = w.time;
+        parts[1].time = w.time;
+
+        self.events = StepEvents {
+            hits: [hit1 as u32, hit2 as u32],
+            whiffs: [whiff1 as u32, whiff2 as u32],
+            damage_dealt: [damage1_dealt, damage2_dealt],
+            damage_taken: [damage1_taken, damage2_taken],
+            healed: [heal1, heal2],
+            steaks_eaten: [finished_eating1 as u32, finished_eating2 as u32],
+            eating_interrupted: [interrupted_eating1 as u32, interrupted_eating2 as u32],
+            sweeps: [self.fighter1.swept as u32, self.fighter2.swept as u32],
+        };
+
+        if self.mode == ArenaMode::Sumo {
+            // nothing hurts in sumo, not even poison or a fall, so none of
+            // it counts either
+            self.fighter1.health = health1_before;
+            self.fighter2.health = health2_before;
+            (self.fighter1.damage_taken, self.fighter2.damage_taken) = (taken1_before, taken2_before);
+            (self.fighter1.damage_dealt, self.fighter2.damage_dealt) = (dealt1_before, dealt2_before);
+            self.events.damage_dealt = [0.0; 2];
+            self.events.damage_taken = [0.0; 2];
+            self.events.healed = [0.0; 2];
+            parts = self.sumo_rewards(hit1, hit2, whiff1, whiff2);
+        }
 
-        // Check win conditions
-        if self.fighter1.health <= 0.0 {
+        // Check win conditions, falling into the void loses too
+        let void1 = self.in_void(&self.fighter1);
+        let void2 = self.in_void(&self.fighter2);
+        // Speed bonus: up to +5.0 for quick kills
+        let speed_bonus = w.speed_bonus * (1.0 - self.tick as f64 / self.max_ticks as f64);
+        let [out1, out2] = self.out();
+        if out1 && out2 {
+            // both out on the same tick (the void, lava, poison, a fall), nobody won
+            self.done = true;
+            self.winner = -1;
+            self.winner_reason = Some("draw");
+            parts[0].terminal = w.draw;
+            parts[1].terminal = w.draw;
+        } else if out1 {
             self.done = true;
             self.winner = 2;
-            reward1 -= 10.0;
-            reward2 += 10.0;
-            // Speed bonus: up to +5.0 for quick kills
-            let speed_bonus = 5.0 * (1.0 - self.tick as f64 / self.max_ticks as f64);
-            reward2 += speed_bonus;
-        } else if self.fighter2.health <= 0.0 {
+            self.winner_reason = Some(if void1 { "void" } else { "kill" });
+            parts[0].terminal = w.loss;
+            parts[1].terminal = w.win + speed_bonus;
+        } else if out2 {
             self.done = true;
             self.winner = 1;
-            reward1 += 10.0;
-            reward2 -= 10.0;
-            // Speed bonus: up to +5.0 for quick kills
-            let speed_bonus = 5.0 * (1.0 - self.tick as f64 / self.max_ticks as f64);
-            reward1 += speed_bonus;
+            self.winner_reason = Some(if void2 { "void" } else { "kill" });
+            parts[0].terminal = w.win + speed_bonus;
+            parts[1].terminal = w.loss;
         } else if self.tick >= self.max_ticks {
             self.done = true;
+            self.winner_reason = Some("timeout");
             // Winner by health
             if self.fighter1.health > self.fighter2.health {
                 self.winner = 1;
-                reward1 += 2.0;
-                reward2 -= 2.0;
+                parts[0].terminal = w.timeout_win;
+                parts[1].terminal = w.timeout_loss;
             } else if self.fighter2.health > self.fighter1.health {
                 self.winner = 2;
-                reward1 -= 2.0;
-                reward2 += 2.0;
+                parts[0].terminal = w.timeout_loss;
+                parts[1].terminal = w.timeout_win;
             } else {
                 self.winner = -1; // Draw
+                self.winner_reason = Some("draw");
                 // Penalize both fighters for not finishing the fight
-                reward1 -= 3.0;
-                reward2 -= 3.0;
+                parts[0].terminal = w.draw;
+                parts[1].terminal = w.draw;
+            }
+        }
+        if self.done && self.metered {
+            metrics::episode_ended(self.tick, self.winner);
+        }
+
+        self.breakdown = parts;
+        (parts[0].total(), parts[1].total(), self.done)
+    }
+}
+
+impl Default for FastArena {
+    /// FastArena() from Python
+    fn default() -> Self {
+        Self::new(32.0, 2400, None, None).expect("the default arena size is valid")
+    }
+}
+
+#[pymethods]
+impl FastArena {
+    /// `config` for anything but the default constants, kit and floor,
+    /// `rewards` for anything but the default reward shaping
+    #[new]
+    #[pyo3(signature = (arena_size=32.0, max_ticks=2400, config=None, rewards=None))]
+    pub fn new(arena_size: f64, max_ticks: u32, config: Option<&ArenaConfig>, rewards: Option<RewardConfig>) -> PyResult<Self> {
+        check_arena_size(arena_size)?;
+        let half = arena_size / 2.0;
+        let mut arena = Self {
+            fighter1: Fighter::default(),
+            fighter2: Fighter::default(),
+            tick: 0,
+            done: false,
+            winner: 0,
+            winner_reason: None,
+            min_x: -half,
+            max_x: half,
+            min_z: -half,
+            max_z: half,
+            floor_y: 0.0,
+            platform_size: None,
+            terrain: Terrain::default(),
+            mode: ArenaMode::Fight,
+            max_ticks,
+            constants: ArenaConstants::default(),
+            kits: [Kit::default(), Kit::default()],
+            spawn_distance: SPAWN_DISTANCE,
+            arrows: vec![],
+            pearls: vec![],
+            potions: vec![],
+            hooks: vec![],
+            opponent: None,
+            rewards: rewards.unwrap_or_default(),
+            reward_fn: None,
+            events: StepEvents::default(),
+            breakdown: [RewardBreakdown::default(); 2],
+            metered: true,
+        };
+        if let Some(config) = config {
+            config.apply(&mut arena);
+        }
+        arena.terrain = Terrain::new(arena.min_x, arena.max_x, arena.min_z, arena.max_z, arena.floor_y);
+        Ok(arena)
+    }
+
+    /// an arena with the size, constants and kits of a .toml, .yaml or .json
+    /// config file, already reset
+    #[staticmethod]
+    fn from_file(py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        config::load::<ArenaFile>(py, &path)?.build()
+    }
+
+    /// Reset arena for new episode, spawn_distance defaults to the arena's.
+    /// loadouts replace the fighters' kits for this and later episodes
+    #[pyo3(signature = (spawn_distance=None, loadout1=None, loadout2=None))]
+    pub fn reset(&mut self, spawn_distance: Option<f64>, loadout1: Option<&Loadout>, loadout2: Option<&Loadout>) {
+        for (kit, loadout) in self.kits.iter_mut().zip([loadout1, loadout2]) {
+            if let Some(loadout) = loadout {
+                *kit = loadout.kit(self.constants.combat_version);
             }
         }
+        if !self.done && self.tick > 0 && self.metered {
+            metrics::episode_abandoned(self.tick);
+        }
+        let spawn_distance = spawn_distance.unwrap_or(self.spawn_distance);
+        // MC yaw: 0=+Z, 90=-X, -90=+X, 180=-Z
+        self.fighter1 = Fighter {
+            x: -spawn_distance / 2.0,
+            z: 0.0,
+            yaw: -90.0, // Facing +X (east, toward fighter2)
+            ..self.spawned(&self.kits[0])
+        };
+        self.fighter2 = Fighter {
+            x: spawn_distance / 2.0,
+            z: 0.0,
+            yaw: 90.0, // Facing -X (west, toward fighter1)
+            ..self.spawned(&self.kits[1])
+        };
+        self.arrows.clear();
+        self.pearls.clear();
+        self.potions.clear();
+        self.hooks.clear();
+        self.tick = 0;
+        self.done = false;
+        self.winner = 0;
+        self.winner_reason = None;
+        self.events = StepEvents::default();
+        self.breakdown = [RewardBreakdown::default(); 2];
+        if let Some(reward_fn) = &mut self.reward_fn {
+            reward_fn.clear();
+        }
+    }
+
+    /// Step the simulation by one tick, actions are FighterActions or flat
+    /// arrays of their values (see action_spec(), flags on above 0.5)
+    /// Returns: (reward1, reward2, done)
+    fn step(&mut self, py: Python<'_>, action1: ActionInput, action2: ActionInput) -> PyResult<(f64, f64, bool)> {
+        self.step_actions(py, &action1.0, &action2.0)
+    }
+
+    /// step() that also returns an info dict of what the rewards are made
+    /// of: damage1/2, hit1/2, whiff1/2, jump_spam1/2, heal1/2, eat1/2,
+    /// proximity1/2, edge_control1/2, time1/2, terminal1/2 and reward_fn1/2,
+    /// adding up to reward1/2, then the step's events as in set_reward_fn()
+    /// Returns: (reward1, reward2, done, info)
+    fn step_info<'py>(
+        &mut self,
+        py: Python<'py>,
+        action1: ActionInput,
+        action2: ActionInput,
+    ) -> PyResult<(f64, f64, bool, Bound<'py, PyDict>)> {
+        let (reward1, reward2, done) = self.step_actions(py, &action1.0, &action2.0)?;
+        let info = PyDict::new(py);
+        RewardBreakdown::fill(&self.breakdown, &info)?;
+        self.events.fill(&info)?;
+        Ok((reward1, reward2, done, info))
+    }
+
+    /// step() with an index into discrete_actions() for each fighter
+    /// Returns: (reward1, reward2, done)
+    pub fn step_discrete(&mut self, py: Python<'_>, action1: usize, action2: usize) -> PyResult<(f64, f64, bool)> {
+        self.step_actions(py, &discrete_action(action1)?, &discrete_action(action2)?)
+    }
+
+    /// the names of step_discrete()'s actions, by index: idle, forward,
+    /// sprint, backward, strafe_left/right, attack, sprint_attack, jump_crit,
+    /// eat, turn_left/right (15 degrees), look_up/down (10), draw, block,
+    /// pearl, potion and rod
+    #[staticmethod]
+    fn discrete_actions() -> Vec<&'static str> {
+        DISCRETE_ACTIONS.to_vec()
+    }
+
+    /// step() with why the episode ended, telling termination from
+    /// truncation at max_ticks, and the tick's events
+    pub fn step_result(&mut self, py: Python<'_>, action1: ActionInput, action2: ActionInput) -> PyResult<StepResult> {
+        let (reward1, reward2, done) = self.step_actions(py, &action1.0, &action2.0)?;
+        // a timeout, decided on health or a draw, with both still up
+        let truncated = done && self.out() == [false, false];
+        Ok(StepResult {
+            reward1,
+            reward2,
+            terminated: done && !truncated,
+            truncated,
+            winner: self.winner,
+            winner_reason: self.winner_reason,
+            tick: self.tick,
+            events: self.events.list(),
+        })
+    }
+
+    /// `reward_fn(obs1, obs2, events) -> (reward1, reward2)` after every
+    /// `every` steps and the last one of an episode, None to go back to the
+    /// built-in rewards. "add" mode adds what it returns to them, "replace"
+    /// takes their place (0 on the steps in between). `events` sums what
+    /// happened since the last call: hits1/2, whiffs1/2, damage_dealt1/2,
+    /// damage_taken1/2, healed1/2, steaks_eaten1/2, eating_interrupted1/2,
+    /// sweeps1/2, reward1/2 (the built-in ones) and steps, with the tick, done, winner
+    /// and winner_reason after it
+    #[pyo3(signature = (reward_fn, mode="add", every=1))]
+    fn set_reward_fn(&mut self, reward_fn: Option<PyObject>, mode: &str, every: u32) -> PyResult<()> {
+        self.reward_fn = reward_fn.map(|f| RewardFn::new(f, mode, every)).transpose()?;
+        Ok(())
+    }
 
-        (reward1, reward2, self.done)
+    #[getter]
+    fn reward_fn(&self, py: Python<'_>) -> Option<PyObject> {
+        self.reward_fn.as_ref().map(|f| f.callable(py))
     }
 
-    fn get_obs1(&self) -> Vec<f64> {
-        self.get_obs(&self.fighter1, &self.fighter2)
+    pub fn get_obs1(&self) -> Vec<f64> {
+        self.get_obs(&self.fighter1, &self.fighter2, 2)
     }
 
-    fn get_obs2(&self) -> Vec<f64> {
-        self.get_obs(&self.fighter2, &self.fighter1)
+    pub fn get_obs2(&self) -> Vec<f64> {
+        self.get_obs(&self.fighter2, &self.fighter1, 1)
     }
 
-    /// Get observation vector
-    fn get_obs(&self, me: &Fighter, enemy: &Fighter) -> Vec<f64> {
+    /// Get observation vector, `enemy_id` is who the enemy's arrows belong to
+    pub(crate) fn get_obs(&self, me: &Fighter, enemy: &Fighter, enemy_id: u8) -> Vec<f64> {
         let dx = enemy.x - me.x;
         let dy = enemy.y - me.y;
         let dz = enemy.z - me.z;
@@ -569,16 +3348,35 @@ impl FastArena {
         // Calculate enemy yaw relative to looking at us
         let enemy_to_me_yaw = (-(-dx)).atan2(-dz).to_degrees();
 
-        vec![
+        let incoming = self.arrows.iter().filter(|arrow| arrow.owner == enemy_id).count();
+        let arrow = self.incoming_arrow(me, enemy_id).unwrap_or((0.0, 0.0, 0.0));
+        let potion = self
+            .potions
+            .iter()
+            .filter(|potion| potion.owner == enemy_id)
+            .map(|potion| (potion.x - me.x, potion.y - me.y, potion.z - me.z))
+            .min_by(|a, b| (a.0 * a.0 + a.1 * a.1 + a.2 * a.2).total_cmp(&(b.0 * b.0 + b.1 * b.1 + b.2 * b.2)))
+            .unwrap_or((0.0, 0.0, 0.0));
+        let my_hook = self.hooks.iter().find(|hook| hook.owner != enemy_id);
+        let hook = my_hook.map_or((0.0, 0.0, 0.0), |hook| (hook.x - me.x, hook.y - me.y, hook.z - me.z));
+        let hooked = self.hooks.iter().any(|hook| hook.owner == enemy_id && hook.hooked);
+        let my_pearl = self.pearls.iter().any(|pearl| pearl.owner != enemy_id);
+        let pearl = self
+            .pearls
+            .iter()
+            .find(|pearl| pearl.owner == enemy_id)
+            .map_or((0.0, 0.0, 0.0), |pearl| (pearl.x - me.x, pearl.y - me.y, pearl.z - me.z));
+
+        let mut obs = vec![
             // My state (13)
             me.x / 32.0,
-            me.y / 32.0,
+            (me.y - self.floor_y) / 32.0, // height off the floor
             me.z / 32.0,
             me.vx,
             me.vy,
             me.vz,
-            me.health / MAX_HEALTH,
-            me.food / MAX_FOOD,
+            me.health / self.constants.max_health,
+            me.food / self.constants.max_food,
             me.cooldown_progress(),
             me.yaw / 180.0,
             me.pitch / 90.0,
@@ -589,7 +3387,7 @@ impl FastArena {
             dy / 16.0,
             dz / 32.0,
             (dist / 32.0).min(1.0),
-            enemy.health / MAX_HEALTH,
+            enemy.health / self.constants.max_health,
             enemy_to_me_yaw / 180.0,
             enemy.vx,
             enemy.vy,
@@ -602,10 +3400,131 @@ impl FastArena {
             (me.hits_taken as f64) / 20.0,
             // Eating state (4)
             if me.eating { 1.0 } else { 0.0 },
-            me.eating_ticks as f64 / EAT_TICKS as f64,  // Progress (1.0 = just started, 0.0 = done)
+            me.eating_ticks as f64 / self.constants.eat_ticks.max(1) as f64,  // Progress (1.0 = just started, 0.0 = done)
             if enemy.eating { 1.0 } else { 0.0 },  // Enemy is vulnerable!
             me.steaks as f64 / 64.0,  // Steaks remaining
-        ]
+            // Ranged state (7)
+            me.arrows as f64 / 64.0,
+            me.bow_power(),
+            enemy.bow_power(),  // Enemy is about to shoot
+            (incoming as f64 / 4.0).min(1.0),
+            arrow.0 / 32.0,  // Nearest enemy arrow, 0 if there isn't one
+            arrow.1 / 16.0,
+            arrow.2 / 32.0,
+            // Shield state (4)
+            if me.blocking { 1.0 } else { 0.0 },
+            if enemy.blocking { 1.0 } else { 0.0 },  // Hits from in front won't land
+            me.shield_disabled as f64 / SHIELD_DISABLE_TICKS as f64,
+            enemy.shield_disabled as f64 / SHIELD_DISABLE_TICKS as f64,
+            // Pearls (7)
+            me.pearls as f64 / 16.0,
+            enemy.pearls as f64 / 16.0,
+            me.pearl_cooldown as f64 / PEARL_COOLDOWN_TICKS as f64,
+            if my_pearl { 1.0 } else { 0.0 },  // We're about to teleport
+            pearl.0 / 32.0,  // Where the enemy is about to turn up, 0 without a pearl
+            pearl.1 / 16.0,
+            pearl.2 / 32.0,
+            // Effects (10), levels of speed, slowness, strength, weakness and regeneration
+            me.level(0) / 2.0,
+            me.level(1) / 2.0,
+            me.level(2) / 2.0,
+            me.level(3) / 2.0,
+            me.level(4) / 2.0,
+            enemy.level(0) / 2.0,
+            enemy.level(1) / 2.0,
+            enemy.level(2) / 2.0,
+            enemy.level(3) / 2.0,
+            enemy.level(4) / 2.0,
+            // Splash potions (7)
+            me.potions as f64 / 8.0,
+            enemy.potions as f64 / 8.0,
+            me.level(5) / 2.0,  // Poison
+            enemy.level(5) / 2.0,
+            potion.0 / 32.0,  // Nearest enemy potion, 0 if there isn't one
+            potion.1 / 16.0,
+            potion.2 / 32.0,
+            // Fishing rod (7)
+            me.rod_cooldown as f64 / ROD_COOLDOWN_TICKS as f64,
+            if my_hook.is_some() { 1.0 } else { 0.0 },
+            if my_hook.is_some_and(|hook| hook.hooked) { 1.0 } else { 0.0 },
+            if hooked { 1.0 } else { 0.0 },  // The enemy's hook is on us
+            hook.0 / 32.0,  // Our hook, 0 if it isn't out
+            hook.1 / 16.0,
+            hook.2 / 32.0,
+            // Platform (3), 0 without one
+            self.platform_size.unwrap_or(0.0) / 32.0,
+            self.edge_distance(me) / 16.0,
+            self.edge_distance(enemy) / 16.0,
+        ];
+        // Terrain (26): the ground around me in a 5x5 of blocks, how far
+        // above or below my feet (up to 4), and whether a block is between us
+        for dx in -2..=2 {
+            for dz in -2..=2 {
+                let top = self.terrain.column_top(me.x + dx as f64, me.z + dz as f64);
+                obs.push(((top - me.y) / 4.0).clamp(-1.0, 1.0));
+            }
+        }
+        let sight = self.terrain.raycast((me.x, me.y + EYE_HEIGHT, me.z), (dx, dy, dz)).is_none();
+        obs.push(if sight { 1.0 } else { 0.0 });
+        // Hazards (8): what I'm in, and the closest lava and water within
+        // HAZARD_RADIUS of my feet, 0 without any
+        let feet = (me.x, me.y, me.z);
+        let lava = self.terrain.nearest(Block::Lava, feet, HAZARD_RADIUS);
+        let water = self.terrain.nearest(Block::Water, feet, HAZARD_RADIUS);
+        let closeness = |offset: Option<(f64, f64, f64)>| {
+            offset.map_or(0.0, |(x, y, z)| 1.0 - (x * x + y * y + z * z).sqrt() / HAZARD_RADIUS)
+        };
+        let (lava_x, lava_y, lava_z) = lava.unwrap_or((0.0, 0.0, 0.0));
+        obs.extend([
+            if me.in_water { 1.0 } else { 0.0 },
+            if me.in_lava { 1.0 } else { 0.0 },
+            me.fire_ticks as f64 / LAVA_FIRE_TICKS as f64,
+            closeness(lava),
+            lava_x / HAZARD_RADIUS,
+            lava_y / HAZARD_RADIUS,
+            lava_z / HAZARD_RADIUS,
+            closeness(water),
+        ]);
+        obs
+    }
+
+    /// what get_obs1() and get_obs2() hold: shape, dtype (of obs_tensor()
+    /// and the envs, get_obs1() itself is a list of floats), and per feature
+    /// names, low and high bounds
+    #[staticmethod]
+    fn observation_spec(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+        let spec = PyDict::new(py);
+        spec.set_item("shape", (OBS_SIZE,))?;
+        spec.set_item("dtype", "float32")?;
+        spec.set_item("names", OBS_FEATURES.map(|(name, _, _)| name).to_vec())?;
+        spec.set_item("low", OBS_FEATURES.map(|(_, low, _)| low).to_vec())?;
+        spec.set_item("high", OBS_FEATURES.map(|(_, _, high)| high).to_vec())?;
+        Ok(spec)
+    }
+
+    /// what a FighterAction holds, in its field order: shape, and per field
+    /// names, dtypes ("bool" or "float64", in degrees) and low and high
+    /// bounds. turns past them work, yaw wraps and pitch is clamped
+    #[staticmethod]
+    fn action_spec(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+        let bounds = |name: &str| match name {
+            "delta_yaw" => ("float64", -180.0, 180.0),
+            "delta_pitch" => ("float64", -90.0, 90.0),
+            _ => ("bool", 0.0, 1.0),
+        };
+        let spec = PyDict::new(py);
+        spec.set_item("shape", (ACTION_NAMES.len(),))?;
+        spec.set_item("names", ACTION_NAMES.to_vec())?;
+        spec.set_item("dtypes", ACTION_NAMES.iter().map(|name| bounds(name).0).collect::<Vec<_>>())?;
+        spec.set_item("low", ACTION_NAMES.iter().map(|name| bounds(name).1).collect::<Vec<_>>())?;
+        spec.set_item("high", ACTION_NAMES.iter().map(|name| bounds(name).2).collect::<Vec<_>>())?;
+        Ok(spec)
+    }
+
+    /// get_obs1() (agent=1) or get_obs2() as a float32 ObservationTensor
+    #[pyo3(signature = (agent=1))]
+    fn obs_tensor(&self, agent: u8) -> PyResult<ObservationTensor> {
+        Ok(ObservationTensor::new(obs_f32(self, agent)?, vec![OBS_SIZE]))
     }
 
     /// Get fighter 1 state
@@ -618,61 +3537,360 @@ impl FastArena {
         self.fighter2.clone()
     }
 
+    /// "1.21" or "1.8", see ArenaConfig(combat_version=...)
+    #[getter]
+    fn combat_version(&self) -> &'static str {
+        self.constants.combat_version.name()
+    }
+
+    /// "fight" or "sumo", see ArenaConfig(mode=...)
+    #[getter]
+    fn mode(&self) -> &'static str {
+        self.mode.name()
+    }
+
+    /// the scripted fighter 2 of step_vs_bot(), None without one
+    #[getter]
+    fn opponent(&self) -> Option<&'static str> {
+        self.opponent.as_ref().map(|opponent| opponent.kind.name())
+    }
+
+    /// "rusher", "kiter", "eat_camper" or "random" to play fighter 2 in
+    /// step_vs_bot(), None for none. `seed` is for random's buttons, it keeps
+    /// going through reset()
+    #[pyo3(signature = (opponent, seed=0))]
+    fn set_opponent(&mut self, opponent: Option<&str>, seed: u64) -> PyResult<()> {
+        self.opponent = opponent.map(OpponentKind::parse).transpose()?.map(|kind| Opponent::new(kind, seed));
+        Ok(())
+    }
+
+    /// step() with fighter 2 played by the opponent
+    /// Returns: (reward1, done)
+    fn step_vs_bot(&mut self, py: Python<'_>, action: ActionInput) -> PyResult<(f64, bool)> {
+        let Some(mut opponent) = self.opponent.take() else {
+            return Err(PyValueError::new_err("the arena has no opponent, see set_opponent()"));
+        };
+        let action2 = opponent.act(self, &self.fighter2, &self.fighter1);
+        self.opponent = Some(opponent);
+        let (reward1, _, done) = self.step_actions(py, &action.0, &action2)?;
+        Ok((reward1, done))
+    }
+
+    /// put a solid block at (x, y, z), or take it away. x and z are world
+    /// block coordinates inside the arena, y counts layers up from the floor
+    /// (0 stands on it, up to 15). the blocks stay through reset()
+    #[pyo3(signature = (x, y, z, solid=true))]
+    fn set_block(&mut self, x: i32, y: i32, z: i32, solid: bool) -> PyResult<()> {
+        self.put_blocks((x, y, z), (x, y, z), if solid { Block::Solid } else { Block::Air })
+    }
+
+    /// set_block() for every block from (x1, y1, z1) to (x2, y2, z2), both
+    /// corners included
+    #[pyo3(signature = (x1, y1, z1, x2, y2, z2, solid=true))]
+    #[allow(clippy::too_many_arguments)]
+    fn fill(&mut self, x1: i32, y1: i32, z1: i32, x2: i32, y2: i32, z2: i32, solid: bool) -> PyResult<()> {
+        self.put_blocks((x1, y1, z1), (x2, y2, z2), if solid { Block::Solid } else { Block::Air })
+    }
+
+    /// whether there's a solid block at (x, y, z), None outside the arena
+    fn get_block(&self, x: i32, y: i32, z: i32) -> Option<bool> {
+        self.terrain.get(x, y, z).map(|block| block == Block::Solid)
+    }
+
+    /// fill the block at (x, y, z) with "water" or "lava", None for air.
+    /// fighters swim through both, lava burns
+    #[pyo3(signature = (x, y, z, fluid))]
+    fn set_fluid(&mut self, x: i32, y: i32, z: i32, fluid: Option<&str>) -> PyResult<()> {
+        self.put_blocks((x, y, z), (x, y, z), fluid_block(fluid)?)
+    }
+
+    /// set_fluid() for every block from (x1, y1, z1) to (x2, y2, z2)
+    #[pyo3(signature = (x1, y1, z1, x2, y2, z2, fluid))]
+    #[allow(clippy::too_many_arguments)]
+    fn fill_fluid(&mut self, x1: i32, y1: i32, z1: i32, x2: i32, y2: i32, z2: i32, fluid: Option<&str>) -> PyResult<()> {
+        self.put_blocks((x1, y1, z1), (x2, y2, z2), fluid_block(fluid)?)
+    }
+
+    /// "water" or "lava" at (x, y, z), None for anything else
+    fn get_fluid(&self, x: i32, y: i32, z: i32) -> Option<&'static str> {
+        self.terrain.get(x, y, z).and_then(Block::fluid_name)
+    }
+
+    /// replace the blocks, water and lava too, with columns `heights[x][z]` blocks tall, indexed
+    /// from the arena's min corner like BlockGrid's heightmap
+    fn set_heights(&mut self, heights: Vec<Vec<u32>>) -> PyResult<()> {
+        let (min_x, min_z, width, depth) = self.terrain.extent();
+        if heights.len() != width || heights.iter().any(|row| row.len() != depth) {
+            return Err(PyValueError::new_err(format!("heights must be {} rows of {} columns, one per block of the arena", width, depth)));
+        }
+        if heights.iter().flatten().any(|&h| h as usize > TERRAIN_HEIGHT) {
+            return Err(PyValueError::new_err(format!("heights go up to {} blocks", TERRAIN_HEIGHT)));
+        }
+        self.terrain.clear();
+        for (i, row) in heights.iter().enumerate() {
+            for (j, &height) in row.iter().enumerate() {
+                for layer in 0..height as i32 {
+                    self.terrain.set(min_x + i as i32, layer, min_z + j as i32, Block::Solid);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the height of every column, as set_heights() takes them
+    pub fn heights(&self) -> Vec<Vec<u32>> {
+        let (min_x, min_z, width, depth) = self.terrain.extent();
+        (0..width as i32)
+            .map(|i| (0..depth as i32).map(|j| self.terrain.height(min_x + i, min_z + j)).collect())
+            .collect()
+    }
+
+    fn clear_blocks(&mut self) {
+        self.terrain.clear();
+    }
+
     /// Run N ticks with given actions (for batched simulation)
-    fn step_n(&mut self, n: u32, action1: &FighterAction, action2: &FighterAction) -> (f64, f64, bool) {
+    fn step_n(&mut self, py: Python<'_>, n: u32, action1: ActionInput, action2: ActionInput) -> PyResult<(f64, f64, bool)> {
         let mut total_r1 = 0.0;
         let mut total_r2 = 0.0;
 
         for _ in 0..n {
-            let (r1, r2, done) = self.step(action1, action2);
+            let (r1, r2, done) = self.step_actions(py, &action1.0, &action2.0)?;
             total_r1 += r1;
             total_r2 += r2;
             if done { break; }
         }
 
-        (total_r1, total_r2, self.done)
+        Ok((total_r1, total_r2, self.done))
+    }
+}
+
+/// one arena's part of ArenaVec.step_all(), taken after the step and the
+/// auto reset
+struct BatchRow {
+    reward1: f64,
+    reward2: f64,
+    done: bool,
+    winner: i32,
+    obs1: Vec<f32>,
+    obs2: Vec<f32>,
+}
+
+impl BatchRow {
+    fn finish(arena: &mut FastArena, (reward1, reward2, done): (f64, f64, bool), auto_reset: bool) -> Self {
+        let winner = arena.winner;
+        if done && auto_reset {
+            arena.reset(None, None, None);
+        }
+        Self {
+            reward1,
+            reward2,
+            done,
+            winner,
+            obs1: arena.get_obs1().into_iter().map(|v| v as f32).collect(),
+            obs2: arena.get_obs2().into_iter().map(|v| v as f32).collect(),
+        }
+    }
+}
+
+/// step_all()'s arrays before they go to numpy
+pub struct Batch {
+    pub rewards1: Vec<f64>,
+    pub rewards2: Vec<f64>,
+    pub dones: Vec<bool>,
+    pub winners: Vec<i32>,
+    /// (len * OBS_SIZE), row by row
+    pub obs1: Vec<f32>,
+    pub obs2: Vec<f32>,
+}
+
+impl Batch {
+    fn new(rows: Vec<BatchRow>) -> Self {
+        let count = rows.len();
+        let mut batch = Self {
+            rewards1: Vec::with_capacity(count),
+            rewards2: Vec::with_capacity(count),
+            dones: Vec::with_capacity(count),
+            winners: Vec::with_capacity(count),
+            obs1: Vec::with_capacity(count * OBS_SIZE),
+            obs2: Vec::with_capacity(count * OBS_SIZE),
+        };
+        for row in rows {
+            batch.rewards1.push(row.reward1);
+            batch.rewards2.push(row.reward2);
+            batch.dones.push(row.done);
+            batch.winners.push(row.winner);
+            batch.obs1.extend(row.obs1);
+            batch.obs2.extend(row.obs2);
+        }
+        batch
     }
 }
 
 #[pyclass]
 pub struct ArenaVec {
-    arenas: Vec<FastArena>,
+    pub arenas: Vec<FastArena>,
+}
+
+impl ArenaVec {
+    /// step_all() of arenas without a reward_fn, across the current rayon
+    /// pool (the global one unless called in ThreadPool::install)
+    pub fn step_batch(&mut self, actions1: &[FighterAction], actions2: &[FighterAction], auto_reset: bool) -> Batch {
+        let rows = self
+            .arenas
+            .par_iter_mut()
+            .zip(actions1.par_iter())
+            .zip(actions2.par_iter())
+            .map(|((arena, action1), action2)| {
+                let step = arena.simulate(action1, action2);
+                BatchRow::finish(arena, step, auto_reset)
+            })
+            .collect();
+        Batch::new(rows)
+    }
 }
 
 #[pymethods]
 impl ArenaVec {
     #[new]
-    fn new(count: usize, arena_size: f64, max_ticks: u32) -> Self {
+    #[pyo3(signature = (count, arena_size, max_ticks, config=None, rewards=None))]
+    fn new(count: usize, arena_size: f64, max_ticks: u32, config: Option<&ArenaConfig>, rewards: Option<RewardConfig>) -> PyResult<Self> {
+        check_arena_size(arena_size)?;
         let arenas = (0..count)
-            .map(|_| FastArena::new(arena_size, max_ticks))
-            .collect();
-        Self { arenas }
+            .map(|_| FastArena::new(arena_size, max_ticks, config, rewards.clone()))
+            .collect::<PyResult<_>>()?;
+        Ok(Self { arenas })
     }
 
     fn len(&self) -> usize {
         self.arenas.len()
     }
 
-    /// Reset all arenas
-    fn reset_all(&mut self, spawn_distance: f64) {
+    /// FastArena.set_heights() on every arena
+    fn set_heights(&mut self, heights: Vec<Vec<u32>>) -> PyResult<()> {
+        for arena in &mut self.arenas {
+            arena.set_heights(heights.clone())?;
+        }
+        Ok(())
+    }
+
+    /// `count` copies of FastArena.from_file(path)
+    #[staticmethod]
+    fn from_file(py: Python<'_>, count: usize, path: PathBuf) -> PyResult<Self> {
+        let arena = config::load::<ArenaFile>(py, &path)?.build()?;
+        Ok(Self { arenas: vec![arena; count] })
+    }
+
+    /// Reset all arenas, spawn_distance defaults to each arena's
+    #[pyo3(signature = (spawn_distance=None, loadout1=None, loadout2=None))]
+    fn reset_all(&mut self, spawn_distance: Option<f64>, loadout1: Option<&Loadout>, loadout2: Option<&Loadout>) {
         for arena in &mut self.arenas {
-            arena.reset(spawn_distance);
+            arena.reset(spawn_distance, loadout1, loadout2);
         }
     }
 
     /// Reset specific arena
-    fn reset(&mut self, idx: usize, spawn_distance: f64) {
+    #[pyo3(signature = (idx, spawn_distance=None, loadout1=None, loadout2=None))]
+    fn reset(&mut self, idx: usize, spawn_distance: Option<f64>, loadout1: Option<&Loadout>, loadout2: Option<&Loadout>) {
         if idx < self.arenas.len() {
-            self.arenas[idx].reset(spawn_distance);
+            self.arenas[idx].reset(spawn_distance, loadout1, loadout2);
         }
     }
 
     /// Step specific arena
-    fn step(&mut self, idx: usize, action1: &FighterAction, action2: &FighterAction) -> (f64, f64, bool) {
+    fn step(&mut self, py: Python<'_>, idx: usize, action1: ActionInput, action2: ActionInput) -> PyResult<(f64, f64, bool)> {
         if idx < self.arenas.len() {
-            self.arenas[idx].step(action1, action2)
+            self.arenas[idx].step_actions(py, &action1.0, &action2.0)
+        } else {
+            Ok((0.0, 0.0, true))
+        }
+    }
+
+    /// step every arena with a row each of `actions1` and `actions2`,
+    /// (len, 15) float32 or float64 arrays as in FastArena.step() (10
+    /// columns without the items). finished arenas are reset, so their obs
+    /// are the next episode's first, unless `auto_reset` is off. the arenas
+    /// step on rayon's thread pool with the GIL released, serially when a
+    /// reward_fn is set
+    /// Returns: (rewards1, rewards2, dones, winners, obs1, obs2), with
+    /// (len,) float64, bool and int32 arrays and (len, OBS_SIZE) float32 obs
+    #[pyo3(signature = (actions1, actions2, auto_reset=true))]
+    #[allow(clippy::type_complexity)]
+    fn step_all<'py>(
+        &mut self,
+        py: Python<'py>,
+        actions1: &Bound<'py, PyAny>,
+        actions2: &Bound<'py, PyAny>,
+        auto_reset: bool,
+    ) -> PyResult<(
+        Bound<'py, PyArray1<f64>>,
+        Bound<'py, PyArray1<f64>>,
+        Bound<'py, PyArray1<bool>>,
+        Bound<'py, PyArray1<i32>>,
+        Bound<'py, PyArray2<f32>>,
+        Bound<'py, PyArray2<f32>>,
+    )> {
+        let count = self.arenas.len();
+        let actions1 = action_rows(actions1, count)?;
+        let actions2 = action_rows(actions2, count)?;
+        // a reward_fn needs the GIL every step, so only plain arenas go
+        // across the rayon pool
+        let batch = if self.arenas.iter().any(|arena| arena.reward_fn.is_some()) {
+            let rows = self
+                .arenas
+                .iter_mut()
+                .zip(&actions1)
+                .zip(&actions2)
+                .map(|((arena, action1), action2)| {
+                    let step = arena.step_actions(py, action1, action2)?;
+                    Ok(BatchRow::finish(arena, step, auto_reset))
+                })
+                .collect::<PyResult<_>>()?;
+            Batch::new(rows)
         } else {
-            (0.0, 0.0, true)
+            py.allow_threads(|| self.step_batch(&actions1, &actions2, auto_reset))
+        };
+        Ok((
+            PyArray1::from_vec(py, batch.rewards1),
+            PyArray1::from_vec(py, batch.rewards2),
+            PyArray1::from_vec(py, batch.dones),
+            PyArray1::from_vec(py, batch.winners),
+            PyArray1::from_vec(py, batch.obs1).reshape([count, OBS_SIZE])?,
+            PyArray1::from_vec(py, batch.obs2).reshape([count, OBS_SIZE])?,
+        ))
+    }
+
+    /// FastArena.set_reward_fn() on every arena, all calling the same function
+    #[pyo3(signature = (reward_fn, mode="add", every=1))]
+    fn set_reward_fn(&mut self, py: Python<'_>, reward_fn: Option<PyObject>, mode: &str, every: u32) -> PyResult<()> {
+        for arena in &mut self.arenas {
+            arena.set_reward_fn(reward_fn.as_ref().map(|f| f.clone_ref(py)), mode, every)?;
+        }
+        Ok(())
+    }
+
+    /// FastArena.set_opponent() on every arena, arena i's seed is seed + i
+    #[pyo3(signature = (opponent, seed=0))]
+    fn set_opponent(&mut self, opponent: Option<&str>, seed: u64) -> PyResult<()> {
+        for (i, arena) in self.arenas.iter_mut().enumerate() {
+            arena.set_opponent(opponent, seed.wrapping_add(i as u64))?;
+        }
+        Ok(())
+    }
+
+    /// Step specific arena with discrete actions, see FastArena.step_discrete()
+    fn step_discrete(&mut self, py: Python<'_>, idx: usize, action1: usize, action2: usize) -> PyResult<(f64, f64, bool)> {
+        match self.arenas.get_mut(idx) {
+            Some(arena) => arena.step_discrete(py, action1, action2),
+            None => Ok((0.0, 0.0, true)),
+        }
+    }
+
+    /// Step specific arena against its opponent, see FastArena.step_vs_bot()
+    fn step_vs_bot(&mut self, py: Python<'_>, idx: usize, action: ActionInput) -> PyResult<(f64, bool)> {
+        match self.arenas.get_mut(idx) {
+            Some(arena) => arena.step_vs_bot(py, action),
+            None => Ok((0.0, true)),
         }
     }
 
@@ -681,7 +3899,7 @@ impl ArenaVec {
         if idx < self.arenas.len() {
             self.arenas[idx].get_obs1()
         } else {
-            vec![0.0; 27]
+            vec![0.0; OBS_SIZE]
         }
     }
 
@@ -689,10 +3907,18 @@ impl ArenaVec {
         if idx < self.arenas.len() {
             self.arenas[idx].get_obs2()
         } else {
-            vec![0.0; 27]
+            vec![0.0; OBS_SIZE]
         }
     }
 
+    /// every arena's obs for `agent` as one (len, OBS_SIZE) float32
+    /// ObservationTensor, filled in Rust
+    #[pyo3(signature = (agent=1))]
+    fn obs_tensor(&self, agent: u8) -> PyResult<ObservationTensor> {
+        let rows = self.arenas.iter().map(|arena| obs_f32(arena, agent)).collect::<PyResult<Vec<_>>>()?;
+        Ok(ObservationTensor::batch(rows, OBS_SIZE))
+    }
+
     /// Check if arena is done
     fn is_done(&self, idx: usize) -> bool {
         if idx < self.arenas.len() {
@@ -710,4 +3936,309 @@ impl ArenaVec {
             0
         }
     }
+
+    /// step the arenas for SharedArenaVec(path) in other processes, through
+    /// the files `<path>.act` and `<path>.obs`. blocks until a client calls
+    /// close(), `timeout` seconds go by without a step, or Ctrl-C. returns the
+    /// arena steps done
+    #[cfg(unix)]
+    #[pyo3(signature = (path, timeout=None))]
+    fn serve_shared(&mut self, py: Python<'_>, path: PathBuf, timeout: Option<f64>) -> PyResult<u64> {
+        crate::shared_arena::serve(py, &mut self.arenas, &path, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(len: usize) -> Vec<f64> {
+        let mut values = vec![0.0; len];
+        values[8] = 5.0;
+        values[9] = -2.5;
+        values
+    }
+
+    #[test]
+    fn from_values_length() {
+        for len in [0, 9, ACTION_NAMES.len() + 1] {
+            assert!(FighterAction::from_values(&values(len.max(10))[..len]).is_err());
+        }
+        for len in 10..=ACTION_NAMES.len() {
+            let action = FighterAction::from_values(&values(len)).unwrap();
+            assert_eq!((action.delta_yaw, action.delta_pitch), (5.0, -2.5));
+        }
+    }
+
+    #[test]
+    fn from_values_threshold() {
+        let mut row = values(ACTION_NAMES.len());
+        row[0] = ACTION_THRESHOLD;
+        row[6] = 0.51;
+        row[14] = 1.0;
+        row[1] = -3.0;
+        let action = FighterAction::from_values(&row).unwrap();
+        assert!(!action.forward && action.attack && action.rod && !action.backward);
+        // a short array leaves the items off
+        let action = FighterAction::from_values(&[1.0; 10]).unwrap();
+        assert!(action.forward && action.eat && !action.draw && !action.rod);
+    }
+
+    #[test]
+    fn from_values_rejects_non_finite_turns() {
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            for i in [8, 9] {
+                let mut row = values(10);
+                row[i] = bad;
+                assert!(FighterAction::from_values(&row).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn rows_to_actions_checks_the_count() {
+        let rows = vec![values(10), values(ACTION_NAMES.len())];
+        assert_eq!(rows_to_actions(&rows, 2).unwrap().len(), 2);
+        assert!(rows_to_actions(&rows, 3).is_err());
+        assert!(rows_to_actions(&[values(10), values(11)[..9].to_vec()], 2).is_err());
+    }
+
+    #[test]
+    fn discrete_action_bounds() {
+        for index in 0..DISCRETE_ACTIONS.len() {
+            assert!(discrete_action(index).is_ok());
+        }
+        assert!(discrete_action(DISCRETE_ACTIONS.len()).is_err());
+        assert_eq!(discrete_action(10).unwrap().delta_yaw, -DISCRETE_TURN);
+    }
+
+    #[test]
+    fn breakdown_totals_the_rewards() {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        arena.reset(Some(2.0), None, None);
+        let attack = FighterAction { forward: true, sprint: true, attack: true, ..Default::default() };
+        let idle = FighterAction::default();
+        let mut done = false;
+        while !done {
+            let (reward1, reward2, end) = arena.simulate(&attack, &idle);
+            done = end;
+            for (parts, reward) in arena.breakdown.iter().zip([reward1, reward2]) {
+                assert_eq!(parts.total(), reward);
+                assert!((parts.values().iter().sum::<f64>() - reward).abs() < 1e-12);
+            }
+        }
+        assert!(arena.breakdown.iter().all(|parts| parts.terminal != 0.0));
+    }
+
+    #[test]
+    fn a_lone_fall_hurts_but_nobody_dealt_it() {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        arena.reset(Some(4.0), None, None);
+        arena.fighter1.y = arena.floor_y + 10.0;
+        arena.fighter1.on_ground = false;
+        let idle = FighterAction::default();
+        let (mut taken, mut dealt) = (0.0, 0.0);
+        while taken == 0.0 {
+            arena.simulate(&idle, &idle);
+            taken += arena.events.damage_taken[0];
+            dealt += arena.events.damage_dealt[1];
+            assert!(arena.tick < 100, "never landed");
+        }
+        assert!(arena.fighter1.on_ground);
+        // the events net out regeneration, the total is the fall alone
+        assert!(taken > 0.0);
+        assert_eq!(arena.fighter1.damage_taken, (10.0 - SAFE_FALL_DISTANCE).ceil());
+        assert_eq!(dealt, 0.0);
+        assert_eq!(arena.fighter2.damage_dealt, 0.0);
+        assert!(arena.breakdown[1].damage == 0.0 && arena.breakdown[0].damage < 0.0);
+    }
+
+    #[test]
+    fn both_in_the_void_at_once_is_a_draw() {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        arena.platform_size = Some(4.0);
+        arena.reset(Some(2.0), None, None);
+        (arena.fighter1.x, arena.fighter1.z) = (-10.0, 0.0);
+        (arena.fighter2.x, arena.fighter2.z) = (10.0, 0.0);
+        let idle = FighterAction::default();
+        let (mut reward1, mut reward2, mut done) = (0.0, 0.0, false);
+        while !done {
+            (reward1, reward2, done) = arena.simulate(&idle, &idle);
+            assert!(arena.tick < 100, "never fell");
+        }
+        assert_eq!(arena.out(), [true, true]);
+        assert_eq!((arena.winner, arena.winner_reason), (-1, Some("draw")));
+        assert_eq!(reward1, reward2);
+        assert!(arena.breakdown.iter().all(|parts| parts.terminal == arena.rewards.draw));
+    }
+
+    #[test]
+    fn lava_and_poison_arent_dealt_damage() {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        arena.reset(Some(6.0), None, None);
+        let (x, z) = (arena.fighter1.x.floor() as i32, arena.fighter1.z.floor() as i32);
+        arena.put_blocks((x - 1, 0, z - 1), (x + 1, 1, z + 1), Block::Lava).unwrap();
+        arena.fighter2.effects[5] = ActiveEffect { level: 1, ticks: 200, elapsed: 0 };
+        let idle = FighterAction::default();
+        let mut dealt = [0.0; 2];
+        for _ in 0..60 {
+            arena.simulate(&idle, &idle);
+            dealt[0] += arena.events.damage_dealt[0];
+            dealt[1] += arena.events.damage_dealt[1];
+        }
+        assert!(arena.fighter1.in_lava && arena.fighter1.damage_taken >= LAVA_DAMAGE);
+        assert!(arena.fighter2.damage_taken > 0.0);
+        assert_eq!(dealt, [0.0, 0.0]);
+        assert_eq!((arena.fighter1.damage_dealt, arena.fighter2.damage_dealt), (0.0, 0.0));
+    }
+
+    #[test]
+    fn infinite_effects_keep_their_interval() {
+        let arena = FastArena::new(32.0, 300, None, None).unwrap();
+        let over = |slot: usize, level: u32, health: f64, ticks: u32| {
+            let mut fighter = Fighter { health, ..Fighter::default() };
+            fighter.effects[slot] = ActiveEffect { level, ticks: u32::MAX, elapsed: 0 };
+            for _ in 0..ticks {
+                arena.tick_effects(&mut fighter);
+            }
+            fighter
+        };
+        // level I every 50 ticks, level V every 3
+        assert_eq!(over(4, 1, 1.0, 100).health, 3.0);
+        assert_eq!(over(4, 5, 1.0, 9).health, 4.0);
+        let poisoned = over(5, 1, 10.0, 100);
+        assert_eq!((poisoned.health, poisoned.damage_taken), (6.0, 4.0));
+    }
+
+    #[test]
+    fn sumo_counts_no_damage() {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        (arena.mode, arena.platform_size) = (ArenaMode::Sumo, ArenaMode::Sumo.platform(None));
+        arena.reset(Some(6.0), None, None);
+        arena.fighter1.effects[5] = ActiveEffect { level: 1, ticks: 200, elapsed: 0 };
+        let idle = FighterAction::default();
+        for _ in 0..60 {
+            arena.simulate(&idle, &idle);
+            assert_eq!(arena.events.damage_taken, [0.0; 2]);
+        }
+        assert_eq!(arena.fighter1.health, arena.constants.max_health);
+        assert_eq!(arena.fighter1.damage_taken, 0.0);
+    }
+
+    /// fighter 1 on the ground swinging at fighter 2 two blocks ahead
+    fn swing(mode: ArenaMode, kit: &Kit) -> (Fighter, Fighter, bool) {
+        let c = ArenaConstants::default();
+        let mut attacker = Fighter { on_ground: true, ..Fighter::default() };
+        let mut defender = Fighter { z: 2.0, on_ground: true, ..Fighter::default() };
+        let hit = FastArena::melee(&c, &Terrain::default(), mode, (kit, kit), &mut attacker, &mut defender);
+        (attacker, defender, hit)
+    }
+
+    #[test]
+    fn sumo_swings_empty_handed() {
+        let knockback = Kit { knockback: 2, sharpness: 5, ..Kit::default() };
+        let (attacker, defender, hit) = swing(ArenaMode::Sumo, &knockback);
+        let (_, fist, _) = swing(ArenaMode::Sumo, &Kit::default());
+        assert!(hit);
+        assert_eq!(defender.health, fist.health);
+        assert_eq!((defender.vx, defender.vy, defender.vz), (fist.vx, fist.vy, fist.vz));
+        assert_eq!(attacker.attack_cooldown, FIST.1);
+        let (_, knocked, _) = swing(ArenaMode::Fight, &knockback);
+        assert!(knocked.vz > fist.vz);
+    }
+
+    #[test]
+    fn only_a_sword_in_a_fight_sweeps() {
+        let (attacker, _, hit) = swing(ArenaMode::Fight, &Kit::default());
+        assert!(hit && attacker.swept && attacker.sweeps == 1);
+        let axe = Kit { sword: false, ..Kit::default() };
+        let (attacker, _, _) = swing(ArenaMode::Fight, &axe);
+        assert!(!attacker.swept && attacker.sweeps == 0);
+        let (attacker, _, hit) = swing(ArenaMode::Sumo, &Kit::default());
+        assert!(hit && !attacker.swept && attacker.sweeps == 0);
+    }
+
+    #[test]
+    fn arena_files_need_a_positive_size_and_spawn_distance() {
+        let file = |size: f64, spawn_distance: f64| ArenaFile {
+            arena: ArenaSection { size, spawn_distance, ..ArenaSection::default() },
+            kits: HashMap::new(),
+        };
+        assert!(file(32.0, 4.0).build().is_ok());
+        for bad in [f64::NAN, f64::INFINITY, 0.0, -1.0] {
+            assert!(file(bad, 4.0).build().is_err());
+            assert!(file(32.0, bad).build().is_err());
+        }
+    }
+
+    #[test]
+    fn arenas_need_a_positive_bounded_size() {
+        assert!(FastArena::new(MAX_ARENA_SIZE, 300, None, None).is_ok());
+        for bad in [f64::NAN, f64::INFINITY, 0.0, -1.0, MAX_ARENA_SIZE + 1.0] {
+            assert!(FastArena::new(bad, 300, None, None).is_err());
+            assert!(ArenaVec::new(0, bad, 300, None, None).is_err());
+        }
+    }
+
+    #[test]
+    fn a_fill_partly_outside_changes_nothing() {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        assert!(arena.put_blocks((10, 0, 0), (20, 0, 0), Block::Solid).is_err());
+        assert!(arena.put_blocks((0, -1, 0), (0, 1, 0), Block::Water).is_err());
+        assert!((-16..16).all(|x| (0..TERRAIN_HEIGHT as i32).all(|y| arena.terrain.get(x, y, 0) == Some(Block::Air))));
+        arena.put_blocks((10, 0, 0), (15, 0, 0), Block::Solid).unwrap();
+        assert_eq!(arena.terrain.get(15, 0, 0), Some(Block::Solid));
+    }
+
+    /// fighter 1 draws fully and shoots along the line at fighter 2
+    fn shoot(blocking: bool) -> FastArena {
+        let mut arena = FastArena::new(32.0, 300, None, None).unwrap();
+        arena.reset(Some(6.0), None, None);
+        arena.fighter1.arrows = 1;
+        arena.fighter2.blocking = blocking;
+        for _ in 0..BOW_FULL_DRAW_TICKS {
+            assert!(FastArena::process_bow(&mut arena.fighter1, 1, true).is_none());
+        }
+        let arrow = FastArena::process_bow(&mut arena.fighter1, 1, false).expect("a full draw shoots");
+        assert_eq!(arena.fighter1.arrows, 0);
+        arena.arrows.push(arrow);
+        while !arena.arrows.is_empty() {
+            arena.fly_arrows();
+            assert!(arena.arrows.iter().all(|arrow| arrow.ticks < 10), "never arrived");
+        }
+        arena
+    }
+
+    #[test]
+    fn an_arrow_hits_unless_its_blocked() {
+        let arena = shoot(false);
+        assert!(arena.fighter2.damage_taken > 0.0);
+        assert_eq!(arena.fighter1.damage_dealt, arena.fighter2.damage_taken);
+        assert_eq!(arena.fighter1.hits_landed, 1);
+        let arena = shoot(true);
+        assert_eq!(arena.fighter2.damage_taken, 0.0);
+        assert_eq!(arena.fighter1.damage_dealt, 0.0);
+    }
+
+    #[test]
+    fn knockback_levels_push_further() {
+        for combat_version in [CombatVersion::Modern, CombatVersion::Legacy] {
+            let c = ArenaConstants { combat_version, ..ArenaConstants::default() };
+            let knocked = |knockback: u32, sprinting: bool| {
+                let kit = Kit { knockback, ..Kit::default() };
+                let mut attacker = Fighter { on_ground: true, sprinting, vz: 0.2, ..Fighter::default() };
+                let mut defender = Fighter { z: 2.0, on_ground: true, ..Fighter::default() };
+                assert!(FastArena::melee(&c, &Terrain::default(), ArenaMode::Fight, (&kit, &kit), &mut attacker, &mut defender));
+                (attacker.vz, defender.vz)
+            };
+            let (still, plain) = knocked(0, false);
+            let (slowed, one) = knocked(1, false);
+            let (_, two) = knocked(2, false);
+            assert!(plain > 0.0 && one > plain && two > one, "{:?}", combat_version);
+            // sprinting counts as a level, and a level hit slows the attacker
+            assert_eq!(knocked(0, true).1, one);
+            assert_eq!(still, 0.2);
+            assert!(slowed < still);
+        }
+    }
 }
@@ -0,0 +1,202 @@
+//! Account creation for offline and Microsoft (online-mode) logins,
+//! plus management of the cached Microsoft tokens
+
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use parking_lot::Mutex;
+
+use azalea::{Account, AccountOpts};
+use azalea::auth::AuthOpts;
+use azalea::auth::cache::CachedAccount;
+
+use crate::RUNTIME;
+
+/// build an account for `connect(..., auth=...)`
+///
+/// for "microsoft" the username is the cache key (usually the account email).
+/// if nothing is cached yet this prints a device code link and blocks until
+/// the user logs in
+pub fn make_account(username: &str, auth: &str, cache_path: Option<PathBuf>) -> PyResult<Account> {
+    match auth {
+        "offline" => Ok(Account::offline(username)),
+        "microsoft" => microsoft_account(username, cache_path),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown auth mode '{}' (expected 'offline' or 'microsoft')",
+            auth
+        ))),
+    }
+}
+
+fn microsoft_account(cache_key: &str, cache_path: Option<PathBuf>) -> PyResult<Account> {
+    let result = RUNTIME.block_on(async {
+        match cache_path {
+            // custom cache file, so build the account from the auth result ourselves
+            Some(path) => {
+                let res = azalea::auth::auth(
+                    cache_key,
+                    AuthOpts {
+                        cache_file: Some(path),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                Ok(Account {
+                    username: res.profile.name,
+                    access_token: Some(Arc::new(Mutex::new(res.access_token))),
+                    uuid: Some(res.profile.id),
+                    account_opts: AccountOpts::Microsoft {
+                        email: cache_key.to_string(),
+                    },
+                    certs: Arc::new(Mutex::new(None)),
+                })
+            }
+            // default azalea cache (~/.minecraft/azalea-auth.json)
+            None => Account::microsoft(cache_key).await,
+        }
+    });
+
+    result.map_err(|e: azalea::auth::AuthError| {
+        PyRuntimeError::new_err(format!("Microsoft authentication failed: {}", e))
+    })
+}
+
+/// azalea's default cache file (~/.minecraft/azalea-auth.json)
+fn default_cache_path() -> PyResult<PathBuf> {
+    minecraft_folder_path::minecraft_dir()
+        .map(|dir| dir.join("azalea-auth.json"))
+        .ok_or_else(|| PyRuntimeError::new_err("could not find the .minecraft directory"))
+}
+
+fn resolve_cache_path(cache_path: Option<PathBuf>) -> PyResult<PathBuf> {
+    match cache_path {
+        Some(path) => Ok(path),
+        None => default_cache_path(),
+    }
+}
+
+fn read_cache(path: &Path) -> PyResult<Vec<CachedAccount>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| PyIOError::new_err(format!("failed to read {}: {}", path.display(), e)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| PyValueError::new_err(format!("failed to parse {}: {}", path.display(), e)))
+}
+
+fn write_cache(path: &Path, cache: &[CachedAccount]) -> PyResult<()> {
+    let contents = serde_json::to_string_pretty(cache)
+        .map_err(|e| PyValueError::new_err(format!("failed to serialize auth cache: {}", e)))?;
+    std::fs::write(path, contents)
+        .map_err(|e| PyIOError::new_err(format!("failed to write {}: {}", path.display(), e)))
+}
+
+/// a cached Microsoft login (one entry of the auth cache file)
+#[pyclass]
+#[derive(Clone)]
+pub struct CachedProfile {
+    #[pyo3(get)]
+    pub cache_key: String,
+    #[pyo3(get)]
+    pub username: String,
+    #[pyo3(get)]
+    pub uuid: String,
+    /// unix seconds when the minecraft token expires
+    #[pyo3(get)]
+    pub minecraft_expires_at: u64,
+    /// unix seconds when the microsoft token expires (it's refreshed automatically)
+    #[pyo3(get)]
+    pub microsoft_expires_at: u64,
+}
+
+impl From<&CachedAccount> for CachedProfile {
+    fn from(account: &CachedAccount) -> Self {
+        Self {
+            cache_key: account.cache_key.clone(),
+            username: account.profile.name.clone(),
+            uuid: account.profile.id.to_string(),
+            minecraft_expires_at: account.mca.expires_at,
+            microsoft_expires_at: account.msa.expires_at,
+        }
+    }
+}
+
+#[pymethods]
+impl CachedProfile {
+    /// true if the minecraft token has to be renewed before joining
+    fn is_expired(&self) -> bool {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        self.minecraft_expires_at < now
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "CachedProfile(cache_key={}, username={}, expired={})",
+            self.cache_key,
+            self.username,
+            self.is_expired()
+        )
+    }
+}
+
+/// list every account in the auth cache
+#[pyfunction]
+#[pyo3(signature = (cache_path=None))]
+pub fn list_cached_accounts(cache_path: Option<PathBuf>) -> PyResult<Vec<CachedProfile>> {
+    let path = resolve_cache_path(cache_path)?;
+    Ok(read_cache(&path)?.iter().map(CachedProfile::from).collect())
+}
+
+/// force a new minecraft token for a cached account
+///
+/// the microsoft refresh token is reused, so this doesn't need the user
+#[pyfunction]
+#[pyo3(signature = (cache_key, cache_path=None))]
+pub fn refresh_cached_account(py: Python<'_>, cache_key: &str, cache_path: Option<PathBuf>) -> PyResult<CachedProfile> {
+    let path = resolve_cache_path(cache_path)?;
+
+    // expire the minecraft token so azalea redoes the xbox/minecraft exchange
+    let mut cache = read_cache(&path)?;
+    let Some(entry) = cache.iter_mut().find(|a| a.cache_key == cache_key) else {
+        return Err(PyValueError::new_err(format!("'{}' is not in the auth cache", cache_key)));
+    };
+    entry.mca.expires_at = 0;
+    write_cache(&path, &cache)?;
+
+    py.allow_threads(|| {
+        RUNTIME.block_on(azalea::auth::auth(
+            cache_key,
+            AuthOpts {
+                cache_file: Some(path.clone()),
+                ..Default::default()
+            },
+        ))
+    })
+    .map_err(|e| PyRuntimeError::new_err(format!("failed to refresh '{}': {}", cache_key, e)))?;
+
+    read_cache(&path)?
+        .iter()
+        .find(|a| a.cache_key == cache_key)
+        .map(CachedProfile::from)
+        .ok_or_else(|| PyRuntimeError::new_err(format!("'{}' was not written back to the cache", cache_key)))
+}
+
+/// remove an account from the auth cache. returns false if it wasn't cached
+#[pyfunction]
+#[pyo3(signature = (cache_key, cache_path=None))]
+pub fn invalidate_cached_account(cache_key: &str, cache_path: Option<PathBuf>) -> PyResult<bool> {
+    let path = resolve_cache_path(cache_path)?;
+    let mut cache = read_cache(&path)?;
+    let before = cache.len();
+    cache.retain(|a| a.cache_key != cache_key);
+    if cache.len() == before {
+        return Ok(false);
+    }
+    write_cache(&path, &cache)?;
+    Ok(true)
+}
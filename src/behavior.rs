@@ -0,0 +1,630 @@
+//! Behavior trees built in Python and run from the tick loop
+//!
+//! a tree of BehaviorNodes is compiled into a flat list and ticked from the
+//! root on every game tick, so a higher priority branch takes over the tick
+//! its condition starts holding. a running action that isn't reached any
+//! more is halted: pathfinding stops and the keys are let go. actions the
+//! bot's Permissions don't allow fail, so a selector moves on to the next one
+
+use std::fmt;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use azalea::ecs::entity::Entity;
+use azalea::pathfinder::goals::RadiusGoal;
+use azalea::pathfinder::PathfinderClientExt;
+use azalea::BlockPos;
+use azalea_client::Client;
+use azalea_core::position::Vec3;
+use azalea_entity::inventory::Inventory;
+use azalea_inventory::components::Food;
+use azalea_world::MinecraftEntityId;
+
+use crate::bot::BotShared;
+use crate::errors::in_game;
+use crate::item::Item;
+use crate::logging::COMBAT;
+use crate::pathcost;
+use crate::permissions::Capability;
+use crate::policy::release;
+use crate::state::{PyEntity, PyGameState};
+
+/// ticks to wait for an item use to start before eating counts as failed,
+/// e.g. on a full hunger bar
+const EAT_START_TICKS: u64 = 20;
+/// aim this far above the target's feet, about a player's chest
+const AIM_HEIGHT: f64 = 1.2;
+/// blocks past a goto's radius that still count as arriving
+const GOTO_SLACK: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Success => "success",
+            Status::Failure => "failure",
+            Status::Running => "running",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Condition {
+    HealthBelow(f32),
+    FoodBelow(u32),
+    TargetWithin(f64),
+    HasFood(Option<String>),
+    Eating,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Goto { x: i32, y: i32, z: i32, radius: f32 },
+    AttackTarget { reach: f64 },
+    Eat(Option<String>),
+    Flee { distance: f64 },
+    Wait(u64),
+    Stop,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Selector(Vec<BehaviorNode>),
+    Sequence(Vec<BehaviorNode>),
+    Inverter(Box<BehaviorNode>),
+    Condition(Condition),
+    Action(Action),
+}
+
+/// one node of a behavior tree, made with the static methods and run with
+/// PyBot.attach_behavior()
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct BehaviorNode {
+    kind: Kind,
+    /// shown in PyBot.behavior_status() instead of the node's kind
+    #[pyo3(get)]
+    name: Option<String>,
+}
+
+impl BehaviorNode {
+    fn new(kind: Kind) -> Self {
+        Self { kind, name: None }
+    }
+
+    fn children(&self) -> Vec<&BehaviorNode> {
+        match &self.kind {
+            Kind::Selector(children) | Kind::Sequence(children) => children.iter().collect(),
+            Kind::Inverter(child) => vec![child],
+            Kind::Condition(_) | Kind::Action(_) => vec![],
+        }
+    }
+
+    fn kind_name(&self) -> &'static str {
+        match &self.kind {
+            Kind::Selector(_) => "selector",
+            Kind::Sequence(_) => "sequence",
+            Kind::Inverter(_) => "inverter",
+            Kind::Condition(Condition::HealthBelow(_)) => "health_below",
+            Kind::Condition(Condition::FoodBelow(_)) => "food_below",
+            Kind::Condition(Condition::TargetWithin(_)) => "target_within",
+            Kind::Condition(Condition::HasFood(_)) => "has_food",
+            Kind::Condition(Condition::Eating) => "is_eating",
+            Kind::Action(Action::Goto { .. }) => "goto",
+            Kind::Action(Action::AttackTarget { .. }) => "attack_target",
+            Kind::Action(Action::Eat(_)) => "eat",
+            Kind::Action(Action::Flee { .. }) => "flee",
+            Kind::Action(Action::Wait(_)) => "wait",
+            Kind::Action(Action::Stop) => "stop",
+        }
+    }
+}
+
+fn check_distance(what: &str, distance: f64) -> PyResult<()> {
+    if distance.is_nan() || distance <= 0.0 {
+        return Err(PyValueError::new_err(format!("{} must be positive, got {}", what, distance)));
+    }
+    Ok(())
+}
+
+fn item_name(item: Option<String>) -> Option<String> {
+    item.map(|item| item.strip_prefix("minecraft:").unwrap_or(&item).to_string())
+}
+
+#[pymethods]
+impl BehaviorNode {
+    /// the first child that doesn't fail, tried in order every tick
+    #[staticmethod]
+    fn selector(children: Vec<BehaviorNode>) -> PyResult<Self> {
+        if children.is_empty() {
+            return Err(PyValueError::new_err("a selector needs at least one child"));
+        }
+        Ok(Self::new(Kind::Selector(children)))
+    }
+
+    /// each child in order, stopping at the first that doesn't succeed
+    #[staticmethod]
+    fn sequence(children: Vec<BehaviorNode>) -> PyResult<Self> {
+        if children.is_empty() {
+            return Err(PyValueError::new_err("a sequence needs at least one child"));
+        }
+        Ok(Self::new(Kind::Sequence(children)))
+    }
+
+    /// success for failure and the other way round, running stays running
+    #[staticmethod]
+    fn inverter(child: BehaviorNode) -> Self {
+        Self::new(Kind::Inverter(Box::new(child)))
+    }
+
+    #[staticmethod]
+    fn health_below(health: f32) -> Self {
+        Self::new(Kind::Condition(Condition::HealthBelow(health)))
+    }
+
+    #[staticmethod]
+    fn food_below(food: u32) -> Self {
+        Self::new(Kind::Condition(Condition::FoodBelow(food)))
+    }
+
+    /// the target is in sight and within `distance` blocks
+    #[staticmethod]
+    fn target_within(distance: f64) -> PyResult<Self> {
+        check_distance("distance", distance)?;
+        Ok(Self::new(Kind::Condition(Condition::TargetWithin(distance))))
+    }
+
+    /// something edible (or `item`, like "golden_apple") is in the hotbar
+    #[staticmethod]
+    #[pyo3(signature = (item=None))]
+    fn has_food(item: Option<String>) -> Self {
+        Self::new(Kind::Condition(Condition::HasFood(item_name(item))))
+    }
+
+    #[staticmethod]
+    fn is_eating() -> Self {
+        Self::new(Kind::Condition(Condition::Eating))
+    }
+
+    /// pathfind to within `radius` of the block. succeeds on arriving, fails
+    /// if the pathfinder gives up somewhere else
+    #[staticmethod]
+    #[pyo3(signature = (x, y, z, radius=1.0))]
+    fn goto(x: i32, y: i32, z: i32, radius: f32) -> PyResult<Self> {
+        check_distance("radius", radius as f64)?;
+        Ok(Self::new(Kind::Action(Action::Goto { x, y, z, radius })))
+    }
+
+    /// face the target and sprint at it, hitting once in `reach` with the
+    /// attack charged. succeeds on a hit, fails without a target
+    #[staticmethod]
+    #[pyo3(signature = (reach=3.0))]
+    fn attack_target(reach: f64) -> PyResult<Self> {
+        check_distance("reach", reach)?;
+        Ok(Self::new(Kind::Action(Action::AttackTarget { reach })))
+    }
+
+    /// switch to food from the hotbar (`item` if given) and eat it, running
+    /// until it's eaten. fails without food, or if eating doesn't start
+    #[staticmethod]
+    #[pyo3(signature = (item=None))]
+    fn eat(item: Option<String>) -> Self {
+        Self::new(Kind::Action(Action::Eat(item_name(item))))
+    }
+
+    /// sprint away from the target until it's `distance` blocks off
+    #[staticmethod]
+    #[pyo3(signature = (distance=16.0))]
+    fn flee(distance: f64) -> PyResult<Self> {
+        check_distance("distance", distance)?;
+        Ok(Self::new(Kind::Action(Action::Flee { distance })))
+    }
+
+    /// running for `ticks` game ticks, then success
+    #[staticmethod]
+    fn wait(ticks: u64) -> Self {
+        Self::new(Kind::Action(Action::Wait(ticks)))
+    }
+
+    /// let go of the keys and stop pathfinding
+    #[staticmethod]
+    fn stop() -> Self {
+        Self::new(Kind::Action(Action::Stop))
+    }
+
+    /// a copy called `name` in PyBot.behavior_status()
+    fn named(&self, name: String) -> Self {
+        Self {
+            name: Some(name),
+            ..self.clone()
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for BehaviorNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let item = |item: &Option<String>| item.as_ref().map_or(String::new(), |item| format!("{:?}", item));
+        let args = match &self.kind {
+            Kind::Selector(_) | Kind::Sequence(_) | Kind::Inverter(_) => {
+                let children: Vec<String> = self.children().iter().map(|c| c.to_string()).collect();
+                children.join(", ")
+            }
+            Kind::Condition(Condition::HealthBelow(health)) => health.to_string(),
+            Kind::Condition(Condition::FoodBelow(food)) => food.to_string(),
+            Kind::Condition(Condition::TargetWithin(distance)) => distance.to_string(),
+            Kind::Condition(Condition::HasFood(food)) | Kind::Action(Action::Eat(food)) => item(food),
+            Kind::Condition(Condition::Eating) | Kind::Action(Action::Stop) => String::new(),
+            Kind::Action(Action::Goto { x, y, z, radius }) => format!("{}, {}, {}, {}", x, y, z, radius),
+            Kind::Action(Action::AttackTarget { reach }) => reach.to_string(),
+            Kind::Action(Action::Flee { distance }) => distance.to_string(),
+            Kind::Action(Action::Wait(ticks)) => ticks.to_string(),
+        };
+        write!(f, "{}({})", self.kind_name(), args)?;
+        if let Some(name) = &self.name {
+            write!(f, ".named({:?})", name)?;
+        }
+        Ok(())
+    }
+}
+
+enum Step {
+    Selector,
+    Sequence,
+    Inverter,
+    Condition(Condition),
+    Action(Action),
+}
+
+struct Compiled {
+    step: Step,
+    children: Vec<usize>,
+    /// names (or kinds) from the root, like "root/sequence/eat"
+    path: String,
+}
+
+/// what an action remembers between ticks, reset once it stops running
+#[derive(Clone, Copy, Default)]
+struct Memory {
+    /// the behavior tick it started running on
+    started: Option<u64>,
+    /// eat saw the item in use
+    eating: bool,
+}
+
+/// the target and where everything is this tick
+struct Ctx<'a> {
+    client: &'a Client,
+    shared: &'a BotShared,
+    state: &'a PyGameState,
+    target: Option<(Entity, PyEntity)>,
+    tick: u64,
+}
+
+impl Ctx<'_> {
+    fn allows(&self, capability: Capability) -> bool {
+        self.shared.permissions.lock().allows(capability)
+    }
+
+    fn target_distance(&self) -> Option<f64> {
+        let (_, target) = self.target.as_ref()?;
+        let (dx, dy, dz) = (target.x - self.state.x, target.y - self.state.y, target.z - self.state.z);
+        Some((dx * dx + dy * dy + dz * dz).sqrt())
+    }
+}
+
+/// a compiled tree and its state, run by the bot's tick loop
+pub struct BehaviorTree {
+    nodes: Vec<Compiled>,
+    /// each node's result this tick, None if it wasn't reached
+    status: Vec<Option<Status>>,
+    memory: Vec<Memory>,
+    /// who the target is, None for the nearest player
+    target: Option<String>,
+    entity_radius: f64,
+    /// ticks it has run
+    pub ticks: u64,
+}
+
+impl BehaviorTree {
+    pub fn compile(root: &BehaviorNode, target: Option<String>, entity_radius: f64) -> Self {
+        let mut nodes = vec![];
+        compile(root, "", &mut nodes);
+        let n = nodes.len();
+        Self {
+            nodes,
+            status: vec![None; n],
+            memory: vec![Memory::default(); n],
+            target,
+            entity_radius,
+            ticks: 0,
+        }
+    }
+
+    /// run the tree from the root, called on every game tick
+    pub fn tick(&mut self, client: &Client, shared: &BotShared) {
+        if !in_game(client) {
+            return;
+        }
+        // the state can be missing a component for a few ticks after spawning
+        let Ok(state) = shared.game_state(client, self.entity_radius, None) else {
+            return;
+        };
+        if state.is_dead {
+            return;
+        }
+        let ctx = Ctx {
+            client,
+            shared,
+            target: find_target(client, &state, self.target.as_deref()),
+            state: &state,
+            tick: self.ticks,
+        };
+        let previous = std::mem::replace(&mut self.status, vec![None; self.nodes.len()]);
+        run(&self.nodes, 0, &ctx, &mut self.status, &mut self.memory);
+        for (i, node) in self.nodes.iter().enumerate() {
+            if self.status[i] == Some(Status::Running) {
+                continue;
+            }
+            if previous[i] == Some(Status::Running) {
+                if let Step::Action(action) = &node.step {
+                    tracing::trace!(target: COMBAT, node = %node.path, "behavior halted");
+                    halt(action, client, shared);
+                }
+            }
+            self.memory[i] = Memory::default();
+        }
+        self.ticks += 1;
+    }
+
+    /// (path, status) of every node depth first, status "idle" for nodes
+    /// that weren't reached on the last tick
+    pub fn status(&self) -> Vec<(String, &'static str)> {
+        self.nodes
+            .iter()
+            .zip(&self.status)
+            .map(|(node, status)| (node.path.clone(), status.map_or("idle", Status::name)))
+            .collect()
+    }
+
+    /// path of the action running right now, if one is
+    pub fn running(&self) -> Option<String> {
+        self.nodes
+            .iter()
+            .zip(&self.status)
+            .find(|(node, status)| matches!(node.step, Step::Action(_)) && **status == Some(Status::Running))
+            .map(|(node, _)| node.path.clone())
+    }
+
+    /// let go of whatever the running action was doing, on detaching
+    pub fn halt(&self, client: &Client, shared: &BotShared) {
+        for (node, status) in self.nodes.iter().zip(&self.status) {
+            if let (Step::Action(action), Some(Status::Running)) = (&node.step, status) {
+                halt(action, client, shared);
+            }
+        }
+    }
+}
+
+fn compile(node: &BehaviorNode, parent: &str, nodes: &mut Vec<Compiled>) -> usize {
+    let label = node.name.as_deref().unwrap_or(node.kind_name());
+    let path = if parent.is_empty() {
+        label.to_string()
+    } else {
+        format!("{}/{}", parent, label)
+    };
+    let step = match &node.kind {
+        Kind::Selector(_) => Step::Selector,
+        Kind::Sequence(_) => Step::Sequence,
+        Kind::Inverter(_) => Step::Inverter,
+        Kind::Condition(condition) => Step::Condition(condition.clone()),
+        Kind::Action(action) => Step::Action(action.clone()),
+    };
+    let index = nodes.len();
+    nodes.push(Compiled {
+        step,
+        children: vec![],
+        path: path.clone(),
+    });
+    let children = node.children().into_iter().map(|child| compile(child, &path, nodes)).collect();
+    nodes[index].children = children;
+    index
+}
+
+fn run(nodes: &[Compiled], i: usize, ctx: &Ctx, status: &mut [Option<Status>], memory: &mut [Memory]) -> Status {
+    let node = &nodes[i];
+    let result = match &node.step {
+        Step::Selector => node
+            .children
+            .iter()
+            .map(|&child| run(nodes, child, ctx, status, memory))
+            .find(|s| *s != Status::Failure)
+            .unwrap_or(Status::Failure),
+        Step::Sequence => node
+            .children
+            .iter()
+            .map(|&child| run(nodes, child, ctx, status, memory))
+            .find(|s| *s != Status::Success)
+            .unwrap_or(Status::Success),
+        Step::Inverter => match run(nodes, node.children[0], ctx, status, memory) {
+            Status::Success => Status::Failure,
+            Status::Failure => Status::Success,
+            Status::Running => Status::Running,
+        },
+        Step::Condition(condition) => {
+            if check(condition, ctx) {
+                Status::Success
+            } else {
+                Status::Failure
+            }
+        }
+        Step::Action(action) => act(action, ctx, &mut memory[i]),
+    };
+    status[i] = Some(result);
+    result
+}
+
+fn check(condition: &Condition, ctx: &Ctx) -> bool {
+    match condition {
+        Condition::HealthBelow(health) => ctx.state.health < *health,
+        Condition::FoodBelow(food) => ctx.state.food < *food,
+        Condition::TargetWithin(distance) => ctx.target_distance().is_some_and(|d| d <= *distance),
+        Condition::HasFood(item) => food_slot(ctx.client, item.as_deref()).is_some(),
+        Condition::Eating => ctx.state.is_using_item,
+    }
+}
+
+fn act(action: &Action, ctx: &Ctx, memory: &mut Memory) -> Status {
+    let started = *memory.started.get_or_insert(ctx.tick);
+    match action {
+        Action::Goto { x, y, z, radius } => {
+            if !ctx.allows(Capability::Move) {
+                return Status::Failure;
+            }
+            let goal = BlockPos::new(*x, *y, *z).center();
+            let here = Vec3::new(ctx.state.x, ctx.state.y, ctx.state.z);
+            let arrived = here.distance_to(goal) <= *radius as f64 + GOTO_SLACK;
+            if started == ctx.tick {
+                // already there, without planning a path that goes nowhere
+                if arrived {
+                    return Status::Success;
+                }
+                let may_mine = ctx.allows(Capability::BreakBlocks);
+                let goal = RadiusGoal { pos: goal, radius: *radius };
+                pathcost::start_goto(ctx.client, goal, ctx.shared.path_costs.lock().as_ref(), may_mine);
+                return Status::Running;
+            }
+            // the goto is picked up on the tick after it's started
+            if !ctx.client.is_goto_target_reached() {
+                return Status::Running;
+            }
+            if arrived {
+                Status::Success
+            } else {
+                Status::Failure
+            }
+        }
+        Action::AttackTarget { reach } => {
+            let (Some((entity, target)), Some(distance)) = (ctx.target.as_ref(), ctx.target_distance()) else {
+                return Status::Failure;
+            };
+            if !ctx.allows(Capability::AttackPlayers) {
+                return Status::Failure;
+            }
+            let mut sanitizer = ctx.shared.sanitizer.lock();
+            if ctx.allows(Capability::Look) {
+                sanitizer.look_at(ctx.client, Vec3::new(target.smooth_x, target.smooth_y + AIM_HEIGHT, target.smooth_z));
+            }
+            if ctx.allows(Capability::Move) {
+                sanitizer.sprint(ctx.client, azalea::SprintDirection::Forward);
+            }
+            if distance > *reach || ctx.state.attack_cooldown < 1.0 || !sanitizer.click() {
+                return Status::Running;
+            }
+            ctx.client.attack(*entity);
+            *ctx.shared.target.lock() = Some(*entity);
+            Status::Success
+        }
+        Action::Eat(item) => {
+            if !ctx.allows(Capability::UseItems) {
+                return Status::Failure;
+            }
+            if ctx.state.is_using_item {
+                memory.eating = true;
+                return Status::Running;
+            }
+            if memory.eating {
+                return Status::Success;
+            }
+            if ctx.tick - started > EAT_START_TICKS {
+                return Status::Failure;
+            }
+            let Some(slot) = food_slot(ctx.client, item.as_deref()) else {
+                return Status::Failure;
+            };
+            if ctx.state.selected_slot != slot {
+                ctx.client.set_selected_hotbar_slot(slot);
+            } else {
+                ctx.shared.sanitizer.lock().use_item(ctx.client);
+            }
+            Status::Running
+        }
+        Action::Flee { distance } => {
+            let (Some((_, target)), Some(away)) = (ctx.target.as_ref(), ctx.target_distance()) else {
+                return Status::Success;
+            };
+            if away >= *distance {
+                return Status::Success;
+            }
+            if !ctx.allows(Capability::Move) {
+                return Status::Failure;
+            }
+            let mut sanitizer = ctx.shared.sanitizer.lock();
+            if ctx.allows(Capability::Look) {
+                // a point on the far side of us from them, at eye level
+                let (x, z) = (2.0 * ctx.state.x - target.x, 2.0 * ctx.state.z - target.z);
+                sanitizer.look_at(ctx.client, Vec3::new(x, ctx.state.y + 1.62, z));
+            }
+            sanitizer.sprint(ctx.client, azalea::SprintDirection::Forward);
+            Status::Running
+        }
+        Action::Wait(ticks) => {
+            if ctx.tick - started >= *ticks {
+                Status::Success
+            } else {
+                Status::Running
+            }
+        }
+        Action::Stop => {
+            release(ctx.client, ctx.shared);
+            ctx.client.stop_pathfinding();
+            Status::Success
+        }
+    }
+}
+
+fn halt(action: &Action, client: &Client, shared: &BotShared) {
+    match action {
+        Action::Goto { .. } => client.stop_pathfinding(),
+        Action::AttackTarget { .. } | Action::Flee { .. } => release(client, shared),
+        Action::Eat(_) | Action::Wait(_) | Action::Stop => {}
+    }
+}
+
+/// hotbar slot of the first food, or of `item` if it's given and edible
+fn food_slot(client: &Client, item: Option<&str>) -> Option<u8> {
+    let ecs = client.ecs.lock();
+    let inventory = ecs.get::<Inventory>(client.entity)?;
+    let player = inventory.inventory_menu.as_player();
+    // the hotbar is the last row of the main inventory
+    player.inventory[27..]
+        .iter()
+        .position(|stack| {
+            stack.get_component::<Food>().is_some()
+                && item.is_none_or(|name| Item::from_stack(stack).is_some_and(|i| i.id == name))
+        })
+        .map(|slot| slot as u8)
+}
+
+fn find_target(client: &Client, state: &PyGameState, username: Option<&str>) -> Option<(Entity, PyEntity)> {
+    let player = match username {
+        Some(username) => {
+            let entity = client.player_uuid_by_username(username).and_then(|uuid| client.entity_by_uuid(uuid))?;
+            let id = *client.ecs.lock().get::<MinecraftEntityId>(entity)?;
+            state.entities.iter().find(|e| e.id as i32 == id.0)?.clone()
+        }
+        None => state.nearest_player()?,
+    };
+    let entity = client.ecs_entity_by_minecraft_entity(MinecraftEntityId(player.id as i32))?;
+    Some((entity, player))
+}
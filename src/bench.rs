@@ -0,0 +1,244 @@
+//! Arena throughput, so regressions between releases show up as numbers
+//!
+//! shared by pyzalea.bench() and the `bench` binary
+
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use rayon::{ThreadPoolBuildError, ThreadPoolBuilder};
+
+use crate::arena::{ArenaVec, FastArena, FighterAction, OBS_SIZE};
+
+const ARENA_SIZE: f64 = 32.0;
+const MAX_TICKS: u32 = 2400;
+const SPAWN_DISTANCE: f64 = 6.0;
+/// steps between clock checks
+const CHECK_EVERY: u64 = 256;
+
+/// what each step does with the observations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObsMode {
+    /// nothing, just the simulation
+    None,
+    /// get_obs1()/get_obs2() vectors, like a Python loop calling them
+    List,
+    /// both fighters' obs into one float32 buffer, like obs_tensor()
+    Tensor,
+}
+
+impl ObsMode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(ObsMode::None),
+            "list" => Some(ObsMode::List),
+            "tensor" => Some(ObsMode::Tensor),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ObsMode::None => "none",
+            ObsMode::List => "list",
+            ObsMode::Tensor => "tensor",
+        }
+    }
+}
+
+/// one benchmark run
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct BenchResult {
+    /// "arena" for one FastArena, "vec" for an ArenaVec's step_all()
+    #[pyo3(get)]
+    pub mode: &'static str,
+    #[pyo3(get)]
+    pub arenas: usize,
+    #[pyo3(get)]
+    pub threads: usize,
+    #[pyo3(get)]
+    pub obs: &'static str,
+    /// arena steps (ticks of one arena) done
+    #[pyo3(get)]
+    pub steps: u64,
+    #[pyo3(get)]
+    pub seconds: f64,
+}
+
+#[pymethods]
+impl BenchResult {
+    #[getter]
+    pub fn steps_per_second(&self) -> f64 {
+        if self.seconds > 0.0 {
+            self.steps as f64 / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "BenchResult(mode={}, arenas={}, threads={}, obs={}, steps_per_second={:.0})",
+            self.mode,
+            self.arenas,
+            self.threads,
+            self.obs,
+            self.steps_per_second()
+        )
+    }
+}
+
+/// xorshift, so runs are repeatable without a rand dependency
+struct Actions(u64);
+
+impl Actions {
+    fn next(&mut self) -> FighterAction {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        let bits = self.0;
+        let bit = |n: u32| bits >> n & 1 == 1;
+        FighterAction {
+            // mostly pressing forward, so fights actually happen
+            forward: bits & 3 != 0,
+            backward: false,
+            left: bit(2) && bit(3),
+            right: bit(4) && bit(5),
+            jump: bit(6) && bit(7) && bit(8),
+            sprint: bit(9),
+            attack: bit(10),
+            eat: bit(11) && bit(12) && bit(13) && bit(14),
+            delta_yaw: ((bits >> 16) % 21) as f64 - 10.0,
+            delta_pitch: 0.0,
+            draw: false,
+            block: false,
+            pearl: false,
+            potion: false,
+            rod: false,
+        }
+    }
+}
+
+/// step `arenas` until `duration` is up, returns the steps done
+fn run_arenas(arenas: &mut [FastArena], obs: ObsMode, duration: Duration, seed: u64) -> u64 {
+    let mut actions = Actions(seed | 1);
+    let mut buffer = vec![0.0f32; arenas.len() * OBS_SIZE * 2];
+    let start = Instant::now();
+    let mut steps = 0;
+    loop {
+        for (i, arena) in arenas.iter_mut().enumerate() {
+            if arena.done {
+                arena.reset(Some(SPAWN_DISTANCE), None, None);
+            }
+            let (a1, a2) = (actions.next(), actions.next());
+            arena.simulate(&a1, &a2);
+            match obs {
+                ObsMode::None => {}
+                ObsMode::List => {
+                    std::hint::black_box((arena.get_obs1(), arena.get_obs2()));
+                }
+                ObsMode::Tensor => {
+                    let row = &mut buffer[i * OBS_SIZE * 2..(i + 1) * OBS_SIZE * 2];
+                    for (slot, v) in row.iter_mut().zip(arena.get_obs1().into_iter().chain(arena.get_obs2())) {
+                        *slot = v as f32;
+                    }
+                }
+            }
+            steps += 1;
+            if steps % CHECK_EVERY == 0 && start.elapsed() >= duration {
+                std::hint::black_box(&buffer);
+                return steps;
+            }
+        }
+    }
+}
+
+fn new_arenas(count: usize) -> Vec<FastArena> {
+    (0..count)
+        .map(|_| {
+            let mut arena = FastArena::new(ARENA_SIZE, MAX_TICKS, None, None).expect("the bench arena size is valid");
+            // so a bench in a process serving metrics() doesn't show up there
+            arena.metered = false;
+            arena.reset(Some(SPAWN_DISTANCE), None, None);
+            arena
+        })
+        .collect()
+}
+
+/// one FastArena on this thread
+pub fn bench_arena(obs: ObsMode, seconds: f64) -> BenchResult {
+    let mut arenas = new_arenas(1);
+    let start = Instant::now();
+    let steps = run_arenas(&mut arenas, obs, Duration::from_secs_f64(seconds), 1);
+    BenchResult {
+        mode: "arena",
+        arenas: 1,
+        threads: 1,
+        obs: obs.name(),
+        steps,
+        seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+/// ArenaVec.step_all() of `count` arenas on a rayon pool of `threads`
+/// threads, which always returns the obs as float32 arrays
+pub fn bench_vec(count: usize, threads: usize, seconds: f64) -> Result<BenchResult, ThreadPoolBuildError> {
+    let threads = threads.clamp(1, count.max(1));
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let mut arenas = ArenaVec { arenas: new_arenas(count) };
+    let mut actions = Actions(1);
+    let duration = Duration::from_secs_f64(seconds);
+    let start = Instant::now();
+    let mut steps = 0;
+    while start.elapsed() < duration {
+        let actions1: Vec<_> = (0..count).map(|_| actions.next()).collect();
+        let actions2: Vec<_> = (0..count).map(|_| actions.next()).collect();
+        std::hint::black_box(pool.install(|| arenas.step_batch(&actions1, &actions2, true)));
+        steps += count as u64;
+    }
+    Ok(BenchResult {
+        mode: "vec",
+        arenas: count,
+        threads,
+        obs: ObsMode::Tensor.name(),
+        steps,
+        seconds: start.elapsed().as_secs_f64(),
+    })
+}
+
+/// every combination: one FastArena per obs mode, then an ArenaVec per
+/// (arenas, threads)
+pub fn bench_all(arenas: &[usize], threads: &[usize], obs: &[ObsMode], seconds: f64) -> Result<Vec<BenchResult>, ThreadPoolBuildError> {
+    let mut results: Vec<_> = obs.iter().map(|&mode| bench_arena(mode, seconds)).collect();
+    for &count in arenas {
+        for &threads in threads {
+            results.push(bench_vec(count, threads, seconds)?);
+        }
+    }
+    Ok(results)
+}
+
+/// steps/second of FastArena and ArenaVec, each run takes `seconds`. a
+/// FastArena runs per obs, "none", "list" (get_obs1/2) or "tensor"
+/// (obs_tensor-style float32 buffers), and an ArenaVec.step_all() per
+/// (arenas, threads)
+#[pyfunction]
+#[pyo3(signature = (arenas=vec![64], threads=vec![1, 2, 4], obs=vec!["none".to_string(), "tensor".to_string()], seconds=1.0))]
+pub fn bench(py: Python<'_>, arenas: Vec<usize>, threads: Vec<usize>, obs: Vec<String>, seconds: f64) -> PyResult<Vec<BenchResult>> {
+    let obs = obs
+        .iter()
+        .map(|name| {
+            ObsMode::parse(name).ok_or_else(|| {
+                PyValueError::new_err(format!("unknown obs mode {:?}, expected \"none\", \"list\" or \"tensor\"", name))
+            })
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    if arenas.contains(&0) || threads.contains(&0) {
+        return Err(PyValueError::new_err("arenas and threads must be at least 1"));
+    }
+    if seconds.is_nan() || seconds <= 0.0 {
+        return Err(PyValueError::new_err("seconds must be positive"));
+    }
+    py.allow_threads(|| bench_all(&arenas, &threads, &obs, seconds)).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
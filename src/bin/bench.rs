@@ -0,0 +1,45 @@
+//! Arena throughput from the command line, same runs as pyzalea.bench()
+//!
+//!     cargo run --release --bin bench -- --arenas 64,256 --threads 1,4 --obs none,tensor --seconds 2
+
+use pyzalea::bench::{bench_all, ObsMode};
+
+fn list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|v| parse(v.trim()).ok_or_else(|| format!("bad value {:?}", v)))
+        .collect()
+}
+
+fn main() -> Result<(), String> {
+    let mut arenas = vec![64];
+    let mut threads = vec![1, 2, 4];
+    let mut obs = vec![ObsMode::None, ObsMode::Tensor];
+    let mut seconds = 1.0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        let count = |v: &str| v.parse::<usize>().ok().filter(|n| *n > 0);
+        match flag.as_str() {
+            "--arenas" => arenas = list(&value, count)?,
+            "--threads" => threads = list(&value, count)?,
+            "--obs" => obs = list(&value, ObsMode::parse)?,
+            "--seconds" => seconds = value.parse().ok().filter(|s: &f64| *s > 0.0).ok_or("bad --seconds")?,
+            _ => return Err(format!("unknown flag {}, expected --arenas, --threads, --obs or --seconds", flag)),
+        }
+    }
+
+    println!("{:<6} {:>7} {:>8} {:>7} {:>14}", "mode", "arenas", "threads", "obs", "steps/s");
+    for result in bench_all(&arenas, &threads, &obs, seconds).map_err(|e| e.to_string())? {
+        println!(
+            "{:<6} {:>7} {:>8} {:>7} {:>14.0}",
+            result.mode,
+            result.arenas,
+            result.threads,
+            result.obs,
+            result.steps_per_second()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+//! Blocks around the bot, as a fixed-size grid or a heightmap
+
+use numpy::{PyArray1, PyArray3, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use azalea::pathfinder::world::is_block_state_passable;
+use azalea::registry::builtin::BlockKind;
+use azalea::registry::Registry;
+use azalea::BlockPos;
+use azalea_client::Client;
+use azalea_core::position::{ChunkBlockPos, ChunkPos};
+use azalea_world::heightmap::HeightmapKind;
+
+/// largest radius get_state will read, 33x33x33 blocks
+pub const MAX_GRID_RADIUS: u32 = 16;
+
+/// largest get_heightmap radius, 8 chunks each way
+pub const MAX_HEIGHTMAP_RADIUS: u32 = 128;
+
+/// name of a block id from a grid, like "stone"
+pub fn block_name(id: u32) -> Option<String> {
+    let name = BlockKind::from_u32(id)?.to_string();
+    Some(name.strip_prefix("minecraft:").unwrap_or(&name).to_string())
+}
+
+/// (2r+1)^3 blocks centered on the bot, indexed [x][y][z] from the min corner
+#[pyclass]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockGrid {
+    #[pyo3(get)]
+    pub radius: u32,
+    /// world position of [0][0][0]
+    #[pyo3(get)]
+    pub origin: (i32, i32, i32),
+    /// block kind ids, 0 (air) where the chunk isn't loaded
+    ids: Vec<u32>,
+    /// whether the bot could walk through, false where the chunk isn't loaded
+    passable: Vec<bool>,
+}
+
+#[pymethods]
+impl BlockGrid {
+    /// blocks per side
+    #[getter]
+    fn size(&self) -> usize {
+        self.radius as usize * 2 + 1
+    }
+
+    /// (size, size, size) uint32 array of block ids
+    fn ids<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray3<u32>>> {
+        let n = self.size();
+        PyArray1::from_slice(py, &self.ids).reshape([n, n, n])
+    }
+
+    /// (size, size, size) bool array, True where the bot fits through
+    fn passable<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray3<bool>>> {
+        let n = self.size();
+        PyArray1::from_slice(py, &self.passable).reshape([n, n, n])
+    }
+
+    /// block id at an offset from the bot's block, None outside the grid
+    fn block_at(&self, dx: i32, dy: i32, dz: i32) -> Option<u32> {
+        self.index(dx, dy, dz).map(|i| self.ids[i])
+    }
+
+    fn is_passable(&self, dx: i32, dy: i32, dz: i32) -> Option<bool> {
+        self.index(dx, dy, dz).map(|i| self.passable[i])
+    }
+
+    /// "stone" for a stone id, None for ids that don't exist
+    #[staticmethod]
+    fn block_name(id: u32) -> Option<String> {
+        block_name(id)
+    }
+
+    fn __repr__(&self) -> String {
+        let n = self.size();
+        format!("BlockGrid({}x{}x{}, origin={:?})", n, n, n, self.origin)
+    }
+}
+
+impl BlockGrid {
+    fn index(&self, dx: i32, dy: i32, dz: i32) -> Option<usize> {
+        let r = self.radius as i32;
+        let n = self.size();
+        let offset = |d: i32| (-r..=r).contains(&d).then_some((d + r) as usize);
+        Some((offset(dx)? * n + offset(dy)?) * n + offset(dz)?)
+    }
+
+    /// read the blocks around the bot from the chunk cache
+    pub fn read(client: &Client, radius: u32) -> Self {
+        let radius = radius.min(MAX_GRID_RADIUS);
+        let r = radius as i32;
+        let center = BlockPos::from(client.position());
+        let origin = (center.x - r, center.y - r, center.z - r);
+        let n = radius as usize * 2 + 1;
+        let mut ids = Vec::with_capacity(n * n * n);
+        let mut passable = Vec::with_capacity(n * n * n);
+
+        let world = client.world();
+        let world = world.read();
+        for x in -r..=r {
+            for y in -r..=r {
+                for z in -r..=r {
+                    let pos = BlockPos::new(center.x + x, center.y + y, center.z + z);
+                    match world.get_block_state(pos) {
+                        Some(state) => {
+                            ids.push(BlockKind::from(state).to_u32());
+                            passable.push(is_block_state_passable(state));
+                        }
+                        None => {
+                            ids.push(0);
+                            passable.push(false);
+                        }
+                    }
+                }
+            }
+        }
+        Self {
+            radius,
+            origin,
+            ids,
+            passable,
+        }
+    }
+}
+
+/// heightmaps the server sends to clients
+pub fn heightmap_kind(name: &str) -> PyResult<HeightmapKind> {
+    match name {
+        "world_surface" => Ok(HeightmapKind::WorldSurface),
+        "motion_blocking" => Ok(HeightmapKind::MotionBlocking),
+        "motion_blocking_no_leaves" => Ok(HeightmapKind::MotionBlockingNoLeaves),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown heightmap: {} (expected world_surface, motion_blocking or motion_blocking_no_leaves)",
+            name
+        ))),
+    }
+}
+
+/// y of the highest block in each column around the bot, indexed [x][z] from
+/// (x - radius, z - radius). NaN where the chunk (or its heightmap) isn't loaded
+pub fn heightmap(client: &Client, radius: u32, kind: HeightmapKind) -> Vec<f32> {
+    let r = radius as i32;
+    let center = BlockPos::from(client.position());
+    let world = client.world();
+    let world = world.read();
+
+    let mut heights = Vec::with_capacity(((2 * r + 1) * (2 * r + 1)) as usize);
+    for x in -r..=r {
+        for z in -r..=r {
+            let pos = BlockPos::new(center.x + x, 0, center.z + z);
+            let height = world.chunks.get(&ChunkPos::from(&pos)).and_then(|chunk| {
+                let chunk = chunk.read();
+                let column = ChunkBlockPos::from(&pos);
+                let heightmap = chunk.heightmaps.get(&kind)?;
+                Some(heightmap.get_highest_taken(column.x, column.z) as f32)
+            });
+            heights.push(height.unwrap_or(f32::NAN));
+        }
+    }
+    heights
+}
@@ -0,0 +1,126 @@
+//! Boss bars currently shown to the bot
+
+use pyo3::prelude::*;
+
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::game::c_boss_event::{BossBarColor, BossBarOverlay, Operation};
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct BossBar {
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub title: String,
+    /// 0.0-1.0
+    #[pyo3(get)]
+    pub progress: f32,
+    /// "pink", "blue", "red", "green", "yellow", "purple" or "white"
+    #[pyo3(get)]
+    pub color: String,
+    /// "progress" or "notched_6/10/12/20"
+    #[pyo3(get)]
+    pub overlay: String,
+}
+
+#[pymethods]
+impl BossBar {
+    fn __repr__(&self) -> String {
+        format!("BossBar(title={:?}, progress={:.2}, color={})", self.title, self.progress, self.color)
+    }
+}
+
+/// a boss bar was added, changed or removed ("add", "update" or "remove")
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct BossBarEvent {
+    #[pyo3(get)]
+    pub kind: String,
+    /// state after the change (the last known state for "remove")
+    #[pyo3(get)]
+    pub bar: BossBar,
+}
+
+#[pymethods]
+impl BossBarEvent {
+    fn __repr__(&self) -> String {
+        format!("BossBarEvent(kind={}, bar={})", self.kind, self.bar.__repr__())
+    }
+}
+
+fn color_name(color: BossBarColor) -> &'static str {
+    match color {
+        BossBarColor::Pink => "pink",
+        BossBarColor::Blue => "blue",
+        BossBarColor::Red => "red",
+        BossBarColor::Green => "green",
+        BossBarColor::Yellow => "yellow",
+        BossBarColor::Purple => "purple",
+        BossBarColor::White => "white",
+    }
+}
+
+fn overlay_name(overlay: BossBarOverlay) -> &'static str {
+    match overlay {
+        BossBarOverlay::Progress => "progress",
+        BossBarOverlay::Notched6 => "notched_6",
+        BossBarOverlay::Notched10 => "notched_10",
+        BossBarOverlay::Notched12 => "notched_12",
+        BossBarOverlay::Notched20 => "notched_20",
+    }
+}
+
+/// boss bars in the order they're drawn (top to bottom)
+#[derive(Debug, Default)]
+pub struct BossBars {
+    bars: Vec<BossBar>,
+}
+
+impl BossBars {
+    pub fn clear(&mut self) {
+        self.bars.clear();
+    }
+
+    pub fn all(&self) -> Vec<BossBar> {
+        self.bars.clone()
+    }
+
+    /// apply a boss event packet, returning the event to queue for it
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) -> Option<BossBarEvent> {
+        let ClientboundGamePacket::BossEvent(p) = packet else {
+            return None;
+        };
+        let id = p.id.to_string();
+
+        if let Operation::Add(add) = &p.operation {
+            let bar = BossBar {
+                id,
+                title: add.name.to_string(),
+                progress: add.progress,
+                color: color_name(add.style.color).to_string(),
+                overlay: overlay_name(add.style.overlay).to_string(),
+            };
+            self.bars.push(bar.clone());
+            return Some(BossBarEvent { kind: "add".to_string(), bar });
+        }
+
+        let index = self.bars.iter().position(|b| b.id == id)?;
+        if let Operation::Remove = p.operation {
+            let bar = self.bars.remove(index);
+            return Some(BossBarEvent { kind: "remove".to_string(), bar });
+        }
+
+        let bar = &mut self.bars[index];
+        match &p.operation {
+            Operation::UpdateProgress(progress) => bar.progress = *progress,
+            Operation::UpdateName(name) => bar.title = name.to_string(),
+            Operation::UpdateStyle(style) => {
+                bar.color = color_name(style.color).to_string();
+                bar.overlay = overlay_name(style.overlay).to_string();
+            }
+            // darken/fog/music flags aren't exposed
+            _ => return None,
+        }
+        Some(BossBarEvent { kind: "update".to_string(), bar: bar.clone() })
+    }
+}
@@ -0,0 +1,511 @@
+//! Capturing a session's clientbound packets, and replaying them offline
+//!
+//! connect(capture=path) writes every configuration and game packet the bot
+//! receives, plus a marker per game tick with where the bot was and how it
+//! was moving (that part is ours, the server never sends it). PacketReplay
+//! feeds the packets back into an ECS with azalea's plugins and no network,
+//! through the same event handler a live bot uses, so its PyBot gives the
+//! same PyGameState for every tick
+//!
+//! the file is "PZCP", a u32 format version, the i32 protocol number and
+//! the bot's GameProfile (u32 length first), then records: a u8 tag and for
+//! packets a u32 length and the packet's id and body, as azalea serializes
+//! them. all numbers are little-endian
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use tokio::sync::mpsc;
+
+use azalea::app::{App, Main, Plugin, Update};
+use azalea::auth::game_profile::GameProfile;
+use azalea::buf::{AzaleaRead, AzaleaWrite};
+use azalea::ecs::prelude::*;
+use azalea::physics::PhysicsSystems;
+use azalea::Vec3;
+use azalea_client::auto_reconnect::AutoReconnectPlugin;
+use azalea_client::connection::RawConnection;
+use azalea_client::events::LocalPlayerEvents;
+use azalea_client::local_player::InstanceHolder;
+use azalea_client::packet::config::ReceiveConfigPacketEvent;
+use azalea_client::packet::game::ReceiveGamePacketEvent;
+use azalea_client::player::GameProfileComponent;
+use azalea_client::{start_ecs_runner, Client, ClientInformation, Event, InConfigState, LocalPlayerBundle};
+use azalea_core::tick::GameTick;
+use azalea_entity::metadata::{PlayerMetadataBundle, Sprinting};
+use azalea_entity::{Crouching, LookDirection, Physics, Position};
+use azalea_protocol::packets::{ConnectionProtocol, PROTOCOL_VERSION};
+use azalea_protocol::write::serialize_packet;
+use azalea_world::Instance;
+
+use crate::bot::{bot_plugins, default_plugins, handle_event, BotShared, BotState, ConnectOptions, PyBot};
+use crate::errors::in_game;
+use crate::logging::CONNECTION;
+use crate::state::PyGameState;
+use crate::RUNTIME;
+
+const MAGIC: &[u8; 4] = b"PZCP";
+const FORMAT_VERSION: u32 = 1;
+
+const CONFIG_PACKET: u8 = 0;
+const GAME_PACKET: u8 = 1;
+const TICK: u8 = 2;
+
+/// Main updates to wait for the client to switch protocols, e.g. after
+/// finish_configuration
+const MAX_PHASE_UPDATES: usize = 20;
+
+/// the local parts of the bot's state after a game tick, which PacketReplay
+/// can't get from packets
+#[derive(Clone, Copy)]
+struct OwnState {
+    position: Vec3,
+    velocity: Vec3,
+    yaw: f32,
+    pitch: f32,
+    on_ground: bool,
+    sprinting: bool,
+    crouching: bool,
+}
+
+impl OwnState {
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        for v in [self.position.x, self.position.y, self.position.z, self.velocity.x, self.velocity.y, self.velocity.z] {
+            out.write_all(&v.to_le_bytes())?;
+        }
+        out.write_all(&self.yaw.to_le_bytes())?;
+        out.write_all(&self.pitch.to_le_bytes())?;
+        let flags = self.on_ground as u8 | (self.sprinting as u8) << 1 | (self.crouching as u8) << 2;
+        out.write_all(&[flags])
+    }
+
+    fn read(input: &mut impl Read) -> io::Result<Self> {
+        let mut f64s = [0.0; 6];
+        for v in &mut f64s {
+            *v = f64::from_le_bytes(read_array(input)?);
+        }
+        let yaw = f32::from_le_bytes(read_array(input)?);
+        let pitch = f32::from_le_bytes(read_array(input)?);
+        let [flags] = read_array(input)?;
+        Ok(Self {
+            position: Vec3::new(f64s[0], f64s[1], f64s[2]),
+            velocity: Vec3::new(f64s[3], f64s[4], f64s[5]),
+            yaw,
+            pitch,
+            on_ground: flags & 1 != 0,
+            sprinting: flags & 2 != 0,
+            crouching: flags & 4 != 0,
+        })
+    }
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    input.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// an open capture file, in BotShared.capture
+pub struct PacketCapture {
+    out: BufWriter<File>,
+    pub path: PathBuf,
+    /// the header goes out with the first packet, once we know the profile
+    started: bool,
+    pub packets: u64,
+    pub ticks: u64,
+}
+
+impl PacketCapture {
+    pub fn create(path: &Path) -> PyResult<Self> {
+        let file = File::create(path)
+            .map_err(|e| PyIOError::new_err(format!("can't create {}: {}", path.display(), e)))?;
+        Ok(Self {
+            out: BufWriter::new(file),
+            path: path.to_path_buf(),
+            started: false,
+            packets: 0,
+            ticks: 0,
+        })
+    }
+
+    fn start(&mut self, profile: &GameProfile) -> io::Result<()> {
+        self.out.write_all(MAGIC)?;
+        self.out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        self.out.write_all(&PROTOCOL_VERSION.to_le_bytes())?;
+        let mut buf = vec![];
+        profile.azalea_write(&mut buf)?;
+        self.out.write_all(&(buf.len() as u32).to_le_bytes())?;
+        self.out.write_all(&buf)?;
+        self.started = true;
+        Ok(())
+    }
+
+    fn packet(&mut self, tag: u8, raw: &[u8]) -> io::Result<()> {
+        self.out.write_all(&[tag])?;
+        self.out.write_all(&(raw.len() as u32).to_le_bytes())?;
+        self.out.write_all(raw)?;
+        self.packets += 1;
+        Ok(())
+    }
+
+    fn tick(&mut self, own: &OwnState) -> io::Result<()> {
+        self.out.write_all(&[TICK])?;
+        own.write(&mut self.out)?;
+        self.ticks += 1;
+        // a tick at a time, so a bot that's never closed still leaves a usable file
+        self.out.flush()
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// writes what connect(capture=...) bots receive
+pub struct CapturePlugin;
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, capture_packets)
+            .add_systems(GameTick, capture_tick.after(PhysicsSystems));
+    }
+}
+
+/// run `write` on the bot's capture, if it has one. nothing's written before
+/// the login finishes, and a failed write ends the capture
+fn with_capture(state: &BotState, profile: Option<&GameProfileComponent>, write: impl FnOnce(&mut PacketCapture) -> io::Result<()>) {
+    let Some(shared) = &state.shared else {
+        return;
+    };
+    let mut capture = shared.capture.lock();
+    let Some(open) = capture.as_mut() else {
+        return;
+    };
+    let result = match profile {
+        Some(profile) if !open.started => open.start(profile),
+        None if !open.started => return,
+        _ => Ok(()),
+    };
+    if let Err(e) = result.and_then(|()| write(open)) {
+        let _span = shared.span.enter();
+        tracing::warn!(target: CONNECTION, path = %open.path.display(), "packet capture stopped: {}", e);
+        *capture = None;
+    }
+}
+
+fn capture_packets(
+    mut config_packets: MessageReader<ReceiveConfigPacketEvent>,
+    mut game_packets: MessageReader<ReceiveGamePacketEvent>,
+    query: Query<(&BotState, Option<&GameProfileComponent>)>,
+) {
+    let packets = config_packets
+        .read()
+        .map(|e| (e.entity, CONFIG_PACKET, serialize_packet(&*e.packet)))
+        .chain(game_packets.read().map(|e| (e.entity, GAME_PACKET, serialize_packet(&*e.packet))));
+    for (entity, tag, raw) in packets {
+        let Ok((state, profile)) = query.get(entity) else {
+            continue;
+        };
+        with_capture(state, profile, |capture| match raw {
+            Ok(raw) => capture.packet(tag, &raw),
+            Err(e) => Err(io::Error::other(e.to_string())),
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn capture_tick(
+    query: Query<(
+        &BotState,
+        Option<&GameProfileComponent>,
+        &Position,
+        &Physics,
+        &LookDirection,
+        Option<&Sprinting>,
+        Option<&Crouching>,
+    )>,
+) {
+    for (state, profile, position, physics, look, sprinting, crouching) in &query {
+        let own = OwnState {
+            position: **position,
+            velocity: physics.velocity,
+            yaw: look.y_rot(),
+            pitch: look.x_rot(),
+            on_ground: physics.on_ground(),
+            sprinting: sprinting.is_some_and(|s| s.0),
+            crouching: crouching.is_some_and(|c| **c),
+        };
+        with_capture(state, profile, |capture| capture.tick(&own));
+    }
+}
+
+enum Record {
+    Packet(ConnectionProtocol, Box<[u8]>),
+    Tick(OwnState),
+}
+
+fn read_record(input: &mut impl Read) -> io::Result<Option<Record>> {
+    let mut tag = [0u8];
+    match input.read_exact(&mut tag) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let protocol = match tag[0] {
+        CONFIG_PACKET => ConnectionProtocol::Configuration,
+        GAME_PACKET => ConnectionProtocol::Game,
+        TICK => return Ok(Some(Record::Tick(OwnState::read(input)?))),
+        other => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unknown record tag {}", other))),
+    };
+    let len = u32::from_le_bytes(read_array(input)?) as usize;
+    let mut raw = vec![0u8; len];
+    input.read_exact(&mut raw)?;
+    Ok(Some(Record::Packet(protocol, raw.into_boxed_slice())))
+}
+
+/// a connect(capture=...) file played back offline. iterating gives the
+/// PyGameState after every captured game tick the bot was in game for, and
+/// `bot` is a PyBot over the replayed world: get_state(), poll_events(),
+/// chat history, on_packet() callbacks and so on all work as they did live
+#[pyclass]
+pub struct PacketReplay {
+    ecs: Arc<Mutex<World>>,
+    client: Client,
+    state: BotState,
+    events: mpsc::UnboundedReceiver<Event>,
+    input: BufReader<File>,
+    bot: Py<PyBot>,
+    #[pyo3(get)]
+    path: PathBuf,
+    /// whose session it was
+    #[pyo3(get)]
+    username: String,
+    /// captured ticks replayed so far
+    #[pyo3(get)]
+    tick: u64,
+    done: bool,
+}
+
+fn replay_err(path: &Path, e: impl std::fmt::Display) -> PyErr {
+    PyIOError::new_err(format!("can't replay {}: {}", path.display(), e))
+}
+
+impl PacketReplay {
+    fn open(path: &Path) -> PyResult<(BufReader<File>, GameProfile)> {
+        let file = File::open(path).map_err(|e| replay_err(path, e))?;
+        let mut input = BufReader::new(file);
+        let header: [u8; 16] = read_array(&mut input).map_err(|e| replay_err(path, e))?;
+        if &header[..4] != MAGIC {
+            return Err(PyValueError::new_err(format!("{} isn't a packet capture", path.display())));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let protocol = i32::from_le_bytes(header[8..12].try_into().unwrap());
+        if version != FORMAT_VERSION {
+            return Err(PyValueError::new_err(format!(
+                "{} is capture format {}, this build reads {}",
+                path.display(),
+                version,
+                FORMAT_VERSION
+            )));
+        }
+        if protocol != PROTOCOL_VERSION {
+            return Err(PyValueError::new_err(format!(
+                "{} was captured with protocol {}, this build speaks {}",
+                path.display(),
+                protocol,
+                PROTOCOL_VERSION
+            )));
+        }
+        let mut profile = vec![0u8; u32::from_le_bytes(header[12..16].try_into().unwrap()) as usize];
+        input.read_exact(&mut profile).map_err(|e| replay_err(path, e))?;
+        let profile = GameProfile::azalea_read(&mut Cursor::new(profile.as_slice())).map_err(|e| replay_err(path, e))?;
+        Ok((input, profile))
+    }
+
+    fn run_update(&mut self) {
+        let _runtime = RUNTIME.enter();
+        let mut ecs = self.ecs.lock();
+        ecs.run_schedule(Main);
+        ecs.clear_trackers();
+    }
+
+    /// what azalea's schedule loop does once per tick, then our own state as
+    /// it was captured
+    fn run_tick(&mut self, own: &OwnState) {
+        let _runtime = RUNTIME.enter();
+        let mut ecs = self.ecs.lock();
+        ecs.run_schedule(Main);
+        ecs.run_schedule(GameTick);
+        ecs.clear_trackers();
+        let mut entity = ecs.entity_mut(self.client.entity);
+        if let Some(mut position) = entity.get_mut::<Position>() {
+            **position = own.position;
+        }
+        if let Some(mut physics) = entity.get_mut::<Physics>() {
+            physics.velocity = own.velocity;
+            physics.set_on_ground(own.on_ground);
+        }
+        if let Some(mut look) = entity.get_mut::<LookDirection>() {
+            *look = LookDirection::new(own.yaw, own.pitch);
+        }
+        if let Some(mut sprinting) = entity.get_mut::<Sprinting>() {
+            sprinting.0 = own.sprinting;
+        }
+        if let Some(mut crouching) = entity.get_mut::<Crouching>() {
+            **crouching = own.crouching;
+        }
+    }
+
+    /// hand queued events to the live bot's handler
+    fn handle_events(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            let _ = RUNTIME.block_on(handle_event(self.client.clone(), event, self.state.clone()));
+        }
+    }
+
+    fn protocol(&self) -> ConnectionProtocol {
+        self.ecs.lock().get::<RawConnection>(self.client.entity).map_or(ConnectionProtocol::Game, |c| c.state)
+    }
+
+    fn inject(&mut self, protocol: ConnectionProtocol, raw: Box<[u8]>) {
+        // packets after a protocol switch have to wait until the client has made it
+        for _ in 0..MAX_PHASE_UPDATES {
+            if self.protocol() == protocol {
+                break;
+            }
+            self.run_update();
+            self.handle_events();
+        }
+        let mut ecs = self.ecs.lock();
+        if let Some(mut connection) = ecs.get_mut::<RawConnection>(self.client.entity) {
+            connection.injected_clientbound_packets.push(raw);
+        }
+    }
+
+    /// replay up to the next captured tick, false at the end of the file
+    fn advance(&mut self) -> PyResult<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        loop {
+            match read_record(&mut self.input).map_err(|e| replay_err(&self.path, e))? {
+                None => {
+                    self.done = true;
+                    return Ok(false);
+                }
+                Some(Record::Packet(protocol, raw)) => self.inject(protocol, raw),
+                Some(Record::Tick(own)) => {
+                    self.run_tick(&own);
+                    self.handle_events();
+                    self.tick += 1;
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl PacketReplay {
+    #[new]
+    fn new(py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        let (input, profile) = PacketReplay::open(&path)?;
+        let username = profile.name.clone();
+        let opts = ConnectOptions {
+            // nowhere to go offline, but the TransferEvent still shows up
+            follow_transfers: false,
+            ..Default::default()
+        };
+        let shared = BotShared::new(&opts, &username);
+
+        let mut app = App::new();
+        app.add_plugins(default_plugins(false).disable::<AutoReconnectPlugin>())
+            .add_plugins(bot_plugins());
+        let (ecs, _start_running, _exit) = start_ecs_runner(app.main_mut());
+
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let state = BotState {
+            shared: Some(shared.clone()),
+            swarm: None,
+        };
+        let entity = {
+            let mut ecs = ecs.lock();
+            let entity = ecs.spawn_empty().id();
+            let instance = Arc::new(RwLock::new(Instance::default()));
+            ecs.entity_mut(entity).insert((
+                LocalPlayerBundle {
+                    raw_connection: RawConnection::new_networkless(ConnectionProtocol::Configuration),
+                    instance_holder: InstanceHolder::new(entity, instance),
+                    metadata: PlayerMetadataBundle::default(),
+                },
+                ClientInformation::default(),
+                state.clone(),
+                LocalPlayerEvents(events_tx),
+            ));
+            entity
+        };
+        let client = Client::new(entity, ecs.clone());
+        *shared.client.lock() = Some(client.clone());
+
+        let mut replay = Self {
+            ecs,
+            client,
+            state,
+            events,
+            input,
+            bot: Py::new(py, PyBot::from_shared(shared, username.clone()))?,
+            path,
+            username,
+            tick: 0,
+            done: false,
+        };
+        // like azalea's login: the client exists before it's configuring
+        replay.run_update();
+        replay
+            .ecs
+            .lock()
+            .entity_mut(entity)
+            .insert((InConfigState, GameProfileComponent(profile)));
+        replay.run_update();
+        replay.handle_events();
+        Ok(replay)
+    }
+
+    /// the replayed bot, e.g. replay.bot.poll_events(). its actions go nowhere
+    #[getter]
+    fn bot(&self, py: Python<'_>) -> Py<PyBot> {
+        self.bot.clone_ref(py)
+    }
+
+    /// replay the next captured tick the bot is in game for and return the
+    /// state after it, None at the end of the capture
+    fn step(&mut self, py: Python<'_>) -> PyResult<Option<PyGameState>> {
+        loop {
+            if !py.allow_threads(|| self.advance())? {
+                return Ok(None);
+            }
+            if in_game(&self.client) {
+                break;
+            }
+        }
+        let bot = self.bot.bind(py);
+        bot.call_method0("dispatch_packets")?;
+        Ok(Some(bot.call_method0("get_state")?.extract()?))
+    }
+
+    fn __iter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyGameState>> {
+        self.step(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PacketReplay(path={:?}, username={:?}, tick={})", self.path.display().to_string(), self.username, self.tick)
+    }
+}
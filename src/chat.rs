@@ -0,0 +1,65 @@
+//! Received chat messages, kept in a ring buffer per bot
+
+use pyo3::prelude::*;
+
+use azalea_client::chat::ChatPacket;
+
+use crate::events::unix_seconds;
+
+/// how many messages each bot remembers
+pub const MAX_CHAT_HISTORY: usize = 512;
+
+/// a received chat or system message
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ChatMessage {
+    /// unix seconds when the message arrived
+    #[pyo3(get)]
+    pub timestamp: f64,
+    /// username of the sender, if it could be worked out
+    #[pyo3(get)]
+    pub sender: Option<String>,
+    /// only known for signed player chat
+    #[pyo3(get)]
+    pub sender_uuid: Option<String>,
+    /// message without the sender part
+    #[pyo3(get)]
+    pub content: String,
+    /// the whole message as plain text
+    #[pyo3(get)]
+    pub text: String,
+    /// the message component as JSON
+    #[pyo3(get)]
+    pub raw_json: String,
+    #[pyo3(get)]
+    pub is_whisper: bool,
+    /// position in the bot's chat stream, used by wait_for_chat
+    pub seq: u64,
+}
+
+impl ChatMessage {
+    pub fn from_packet(packet: &ChatPacket, seq: u64) -> Self {
+        let message = packet.message();
+        let (sender, content) = packet.split_sender_and_content();
+        Self {
+            timestamp: unix_seconds(),
+            sender,
+            sender_uuid: packet.sender_uuid().map(|u| u.to_string()),
+            content,
+            text: message.to_string(),
+            raw_json: serde_json::to_string(&message).unwrap_or_default(),
+            is_whisper: packet.is_whisper(),
+            seq,
+        }
+    }
+}
+
+#[pymethods]
+impl ChatMessage {
+    fn __repr__(&self) -> String {
+        format!(
+            "ChatMessage(sender={:?}, text={:?})",
+            self.sender, self.text
+        )
+    }
+}
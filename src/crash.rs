@@ -0,0 +1,135 @@
+//! Panics and handler errors on connection threads, reported instead of lost
+//!
+//! a panic hook keeps the message and backtrace of panics on connection
+//! threads. one in the event handler becomes a CrashEvent and the bot keeps
+//! going, one that takes the whole thread down also marks its bots crashed so
+//! their next call raises BotCrashedError. shutdown::reap() catches threads
+//! that end without either
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Once};
+use std::task::Poll;
+
+use pyo3::prelude::*;
+
+use crate::bot::BotShared;
+use crate::events::{unix_seconds, BotEvent};
+use crate::logging::CONNECTION;
+
+/// a panic or error on the bot's connection thread
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct CrashEvent {
+    /// the panic message, or the error
+    #[pyo3(get)]
+    pub message: String,
+    /// where it panicked, as file:line:column
+    #[pyo3(get)]
+    pub location: Option<String>,
+    #[pyo3(get)]
+    pub backtrace: Option<String>,
+    /// the connection thread is gone, calls on the bot raise BotCrashedError.
+    /// False when only one event handler call failed
+    #[pyo3(get)]
+    pub fatal: bool,
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl CrashEvent {
+    fn __repr__(&self) -> String {
+        format!("CrashEvent(message={:?}, fatal={})", self.message, self.fatal)
+    }
+}
+
+impl CrashEvent {
+    pub fn new(message: String, fatal: bool) -> Self {
+        Self {
+            message,
+            location: None,
+            backtrace: None,
+            fatal,
+            timestamp: unix_seconds(),
+        }
+    }
+}
+
+thread_local! {
+    static BOT_THREAD: Cell<bool> = const { Cell::new(false) };
+    /// what the hook saw of the last panic on this thread
+    static LAST_PANIC: RefCell<Option<CrashEvent>> = const { RefCell::new(None) };
+}
+
+fn payload_message(payload: &dyn Any) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
+
+/// call on a new connection thread, before anything can panic
+pub fn mark_bot_thread() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !BOT_THREAD.with(Cell::get) {
+                return previous(info);
+            }
+            let message = payload_message(info.payload());
+            let location = info.location().map(|l| l.to_string());
+            tracing::error!(target: CONNECTION, location = location.as_deref().unwrap_or("?"), "panicked: {}", message);
+            let crash = CrashEvent {
+                location,
+                backtrace: Some(Backtrace::force_capture().to_string()),
+                ..CrashEvent::new(message, false)
+            };
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(crash));
+        }));
+    });
+    BOT_THREAD.with(|bot_thread| bot_thread.set(true));
+}
+
+/// the CrashEvent for a panic that was just caught on this thread
+pub fn caught(payload: &dyn Any, fatal: bool) -> CrashEvent {
+    let crash = LAST_PANIC
+        .with(|last| last.borrow_mut().take())
+        .unwrap_or_else(|| CrashEvent::new(payload_message(payload), fatal));
+    CrashEvent { fatal, ..crash }
+}
+
+/// queue the CrashEvent, and if it's fatal mark the bots crashed
+pub fn report(bots: &[Arc<BotShared>], crash: CrashEvent) {
+    for bot in bots {
+        if crash.fatal {
+            if bot.closing.load(Ordering::SeqCst) || bot.crash.lock().is_some() {
+                continue;
+            }
+            *bot.crash.lock() = Some(crash.clone());
+            bot.client.lock().take();
+            bot.connected.store(false, Ordering::SeqCst);
+            bot.joined.store(false, Ordering::SeqCst);
+        }
+        bot.push_event(BotEvent::Crash(crash.clone()));
+    }
+}
+
+/// `future`, with panics as Err instead of unwinding through the caller
+pub async fn catch_unwind<F: Future>(future: F) -> Result<F::Output, Box<dyn Any + Send>> {
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(move |cx| match std::panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+        Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+        Ok(Poll::Pending) => Poll::Pending,
+        Err(payload) => Poll::Ready(Err(payload)),
+    })
+    .await
+}
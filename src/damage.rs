@@ -0,0 +1,115 @@
+//! The last time the bot got hurt
+//!
+//! damage_event says who and how but not how much, the amount comes from the
+//! health drop in the set_health after it
+
+use pyo3::prelude::*;
+
+use azalea::registry::data::DamageKind;
+use azalea::registry::DataRegistry;
+use azalea::Identifier;
+use azalea_client::Client;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_world::MinecraftEntityId;
+
+use crate::death::entity_name;
+use crate::events::unix_seconds;
+use crate::state::ticks_connected;
+
+/// how long the bot counts as recently hurt, vanilla's hurt animation length
+pub const RECENTLY_HURT_TICKS: u64 = 10;
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct DamageEvent {
+    /// health lost, 0.0 until the server sends our new health (and for
+    /// hits that armor or resistance soaked up)
+    #[pyo3(get)]
+    pub amount: f32,
+    /// damage type, like "minecraft:mob_attack" or "minecraft:lava"
+    #[pyo3(get)]
+    pub cause: Option<String>,
+    /// username for players, otherwise the entity kind ("minecraft:zombie")
+    #[pyo3(get)]
+    pub attacker: Option<String>,
+    #[pyo3(get)]
+    pub attacker_id: Option<i32>,
+    /// bot.current_tick() when it happened
+    #[pyo3(get)]
+    pub tick: u64,
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl DamageEvent {
+    fn __repr__(&self) -> String {
+        format!(
+            "DamageEvent(amount={:.1}, cause={:?}, attacker={:?}, tick={})",
+            self.amount, self.cause, self.attacker, self.tick
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Damage {
+    last: Option<DamageEvent>,
+    /// last_damage is still waiting for its amount
+    pending: bool,
+    health: Option<f32>,
+}
+
+impl Damage {
+    pub fn handle_packet(&mut self, bot: &Client, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::DamageEvent(p) => {
+                if bot.ecs_entity_by_minecraft_entity(p.entity_id) != Some(bot.entity) {
+                    return;
+                }
+                let attacker_id = p.source_cause_id.0.map(|id| id as i32);
+                self.last = Some(DamageEvent {
+                    amount: 0.0,
+                    cause: damage_type(bot, p.source_type_id),
+                    attacker: attacker_id.and_then(|id| entity_name(bot, MinecraftEntityId(id))),
+                    attacker_id,
+                    tick: ticks_connected(bot),
+                    timestamp: unix_seconds(),
+                });
+                self.pending = true;
+            }
+            ClientboundGamePacket::SetHealth(p) => {
+                if let (Some(before), Some(last)) = (self.health, self.last.as_mut()) {
+                    if self.pending && p.health < before {
+                        last.amount = before - p.health;
+                        self.pending = false;
+                    }
+                }
+                self.health = Some(p.health);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn last(&self) -> Option<DamageEvent> {
+        self.last.clone()
+    }
+
+    pub fn recently_hurt(&self, tick: u64) -> bool {
+        self.last
+            .as_ref()
+            .is_some_and(|d| tick.saturating_sub(d.tick) <= RECENTLY_HURT_TICKS)
+    }
+}
+
+fn damage_type(bot: &Client, id: u32) -> Option<String> {
+    let world = bot.world();
+    let world = world.read();
+    world
+        .registries
+        .protocol_id_to_identifier(Identifier::from(DamageKind::NAME), id)
+        .map(|id| id.to_string())
+}
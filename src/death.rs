@@ -0,0 +1,109 @@
+//! Where and how the bot died
+//!
+//! the death packet only has the message, so the killer comes from the last
+//! damage we took
+
+use pyo3::prelude::*;
+use std::time::{Duration, Instant};
+
+use azalea_client::Client;
+use azalea_client::player::GameProfileComponent;
+use azalea_entity::EntityKindComponent;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::game::c_player_combat_kill::ClientboundPlayerCombatKill;
+use azalea_world::MinecraftEntityId;
+
+use crate::events::unix_seconds;
+use crate::world::dimension;
+
+/// damage older than this doesn't count as the kill (vanilla's kill credit window)
+const KILL_CREDIT: Duration = Duration::from_secs(5);
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct DeathEvent {
+    /// death message as plain text, e.g. "Bot was slain by Zombie"
+    #[pyo3(get)]
+    pub message: String,
+    /// username for players, otherwise the entity kind ("minecraft:zombie")
+    #[pyo3(get)]
+    pub killer: Option<String>,
+    #[pyo3(get)]
+    pub killer_id: Option<i32>,
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    /// e.g. "minecraft:the_nether"
+    #[pyo3(get)]
+    pub dimension: Option<String>,
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl DeathEvent {
+    #[getter]
+    fn position(&self) -> (f64, f64, f64) {
+        (self.x, self.y, self.z)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DeathEvent(message={:?}, killer={:?}, pos=({:.1}, {:.1}, {:.1}))",
+            self.message, self.killer, self.x, self.y, self.z
+        )
+    }
+}
+
+/// whoever damaged us last
+#[derive(Clone, Debug)]
+pub struct Attacker {
+    pub id: i32,
+    pub name: Option<String>,
+    pub at: Instant,
+}
+
+/// username or entity kind of an entity we can see
+pub fn entity_name(bot: &Client, id: MinecraftEntityId) -> Option<String> {
+    let entity = bot.ecs_entity_by_minecraft_entity(id)?;
+    let ecs = bot.ecs.lock();
+    if let Some(profile) = ecs.get::<GameProfileComponent>(entity) {
+        return Some(profile.0.name.clone());
+    }
+    ecs.get::<EntityKindComponent>(entity).map(|kind| kind.0.to_string())
+}
+
+/// the attacker, if this packet is us taking damage from an entity
+pub fn attacker_from_packet(bot: &Client, packet: &ClientboundGamePacket) -> Option<Attacker> {
+    let ClientboundGamePacket::DamageEvent(p) = packet else {
+        return None;
+    };
+    if bot.ecs_entity_by_minecraft_entity(p.entity_id) != Some(bot.entity) {
+        return None;
+    }
+    let id = p.source_cause_id.0?;
+    let id = MinecraftEntityId(id as i32);
+    Some(Attacker {
+        id: id.0,
+        name: entity_name(bot, id),
+        at: Instant::now(),
+    })
+}
+
+pub fn death_event(bot: &Client, packet: &ClientboundPlayerCombatKill, attacker: Option<Attacker>) -> DeathEvent {
+    let attacker = attacker.filter(|a| a.at.elapsed() <= KILL_CREDIT);
+    let pos = bot.position();
+    DeathEvent {
+        message: packet.message.to_string(),
+        killer: attacker.as_ref().and_then(|a| a.name.clone()),
+        killer_id: attacker.map(|a| a.id),
+        x: pos.x,
+        y: pos.y,
+        z: pos.z,
+        dimension: dimension(bot),
+        timestamp: unix_seconds(),
+    }
+}
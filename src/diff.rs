@@ -0,0 +1,142 @@
+//! What changed between two game states, for reward functions
+
+use pyo3::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::entity_kind::Category;
+use crate::state::{PyEntity, PyGameState};
+
+#[pyclass]
+#[derive(Clone)]
+pub struct StateDiff {
+    /// positive when we lost health
+    #[pyo3(get)]
+    pub health_lost: f32,
+    #[pyo3(get)]
+    pub food_change: i64,
+    #[pyo3(get)]
+    pub xp_gained: i64,
+    #[pyo3(get)]
+    pub dx: f64,
+    #[pyo3(get)]
+    pub dy: f64,
+    #[pyo3(get)]
+    pub dz: f64,
+    /// ticks between the two states
+    #[pyo3(get)]
+    pub ticks: i64,
+    /// alive before, dead now
+    #[pyo3(get)]
+    pub died: bool,
+    #[pyo3(get)]
+    pub respawned: bool,
+    #[pyo3(get)]
+    pub dimension_changed: bool,
+    #[pyo3(get)]
+    pub entities_appeared: Vec<PyEntity>,
+    /// entities from the previous state that are gone (out of range or removed)
+    #[pyo3(get)]
+    pub entities_disappeared: Vec<PyEntity>,
+    /// hostiles and projectiles that appeared, the things that can hurt us
+    #[pyo3(get)]
+    pub damage_sources: Vec<PyEntity>,
+    /// health lost by each entity seen in both states, by id
+    #[pyo3(get)]
+    pub entity_damage: HashMap<u32, f32>,
+    /// effect names
+    #[pyo3(get)]
+    pub effects_gained: Vec<String>,
+    #[pyo3(get)]
+    pub effects_lost: Vec<String>,
+}
+
+#[pymethods]
+impl StateDiff {
+    /// (dx, dy, dz)
+    #[getter]
+    fn movement(&self) -> (f64, f64, f64) {
+        (self.dx, self.dy, self.dz)
+    }
+
+    #[getter]
+    fn distance_moved(&self) -> f64 {
+        (self.dx * self.dx + self.dy * self.dy + self.dz * self.dz).sqrt()
+    }
+
+    /// total health other entities lost
+    #[getter]
+    fn damage_dealt(&self) -> f32 {
+        self.entity_damage.values().sum()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "StateDiff(health_lost={}, moved={:.2}, appeared={}, disappeared={}, ticks={})",
+            self.health_lost,
+            self.distance_moved(),
+            self.entities_appeared.len(),
+            self.entities_disappeared.len(),
+            self.ticks
+        )
+    }
+}
+
+pub fn diff(current: &PyGameState, previous: &PyGameState) -> StateDiff {
+    let before: HashMap<u32, &PyEntity> = previous.entities.iter().map(|e| (e.id, e)).collect();
+    let now: HashSet<u32> = current.entities.iter().map(|e| e.id).collect();
+
+    let entities_appeared: Vec<PyEntity> = current
+        .entities
+        .iter()
+        .filter(|e| !before.contains_key(&e.id))
+        .cloned()
+        .collect();
+    let entities_disappeared = previous
+        .entities
+        .iter()
+        .filter(|e| !now.contains(&e.id))
+        .cloned()
+        .collect();
+    let damage_sources = entities_appeared
+        .iter()
+        .filter(|e| matches!(e.category, Category::Hostile | Category::Projectile))
+        .cloned()
+        .collect();
+    let entity_damage = current
+        .entities
+        .iter()
+        .filter_map(|e| {
+            let lost = before.get(&e.id)?.health - e.health;
+            (lost > 0.0).then_some((e.id, lost))
+        })
+        .collect();
+
+    let effect_names = |state: &PyGameState| -> HashSet<String> {
+        state.effects.iter().map(|e| e.name.clone()).collect()
+    };
+    let effects_before = effect_names(previous);
+    let effects_now = effect_names(current);
+    let mut effects_gained: Vec<String> = effects_now.difference(&effects_before).cloned().collect();
+    let mut effects_lost: Vec<String> = effects_before.difference(&effects_now).cloned().collect();
+    effects_gained.sort();
+    effects_lost.sort();
+
+    StateDiff {
+        health_lost: previous.health - current.health,
+        food_change: current.food as i64 - previous.food as i64,
+        xp_gained: current.xp_total as i64 - previous.xp_total as i64,
+        dx: current.x - previous.x,
+        dy: current.y - previous.y,
+        dz: current.z - previous.z,
+        ticks: current.tick as i64 - previous.tick as i64,
+        died: current.is_dead && !previous.is_dead,
+        respawned: previous.is_dead && !current.is_dead,
+        dimension_changed: current.dimension != previous.dimension,
+        entities_appeared,
+        entities_disappeared,
+        damage_sources,
+        entity_damage,
+        effects_gained,
+        effects_lost,
+    }
+}
@@ -0,0 +1,190 @@
+//! Observation tensors over DLPack
+//!
+//! torch.from_dlpack(), jax.numpy.from_dlpack() and numpy.from_dlpack() take
+//! them without copying, the consumer holds on to the Rust buffer until it's
+//! done with it
+
+use std::ffi::{c_void, CStr};
+use std::sync::Arc;
+
+use pyo3::exceptions::PyBufferError;
+use pyo3::ffi;
+use pyo3::prelude::*;
+
+/// kDLCPU
+const CPU: i32 = 1;
+/// kDLFloat
+const FLOAT: u8 = 2;
+
+const DLTENSOR: &CStr = c"dltensor";
+
+#[repr(C)]
+struct DLDevice {
+    device_type: i32,
+    device_id: i32,
+}
+
+#[repr(C)]
+struct DLDataType {
+    code: u8,
+    bits: u8,
+    lanes: u16,
+}
+
+#[repr(C)]
+struct DLTensor {
+    data: *mut c_void,
+    device: DLDevice,
+    ndim: i32,
+    dtype: DLDataType,
+    shape: *mut i64,
+    /// null for a compact row-major tensor
+    strides: *mut i64,
+    byte_offset: u64,
+}
+
+#[repr(C)]
+struct DLManagedTensor {
+    dl_tensor: DLTensor,
+    manager_ctx: *mut c_void,
+    deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+/// what a DLManagedTensor keeps alive
+struct Context {
+    data: Arc<Vec<f32>>,
+    shape: Vec<i64>,
+}
+
+/// called by whoever owns the tensor last, maybe without the GIL
+unsafe extern "C" fn deleter(tensor: *mut DLManagedTensor) {
+    if tensor.is_null() {
+        return;
+    }
+    // SAFETY: both boxes were leaked in __dlpack__ and are only freed here
+    let tensor = Box::from_raw(tensor);
+    drop(Box::from_raw(tensor.manager_ctx as *mut Context));
+}
+
+unsafe extern "C" fn capsule_destructor(capsule: *mut ffi::PyObject) {
+    // consumers rename the capsule to "used_dltensor" when they take the
+    // tensor, then it's theirs to delete
+    if ffi::PyCapsule_IsValid(capsule, DLTENSOR.as_ptr()) == 1 {
+        let tensor = ffi::PyCapsule_GetPointer(capsule, DLTENSOR.as_ptr()) as *mut DLManagedTensor;
+        deleter(tensor);
+    }
+}
+
+/// float32 observations in a Rust buffer, shape (features,) or (batch, features).
+/// pass it to from_dlpack() of your framework of choice
+#[pyclass]
+pub struct ObservationTensor {
+    data: Arc<Vec<f32>>,
+    shape: Vec<i64>,
+}
+
+impl ObservationTensor {
+    pub fn new(data: Vec<f32>, shape: Vec<usize>) -> Self {
+        debug_assert_eq!(data.len(), shape.iter().product::<usize>());
+        Self {
+            data: Arc::new(data),
+            shape: shape.into_iter().map(|n| n as i64).collect(),
+        }
+    }
+
+    /// rows of `width` values each
+    pub fn batch(rows: impl IntoIterator<Item = Vec<f32>>, width: usize) -> Self {
+        let mut data = vec![];
+        let mut count = 0;
+        for row in rows {
+            data.extend(row);
+            count += 1;
+        }
+        Self::new(data, vec![count, width])
+    }
+}
+
+#[pymethods]
+impl ObservationTensor {
+    #[getter]
+    fn shape(&self) -> Vec<i64> {
+        self.shape.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.shape.first().copied().unwrap_or(0) as usize
+    }
+
+    /// the values as a flat list, mostly for debugging
+    fn tolist(&self) -> Vec<f32> {
+        self.data.to_vec()
+    }
+
+    /// the DLPack protocol. always on the cpu, and shared with every other
+    /// from_dlpack() of this tensor unless copy=True, so treat it as read-only
+    #[pyo3(signature = (*, stream=None, max_version=None, dl_device=None, copy=None))]
+    fn __dlpack__<'py>(
+        &self,
+        py: Python<'py>,
+        stream: Option<&Bound<'py, PyAny>>,
+        max_version: Option<(u32, u32)>,
+        dl_device: Option<(i32, i32)>,
+        copy: Option<bool>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        // no streams on the cpu, and we only make unversioned (pre-1.0) capsules,
+        // which every max_version allows
+        let _ = (stream, max_version);
+        if let Some(device) = dl_device {
+            if device != (CPU, 0) {
+                return Err(PyBufferError::new_err("observation tensors are only on the cpu"));
+            }
+        }
+        let data = if copy == Some(true) {
+            Arc::new(self.data.to_vec())
+        } else {
+            self.data.clone()
+        };
+        let context = Box::into_raw(Box::new(Context {
+            data,
+            shape: self.shape.clone(),
+        }));
+        // SAFETY: context stays alive until the deleter runs, and the tensor
+        // points into it
+        unsafe {
+            let tensor = Box::into_raw(Box::new(DLManagedTensor {
+                dl_tensor: DLTensor {
+                    data: (*context).data.as_ptr() as *mut c_void,
+                    device: DLDevice {
+                        device_type: CPU,
+                        device_id: 0,
+                    },
+                    ndim: (*context).shape.len() as i32,
+                    dtype: DLDataType {
+                        code: FLOAT,
+                        bits: 32,
+                        lanes: 1,
+                    },
+                    shape: (*context).shape.as_mut_ptr(),
+                    strides: std::ptr::null_mut(),
+                    byte_offset: 0,
+                },
+                manager_ctx: context as *mut c_void,
+                deleter: Some(deleter),
+            }));
+            let capsule = ffi::PyCapsule_New(tensor as *mut c_void, DLTENSOR.as_ptr(), Some(capsule_destructor));
+            if capsule.is_null() {
+                deleter(tensor);
+                return Err(PyErr::fetch(py));
+            }
+            Ok(Bound::from_owned_ptr(py, capsule))
+        }
+    }
+
+    fn __dlpack_device__(&self) -> (i32, i32) {
+        (CPU, 0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ObservationTensor(shape={:?}, dtype=float32)", self.shape)
+    }
+}
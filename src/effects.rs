@@ -0,0 +1,119 @@
+//! The bot's active potion effects
+//!
+//! tracked from packets instead of azalea's ActiveEffects, which keeps the
+//! duration from when the effect was applied and never counts it down
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Instant;
+
+use azalea::registry::builtin::MobEffect;
+use azalea_client::Client;
+use azalea_entity::MobEffectData;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+/// vanilla sends -1 (as a varint) for effects that never run out
+const INFINITE: u32 = u32::MAX;
+
+fn never_runs_out() -> i64 {
+    -1
+}
+
+#[pyclass]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Effect {
+    /// effect id without the namespace, like "speed"
+    #[pyo3(get)]
+    pub name: String,
+    /// 0 for level I, 1 for level II, ...
+    #[pyo3(get)]
+    #[serde(default)]
+    pub amplifier: u32,
+    /// ticks left, -1 if it doesn't run out
+    #[pyo3(get)]
+    #[serde(default = "never_runs_out")]
+    pub duration: i64,
+    /// from a beacon or conduit
+    #[pyo3(get)]
+    #[serde(default)]
+    pub ambient: bool,
+}
+
+#[pymethods]
+impl Effect {
+    /// for giving one out, like Loadout(effects=[Effect("speed", amplifier=1, duration=600)])
+    #[new]
+    #[pyo3(signature = (name, amplifier=0, duration=-1))]
+    fn new(name: &str, amplifier: u32, duration: i64) -> PyResult<Self> {
+        if duration == 0 || duration < -1 {
+            return Err(PyValueError::new_err("duration must be positive ticks, or -1 to never run out"));
+        }
+        Ok(Self {
+            name: name.strip_prefix("minecraft:").unwrap_or(name).to_string(),
+            amplifier,
+            duration,
+            ambient: false,
+        })
+    }
+
+    pub(crate) fn __repr__(&self) -> String {
+        format!("Effect({}, amplifier={}, duration={})", self.name, self.amplifier, self.duration)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Effects {
+    active: HashMap<MobEffect, (MobEffectData, Instant)>,
+}
+
+impl Effects {
+    pub fn handle_packet(&mut self, bot: &Client, packet: &ClientboundGamePacket) {
+        let own = |id| bot.ecs_entity_by_minecraft_entity(id) == Some(bot.entity);
+        match packet {
+            ClientboundGamePacket::UpdateMobEffect(p) if own(p.entity_id) => {
+                self.active.insert(p.mob_effect, (p.data.clone(), Instant::now()));
+            }
+            ClientboundGamePacket::RemoveMobEffect(p) if own(p.entity_id) => {
+                self.active.remove(&p.effect);
+            }
+            // the server sends the effects again after a respawn or dimension change
+            ClientboundGamePacket::Respawn(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    /// effects that haven't run out yet, sorted by name
+    pub fn active(&self) -> Vec<Effect> {
+        let mut effects: Vec<Effect> = self
+            .active
+            .iter()
+            .filter_map(|(effect, (data, received))| {
+                let duration = if data.duration_ticks == INFINITE {
+                    -1
+                } else {
+                    let elapsed = (received.elapsed().as_millis() / 50) as i64;
+                    let left = data.duration_ticks as i64 - elapsed;
+                    if left <= 0 {
+                        return None;
+                    }
+                    left
+                };
+                let name = effect.to_string();
+                Some(Effect {
+                    name: name.strip_prefix("minecraft:").unwrap_or(&name).to_string(),
+                    amplifier: data.amplifier,
+                    duration,
+                    ambient: data.flags.ambient,
+                })
+            })
+            .collect();
+        effects.sort_by(|a, b| a.name.cmp(&b.name));
+        effects
+    }
+}
@@ -0,0 +1,57 @@
+//! Rough grouping of entity kinds, so Python doesn't have to keep its own lists
+
+use azalea::registry::builtin::EntityKind;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Player,
+    /// attacks on sight, or when provoked like endermen and piglins
+    Hostile,
+    /// animals, villagers and golems
+    Passive,
+    /// a dropped item stack
+    Item,
+    Projectile,
+    /// boats, minecarts, displays, orbs, ...
+    Other,
+}
+
+impl Category {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Player => "player",
+            Self::Hostile => "hostile",
+            Self::Passive => "passive",
+            Self::Item => "item",
+            Self::Projectile => "projectile",
+            Self::Other => "other",
+        }
+    }
+}
+
+pub fn category(kind: EntityKind) -> Category {
+    use EntityKind::*;
+    match kind {
+        Player => Category::Player,
+        Blaze | Bogged | Breeze | CaveSpider | Creaking | Creeper | Drowned | ElderGuardian
+        | EnderDragon | Enderman | Endermite | Evoker | Ghast | Giant | Guardian | Hoglin | Husk
+        | Illusioner | MagmaCube | Parched | Phantom | Piglin | PiglinBrute | Pillager | Ravager
+        | Shulker | Silverfish | Skeleton | Slime | Spider | Stray | Vex | Vindicator | Warden
+        | Witch | Wither | WitherSkeleton | Zoglin | Zombie | ZombieNautilus | ZombieVillager
+        | ZombifiedPiglin => Category::Hostile,
+        Allay | Armadillo | Axolotl | Bat | Bee | Camel | CamelHusk | Cat | Chicken | Cod
+        | CopperGolem | Cow | Dolphin | Donkey | Fox | Frog | GlowSquid | Goat | HappyGhast
+        | Horse | IronGolem | Llama | Mooshroom | Mule | Nautilus | Ocelot | Panda | Parrot | Pig
+        | PolarBear | Pufferfish | Rabbit | Salmon | Sheep | SkeletonHorse | Sniffer | SnowGolem
+        | Squid | Strider | Tadpole | TraderLlama | TropicalFish | Turtle | Villager
+        | WanderingTrader | Wolf | ZombieHorse => Category::Passive,
+        Item => Category::Item,
+        Arrow | BreezeWindCharge | DragonFireball | Egg | EnderPearl | ExperienceBottle
+        | Fireball | FireworkRocket | FishingBobber | LingeringPotion | LlamaSpit
+        | ShulkerBullet | SmallFireball | Snowball | SpectralArrow | SplashPotion | Trident
+        | WindCharge | WitherSkull => Category::Projectile,
+        _ => Category::Other,
+    }
+}
@@ -0,0 +1,63 @@
+//! What other entities are holding and wearing
+//!
+//! azalea reads set_equipment but throws it away, so keep the last one per entity
+
+use std::collections::HashMap;
+
+use azalea_inventory::components::EquipmentSlot;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+use crate::item::Item;
+
+#[derive(Clone, Debug, Default)]
+pub struct Gear {
+    pub mainhand: Option<Item>,
+    pub offhand: Option<Item>,
+    /// head, chest, legs, feet
+    pub armor: [Option<Item>; 4],
+}
+
+/// keyed by the protocol entity id
+#[derive(Debug, Default)]
+pub struct EntityEquipment {
+    entities: HashMap<i32, Gear>,
+}
+
+impl EntityEquipment {
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::SetEquipment(p) => {
+                let gear = self.entities.entry(p.entity_id.0).or_default();
+                for (slot, stack) in &p.slots.slots {
+                    let item = Item::from_stack(stack);
+                    match slot {
+                        EquipmentSlot::Mainhand => gear.mainhand = item,
+                        EquipmentSlot::Offhand => gear.offhand = item,
+                        EquipmentSlot::Head => gear.armor[0] = item,
+                        EquipmentSlot::Chest => gear.armor[1] = item,
+                        EquipmentSlot::Legs => gear.armor[2] = item,
+                        EquipmentSlot::Feet => gear.armor[3] = item,
+                        // horse/wolf armor and saddles
+                        _ => {}
+                    }
+                }
+            }
+            ClientboundGamePacket::RemoveEntities(p) => {
+                for id in &p.entity_ids {
+                    self.entities.remove(&id.0);
+                }
+            }
+            // every entity is sent again in the new dimension
+            ClientboundGamePacket::Respawn(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Gear> {
+        self.entities.get(&id)
+    }
+}
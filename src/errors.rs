@@ -0,0 +1,56 @@
+//! Exceptions for bot calls that can't get at the client
+//!
+//! BotError
+//! ├── NotConnectedError      no connection (never connected, or disconnected)
+//! ├── NotInGameError         connected but not spawned in a world yet
+//! ├── ComponentMissingError  in game, but azalea doesn't have that data
+//! ├── BotCrashedError        the connection thread panicked or died
+//! └── PermissionDeniedError  the bot's Permissions don't allow that
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+use azalea::ecs::component::Component;
+use azalea_client::Client;
+use azalea_entity::Position;
+
+use crate::crash::CrashEvent;
+
+create_exception!(pyzalea, BotError, PyException);
+create_exception!(pyzalea, NotConnectedError, BotError);
+create_exception!(pyzalea, NotInGameError, BotError);
+create_exception!(pyzalea, ComponentMissingError, BotError);
+create_exception!(pyzalea, BotCrashedError, BotError);
+create_exception!(pyzalea, PermissionDeniedError, BotError);
+
+pub fn not_connected() -> PyErr {
+    NotConnectedError::new_err("the bot isn't connected")
+}
+
+pub fn crashed(crash: &CrashEvent) -> PyErr {
+    let location = crash.location.as_ref().map(|l| format!(" at {}", l)).unwrap_or_default();
+    BotCrashedError::new_err(format!("the bot's connection thread crashed: {}{}", crash.message, location))
+}
+
+/// azalea adds the position once the server spawns us in a world
+pub fn in_game(client: &Client) -> bool {
+    client.ecs.lock().get::<Position>(client.entity).is_some()
+}
+
+pub fn require_in_game(client: &Client) -> PyResult<()> {
+    if in_game(client) {
+        Ok(())
+    } else {
+        Err(NotInGameError::new_err("the bot hasn't spawned in a world yet"))
+    }
+}
+
+/// a component of the bot's own entity
+pub fn component<T: Component + Clone>(client: &Client) -> PyResult<T> {
+    client.ecs.lock().get::<T>(client.entity).cloned().ok_or_else(|| {
+        let name = std::any::type_name::<T>();
+        let name = name.rsplit("::").next().unwrap_or(name);
+        ComponentMissingError::new_err(format!("the bot has no {} yet", name))
+    })
+}
@@ -0,0 +1,105 @@
+//! Events queued by the connection thread until Python polls them
+
+use pyo3::prelude::*;
+
+use crate::bossbar::BossBarEvent;
+use crate::chat::ChatMessage;
+use crate::crash::CrashEvent;
+use crate::death::DeathEvent;
+use crate::particle::ParticleEvent;
+use crate::sound::SoundEvent;
+use crate::title::TitleEvent;
+use crate::transfer::TransferEvent;
+
+/// max queued events per bot, the oldest are dropped past this
+pub const MAX_QUEUED_EVENTS: usize = 1024;
+
+/// current time as unix seconds, for event timestamps
+pub fn unix_seconds() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// reconnect progress ("reconnecting", "throttled", "reconnected" or "gave_up")
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ReconnectEvent {
+    #[pyo3(get)]
+    pub kind: String,
+    /// 1-based attempt number. for "gave_up", the reconnect attempts made
+    /// before giving up, 0 when the disconnect wasn't retried at all
+    #[pyo3(get)]
+    pub attempt: u32,
+    /// seconds until the next attempt (0 unless reconnecting)
+    #[pyo3(get)]
+    pub delay: f64,
+    /// kick message or connection error that triggered this
+    #[pyo3(get)]
+    pub reason: Option<String>,
+}
+
+#[pymethods]
+impl ReconnectEvent {
+    fn __repr__(&self) -> String {
+        format!(
+            "ReconnectEvent(kind={}, attempt={}, delay={:.1}, reason={:?})",
+            self.kind, self.attempt, self.delay, self.reason
+        )
+    }
+}
+
+/// the bot was kicked or lost its connection
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct DisconnectEvent {
+    /// the kick message as plain text, None if the connection just dropped
+    #[pyo3(get)]
+    pub reason: Option<String>,
+}
+
+#[pymethods]
+impl DisconnectEvent {
+    /// true if the server sent a kick message
+    #[getter]
+    fn kicked(&self) -> bool {
+        self.reason.is_some()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("DisconnectEvent(reason={:?})", self.reason)
+    }
+}
+
+/// everything that can show up in `PyBot.poll_events()`
+#[derive(Clone, Debug)]
+pub enum BotEvent {
+    Reconnect(ReconnectEvent),
+    Disconnect(DisconnectEvent),
+    Chat(ChatMessage),
+    BossBar(BossBarEvent),
+    Title(TitleEvent),
+    Sound(SoundEvent),
+    Particle(ParticleEvent),
+    Death(DeathEvent),
+    Transfer(TransferEvent),
+    Crash(CrashEvent),
+}
+
+impl BotEvent {
+    pub fn into_py(self, py: Python<'_>) -> PyResult<PyObject> {
+        match self {
+            BotEvent::Reconnect(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Disconnect(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Chat(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::BossBar(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Title(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Sound(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Particle(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Death(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Transfer(e) => Ok(Py::new(py, e)?.into_any()),
+            BotEvent::Crash(e) => Ok(Py::new(py, e)?.into_any()),
+        }
+    }
+}
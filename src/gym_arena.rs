@@ -0,0 +1,344 @@
+//! Gymnasium and PettingZoo environments over a FastArena
+//!
+//! in GymArena the agent plays fighter 1 and the env plays fighter 2, with a
+//! scripted opponent or a frozen Python policy, so a FastArena works as a
+//! `gymnasium.Env` without a wrapper of its own. ParallelArena is the
+//! PettingZoo ParallelEnv with both fighters as agents. a timeout is
+//! truncation, a death or a fall in the void termination
+
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::arena::{ActionInput, FastArena, FighterAction, StepResult, OBS_SIZE};
+use crate::live_env::{action_space, fighter_action};
+use crate::opponents::{Opponent, OpponentKind};
+
+/// who plays fighter 2
+enum Player {
+    Scripted(Opponent),
+    /// `policy(obs) -> FighterAction`, called with fighter 2's observation
+    Policy(PyObject),
+}
+
+#[pyclass]
+pub struct GymArena {
+    arena: FastArena,
+    player: Player,
+}
+
+/// Box of a FastArena observation, needs gymnasium
+fn observation_space(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    let spaces = py.import("gymnasium.spaces")?;
+    let numpy = py.import("numpy")?;
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("low", f32::NEG_INFINITY)?;
+    kwargs.set_item("high", f32::INFINITY)?;
+    kwargs.set_item("shape", (OBS_SIZE,))?;
+    kwargs.set_item("dtype", numpy.getattr("float32")?)?;
+    spaces.getattr("Box")?.call((), Some(&kwargs))
+}
+
+fn obs_array<'py>(py: Python<'py>, obs: Vec<f64>) -> Bound<'py, PyArray1<f32>> {
+    PyArray1::from_vec(py, obs.into_iter().map(|v| v as f32).collect())
+}
+
+#[pymethods]
+impl GymArena {
+    /// `opponent` is "rusher", "kiter", "eat_camper" or "random", or a
+    /// `policy(obs) -> FighterAction` (a dict of action_space is fine too).
+    /// `arena` is copied as the one to play in, a default FastArena without
+    /// it. `seed` is the random opponent's until reset() gets another
+    #[new]
+    #[pyo3(signature = (opponent, arena=None, seed=0))]
+    fn new(opponent: &Bound<'_, PyAny>, arena: Option<&FastArena>, seed: u64) -> PyResult<Self> {
+        let player = if let Ok(name) = opponent.extract::<&str>() {
+            Player::Scripted(Opponent::new(OpponentKind::parse(name)?, seed))
+        } else if opponent.is_callable() {
+            Player::Policy(opponent.clone().unbind())
+        } else {
+            return Err(PyValueError::new_err("opponent must be the name of a scripted opponent or a policy function"));
+        };
+        let mut arena = arena.cloned().unwrap_or_default();
+        arena.opponent = None;
+        Ok(Self { arena, player })
+    }
+
+    /// Box of the arena's observation, needs gymnasium
+    #[getter]
+    fn observation_space<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        observation_space(py)
+    }
+
+    /// Dict with a Discrete(2) per FighterAction flag, and Boxes for
+    /// delta_yaw/delta_pitch (degrees), needs gymnasium
+    #[getter]
+    fn action_space<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        action_space(py)
+    }
+
+    /// a copy of the FastArena being played in
+    #[getter]
+    fn arena(&self) -> FastArena {
+        self.arena.clone()
+    }
+
+    /// the scripted opponent's name, None for a policy
+    #[getter]
+    fn opponent(&self) -> Option<&'static str> {
+        match &self.player {
+            Player::Scripted(opponent) => Some(opponent.kind.name()),
+            Player::Policy(_) => None,
+        }
+    }
+
+    /// start an episode and return (obs, info). `seed` reseeds the random
+    /// opponent, `options` can have a "spawn_distance"
+    #[pyo3(signature = (seed=None, options=None))]
+    fn reset<'py>(
+        &mut self,
+        py: Python<'py>,
+        seed: Option<u64>,
+        options: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<(Bound<'py, PyArray1<f32>>, Bound<'py, PyDict>)> {
+        if let (Some(seed), Player::Scripted(opponent)) = (seed, &mut self.player) {
+            *opponent = Opponent::new(opponent.kind, seed);
+        }
+        let spawn_distance = match options {
+            Some(options) => options.get_item("spawn_distance")?.map(|d| d.extract()).transpose()?,
+            None => None,
+        };
+        self.arena.reset(spawn_distance, None, None);
+        let info = PyDict::new(py);
+        info.set_item("tick", 0)?;
+        Ok((obs_array(py, self.arena.get_obs1()), info))
+    }
+
+    /// play a FighterAction (or a dict of action_space) for fighter 1 and
+    /// return (obs, reward, terminated, truncated, info). info has the
+    /// tick, winner, winner_reason and the tick's events as in StepResult
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: &Bound<'py, PyAny>,
+    ) -> PyResult<(Bound<'py, PyArray1<f32>>, f64, bool, bool, Bound<'py, PyDict>)> {
+        let action = fighter_action(action)?;
+        let action2 = match &mut self.player {
+            Player::Scripted(opponent) => opponent.act(&self.arena, &self.arena.fighter2, &self.arena.fighter1),
+            Player::Policy(policy) => {
+                let chosen = policy.call1(py, (obs_array(py, self.arena.get_obs2()),))?;
+                fighter_action(chosen.bind(py))?
+            }
+        };
+        let result = self.arena.step_result(py, ActionInput(action), ActionInput(action2))?;
+        let info = self.info(py, &result)?;
+        Ok((obs_array(py, self.arena.get_obs1()), result.reward1, result.terminated, result.truncated, info))
+    }
+
+    /// what gymnasium.Env has, nothing renders
+    #[getter]
+    fn metadata<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let metadata = PyDict::new(py);
+        metadata.set_item("render_modes", Vec::<String>::new())?;
+        Ok(metadata)
+    }
+
+    #[getter]
+    fn render_mode(&self) -> Option<String> {
+        None
+    }
+
+    #[getter]
+    fn unwrapped(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[getter]
+    fn tick(&self) -> u32 {
+        self.arena.tick
+    }
+
+    fn close(&self) {}
+
+    fn __repr__(&self) -> String {
+        let opponent = self.opponent().unwrap_or("policy");
+        format!("GymArena(opponent={}, tick={}/{})", opponent, self.arena.tick, self.arena.max_ticks)
+    }
+}
+
+impl GymArena {
+    fn info<'py>(&self, py: Python<'py>, result: &StepResult) -> PyResult<Bound<'py, PyDict>> {
+        let info = PyDict::new(py);
+        info.set_item("tick", result.tick)?;
+        info.set_item("winner", result.winner)?;
+        info.set_item("winner_reason", result.winner_reason)?;
+        info.set_item("health", self.arena.fighter1.health)?;
+        info.set_item("opponent_health", self.arena.fighter2.health)?;
+        info.set_item("events", result.events.clone())?;
+        Ok(info)
+    }
+}
+
+/// ParallelEnv's agent names
+const AGENTS: [&str; 2] = ["fighter1", "fighter2"];
+
+#[pyclass]
+pub struct ParallelArena {
+    arena: FastArena,
+    /// the ones still playing, empty once the episode is over
+    #[pyo3(get)]
+    agents: Vec<&'static str>,
+}
+
+#[pymethods]
+impl ParallelArena {
+    /// `arena` is copied as the one to play in, a default FastArena without
+    /// it
+    #[new]
+    #[pyo3(signature = (arena=None))]
+    fn new(arena: Option<&FastArena>) -> Self {
+        let mut arena = arena.cloned().unwrap_or_default();
+        arena.opponent = None;
+        Self { arena, agents: vec![] }
+    }
+
+    #[getter]
+    fn possible_agents(&self) -> Vec<&'static str> {
+        AGENTS.to_vec()
+    }
+
+    #[getter]
+    fn num_agents(&self) -> usize {
+        self.agents.len()
+    }
+
+    #[getter]
+    fn max_num_agents(&self) -> usize {
+        AGENTS.len()
+    }
+
+    /// Box of the arena's observation, the same for both, needs
+    /// gymnasium
+    fn observation_space<'py>(&self, py: Python<'py>, agent: &str) -> PyResult<Bound<'py, PyAny>> {
+        agent_index(agent)?;
+        observation_space(py)
+    }
+
+    /// Dict of a FighterAction like GymArena's, needs gymnasium
+    fn action_space<'py>(&self, py: Python<'py>, agent: &str) -> PyResult<Bound<'py, PyAny>> {
+        agent_index(agent)?;
+        action_space(py)
+    }
+
+    /// start an episode and return (observations, infos), dicts by agent.
+    /// nothing in the arena is random, `seed` is ignored. `options` can have
+    /// a "spawn_distance"
+    #[pyo3(signature = (seed=None, options=None))]
+    fn reset<'py>(
+        &mut self,
+        py: Python<'py>,
+        seed: Option<u64>,
+        options: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<(Bound<'py, PyDict>, Bound<'py, PyDict>)> {
+        let _ = seed;
+        let spawn_distance = match options {
+            Some(options) => options.get_item("spawn_distance")?.map(|d| d.extract()).transpose()?,
+            None => None,
+        };
+        self.arena.reset(spawn_distance, None, None);
+        self.agents = AGENTS.to_vec();
+        let observations = self.observations(py)?;
+        let infos = PyDict::new(py);
+        for agent in AGENTS {
+            infos.set_item(agent, PyDict::new(py))?;
+        }
+        Ok((observations, infos))
+    }
+
+    /// step both with {"fighter1": action, "fighter2": action}, FighterActions
+    /// or dicts of action_space, and return (observations, rewards,
+    /// terminations, truncations, infos), dicts by agent. a missing action
+    /// does nothing. infos have the tick, winner, winner_reason, the
+    /// agent's health and the tick's events
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        actions: &Bound<'py, PyDict>,
+    ) -> PyResult<(Bound<'py, PyDict>, Bound<'py, PyDict>, Bound<'py, PyDict>, Bound<'py, PyDict>, Bound<'py, PyDict>)> {
+        let mut chosen = [FighterAction::default(), FighterAction::default()];
+        for (agent, action) in actions.iter() {
+            chosen[agent_index(agent.extract()?)?] = fighter_action(&action)?;
+        }
+        let [action1, action2] = chosen;
+        let result = self.arena.step_result(py, ActionInput(action1), ActionInput(action2))?;
+        let (rewards, terminations, truncations, infos) = (PyDict::new(py), PyDict::new(py), PyDict::new(py), PyDict::new(py));
+        let healths = [self.arena.fighter1.health, self.arena.fighter2.health];
+        for (i, agent) in AGENTS.into_iter().enumerate() {
+            rewards.set_item(agent, if i == 0 { result.reward1 } else { result.reward2 })?;
+            terminations.set_item(agent, result.terminated)?;
+            truncations.set_item(agent, result.truncated)?;
+            let info = PyDict::new(py);
+            info.set_item("tick", result.tick)?;
+            info.set_item("winner", result.winner)?;
+            info.set_item("winner_reason", result.winner_reason)?;
+            info.set_item("health", healths[i])?;
+            info.set_item("events", result.events.clone())?;
+            infos.set_item(agent, info)?;
+        }
+        let observations = self.observations(py)?;
+        if result.terminated || result.truncated {
+            self.agents.clear();
+        }
+        Ok((observations, rewards, terminations, truncations, infos))
+    }
+
+    /// what ParallelEnv has, nothing renders
+    #[getter]
+    fn metadata<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let metadata = PyDict::new(py);
+        metadata.set_item("name", "pyzalea_arena_v0")?;
+        metadata.set_item("render_modes", Vec::<String>::new())?;
+        Ok(metadata)
+    }
+
+    #[getter]
+    fn render_mode(&self) -> Option<String> {
+        None
+    }
+
+    #[getter]
+    fn unwrapped(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// a copy of the FastArena being played in
+    #[getter]
+    fn arena(&self) -> FastArena {
+        self.arena.clone()
+    }
+
+    fn close(&self) {}
+
+    fn __repr__(&self) -> String {
+        format!("ParallelArena(agents={}, tick={}/{})", self.agents.len(), self.arena.tick, self.arena.max_ticks)
+    }
+}
+
+impl ParallelArena {
+    fn observations<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let observations = PyDict::new(py);
+        observations.set_item(AGENTS[0], obs_array(py, self.arena.get_obs1()))?;
+        observations.set_item(AGENTS[1], obs_array(py, self.arena.get_obs2()))?;
+        Ok(observations)
+    }
+}
+
+fn agent_index(agent: &str) -> PyResult<usize> {
+    AGENTS
+        .iter()
+        .position(|&a| a == agent)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown agent {:?}, expected \"fighter1\" or \"fighter2\"", agent)))
+}
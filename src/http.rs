@@ -0,0 +1,105 @@
+//! The bit of HTTP start_viewer() and start_metrics_server() speak
+//!
+//! a server here is a nonblocking listener polled on its own thread until its
+//! Stop is set. each connection's request is read and answered on a thread of
+//! its own, so a client that never sends anything holds up nobody else
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use pyo3::exceptions::PyIOError;
+use pyo3::prelude::*;
+
+use crate::logging::CONNECTION;
+
+/// biggest request we'll read before giving up on a client
+const MAX_REQUEST: usize = 8192;
+/// for reading a request and writing its response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// a server's stop flag, set by stop() and when its Python object is
+/// garbage collected
+pub struct Stop(Arc<AtomicBool>);
+
+impl Stop {
+    pub fn new() -> Self {
+        Self(Arc::new(AtomicBool::new(false)))
+    }
+
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.0.clone()
+    }
+
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Stop {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// a nonblocking listener on host:port and the address it got, the real port
+/// if it was 0
+pub fn listen(host: &str, port: u16) -> PyResult<(TcpListener, SocketAddr)> {
+    let listener = TcpListener::bind((host, port))
+        .map_err(|e| PyIOError::new_err(format!("can't listen on {}:{}: {}", host, port, e)))?;
+    listener.set_nonblocking(true)?;
+    let address = listener.local_addr()?;
+    Ok((listener, address))
+}
+
+/// hand every connection waiting on `listener` to `handle` on a thread of its
+/// own, named `name`. doesn't block
+pub fn accept<F>(listener: &TcpListener, name: &'static str, handle: F)
+where
+    F: Fn(TcpStream, SocketAddr) -> io::Result<()> + Clone + Send + 'static,
+{
+    while let Ok((stream, address)) = listener.accept() {
+        let handle = handle.clone();
+        let spawned = std::thread::Builder::new().name(name.to_string()).spawn(move || {
+            if let Err(e) = handle(stream, address) {
+                tracing::debug!(target: CONNECTION, %address, "{} request failed: {}", name, e);
+            }
+        });
+        if let Err(e) = spawned {
+            tracing::debug!(target: CONNECTION, %address, "{} can't start a thread: {}", name, e);
+        }
+    }
+}
+
+/// the request's head up to the blank line, None if the client hung up or
+/// sent more than MAX_REQUEST without one
+pub fn read_request(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = vec![];
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf)?;
+        if n == 0 || request.len() > MAX_REQUEST {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(Some(String::from_utf8_lossy(&request).into_owned()))
+}
+
+/// a whole response with `headers` (each ending in \r\n), then the connection
+/// closes
+pub fn respond(stream: &mut TcpStream, status: &str, headers: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        headers,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,75 @@
+//! Item stacks as seen from Python
+
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use azalea_inventory::ItemStack;
+use azalea_inventory::components::{Damage, MaxDamage};
+
+/// a non-empty inventory slot
+#[pyclass]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Item {
+    /// item id without the namespace, like "diamond_sword"
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub count: i32,
+    /// damage taken so far, None for items that can't break
+    #[pyo3(get)]
+    pub damage: Option<i32>,
+    #[pyo3(get)]
+    pub max_damage: Option<i32>,
+}
+
+#[pymethods]
+impl Item {
+    /// uses left before it breaks
+    #[getter]
+    fn durability(&self) -> Option<i32> {
+        Some(self.max_damage? - self.damage.unwrap_or(0))
+    }
+
+    fn __repr__(&self) -> String {
+        match self.durability() {
+            Some(durability) => format!("Item({} x{}, durability={})", self.id, self.count, durability),
+            None => format!("Item({} x{})", self.id, self.count),
+        }
+    }
+}
+
+impl Item {
+    pub fn from_stack(stack: &ItemStack) -> Option<Self> {
+        let data = stack.as_present()?;
+        let id = data.kind.to_string();
+        let max_damage = data.get_component::<MaxDamage>().map(|m| m.amount);
+        Some(Self {
+            id: id.strip_prefix("minecraft:").unwrap_or(&id).to_string(),
+            count: data.count,
+            damage: max_damage.map(|_| data.get_component::<Damage>().map(|d| d.amount).unwrap_or(0)),
+            max_damage,
+        })
+    }
+}
+
+/// what using the held items does, in FighterAction terms
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HandUse {
+    /// or anything else that isn't a bow or a shield
+    Eat,
+    Draw,
+    Block,
+}
+
+/// the main hand goes first like in vanilla, so an offhand shield only comes up
+/// behind a weapon or an empty hand
+pub fn hand_use(main: Option<&str>, offhand: Option<&str>) -> HandUse {
+    let offhand_shield = offhand == Some("shield");
+    match main {
+        Some("bow") => HandUse::Draw,
+        Some("shield") => HandUse::Block,
+        None if offhand_shield => HandUse::Block,
+        Some(id) if offhand_shield && (id.ends_with("_sword") || id.ends_with("_axe")) => HandUse::Block,
+        _ => HandUse::Eat,
+    }
+}
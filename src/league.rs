@@ -0,0 +1,410 @@
+//! A league of FastArena players, rated by Elo
+//!
+//! players are Python policies (earlier snapshots of the one being trained,
+//! usually) and scripted opponents. play() samples a matchup for every arena
+//! of an ArenaVec, runs one episode in each and rates the results, keeping a
+//! matrix of wins and draws. a Python policy is called with get_obs1() or
+//! get_obs2() for its side and returns a FighterAction, or with a list of
+//! them and returns a list if it's batched, once per tick for all its arenas
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::arena::{ArenaVec, FastArena, FighterAction};
+use crate::opponents::{Opponent, OpponentKind};
+
+/// Elo's scale, 400 points is 10 to 1 odds
+const ELO_SCALE: f64 = 400.0;
+
+enum Policy {
+    Python { callable: PyObject, batched: bool },
+    Scripted(OpponentKind),
+}
+
+struct Player {
+    name: String,
+    policy: Policy,
+    rating: f64,
+}
+
+/// one episode of play(), `winner` is None for a draw
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct MatchResult {
+    /// fighter 1's player
+    #[pyo3(get)]
+    pub player1: String,
+    #[pyo3(get)]
+    pub player2: String,
+    #[pyo3(get)]
+    pub winner: Option<String>,
+    #[pyo3(get)]
+    pub ticks: u32,
+}
+
+#[pymethods]
+impl MatchResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "MatchResult(player1={:?}, player2={:?}, winner={}, ticks={})",
+            self.player1,
+            self.player2,
+            self.winner.as_ref().map_or("None".to_string(), |w| format!("{:?}", w)),
+            self.ticks
+        )
+    }
+}
+
+/// self-play opponents and their ratings, see the module docs
+#[pyclass]
+pub struct League {
+    players: Vec<Player>,
+    /// [winner][loser], in players order
+    wins: Vec<Vec<u32>>,
+    draws: Vec<Vec<u32>>,
+    /// how far one result moves a rating
+    #[pyo3(get, set)]
+    pub k: f64,
+    /// a new player's rating unless add_policy() gets one
+    #[pyo3(get, set)]
+    pub initial_rating: f64,
+    /// xorshift for matchups, sides and the scripted players' seeds
+    rng: u64,
+}
+
+/// player a's expected score against b, 1 for a sure win
+fn expected(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / ELO_SCALE))
+}
+
+impl League {
+    fn next(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    fn index(&self, name: &str) -> PyResult<usize> {
+        self.players.iter().position(|p| p.name == name).ok_or_else(|| {
+            let names: Vec<&str> = self.players.iter().map(|p| p.name.as_str()).collect();
+            PyValueError::new_err(format!("unknown player {:?}, the league has [{}]", name, names.join(", ")))
+        })
+    }
+
+    fn add(&mut self, name: String, policy: Policy, rating: Option<f64>) -> PyResult<()> {
+        if self.players.iter().any(|p| p.name == name) {
+            return Err(PyValueError::new_err(format!("the league already has a player {:?}", name)));
+        }
+        self.players.push(Player { name, policy, rating: rating.unwrap_or(self.initial_rating) });
+        for row in self.wins.iter_mut().chain(self.draws.iter_mut()) {
+            row.push(0);
+        }
+        let n = self.players.len();
+        self.wins.push(vec![0; n]);
+        self.draws.push(vec![0; n]);
+        Ok(())
+    }
+
+    /// two different players, `player` against a random other if it's set,
+    /// on random sides
+    fn pick(&mut self, player: Option<usize>) -> (usize, usize) {
+        let n = self.players.len() as u64;
+        let a = player.unwrap_or_else(|| (self.next() % n) as usize);
+        let b = (a + 1 + (self.next() % (n - 1)) as usize) % n as usize;
+        if self.next() & 1 == 0 { (a, b) } else { (b, a) }
+    }
+
+    /// a's score against b: 1 for a win, 0.5 for a draw, 0 for a loss
+    fn rate(&mut self, a: usize, b: usize, score: f64) {
+        let gain = self.k * (score - expected(self.players[a].rating, self.players[b].rating));
+        self.players[a].rating += gain;
+        self.players[b].rating -= gain;
+        if score > 0.5 {
+            self.wins[a][b] += 1;
+        } else if score < 0.5 {
+            self.wins[b][a] += 1;
+        } else {
+            self.draws[a][b] += 1;
+            self.draws[b][a] += 1;
+        }
+    }
+
+    /// every live fighter's action this tick, [fighter 1, fighter 2] per arena
+    fn actions(
+        &self,
+        py: Python<'_>,
+        arenas: &[FastArena],
+        matchups: &[(usize, usize)],
+        scripted: &mut [[Option<Opponent>; 2]],
+    ) -> PyResult<Vec<[FighterAction; 2]>> {
+        let mut actions = vec![[FighterAction::default(), FighterAction::default()]; arenas.len()];
+        for (p, player) in self.players.iter().enumerate() {
+            let seats: Vec<(usize, usize)> = (0..arenas.len())
+                .filter(|&i| !arenas[i].done)
+                .flat_map(|i| [(i, 0), (i, 1)])
+                .filter(|&(i, side)| [matchups[i].0, matchups[i].1][side] == p)
+                .collect();
+            if seats.is_empty() {
+                continue;
+            }
+            let obs = |(i, side): (usize, usize)| if side == 0 { arenas[i].get_obs1() } else { arenas[i].get_obs2() };
+            match &player.policy {
+                Policy::Scripted(_) => {
+                    for (i, side) in seats {
+                        let arena = &arenas[i];
+                        let (me, enemy) = if side == 0 { (&arena.fighter1, &arena.fighter2) } else { (&arena.fighter2, &arena.fighter1) };
+                        if let Some(opponent) = &mut scripted[i][side] {
+                            actions[i][side] = opponent.act(arena, me, enemy);
+                        }
+                    }
+                }
+                Policy::Python { callable, batched: true } => {
+                    let batch: Vec<Vec<f64>> = seats.iter().map(|&seat| obs(seat)).collect();
+                    let chosen: Vec<FighterAction> = callable.call1(py, (batch,))?.extract(py)?;
+                    if chosen.len() != seats.len() {
+                        return Err(PyValueError::new_err(format!(
+                            "{}'s policy got {} observations and returned {} actions",
+                            player.name,
+                            seats.len(),
+                            chosen.len()
+                        )));
+                    }
+                    for ((i, side), action) in seats.into_iter().zip(chosen) {
+                        actions[i][side] = action;
+                    }
+                }
+                Policy::Python { callable, batched: false } => {
+                    for (i, side) in seats {
+                        actions[i][side] = callable.call1(py, (obs((i, side)),))?.extract(py)?;
+                    }
+                }
+            }
+        }
+        Ok(actions)
+    }
+}
+
+#[pymethods]
+impl League {
+    #[new]
+    #[pyo3(signature = (k=32.0, initial_rating=1200.0, seed=0))]
+    fn new(k: f64, initial_rating: f64, seed: u64) -> Self {
+        Self {
+            players: vec![],
+            wins: vec![],
+            draws: vec![],
+            k,
+            initial_rating,
+            rng: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    /// a Python policy, see the module docs. a snapshot of the one being
+    /// trained can start from its rating
+    #[pyo3(signature = (name, policy, batched=false, rating=None))]
+    fn add_policy(&mut self, name: String, policy: PyObject, batched: bool, rating: Option<f64>) -> PyResult<()> {
+        self.add(name, Policy::Python { callable: policy, batched }, rating)
+    }
+
+    /// a scripted opponent, "rusher", "kiter", "eat_camper" or "random"
+    #[pyo3(signature = (name, opponent, rating=None))]
+    fn add_bot(&mut self, name: String, opponent: &str, rating: Option<f64>) -> PyResult<()> {
+        self.add(name, Policy::Scripted(OpponentKind::parse(opponent)?), rating)
+    }
+
+    /// drop a player with its row and column of the matrices
+    fn remove(&mut self, name: &str) -> PyResult<()> {
+        let idx = self.index(name)?;
+        self.players.remove(idx);
+        for matrix in [&mut self.wins, &mut self.draws] {
+            matrix.remove(idx);
+            for row in matrix.iter_mut() {
+                row.remove(idx);
+            }
+        }
+        Ok(())
+    }
+
+    /// names in the order of the matrices
+    #[getter]
+    fn players(&self) -> Vec<String> {
+        self.players.iter().map(|p| p.name.clone()).collect()
+    }
+
+    fn rating(&self, name: &str) -> PyResult<f64> {
+        Ok(self.players[self.index(name)?].rating)
+    }
+
+    /// (name, rating), best first
+    fn leaderboard(&self) -> Vec<(String, f64)> {
+        let mut board: Vec<(String, f64)> = self.players.iter().map(|p| (p.name.clone(), p.rating)).collect();
+        board.sort_by(|a, b| b.1.total_cmp(&a.1));
+        board
+    }
+
+    /// wins[i][j] is how often players[i] beat players[j]
+    #[getter]
+    fn wins(&self) -> Vec<Vec<u32>> {
+        self.wins.clone()
+    }
+
+    #[getter]
+    fn draws(&self) -> Vec<Vec<u32>> {
+        self.draws.clone()
+    }
+
+    /// the chance `a` beats `b` by their ratings
+    fn win_probability(&self, a: &str, b: &str) -> PyResult<f64> {
+        Ok(expected(self.players[self.index(a)?].rating, self.players[self.index(b)?].rating))
+    }
+
+    /// (fighter 1's player, fighter 2's player), two random players, or
+    /// `player` against a random other one
+    #[pyo3(signature = (player=None))]
+    fn matchup(&mut self, player: Option<&str>) -> PyResult<(String, String)> {
+        if self.players.len() < 2 {
+            return Err(PyValueError::new_err("the league needs at least two players"));
+        }
+        let player = player.map(|name| self.index(name)).transpose()?;
+        let (a, b) = self.pick(player);
+        Ok((self.players[a].name.clone(), self.players[b].name.clone()))
+    }
+
+    /// rate a match played elsewhere, `winner` None for a draw
+    #[pyo3(signature = (player1, player2, winner))]
+    fn record(&mut self, player1: &str, player2: &str, winner: Option<&str>) -> PyResult<()> {
+        let (a, b) = (self.index(player1)?, self.index(player2)?);
+        if a == b {
+            return Err(PyValueError::new_err("a player can't play itself"));
+        }
+        let score = match winner {
+            None => 0.5,
+            Some(name) if name == player1 => 1.0,
+            Some(name) if name == player2 => 0.0,
+            Some(name) => return Err(PyValueError::new_err(format!("winner {:?} didn't play", name))),
+        };
+        self.rate(a, b, score);
+        Ok(())
+    }
+
+    /// reset every arena, give each a matchup (`player` in all of them if it's
+    /// set) and step them all until their episodes end, then rate the results
+    /// in arena order
+    #[pyo3(signature = (arenas, player=None))]
+    fn play(&mut self, py: Python<'_>, mut arenas: PyRefMut<'_, ArenaVec>, player: Option<&str>) -> PyResult<Vec<MatchResult>> {
+        if self.players.len() < 2 {
+            return Err(PyValueError::new_err("the league needs at least two players"));
+        }
+        let player = player.map(|name| self.index(name)).transpose()?;
+        let arenas = &mut arenas.arenas;
+        let matchups: Vec<(usize, usize)> = (0..arenas.len()).map(|_| self.pick(player)).collect();
+        let mut scripted: Vec<[Option<Opponent>; 2]> = vec![];
+        for &(a, b) in &matchups {
+            let seeds = [self.next(), self.next()];
+            scripted.push([a, b].map(|p| match self.players[p].policy {
+                Policy::Scripted(kind) => Some(Opponent::new(kind, seeds[usize::from(p == b)])),
+                Policy::Python { .. } => None,
+            }));
+        }
+        for arena in arenas.iter_mut() {
+            arena.reset(None, None, None);
+        }
+        while arenas.iter().any(|arena| !arena.done) {
+            let actions = self.actions(py, arenas, &matchups, &mut scripted)?;
+            for (arena, [action1, action2]) in arenas.iter_mut().zip(&actions) {
+                if !arena.done {
+                    arena.simulate(action1, action2);
+                }
+            }
+        }
+        let mut results = vec![];
+        for (arena, &(a, b)) in arenas.iter().zip(&matchups) {
+            let (score, winner) = match arena.winner {
+                1 => (1.0, Some(a)),
+                2 => (0.0, Some(b)),
+                _ => (0.5, None),
+            };
+            self.rate(a, b, score);
+            results.push(MatchResult {
+                player1: self.players[a].name.clone(),
+                player2: self.players[b].name.clone(),
+                winner: winner.map(|w| self.players[w].name.clone()),
+                ticks: arena.tick,
+            });
+        }
+        Ok(results)
+    }
+
+    fn __len__(&self) -> usize {
+        self.players.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let board: Vec<String> = self.leaderboard().iter().map(|(name, rating)| format!("{:?}: {:.0}", name, rating)).collect();
+        format!("League({{{}}})", board.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn league(players: usize) -> League {
+        let mut league = League::new(32.0, 1200.0, 7);
+        for i in 0..players {
+            league.add(format!("p{}", i), Policy::Scripted(OpponentKind::Rusher), None).unwrap();
+        }
+        league
+    }
+
+    #[test]
+    fn expected_scores() {
+        assert_eq!(expected(1500.0, 1500.0), 0.5);
+        assert!((expected(1600.0, 1200.0) - 10.0 / 11.0).abs() < 1e-12);
+        assert!((expected(1234.0, 987.0) + expected(987.0, 1234.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rate_is_zero_sum() {
+        let mut league = league(3);
+        league.players[2].rating = 1400.0;
+        for (a, b, score) in [(0, 1, 1.0), (1, 2, 0.5), (2, 0, 0.0), (0, 2, 1.0)] {
+            league.rate(a, b, score);
+        }
+        let total: f64 = league.players.iter().map(|p| p.rating).sum();
+        assert!((total - 3800.0).abs() < 1e-9);
+        // an even match moves k/2
+        let mut even = self::league(2);
+        even.rate(0, 1, 1.0);
+        assert_eq!(even.players[0].rating, 1216.0);
+        assert_eq!(even.players[1].rating, 1184.0);
+    }
+
+    #[test]
+    fn rate_fills_the_matrices() {
+        let mut league = league(3);
+        league.rate(0, 1, 1.0);
+        league.rate(0, 1, 1.0);
+        league.rate(2, 1, 0.0);
+        league.rate(0, 2, 0.5);
+        assert_eq!(league.wins, vec![vec![0, 2, 0], vec![0, 0, 1], vec![0, 0, 0]]);
+        assert_eq!(league.draws, vec![vec![0, 0, 1], vec![0, 0, 0], vec![1, 0, 0]]);
+        league.remove("p1").unwrap();
+        assert_eq!(league.wins, vec![vec![0, 0], vec![0, 0]]);
+        assert_eq!(league.draws, vec![vec![0, 1], vec![1, 0]]);
+    }
+
+    #[test]
+    fn pick_never_pairs_a_player_with_itself() {
+        for n in 2..6 {
+            let mut league = league(n);
+            for _ in 0..500 {
+                let (a, b) = league.pick(None);
+                assert!(a != b && a < n && b < n);
+                let (a, b) = league.pick(Some(n - 1));
+                assert!(a != b && (a == n - 1 || b == n - 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Block and sky light, which azalea receives with every chunk but drops
+
+use std::collections::HashMap;
+
+use azalea::BlockPos;
+use azalea_protocol::packets::game::c_light_update::ClientboundLightUpdatePacketData;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+/// one nibble per block, 16x16x16
+const SECTION_BYTES: usize = 2048;
+
+/// light sections of one chunk, one extra below and above the world.
+/// an empty slice is a section that's all dark, None one we never got
+#[derive(Debug, Default)]
+struct ChunkLight {
+    sky: Vec<Option<Box<[u8]>>>,
+    block: Vec<Option<Box<[u8]>>>,
+}
+
+impl ChunkLight {
+    fn update(&mut self, data: &ClientboundLightUpdatePacketData) {
+        apply(&mut self.sky, data.sky_y_mask.iter_ones(), &data.sky_updates);
+        apply(&mut self.block, data.block_y_mask.iter_ones(), &data.block_updates);
+        for i in data.empty_sky_y_mask.iter_ones() {
+            set(&mut self.sky, i, Some(Box::default()));
+        }
+        for i in data.empty_block_y_mask.iter_ones() {
+            set(&mut self.block, i, Some(Box::default()));
+        }
+    }
+}
+
+fn set(sections: &mut Vec<Option<Box<[u8]>>>, index: usize, section: Option<Box<[u8]>>) {
+    if sections.len() <= index {
+        sections.resize(index + 1, None);
+    }
+    sections[index] = section;
+}
+
+fn apply(sections: &mut Vec<Option<Box<[u8]>>>, indices: impl Iterator<Item = usize>, updates: &[Vec<u8>]) {
+    for (index, update) in indices.zip(updates) {
+        if update.len() == SECTION_BYTES {
+            set(sections, index, Some(update.clone().into_boxed_slice()));
+        }
+    }
+}
+
+/// `missing` for a section we never got, which for sky light is open sky
+/// like above the highest one sent
+fn nibble(sections: &[Option<Box<[u8]>>], index: usize, pos: BlockPos, missing: u8) -> u8 {
+    let Some(Some(section)) = sections.get(index) else {
+        return missing;
+    };
+    if section.is_empty() {
+        return 0;
+    }
+    let i = (((pos.y & 15) << 8) | ((pos.z & 15) << 4) | (pos.x & 15)) as usize;
+    let byte = section[i >> 1];
+    if i & 1 == 0 { byte & 0xf } else { byte >> 4 }
+}
+
+/// keyed by chunk (x, z)
+#[derive(Debug, Default)]
+pub struct LightLevels {
+    chunks: HashMap<(i32, i32), ChunkLight>,
+}
+
+impl LightLevels {
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::LevelChunkWithLight(p) => {
+                let mut chunk = ChunkLight::default();
+                chunk.update(&p.light_data);
+                self.chunks.insert((p.x, p.z), chunk);
+            }
+            ClientboundGamePacket::LightUpdate(p) => {
+                self.chunks.entry((p.x, p.z)).or_default().update(&p.light_data);
+            }
+            ClientboundGamePacket::ForgetLevelChunk(p) => {
+                self.chunks.remove(&(p.pos.x, p.pos.z));
+            }
+            ClientboundGamePacket::Respawn(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// (block light, sky light) at `pos`, 0-15 each. sky light is how much of
+    /// the sky it sees, not how bright the sky is right now. `min_y` is the
+    /// world's lowest y
+    pub fn get(&self, pos: BlockPos, min_y: i32) -> Option<(u8, u8)> {
+        let chunk = self.chunks.get(&(pos.x >> 4, pos.z >> 4))?;
+        // section 0 is the one below the world
+        let index = usize::try_from((pos.y >> 4) - (min_y >> 4) + 1).ok()?;
+        Some((nibble(&chunk.block, index, pos, 0), nibble(&chunk.sky, index, pos, 15)))
+    }
+}
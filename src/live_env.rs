@@ -0,0 +1,314 @@
+//! Gymnasium-style PvP environment on a real server
+//!
+//! takes the same FighterAction the FastArena does, so agents trained in the
+//! sim can keep learning against a live opponent. resetting runs commands, so
+//! the bot needs op
+
+use numpy::PyArray1;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::arena::FighterAction;
+use crate::bot::PyBot;
+use crate::item::{hand_use, HandUse};
+use crate::observation::ObservationConfig;
+use crate::rewards::RewardConfig;
+use crate::state::PyGameState;
+
+/// run for the bot and the opponent on every reset, {player} is their name
+const DEFAULT_KIT: &[&str] = &[
+    "/clear {player}",
+    "/effect clear {player}",
+    "/item replace entity {player} armor.head with minecraft:iron_helmet",
+    "/item replace entity {player} armor.chest with minecraft:iron_chestplate",
+    "/item replace entity {player} armor.legs with minecraft:iron_leggings",
+    "/item replace entity {player} armor.feet with minecraft:iron_boots",
+    "/item replace entity {player} hotbar.0 with minecraft:iron_sword",
+    "/effect give {player} minecraft:instant_health 1 10",
+    "/effect give {player} minecraft:saturation 1 10",
+];
+
+#[pyclass]
+pub struct LivePvPEnv {
+    bot: Py<PyBot>,
+    /// username of who we're fighting
+    #[pyo3(get)]
+    opponent: String,
+    config: ObservationConfig,
+    kit: Vec<String>,
+    spawn: Option<(f64, f64, f64)>,
+    opponent_spawn: Option<(f64, f64, f64)>,
+    /// steps before an episode is truncated
+    #[pyo3(get)]
+    max_steps: u32,
+    /// ticks to wait after the reset commands for them to take effect
+    #[pyo3(get)]
+    settle_ticks: u32,
+    #[pyo3(get)]
+    steps: u32,
+    /// damage_dealt, damage_taken, win and loss weigh step()'s reward
+    #[pyo3(get, set)]
+    rewards: RewardConfig,
+    health: f32,
+    /// None while the opponent is out of sight
+    opponent_health: Option<f32>,
+}
+
+#[pymethods]
+impl LivePvPEnv {
+    /// kit is a list of commands run for both players on reset ({player} is
+    /// replaced with their name), spawn/opponent_spawn are where to /tp them.
+    /// `rewards` for other weights than FastArena's defaults
+    #[new]
+    #[pyo3(signature = (bot, opponent, kit=None, spawn=None, opponent_spawn=None, config=None, max_steps=1200, settle_ticks=20, rewards=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        bot: Py<PyBot>,
+        opponent: String,
+        kit: Option<Vec<String>>,
+        spawn: Option<(f64, f64, f64)>,
+        opponent_spawn: Option<(f64, f64, f64)>,
+        config: Option<ObservationConfig>,
+        max_steps: u32,
+        settle_ticks: u32,
+        rewards: Option<RewardConfig>,
+    ) -> Self {
+        Self {
+            bot,
+            opponent,
+            config: config.unwrap_or_default(),
+            kit: kit.unwrap_or_else(|| DEFAULT_KIT.iter().map(|c| c.to_string()).collect()),
+            spawn,
+            opponent_spawn,
+            max_steps,
+            settle_ticks,
+            steps: 0,
+            rewards: rewards.unwrap_or_default(),
+            health: 0.0,
+            opponent_health: None,
+        }
+    }
+
+    /// Box of to_vector() floats, needs gymnasium
+    #[getter]
+    fn observation_space<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let spaces = py.import("gymnasium.spaces")?;
+        let numpy = py.import("numpy")?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("low", f32::NEG_INFINITY)?;
+        kwargs.set_item("high", f32::INFINITY)?;
+        kwargs.set_item("shape", (self.config.feature_names().len(),))?;
+        kwargs.set_item("dtype", numpy.getattr("float32")?)?;
+        spaces.getattr("Box")?.call((), Some(&kwargs))
+    }
+
+    /// Dict with a Discrete(2) per FighterAction flag, and Boxes for
+    /// delta_yaw/delta_pitch (degrees), needs gymnasium
+    #[getter]
+    fn action_space<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        action_space(py)
+    }
+
+    /// re-arm both players, /tp them to their spawns and return (obs, info)
+    #[pyo3(signature = (seed=None, options=None))]
+    fn reset<'py>(
+        &mut self,
+        py: Python<'py>,
+        seed: Option<u64>,
+        options: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<(Bound<'py, PyArray1<f32>>, Bound<'py, PyDict>)> {
+        // nothing random to seed, the server decides
+        let _ = (seed, options);
+        let bot = self.bot.bind(py);
+        if !bot.call_method0("is_op")?.extract::<bool>()? {
+            return Err(PyRuntimeError::new_err("LivePvPEnv needs the bot to be op to run its reset commands"));
+        }
+        let me = bot.getattr("username")?.extract::<String>()?;
+        let mut commands = vec![];
+        for player in [&me, &self.opponent] {
+            commands.extend(self.kit.iter().map(|c| c.replace("{player}", player)));
+        }
+        for (player, spawn) in [(&me, self.spawn), (&self.opponent, self.opponent_spawn)] {
+            if let Some((x, y, z)) = spawn {
+                commands.push(format!("/tp {} {} {} {}", player, x, y, z));
+            }
+        }
+        for command in commands {
+            bot.call_method1("chat", (command,))?;
+        }
+
+        let mut state = bot.call_method0("get_state")?.extract::<PyGameState>()?;
+        for _ in 0..self.settle_ticks {
+            state = bot.call_method0("tick")?.extract()?;
+        }
+        self.steps = 0;
+        self.health = state.health;
+        self.opponent_health = self.opponent_health(py)?;
+        let info = self.info(py, &state, 0.0, 0.0)?;
+        Ok((PyArray1::from_vec(py, self.config.vector(&state)), info))
+    }
+
+    /// apply a FighterAction (or a dict from action_space) for one tick and
+    /// return (obs, reward, terminated, truncated, info)
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: &Bound<'py, PyAny>,
+    ) -> PyResult<(Bound<'py, PyArray1<f32>>, f64, bool, bool, Bound<'py, PyDict>)> {
+        let action = fighter_action(action)?;
+        let bot = self.bot.bind(py);
+        let before = bot.call_method0("get_state")?.extract::<PyGameState>()?;
+
+        if action.attack {
+            bot.call_method1("attack_player", (&self.opponent,))?;
+        }
+        if action.eat {
+            bot.call_method0("use_held_item")?;
+        }
+        let main = before.held_item_id.as_deref();
+        if action.pearl && main == Some("ender_pearl") {
+            bot.call_method0("use_held_item")?;
+        }
+        if action.potion && main == Some("splash_potion") {
+            bot.call_method0("use_held_item")?;
+        }
+        if action.rod && main == Some("fishing_rod") {
+            bot.call_method0("use_held_item")?;
+        }
+        // the bow shoots and the shield drops when they're let go
+        match hand_use(main, before.offhand_id.as_deref()) {
+            HandUse::Draw if action.draw => {
+                bot.call_method0("use_held_item")?;
+            }
+            HandUse::Block if action.block => {
+                bot.call_method1("use_held_item", (main != Some("shield"),))?;
+            }
+            HandUse::Draw | HandUse::Block if before.is_using_item && !action.eat => {
+                bot.call_method0("release_held_item")?;
+            }
+            _ => {}
+        }
+        // PyBot.step() does the movement and the tick
+        let movement = PyDict::new(py);
+        for (key, value) in [
+            ("forward", action.forward),
+            ("backward", action.backward),
+            ("left", action.left),
+            ("right", action.right),
+            ("jump", action.jump),
+            ("sprint", action.sprint),
+        ] {
+            movement.set_item(key, value)?;
+        }
+        movement.set_item("yaw", before.yaw + action.delta_yaw as f32)?;
+        movement.set_item("pitch", (before.pitch + action.delta_pitch as f32).clamp(-90.0, 90.0))?;
+        let state = bot.call_method1("step", (movement,))?.extract::<PyGameState>()?;
+        self.steps += 1;
+
+        let opponent_health = self.opponent_health(py)?;
+        let taken = (self.health - state.health).max(0.0) as f64;
+        let dealt = match (self.opponent_health, opponent_health) {
+            (Some(before), Some(after)) => (before - after).max(0.0) as f64,
+            _ => 0.0,
+        };
+        let w = &self.rewards;
+        let mut reward = dealt * w.damage_dealt + taken * w.damage_taken;
+        // only a drop we saw counts as a kill, 0.0 can also mean "not sent"
+        let killed = self.opponent_health.is_some_and(|h| h > 0.0) && opponent_health.is_some_and(|h| h <= 0.0);
+        let died = state.is_dead || state.health <= 0.0;
+        if killed {
+            reward += w.win;
+        }
+        if died {
+            reward += w.loss;
+        }
+        self.health = state.health;
+        self.opponent_health = opponent_health;
+
+        let terminated = killed || died;
+        let truncated = !terminated && self.steps >= self.max_steps;
+        let info = self.info(py, &state, dealt, taken)?;
+        Ok((PyArray1::from_vec(py, self.config.vector(&state)), reward, terminated, truncated, info))
+    }
+
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        self.bot.bind(py).call_method0("stop")?;
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("LivePvPEnv(opponent={}, steps={}/{})", self.opponent, self.steps, self.max_steps)
+    }
+}
+
+impl LivePvPEnv {
+    fn opponent_health(&self, py: Python<'_>) -> PyResult<Option<f32>> {
+        let opponent = self.bot.bind(py).call_method1("get_player_state", (&self.opponent,))?;
+        if opponent.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(opponent.getattr("health")?.extract()?))
+    }
+
+    fn info<'py>(&self, py: Python<'py>, state: &PyGameState, dealt: f64, taken: f64) -> PyResult<Bound<'py, PyDict>> {
+        let info = PyDict::new(py);
+        info.set_item("health", state.health)?;
+        info.set_item("opponent_health", self.opponent_health)?;
+        info.set_item("damage_dealt", dealt)?;
+        info.set_item("damage_taken", taken)?;
+        info.set_item("tick", state.tick)?;
+        Ok(info)
+    }
+}
+
+/// the Dict space of FighterActions, needs gymnasium
+pub(crate) fn action_space(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    let spaces = py.import("gymnasium.spaces")?;
+    let fields = PyDict::new(py);
+    for flag in ["forward", "backward", "left", "right", "jump", "sprint", "attack", "eat", "draw", "block", "pearl", "potion", "rod"] {
+        fields.set_item(flag, spaces.getattr("Discrete")?.call1((2,))?)?;
+    }
+    let turn = |limit: f32| spaces.getattr("Box")?.call1((-limit, limit, ()));
+    fields.set_item("delta_yaw", turn(180.0)?)?;
+    fields.set_item("delta_pitch", turn(90.0)?)?;
+    spaces.getattr("Dict")?.call1((fields,))
+}
+
+/// a FighterAction, or a dict with its field names (numpy values are fine)
+pub(crate) fn fighter_action(action: &Bound<'_, PyAny>) -> PyResult<FighterAction> {
+    if let Ok(action) = action.extract::<FighterAction>() {
+        return Ok(action);
+    }
+    let dict = action.downcast::<PyDict>()?;
+    let flag = |key: &str| -> PyResult<bool> {
+        Ok(match dict.get_item(key)? {
+            Some(value) => value.is_truthy()?,
+            None => false,
+        })
+    };
+    let degrees = |key: &str| -> PyResult<f64> {
+        Ok(match dict.get_item(key)? {
+            Some(value) => value.extract()?,
+            None => 0.0,
+        })
+    };
+    Ok(FighterAction {
+        forward: flag("forward")?,
+        backward: flag("backward")?,
+        left: flag("left")?,
+        right: flag("right")?,
+        jump: flag("jump")?,
+        sprint: flag("sprint")?,
+        attack: flag("attack")?,
+        eat: flag("eat")?,
+        delta_yaw: degrees("delta_yaw")?,
+        delta_pitch: degrees("delta_pitch")?,
+        draw: flag("draw")?,
+        block: flag("block")?,
+        pearl: flag("pearl")?,
+        potion: flag("potion")?,
+        rod: flag("rod")?,
+    })
+}
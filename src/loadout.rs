@@ -0,0 +1,277 @@
+//! Weapons and armor for FastArena fighters
+//!
+//! a Loadout turns vanilla 1.21 gear into an arena Kit: the weapon's damage
+//! and attack speed, and the armor's points and toughness, which reduce each
+//! hit the way vanilla does (more for weak hits) instead of by a flat fraction.
+//! in 1.8 combat the weapons hit like they did in 1.8
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::arena::{check_potion, effect_index, CombatVersion, Kit};
+use crate::effects::Effect;
+
+/// (name, damage, ticks between full-strength hits, 1.8's damage), from the
+/// attack speed. the damage includes the fist's 1. netherite is 1.8's diamond
+/// plus one like the rest of the tiers
+const WEAPONS: [(&str, f64, u32, f64); 12] = [
+    ("wooden_sword", 4.0, 13, 5.0),
+    ("golden_sword", 4.0, 13, 5.0),
+    ("stone_sword", 5.0, 13, 6.0),
+    ("iron_sword", 6.0, 13, 7.0),
+    ("diamond_sword", 7.0, 13, 8.0),
+    ("netherite_sword", 8.0, 13, 9.0),
+    ("wooden_axe", 7.0, 25, 4.0),
+    ("golden_axe", 7.0, 20, 4.0),
+    ("stone_axe", 9.0, 25, 5.0),
+    ("iron_axe", 9.0, 22, 6.0),
+    ("diamond_axe", 9.0, 20, 7.0),
+    ("netherite_axe", 10.0, 20, 8.0),
+];
+/// an empty hand
+pub(crate) const FIST: (f64, u32, f64) = (1.0, 5, 1.0);
+
+/// (material, helmet/chestplate/leggings/boots armor points, toughness per piece)
+const ARMOR: [(&str, [f64; 4], f64); 6] = [
+    ("leather", [1.0, 3.0, 2.0, 1.0], 0.0),
+    ("golden", [2.0, 5.0, 3.0, 1.0], 0.0),
+    ("chainmail", [2.0, 5.0, 4.0, 1.0], 0.0),
+    ("iron", [2.0, 6.0, 5.0, 2.0], 0.0),
+    ("diamond", [3.0, 8.0, 6.0, 3.0], 2.0),
+    ("netherite", [3.0, 8.0, 6.0, 3.0], 3.0),
+];
+const PIECES: [&str; 4] = ["helmet", "chestplate", "leggings", "boots"];
+
+fn weapon(name: &str) -> Option<(f64, u32, f64)> {
+    WEAPONS.iter().find(|(w, _, _, _)| *w == name).map(|(_, damage, ticks, legacy)| (*damage, *ticks, *legacy))
+}
+
+fn armor(material: &str) -> Option<([f64; 4], f64)> {
+    ARMOR.iter().find(|(m, _, _)| *m == material).map(|(_, points, toughness)| (*points, *toughness))
+}
+
+/// "minecraft:diamond_helmet" or "diamond" -> "diamond"
+fn material(piece: usize, name: Option<String>) -> PyResult<Option<String>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    let name = name.strip_prefix("minecraft:").unwrap_or(&name);
+    let suffix = format!("_{}", PIECES[piece]);
+    let material = name.strip_suffix(&suffix).unwrap_or(name);
+    if armor(material).is_none() {
+        let materials: Vec<&str> = ARMOR.iter().map(|(m, _, _)| *m).collect();
+        return Err(PyValueError::new_err(format!(
+            "unknown {} {:?}, expected one of {}",
+            PIECES[piece],
+            name,
+            materials.join(", ")
+        )));
+    }
+    Ok(Some(material.to_string()))
+}
+
+/// a fighter's weapon and armor, see FastArena.reset()
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Loadout {
+    /// like "iron_sword" or "netherite_axe", None for an empty hand
+    #[pyo3(get)]
+    pub weapon: Option<String>,
+    /// armor materials, like "diamond", None for no piece
+    #[pyo3(get)]
+    pub helmet: Option<String>,
+    #[pyo3(get)]
+    pub chestplate: Option<String>,
+    #[pyo3(get)]
+    pub leggings: Option<String>,
+    #[pyo3(get)]
+    pub boots: Option<String>,
+    #[pyo3(get)]
+    pub steaks: u32,
+    /// for the bow
+    #[pyo3(get)]
+    pub arrows: u32,
+    /// in the offhand, for FighterAction.block
+    #[pyo3(get)]
+    pub shield: bool,
+    #[pyo3(get)]
+    pub pearls: u32,
+    /// potion effects at the start of every episode
+    #[pyo3(get)]
+    pub effects: Vec<Effect>,
+    /// splash potions to throw, all of them `potion`
+    #[pyo3(get)]
+    pub potions: u32,
+    #[pyo3(get)]
+    pub potion: Option<Effect>,
+    #[pyo3(get)]
+    pub rod: bool,
+    /// the weapon's enchantment levels
+    #[pyo3(get)]
+    pub sharpness: u32,
+    #[pyo3(get)]
+    pub knockback: u32,
+}
+
+impl Loadout {
+    fn pieces(&self) -> [&Option<String>; 4] {
+        [&self.helmet, &self.chestplate, &self.leggings, &self.boots]
+    }
+
+    fn weapon_stats(&self) -> (f64, u32, f64) {
+        self.weapon.as_deref().and_then(weapon).unwrap_or(FIST)
+    }
+
+    /// for an arena with `version`'s combat
+    pub fn kit(&self, version: CombatVersion) -> Kit {
+        let (damage, ticks, legacy_damage) = self.weapon_stats();
+        Kit {
+            weapon_damage: if version == CombatVersion::Legacy { legacy_damage } else { damage },
+            armor_points: Some(self.armor_points()),
+            armor_toughness: self.armor_toughness(),
+            attack_cooldown_ticks: Some(ticks),
+            steaks: self.steaks,
+            arrows: self.arrows,
+            shield: self.shield,
+            disables_shields: self.disables_shields(),
+            pearls: self.pearls,
+            effects: self.effects.clone(),
+            potions: self.potions,
+            potion: self.potion.clone(),
+            rod: self.rod,
+            sharpness: self.sharpness,
+            knockback: self.knockback,
+            sword: self.weapon.as_deref().is_some_and(|w| w.ends_with("_sword")),
+            ..Kit::default()
+        }
+    }
+}
+
+#[pymethods]
+impl Loadout {
+    /// armor pieces take a material ("diamond") or the item ("diamond_helmet").
+    /// the defaults are the arena's usual iron sword and diamond armor
+    #[new]
+    #[pyo3(signature = (
+        weapon=Some("iron_sword".to_string()),
+        helmet=Some("diamond".to_string()),
+        chestplate=Some("diamond".to_string()),
+        leggings=Some("diamond".to_string()),
+        boots=Some("diamond".to_string()),
+        steaks=64,
+        arrows=0,
+        shield=false,
+        pearls=0,
+        effects=vec![],
+        potions=0,
+        potion=None,
+        rod=false,
+        sharpness=0,
+        knockback=0,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        weapon: Option<String>,
+        helmet: Option<String>,
+        chestplate: Option<String>,
+        leggings: Option<String>,
+        boots: Option<String>,
+        steaks: u32,
+        arrows: u32,
+        shield: bool,
+        pearls: u32,
+        effects: Vec<Effect>,
+        potions: u32,
+        potion: Option<Effect>,
+        rod: bool,
+        sharpness: u32,
+        knockback: u32,
+    ) -> PyResult<Self> {
+        let weapon = weapon.map(|w| w.strip_prefix("minecraft:").unwrap_or(&w).to_string());
+        if let Some(name) = &weapon {
+            if self::weapon(name).is_none() {
+                let names: Vec<&str> = WEAPONS.iter().map(|(w, _, _, _)| *w).collect();
+                return Err(PyValueError::new_err(format!(
+                    "unknown weapon {:?}, expected one of {}",
+                    name,
+                    names.join(", ")
+                )));
+            }
+        }
+        for effect in &effects {
+            effect_index(&effect.name)?;
+        }
+        potion.as_ref().map_or(Ok(()), check_potion)?;
+        Ok(Self {
+            weapon,
+            helmet: material(0, helmet)?,
+            chestplate: material(1, chestplate)?,
+            leggings: material(2, leggings)?,
+            boots: material(3, boots)?,
+            steaks,
+            arrows,
+            shield,
+            pearls,
+            effects,
+            potions,
+            potion,
+            rod,
+            sharpness,
+            knockback,
+        })
+    }
+
+    /// damage of a hit before armor and sharpness, in 1.21 combat
+    #[getter]
+    fn weapon_damage(&self) -> f64 {
+        self.weapon_stats().0
+    }
+
+    /// ticks between full-strength hits
+    #[getter]
+    fn attack_cooldown_ticks(&self) -> u32 {
+        self.weapon_stats().1
+    }
+
+    /// axes knock a raised shield down for 5 seconds
+    #[getter]
+    fn disables_shields(&self) -> bool {
+        self.weapon.as_deref().is_some_and(|w| w.ends_with("_axe"))
+    }
+
+    #[getter]
+    fn armor_points(&self) -> f64 {
+        let points = self.pieces().into_iter().enumerate().map(|(piece, material)| {
+            material.as_deref().and_then(armor).map_or(0.0, |(points, _)| points[piece])
+        });
+        points.sum()
+    }
+
+    #[getter]
+    fn armor_toughness(&self) -> f64 {
+        let toughness = self.pieces().into_iter().map(|material| material.as_deref().and_then(armor).map_or(0.0, |(_, t)| t));
+        toughness.sum()
+    }
+
+    fn __repr__(&self) -> String {
+        let name = |n: &Option<String>| n.as_ref().map_or("None".to_string(), |n| format!("{:?}", n));
+        format!(
+            "Loadout(weapon={}, helmet={}, chestplate={}, leggings={}, boots={}, steaks={}, arrows={}, shield={}, pearls={}, effects=[{}], potions={}, potion={}, rod={}, sharpness={}, knockback={})",
+            name(&self.weapon),
+            name(&self.helmet),
+            name(&self.chestplate),
+            name(&self.leggings),
+            name(&self.boots),
+            self.steaks,
+            self.arrows,
+            if self.shield { "True" } else { "False" },
+            self.pearls,
+            self.effects.iter().map(|e| e.__repr__()).collect::<Vec<_>>().join(", "),
+            self.potions,
+            self.potion.as_ref().map_or("None".to_string(), |p| p.__repr__()),
+            if self.rod { "True" } else { "False" },
+            self.sharpness,
+            self.knockback
+        )
+    }
+}
@@ -0,0 +1,316 @@
+//! Log output through tracing, with levels per bot and per subsystem
+//!
+//! everything goes to stderr. an event is shown if its level is within the
+//! first of these that's set: its bot and subsystem, its bot, its subsystem,
+//! the default (set_log_level() with neither, else RUST_LOG, else info)
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Metadata, Span, Subscriber};
+use tracing_subscriber::layer::{Context, Filter, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+pub const CONNECTION: &str = "pyzalea::connection";
+pub const COMBAT: &str = "pyzalea::combat";
+pub const PATHFINDING: &str = "pyzalea::pathfinding";
+const BOT: &str = "pyzalea::bot";
+
+/// set_log_level() subsystems, and the targets (module paths) they cover
+const SUBSYSTEMS: &[(&str, &[&str])] = &[
+    (
+        "connection",
+        &[
+            CONNECTION,
+            "azalea_protocol",
+            "azalea_auth",
+            "azalea::swarm",
+            "azalea_client::plugins::connection",
+            "azalea_client::plugins::login",
+            "azalea_client::plugins::join",
+            "azalea_client::plugins::disconnect",
+            "azalea_client::plugins::auto_reconnect",
+        ],
+    ),
+    ("combat", &[COMBAT, "azalea_client::plugins::attack"]),
+    ("pathfinding", &[PATHFINDING, "azalea::pathfinder"]),
+];
+
+#[derive(Default)]
+struct BotLevels {
+    all: Option<LevelFilter>,
+    subsystems: HashMap<&'static str, LevelFilter>,
+}
+
+#[derive(Default)]
+struct Levels {
+    default: Option<LevelFilter>,
+    subsystems: HashMap<&'static str, LevelFilter>,
+    bots: HashMap<String, BotLevels>,
+}
+
+impl Levels {
+    /// the most verbose level anything can log at
+    fn max(&self, env: Option<&EnvFilter>) -> LevelFilter {
+        let default = match (self.default, env) {
+            (Some(level), _) => level,
+            (None, Some(env)) => env.max_level_hint().unwrap_or(LevelFilter::TRACE),
+            (None, None) => LevelFilter::INFO,
+        };
+        let bots = self
+            .bots
+            .values()
+            .flat_map(|bot| bot.all.into_iter().chain(bot.subsystems.values().copied()));
+        // bot spans are error level, they have to exist for per-bot levels to work
+        std::iter::once(LevelFilter::ERROR)
+            .chain(std::iter::once(default))
+            .chain(self.subsystems.values().copied())
+            .chain(bots)
+            .max()
+            .unwrap_or(LevelFilter::INFO)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LEVELS: RwLock<Levels> = RwLock::new(Levels::default());
+}
+
+fn subsystem(target: &str) -> Option<&'static str> {
+    SUBSYSTEMS
+        .iter()
+        .find(|(_, targets)| targets.iter().any(|t| target.starts_with(t)))
+        .map(|(name, _)| *name)
+}
+
+fn is_bot_span(meta: &Metadata<'_>) -> bool {
+    meta.is_span() && meta.target() == BOT
+}
+
+/// which bot a span is for, kept in the span's extensions
+struct BotName(String);
+
+impl Visit for BotName {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+/// the span everything a bot does is logged in, shows up as `bot{name=...}`
+pub fn bot_span(username: &str) -> Span {
+    tracing::error_span!(target: BOT, "bot", name = username)
+}
+
+/// BotShared's span, none until connect() makes one
+pub struct BotSpan(pub Span);
+
+impl Default for BotSpan {
+    fn default() -> Self {
+        Self(Span::none())
+    }
+}
+
+impl std::ops::Deref for BotSpan {
+    type Target = Span;
+
+    fn deref(&self) -> &Span {
+        &self.0
+    }
+}
+
+struct LevelFilters {
+    /// RUST_LOG, if it was set at import
+    env: Option<EnvFilter>,
+}
+
+impl<S> Filter<S> for LevelFilters
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        if is_bot_span(meta) {
+            return true;
+        }
+        let levels = LEVELS.read();
+        let subsystem = subsystem(meta.target());
+        if !levels.bots.is_empty() {
+            let bot_level = cx.lookup_current().and_then(|current| {
+                current.scope().find_map(|span| {
+                    let extensions = span.extensions();
+                    let bot = levels.bots.get(&extensions.get::<BotName>()?.0)?;
+                    subsystem.and_then(|s| bot.subsystems.get(s).copied()).or(bot.all)
+                })
+            });
+            if let Some(level) = bot_level {
+                return *meta.level() <= level;
+            }
+        }
+        if let Some(level) = subsystem.and_then(|s| levels.subsystems.get(s)) {
+            return *meta.level() <= *level;
+        }
+        match (levels.default, &self.env) {
+            (Some(level), _) => *meta.level() <= level,
+            (None, Some(env)) => Filter::<S>::enabled(env, meta, cx),
+            (None, None) => *meta.level() <= LevelFilter::INFO,
+        }
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        if let Some(env) = &self.env {
+            // registers the callsite with RUST_LOG's span filters
+            let _ = Filter::<S>::callsite_enabled(env, meta);
+        }
+        // levels change at runtime, so ask enabled() every time
+        Interest::sometimes()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LEVELS.read().max(self.env.as_ref()))
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, cx: Context<'_, S>) {
+        if is_bot_span(attrs.metadata()) {
+            let mut name = BotName(String::new());
+            attrs.record(&mut name);
+            if let Some(span) = cx.span(id) {
+                span.extensions_mut().insert(name);
+            }
+        }
+        if let Some(env) = &self.env {
+            Filter::<S>::on_new_span(env, attrs, id, cx);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, cx: Context<'_, S>) {
+        if let Some(env) = &self.env {
+            Filter::<S>::on_record(env, id, values, cx);
+        }
+    }
+
+    fn on_enter(&self, id: &Id, cx: Context<'_, S>) {
+        if let Some(env) = &self.env {
+            Filter::<S>::on_enter(env, id, cx);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, cx: Context<'_, S>) {
+        if let Some(env) = &self.env {
+            Filter::<S>::on_exit(env, id, cx);
+        }
+    }
+
+    fn on_close(&self, id: Id, cx: Context<'_, S>) {
+        if let Some(env) = &self.env {
+            Filter::<S>::on_close(env, id, cx);
+        }
+    }
+}
+
+/// install our subscriber, unless the program already has one
+pub fn init() {
+    let env = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|spec| EnvFilter::try_new(spec).ok());
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_filter(LevelFilters { env });
+    // also forwards `log` records, like azalea's LogPlugin did
+    let _ = tracing_subscriber::registry().with(layer).try_init();
+}
+
+fn parse_level(level: &str) -> PyResult<LevelFilter> {
+    match level.to_ascii_lowercase().as_str() {
+        "off" => Ok(LevelFilter::OFF),
+        "error" => Ok(LevelFilter::ERROR),
+        "warn" | "warning" => Ok(LevelFilter::WARN),
+        "info" => Ok(LevelFilter::INFO),
+        "debug" => Ok(LevelFilter::DEBUG),
+        "trace" => Ok(LevelFilter::TRACE),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown log level {:?}, expected \"off\", \"error\", \"warn\", \"info\", \"debug\" or \"trace\"",
+            level
+        ))),
+    }
+}
+
+fn parse_subsystem(name: &str) -> PyResult<&'static str> {
+    SUBSYSTEMS
+        .iter()
+        .map(|(subsystem, _)| *subsystem)
+        .find(|subsystem| *subsystem == name)
+        .ok_or_else(|| {
+            let names: Vec<_> = SUBSYSTEMS.iter().map(|(subsystem, _)| *subsystem).collect();
+            PyValueError::new_err(format!("unknown subsystem {:?}, expected one of {}", name, names.join(", ")))
+        })
+}
+
+/// set the log level of one bot (by username), one subsystem ("connection",
+/// "combat" or "pathfinding"), both, or neither for the default. level is
+/// "off", "error", "warn", "info", "debug" or "trace", None goes back to
+/// what applied before
+#[pyfunction]
+#[pyo3(signature = (level, bot=None, subsystem=None))]
+pub fn set_log_level(level: Option<&str>, bot: Option<&str>, subsystem: Option<&str>) -> PyResult<()> {
+    let level = level.map(parse_level).transpose()?;
+    let subsystem = subsystem.map(parse_subsystem).transpose()?;
+    let mut levels = LEVELS.write();
+    match (bot, subsystem) {
+        (None, None) => levels.default = level,
+        (None, Some(s)) => set(&mut levels.subsystems, s, level),
+        (Some(bot), None) => levels.bots.entry(bot.to_string()).or_default().all = level,
+        (Some(bot), Some(s)) => set(&mut levels.bots.entry(bot.to_string()).or_default().subsystems, s, level),
+    }
+    levels.bots.retain(|_, bot| bot.all.is_some() || !bot.subsystems.is_empty());
+    drop(levels);
+    // our filter's max level changed
+    tracing::callsite::rebuild_interest_cache();
+    Ok(())
+}
+
+fn set(map: &mut HashMap<&'static str, LevelFilter>, key: &'static str, level: Option<LevelFilter>) {
+    match level {
+        Some(level) => map.insert(key, level),
+        None => map.remove(key),
+    };
+}
+
+/// the levels set with set_log_level(), as (bot, subsystem, level)
+#[pyfunction]
+pub fn log_levels() -> Vec<(Option<String>, Option<String>, String)> {
+    let levels = LEVELS.read();
+    let name = |level: &LevelFilter| level.to_string().to_lowercase();
+    let mut out = vec![];
+    if let Some(level) = &levels.default {
+        out.push((None, None, name(level)));
+    }
+    for (subsystem, level) in &levels.subsystems {
+        out.push((None, Some(subsystem.to_string()), name(level)));
+    }
+    for (bot, bot_levels) in &levels.bots {
+        if let Some(level) = &bot_levels.all {
+            out.push((Some(bot.clone()), None, name(level)));
+        }
+        for (subsystem, level) in &bot_levels.subsystems {
+            out.push((Some(bot.clone()), Some(subsystem.to_string()), name(level)));
+        }
+    }
+    out.sort();
+    out
+}
@@ -0,0 +1,214 @@
+//! Prometheus metrics, from metrics() or an HTTP /metrics endpoint
+//!
+//! bots count into their BotShared.counters as events and packets come in.
+//! arenas add their steps once an episode ends (or is reset early), so
+//! ArenaVec threads don't all hit one atomic on every tick
+
+use std::fmt::Write as _;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+use crate::events::BotEvent;
+use crate::http::{self, Stop};
+use crate::logging::CONNECTION;
+use crate::shutdown;
+
+/// per-bot counters, in BotShared
+#[derive(Default)]
+pub struct BotCounters {
+    pub packets: AtomicU64,
+    pub chat_messages: AtomicU64,
+    pub disconnects: AtomicU64,
+    /// successful ones, "reconnected" events
+    pub reconnects: AtomicU64,
+    pub deaths: AtomicU64,
+    pub crashes: AtomicU64,
+}
+
+impl BotCounters {
+    /// count an event as it's queued
+    pub fn count(&self, event: &BotEvent) {
+        let counter = match event {
+            BotEvent::Chat(_) => &self.chat_messages,
+            BotEvent::Disconnect(_) => &self.disconnects,
+            BotEvent::Reconnect(e) if e.kind == "reconnected" => &self.reconnects,
+            BotEvent::Death(_) => &self.deaths,
+            BotEvent::Crash(_) => &self.crashes,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+static ARENA_STEPS: AtomicU64 = AtomicU64::new(0);
+/// ended episodes by FastArena.winner: 1, 2, -1 (draw)
+static EPISODES: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+/// an arena episode ended after `ticks` steps
+pub fn episode_ended(ticks: u32, winner: i32) {
+    ARENA_STEPS.fetch_add(ticks as u64, Ordering::Relaxed);
+    let slot = match winner {
+        1 => 0,
+        2 => 1,
+        _ => 2,
+    };
+    EPISODES[slot].fetch_add(1, Ordering::Relaxed);
+}
+
+/// an arena was reset `ticks` steps into an episode that hadn't ended
+pub fn episode_abandoned(ticks: u32) {
+    ARENA_STEPS.fetch_add(ticks as u64, Ordering::Relaxed);
+}
+
+/// a label value, quoted and escaped
+fn label(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// one metric with its HELP and TYPE lines
+fn family(out: &mut String, name: &str, kind: &str, help: &str, samples: impl IntoIterator<Item = (String, f64)>) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// everything, in the Prometheus text format
+pub fn render() -> String {
+    let bots = shutdown::bots();
+    let mut out = String::new();
+    let count = |state: &str, flag: fn(&crate::bot::BotShared) -> bool| {
+        (format!("{{state={}}}", label(state)), bots.iter().filter(|bot| flag(bot)).count() as f64)
+    };
+    family(
+        &mut out,
+        "pyzalea_bots",
+        "gauge",
+        "Bots with a live connection thread, by state",
+        [
+            count("running", |_| true),
+            count("connected", |bot| bot.connected.load(Ordering::SeqCst)),
+            count("in_game", |bot| bot.joined.load(Ordering::SeqCst)),
+        ],
+    );
+
+    let per_bot = |counter: fn(&BotCounters) -> &AtomicU64| {
+        bots.iter()
+            .map(move |bot| (format!("{{bot={}}}", label(&bot.username)), counter(&bot.counters).load(Ordering::Relaxed) as f64))
+            .collect::<Vec<_>>()
+    };
+    family(&mut out, "pyzalea_packets_received_total", "counter", "Game packets received", per_bot(|c| &c.packets));
+    family(&mut out, "pyzalea_chat_messages_total", "counter", "Chat messages received", per_bot(|c| &c.chat_messages));
+    family(&mut out, "pyzalea_disconnects_total", "counter", "Kicks and dropped connections", per_bot(|c| &c.disconnects));
+    family(&mut out, "pyzalea_reconnects_total", "counter", "Successful reconnects", per_bot(|c| &c.reconnects));
+    family(&mut out, "pyzalea_deaths_total", "counter", "Deaths", per_bot(|c| &c.deaths));
+    family(&mut out, "pyzalea_crashes_total", "counter", "CrashEvents, fatal or not", per_bot(|c| &c.crashes));
+    family(
+        &mut out,
+        "pyzalea_latency_milliseconds",
+        "gauge",
+        "Last measured round trip to the server",
+        bots.iter().filter_map(|bot| {
+            let latency = (*bot.latency_ms.lock())?;
+            Some((format!("{{bot={}}}", label(&bot.username)), latency))
+        }),
+    );
+
+    family(
+        &mut out,
+        "pyzalea_arena_steps_total",
+        "counter",
+        "FastArena steps, counted when the episode ends or is reset",
+        [(String::new(), ARENA_STEPS.load(Ordering::Relaxed) as f64)],
+    );
+    family(
+        &mut out,
+        "pyzalea_arena_episodes_total",
+        "counter",
+        "Ended FastArena episodes, by winner",
+        ["fighter1", "fighter2", "draw"]
+            .iter()
+            .zip(&EPISODES)
+            .map(|(winner, count)| (format!("{{winner={}}}", label(winner)), count.load(Ordering::Relaxed) as f64)),
+    );
+    out
+}
+
+/// the metrics in the Prometheus text format, what /metrics serves
+#[pyfunction]
+pub fn metrics(py: Python<'_>) -> String {
+    py.allow_threads(render)
+}
+
+fn respond(mut stream: TcpStream) -> std::io::Result<()> {
+    let Some(request) = http::read_request(&mut stream)? else {
+        return Ok(());
+    };
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        ("200 OK", render())
+    } else {
+        ("404 Not Found", "metrics are at /metrics\n".to_string())
+    };
+    http::respond(&mut stream, status, "Content-Type: text/plain; version=0.0.4\r\n", &body)
+}
+
+/// a running start_metrics_server(), see stop()
+#[pyclass]
+pub struct MetricsServer {
+    stop: Stop,
+    address: SocketAddr,
+}
+
+#[pymethods]
+impl MetricsServer {
+    /// "http://host:port/metrics", with the real port if it was 0
+    #[getter]
+    fn url(&self) -> String {
+        format!("http://{}/metrics", self.address)
+    }
+
+    /// stop answering scrapes, which garbage collecting the server does too
+    fn stop(&self) {
+        self.stop.stop();
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, _args: &Bound<'_, PyTuple>) -> bool {
+        self.stop();
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        format!("MetricsServer(url={:?})", self.url())
+    }
+}
+
+/// serve metrics() at http://host:port/metrics for Prometheus to scrape.
+/// port 0 picks a free one, see MetricsServer.url
+#[pyfunction]
+#[pyo3(signature = (port=9100, host="127.0.0.1"))]
+pub fn start_metrics_server(port: u16, host: &str) -> PyResult<MetricsServer> {
+    let (listener, address) = http::listen(host, port)?;
+    let stop = Stop::new();
+    let stopped = stop.flag();
+    std::thread::Builder::new()
+        .name("pyzalea-metrics".to_string())
+        .spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                http::accept(&listener, "pyzalea-metrics-request", |stream, _| respond(stream));
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        })?;
+    tracing::info!(target: CONNECTION, %address, "metrics listening");
+    Ok(MetricsServer { stop, address })
+}
@@ -0,0 +1,287 @@
+//! A live 1v1 as a FastArena
+//!
+//! maps what a connected bot sees of itself and its opponent onto a fresh
+//! FastArena every tick, so a planner can run simulated rollouts from the real
+//! situation. the arena only models a flat floor, so the mirror is centred
+//! between the two players with the floor at whoever's standing. anything the
+//! server doesn't tell us about the opponent (food, steaks left) is assumed
+//! from the template arena's kit
+
+use pyo3::prelude::*;
+
+use crate::arena::{effect_slots, FastArena, Fighter, SHIELD_DELAY_TICKS};
+use crate::bot::PyBot;
+use crate::item::{hand_use, HandUse, Item};
+use crate::state::{PyEntity, PyGameState};
+
+/// blocks per tick, faster than walking counts as the opponent sprinting
+const SPRINT_SPEED: f64 = 0.25;
+
+/// when each fighter started using an item and last swung, in game ticks
+#[derive(Default)]
+struct Timers {
+    eating_since: Option<u64>,
+    last_attack: Option<u64>,
+}
+
+impl Timers {
+    fn eating_ticks(&mut self, eating: bool, tick: u64, eat_ticks: u32) -> u32 {
+        if !eating {
+            self.eating_since = None;
+            return 0;
+        }
+        let since = *self.eating_since.get_or_insert(tick);
+        eat_ticks.saturating_sub((tick - since) as u32).max(1)
+    }
+
+    fn attack_cooldown(&self, tick: u64, cooldown_ticks: u32) -> u32 {
+        self.last_attack
+            .map_or(0, |at| cooldown_ticks.saturating_sub((tick - at) as u32))
+    }
+}
+
+/// damage since the mirror started, health drops count as hits
+#[derive(Clone, Copy, Default)]
+struct Stats {
+    dealt: f64,
+    taken: f64,
+    landed: u32,
+    hit: u32,
+}
+
+impl Stats {
+    fn apply(self, fighter: &mut Fighter) {
+        fighter.damage_dealt = self.dealt;
+        fighter.damage_taken = self.taken;
+        fighter.hits_landed = self.landed;
+        fighter.hits_taken = self.hit;
+    }
+}
+
+/// `victim` lost `damage`, to `attacker`
+fn record_hit(victim: &mut Stats, attacker: &mut Stats, damage: f32) {
+    if damage > 0.0 {
+        victim.taken += damage as f64;
+        victim.hit += 1;
+        attacker.dealt += damage as f64;
+        attacker.landed += 1;
+    }
+}
+
+#[pyclass]
+pub struct ArenaMirror {
+    bot: Py<PyBot>,
+    /// username to mirror as fighter 2, None for whoever's the nearest player
+    #[pyo3(get)]
+    opponent: Option<String>,
+    /// size, constants, kits and max_ticks of every mirrored arena
+    template: FastArena,
+    /// the item eating means, like "cooked_beef"
+    #[pyo3(get)]
+    food_item: String,
+    /// the last mirror, None before the first update that saw the opponent
+    #[pyo3(get)]
+    arena: Option<FastArena>,
+    /// world position of the arena's (0, 0, 0), arena + origin = world
+    #[pyo3(get)]
+    origin: (f64, f64, f64),
+    me: Timers,
+    them: Timers,
+    /// the opponent we're tracking and their swing count
+    swings: Option<(u32, u32)>,
+    /// (ours, theirs) last update, for damage and hit stats
+    health: Option<(f32, f32)>,
+    stats: [Stats; 2],
+}
+
+#[pymethods]
+impl ArenaMirror {
+    /// arena is a FastArena to copy the size, constants, kits and max_ticks
+    /// from (FastArena() if None); the bot is fighter 1, the opponent fighter 2
+    #[new]
+    #[pyo3(signature = (bot, opponent=None, arena=None, food_item="cooked_beef"))]
+    fn new(bot: Py<PyBot>, opponent: Option<String>, arena: Option<FastArena>, food_item: &str) -> Self {
+        let mut template = arena.unwrap_or_default();
+        // so resetting the copies doesn't count as abandoning an episode
+        template.tick = 0;
+        Self {
+            bot,
+            opponent,
+            template,
+            food_item: food_item.strip_prefix("minecraft:").unwrap_or(food_item).to_string(),
+            arena: None,
+            origin: (0.0, 0.0, 0.0),
+            me: Timers::default(),
+            them: Timers::default(),
+            swings: None,
+            health: None,
+            stats: [Stats::default(); 2],
+        }
+    }
+
+    /// mirror `state` (bot.get_state() if not given) into a fresh FastArena at
+    /// tick 0, ready to step. None while the opponent is out of sight
+    #[pyo3(signature = (state=None))]
+    fn update(&mut self, py: Python<'_>, state: Option<PyGameState>) -> PyResult<Option<FastArena>> {
+        let bot = self.bot.bind(py);
+        let state = match state {
+            Some(state) => state,
+            None => bot.call_method0("get_state")?.extract()?,
+        };
+        let opponent = match &self.opponent {
+            Some(name) => bot.call_method1("get_player_state", (name,))?.extract::<Option<PyEntity>>()?,
+            None => state.nearest_player(),
+        };
+        let Some(opponent) = opponent else {
+            self.swings = None;
+            self.health = None;
+            self.them = Timers::default();
+            return Ok(None);
+        };
+        let swings = bot.call_method1("swing_count", (opponent.id as i32,))?.extract::<u32>()?;
+        let arena = self.mirror(&state, &opponent, swings);
+        self.arena = Some(arena.clone());
+        Ok(Some(arena))
+    }
+
+    /// bot.tick() and update from the state it returns
+    fn tick(&mut self, py: Python<'_>) -> PyResult<Option<FastArena>> {
+        let state = self.bot.bind(py).call_method0("tick")?.extract::<PyGameState>()?;
+        self.update(py, Some(state))
+    }
+
+    /// arena coordinates to world ones, e.g. for a planned position
+    fn to_world(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        (x + self.origin.0, y + self.origin.1, z + self.origin.2)
+    }
+
+    /// forget the timers and damage stats, e.g. for a new fight
+    fn reset(&mut self) {
+        self.me = Timers::default();
+        self.them = Timers::default();
+        self.swings = None;
+        self.health = None;
+        self.stats = [Stats::default(); 2];
+        self.arena = None;
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ArenaMirror(opponent={}, mirrored={})",
+            self.opponent.as_deref().unwrap_or("nearest"),
+            if self.arena.is_some() { "True" } else { "False" }
+        )
+    }
+}
+
+impl ArenaMirror {
+    fn mirror(&mut self, state: &PyGameState, opponent: &PyEntity, swings: u32) -> FastArena {
+        let mut arena = self.template.clone();
+        arena.reset(None, None, None);
+        let c = arena.constants.clone();
+        let tick = state.tick;
+
+        // our attack cooldown is known, theirs restarts when they swing
+        match self.swings {
+            Some((id, prev)) if id == opponent.id => {
+                if swings > prev {
+                    self.them.last_attack = Some(tick);
+                }
+            }
+            _ => self.them = Timers::default(),
+        }
+        self.swings = Some((opponent.id, swings));
+
+        if let Some((health, opponent_health)) = self.health {
+            let [me, them] = &mut self.stats;
+            record_hit(me, them, health - state.health);
+            record_hit(them, me, opponent_health - opponent.health);
+        }
+        self.health = Some((state.health, opponent.health));
+
+        // centred between the two, with the floor under whoever's standing
+        let (cx, cz) = ((state.x + opponent.x) / 2.0, (state.z + opponent.z) / 2.0);
+        let floor = match (state.is_on_ground, opponent.is_on_ground) {
+            (true, _) => state.y,
+            (false, true) => opponent.y,
+            (false, false) => state.y.min(opponent.y),
+        };
+        self.origin = (cx, floor, cz);
+        // grown if they're further apart than the template fits
+        let half_x = ((state.x - opponent.x).abs() / 2.0 + 1.0).max(arena.max_x);
+        let half_z = ((state.z - opponent.z).abs() / 2.0 + 1.0).max(arena.max_z);
+        (arena.min_x, arena.max_x, arena.min_z, arena.max_z) = (-half_x, half_x, -half_z, half_z);
+        arena.floor_y = 0.0;
+
+        let food = |id: Option<&str>| id == Some(self.food_item.as_str());
+        let eating = state.is_using_item && food(state.held_item_id.as_deref());
+        let blocking = state.is_using_item && hand_use(state.held_item_id.as_deref(), state.offhand_id.as_deref()) == HandUse::Block;
+        let carried = |id: &str| {
+            let items = state.hotbar.iter().chain(std::iter::once(&state.offhand)).flatten();
+            items.filter(|item| item.id == id).map(|item| item.count.max(0) as u32).sum()
+        };
+        let steaks = carried(&self.food_item);
+        arena.fighter1 = Fighter {
+            x: state.x - cx,
+            y: (state.y - floor).max(0.0),
+            z: state.z - cz,
+            vx: state.velocity_x,
+            vy: state.velocity_y,
+            vz: state.velocity_z,
+            yaw: state.yaw as f64,
+            pitch: state.pitch as f64,
+            health: state.health as f64,
+            food: state.food as f64,
+            steaks,
+            arrows: carried("arrow"),
+            shield: carried("shield") > 0,
+            pearls: carried("ender_pearl"),
+            potions: carried("splash_potion"),
+            rod: carried("fishing_rod") > 0,
+            effects: effect_slots(&state.effects),
+            attack_cooldown: ((1.0 - state.attack_cooldown.clamp(0.0, 1.0)) * c.attack_cooldown_ticks as f32).round() as u32,
+            eating_ticks: self.me.eating_ticks(eating, tick, c.eat_ticks),
+            on_ground: state.is_on_ground,
+            sprinting: state.is_sprinting,
+            eating,
+            blocking,
+            block_ticks: if blocking { SHIELD_DELAY_TICKS } else { 0 },
+            ..arena.fighter1.clone()
+        };
+        self.stats[0].apply(&mut arena.fighter1);
+
+        fn held(item: &Option<Item>) -> Option<&str> {
+            item.as_ref().map(|item| item.id.as_str())
+        }
+        let eating = opponent.is_using_item && food(held(&opponent.held_item));
+        let blocking = opponent.is_using_item && hand_use(held(&opponent.held_item), held(&opponent.offhand)) == HandUse::Block;
+        arena.fighter2 = Fighter {
+            x: opponent.x - cx,
+            y: (opponent.y - floor).max(0.0),
+            z: opponent.z - cz,
+            vx: opponent.velocity_x,
+            vy: opponent.velocity_y,
+            vz: opponent.velocity_z,
+            yaw: opponent.yaw as f64,
+            pitch: opponent.pitch as f64,
+            health: opponent.health as f64,
+            food: c.max_food,
+            steaks: arena.kits[1].steaks,
+            arrows: arena.kits[1].arrows,
+            pearls: arena.kits[1].pearls,
+            potions: arena.kits[1].potions,
+            rod: arena.kits[1].rod,
+            shield: [&opponent.held_item, &opponent.offhand].into_iter().flatten().any(|item| item.id == "shield"),
+            attack_cooldown: self.them.attack_cooldown(tick, c.attack_cooldown_ticks),
+            eating_ticks: self.them.eating_ticks(eating, tick, c.eat_ticks),
+            on_ground: opponent.is_on_ground,
+            sprinting: opponent.velocity_x.hypot(opponent.velocity_z) > SPRINT_SPEED,
+            eating,
+            blocking,
+            block_ticks: if blocking { SHIELD_DELAY_TICKS } else { 0 },
+            ..arena.fighter2.clone()
+        };
+        self.stats[1].apply(&mut arena.fighter2);
+        arena
+    }
+}
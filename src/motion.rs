@@ -0,0 +1,174 @@
+//! Smoothed and predicted positions of other entities
+//!
+//! the server moves entities at most once a tick, and players often every
+//! other tick, so aiming at the raw position jumps around. every game tick we
+//! look for entities that moved, estimate their velocity from the last two
+//! updates, and carry them along it until the next one
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use azalea::ecs::prelude::*;
+use azalea_client::Client;
+use azalea_core::position::Vec3;
+use azalea_entity::Position;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_world::MinecraftEntityId;
+
+/// a move further than this between updates is a teleport, not a velocity
+const TELEPORT_DISTANCE: f64 = 8.0;
+const TICK_SECONDS: f64 = 0.05;
+
+/// how PyEntity.smooth_x/y/z are worked out, see PyBot.motion_prediction
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct MotionPrediction {
+    /// ticks to predict ahead of the latest update, e.g. the ping in ticks
+    /// to aim where they'll be when the attack arrives
+    #[pyo3(get, set)]
+    pub horizon: f64,
+    /// ticks to keep carrying an entity along after its last update. after
+    /// that it's taken to have stopped
+    #[pyo3(get, set)]
+    pub max_extrapolation: f64,
+    /// weight of the newest velocity sample, 1.0 for no smoothing
+    #[pyo3(get, set)]
+    pub smoothing: f64,
+}
+
+impl Default for MotionPrediction {
+    fn default() -> Self {
+        Self {
+            horizon: 0.0,
+            max_extrapolation: 3.0,
+            smoothing: 0.5,
+        }
+    }
+}
+
+#[pymethods]
+impl MotionPrediction {
+    #[new]
+    #[pyo3(signature = (horizon=0.0, max_extrapolation=3.0, smoothing=0.5))]
+    fn new(horizon: f64, max_extrapolation: f64, smoothing: f64) -> PyResult<Self> {
+        if horizon.is_nan() || horizon < 0.0 {
+            return Err(PyValueError::new_err("horizon must be 0 or more ticks"));
+        }
+        if max_extrapolation.is_nan() || max_extrapolation < 0.0 {
+            return Err(PyValueError::new_err("max_extrapolation must be 0 or more ticks"));
+        }
+        if smoothing.is_nan() || smoothing <= 0.0 || smoothing > 1.0 {
+            return Err(PyValueError::new_err("smoothing must be in (0, 1]"));
+        }
+        Ok(Self {
+            horizon,
+            max_extrapolation,
+            smoothing,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MotionPrediction(horizon={}, max_extrapolation={}, smoothing={})",
+            self.horizon, self.max_extrapolation, self.smoothing
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Track {
+    /// where the last update put it
+    pos: Vec3,
+    /// the game tick and time we saw it
+    tick: u64,
+    at: Instant,
+    /// blocks per tick
+    velocity: Vec3,
+}
+
+/// keyed by the protocol entity id
+#[derive(Debug, Default)]
+pub struct Motions {
+    pub prediction: MotionPrediction,
+    tracks: HashMap<i32, Track>,
+}
+
+impl Motions {
+    /// call on every game tick, with the tick number
+    pub fn tick(&mut self, client: &Client, tick: u64) {
+        let now = Instant::now();
+        let mut ecs = client.ecs.lock();
+        let mut query = ecs.query::<(Entity, &MinecraftEntityId, &Position)>();
+        for (entity, id, pos) in query.iter(&ecs) {
+            if entity == client.entity {
+                continue;
+            }
+            let pos = **pos;
+            let Some(track) = self.tracks.get_mut(&id.0) else {
+                self.tracks.insert(
+                    id.0,
+                    Track {
+                        pos,
+                        tick,
+                        at: now,
+                        velocity: Vec3::ZERO,
+                    },
+                );
+                continue;
+            };
+            let ticks = tick.saturating_sub(track.tick).max(1) as f64;
+            if pos == track.pos {
+                if ticks > self.prediction.max_extrapolation {
+                    track.velocity = Vec3::ZERO;
+                }
+                continue;
+            }
+            let moved = pos - track.pos;
+            track.velocity = if moved.length() > TELEPORT_DISTANCE {
+                Vec3::ZERO
+            } else {
+                let sample = moved / ticks;
+                track.velocity + (sample - track.velocity) * self.prediction.smoothing
+            };
+            track.pos = pos;
+            track.tick = tick;
+            track.at = now;
+        }
+    }
+
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::RemoveEntities(p) => {
+                for id in &p.entity_ids {
+                    self.tracks.remove(&id.0);
+                }
+            }
+            ClientboundGamePacket::Respawn(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+
+    /// (position, velocity) for an entity the server last put at `pos`. the
+    /// position is carried along the velocity for the time since that update,
+    /// plus the horizon
+    pub fn predict(&self, id: i32, pos: Vec3) -> (Vec3, Vec3) {
+        let Some(track) = self.tracks.get(&id) else {
+            return (pos, Vec3::ZERO);
+        };
+        // moved since this tick's sample, so the update just arrived
+        let elapsed = if track.pos == pos {
+            (track.at.elapsed().as_secs_f64() / TICK_SECONDS).min(self.prediction.max_extrapolation)
+        } else {
+            0.0
+        };
+        let ahead = elapsed + self.prediction.horizon;
+        (pos + track.velocity * ahead, track.velocity)
+    }
+}
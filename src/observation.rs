@@ -0,0 +1,204 @@
+//! Which features to_vector() emits and how they're scaled
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use crate::dlpack::ObservationTensor;
+use crate::state::{PyEntity, PyGameState};
+
+/// feature groups in their default order, this is the layout to_vector() uses
+/// without a config
+const DEFAULT_GROUPS: &[(&str, &[&str])] = &[
+    ("position", &["x", "y", "z"]),
+    ("look", &["yaw", "pitch"]),
+    ("velocity", &["velocity_x", "velocity_y", "velocity_z"]),
+    ("vitals", &["health", "food"]),
+    ("movement", &["is_on_ground", "is_sprinting", "is_sneaking"]),
+    ("combat", &["attack_cooldown"]),
+    ("enemy_position", &["enemy_x", "enemy_y", "enemy_z"]),
+    ("enemy_look", &["enemy_yaw", "enemy_pitch"]),
+    ("enemy_velocity", &["enemy_velocity_x", "enemy_velocity_y", "enemy_velocity_z"]),
+    ("enemy_health", &["enemy_health"]),
+    ("enemy_relative", &["enemy_dx", "enemy_dy", "enemy_dz"]),
+];
+
+/// groups you have to ask for
+const EXTRA_GROUPS: &[(&str, &[&str])] = &[
+    ("hunger", &["saturation"]),
+    ("status", &["is_dead", "xp_level"]),
+    ("hazards", &["is_in_water", "is_in_lava", "is_on_fire", "is_in_web", "is_suffocating"]),
+    ("enemy_presence", &["enemy_visible", "enemy_distance"]),
+];
+
+fn all_groups() -> impl Iterator<Item = &'static (&'static str, &'static [&'static str])> {
+    DEFAULT_GROUPS.iter().chain(EXTRA_GROUPS)
+}
+
+fn find_feature(name: &str) -> Option<&'static str> {
+    all_groups().flat_map(|(_, features)| features.iter().copied()).find(|f| *f == name)
+}
+
+/// to_vector() layout: feature groups (or single features) in order, with
+/// optional (low, high) ranges that map a feature to (v - low) / (high - low)
+#[pyclass]
+#[derive(Clone)]
+pub struct ObservationConfig {
+    features: Vec<&'static str>,
+    ranges: HashMap<&'static str, (f32, f32)>,
+    /// clamp scaled features to 0.0-1.0
+    #[pyo3(get)]
+    clip: bool,
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        Self {
+            features: DEFAULT_GROUPS.iter().flat_map(|(_, f)| f.iter().copied()).collect(),
+            ranges: HashMap::new(),
+            clip: false,
+        }
+    }
+}
+
+#[pymethods]
+impl ObservationConfig {
+    #[new]
+    #[pyo3(signature = (groups=None, ranges=None, clip=false))]
+    fn new(groups: Option<Vec<String>>, ranges: Option<HashMap<String, (f32, f32)>>, clip: bool) -> PyResult<Self> {
+        let mut config = Self { clip, ..Default::default() };
+        if let Some(groups) = groups {
+            config.features.clear();
+            for name in groups {
+                if let Some((_, features)) = all_groups().find(|(group, _)| *group == name) {
+                    config.features.extend(features.iter().copied());
+                } else if let Some(feature) = find_feature(&name) {
+                    config.features.push(feature);
+                } else {
+                    return Err(PyValueError::new_err(format!("unknown feature group: {}", name)));
+                }
+            }
+        }
+        for (name, (low, high)) in ranges.unwrap_or_default() {
+            let Some(feature) = find_feature(&name) else {
+                return Err(PyValueError::new_err(format!("unknown feature: {}", name)));
+            };
+            if low == high {
+                return Err(PyValueError::new_err(format!("empty range for {}", name)));
+            }
+            config.ranges.insert(feature, (low, high));
+        }
+        Ok(config)
+    }
+
+    /// feature names in output order
+    #[getter]
+    fn features(&self) -> Vec<&'static str> {
+        self.features.clone()
+    }
+
+    #[getter]
+    fn ranges(&self) -> HashMap<&'static str, (f32, f32)> {
+        self.ranges.clone()
+    }
+
+    /// (group, [features]) for every group, default ones first
+    #[staticmethod]
+    fn groups() -> Vec<(&'static str, Vec<&'static str>)> {
+        all_groups().map(|(group, features)| (*group, features.to_vec())).collect()
+    }
+
+    /// a (len(states), len(self)) ObservationTensor of the states' vectors,
+    /// for batching bots (say a swarm's) into one from_dlpack()
+    fn tensor(&self, states: Vec<PyRef<'_, PyGameState>>) -> ObservationTensor {
+        ObservationTensor::batch(states.iter().map(|state| self.vector(state)), self.features.len())
+    }
+
+    fn __len__(&self) -> usize {
+        self.features.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ObservationConfig(features={}, ranges={}, clip={})", self.features.len(), self.ranges.len(), self.clip)
+    }
+}
+
+impl ObservationConfig {
+    pub fn feature_names(&self) -> Vec<&'static str> {
+        self.features.clone()
+    }
+
+    pub fn vector(&self, state: &PyGameState) -> Vec<f32> {
+        let enemy = state.nearest_player();
+        self.features
+            .iter()
+            .map(|name| {
+                let value = feature(state, enemy.as_ref(), name);
+                let Some(&(low, high)) = self.ranges.get(name) else {
+                    return value;
+                };
+                let scaled = (value - low) / (high - low);
+                if self.clip {
+                    scaled.clamp(0.0, 1.0)
+                } else {
+                    scaled
+                }
+            })
+            .collect()
+    }
+}
+
+/// one feature, enemy ones are 0.0 when there's no player around
+fn feature(state: &PyGameState, enemy: Option<&PyEntity>, name: &str) -> f32 {
+    let flag = |b: bool| b as u8 as f32;
+    if let Some(enemy_feature) = name.strip_prefix("enemy_") {
+        let Some(enemy) = enemy else {
+            return 0.0;
+        };
+        return match enemy_feature {
+            "x" => enemy.x as f32,
+            "y" => enemy.y as f32,
+            "z" => enemy.z as f32,
+            "yaw" => enemy.yaw,
+            "pitch" => enemy.pitch,
+            "velocity_x" => enemy.velocity_x as f32,
+            "velocity_y" => enemy.velocity_y as f32,
+            "velocity_z" => enemy.velocity_z as f32,
+            "health" => enemy.health,
+            "dx" => (enemy.x - state.x) as f32,
+            "dy" => (enemy.y - state.y) as f32,
+            "dz" => (enemy.z - state.z) as f32,
+            "visible" => 1.0,
+            "distance" => {
+                let (dx, dy, dz) = (enemy.x - state.x, enemy.y - state.y, enemy.z - state.z);
+                (dx * dx + dy * dy + dz * dz).sqrt() as f32
+            }
+            _ => 0.0,
+        };
+    }
+    match name {
+        "x" => state.x as f32,
+        "y" => state.y as f32,
+        "z" => state.z as f32,
+        "yaw" => state.yaw,
+        "pitch" => state.pitch,
+        "velocity_x" => state.velocity_x as f32,
+        "velocity_y" => state.velocity_y as f32,
+        "velocity_z" => state.velocity_z as f32,
+        "health" => state.health,
+        "food" => state.food as f32,
+        "saturation" => state.saturation,
+        "is_on_ground" => flag(state.is_on_ground),
+        "is_sprinting" => flag(state.is_sprinting),
+        "is_sneaking" => flag(state.is_sneaking),
+        "is_dead" => flag(state.is_dead),
+        "is_in_water" => flag(state.is_in_water),
+        "is_in_lava" => flag(state.is_in_lava),
+        "is_on_fire" => flag(state.is_on_fire),
+        "is_in_web" => flag(state.is_in_web),
+        "is_suffocating" => flag(state.is_suffocating),
+        "attack_cooldown" => state.attack_cooldown,
+        "xp_level" => state.xp_level as f32,
+        _ => 0.0,
+    }
+}
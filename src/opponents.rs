@@ -0,0 +1,201 @@
+//! Scripted opponents for FastArena
+//!
+//! fixed baselines to train and evaluate against, so progress doesn't only
+//! show up against earlier versions of the same policy. a FastArena with an
+//! opponent plays it as fighter 2 in step_vs_bot(). they read the arena's
+//! state directly instead of an observation, and only `random` is random,
+//! from its own seeded xorshift so runs repeat
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::Deserialize;
+
+use crate::arena::{FastArena, Fighter, FighterAction};
+
+/// the rusher jumps in for crits this close
+const CRIT_DISTANCE: f64 = 4.0;
+/// the kiter shoots from further than this, if it has arrows
+const BOW_DISTANCE: f64 = 8.0;
+/// ticks of drawing for a full-strength arrow
+const FULL_DRAW_TICKS: u32 = 20;
+/// ticks between the kiter's changes of strafe direction
+const STRAFE_TICKS: u32 = 30;
+/// the eat-camper backs off to eat below this fraction of max health
+const EAT_HEALTH: f64 = 0.6;
+/// and raises its shield when the enemy is this close
+const SHIELD_DISTANCE: f64 = 4.0;
+
+/// `opponent` in `[arena]`, see FastArena.set_opponent()
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpponentKind {
+    /// sprints straight in and swings whenever the cooldown is back
+    Rusher,
+    /// hits and backs off while the cooldown recovers, strafing, and shoots
+    /// from range when it has arrows
+    Kiter,
+    /// fights like the rusher until it's hurt, then backs off behind its
+    /// shield and eats
+    EatCamper,
+    /// random buttons every tick
+    Random,
+}
+
+impl OpponentKind {
+    pub fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "rusher" => Ok(Self::Rusher),
+            "kiter" => Ok(Self::Kiter),
+            "eat_camper" => Ok(Self::EatCamper),
+            "random" => Ok(Self::Random),
+            _ => Err(PyValueError::new_err(format!(
+                "unknown opponent {:?}, expected \"rusher\", \"kiter\", \"eat_camper\" or \"random\"",
+                name
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rusher => "rusher",
+            Self::Kiter => "kiter",
+            Self::EatCamper => "eat_camper",
+            Self::Random => "random",
+        }
+    }
+}
+
+/// a scripted fighter and its random state
+#[derive(Clone, Debug)]
+pub struct Opponent {
+    pub kind: OpponentKind,
+    rng: u64,
+}
+
+/// degrees `from` has to turn to face `to`
+fn turn_towards(from: &Fighter, to: &Fighter) -> f64 {
+    let target = (-(to.x - from.x)).atan2(to.z - from.z).to_degrees();
+    (target - from.yaw + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn distance(a: &Fighter, b: &Fighter) -> f64 {
+    let (dx, dy, dz) = (b.x - a.x, b.y - a.y, b.z - a.z);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+impl Opponent {
+    pub fn new(kind: OpponentKind, seed: u64) -> Self {
+        // spread small seeds over the bits, xorshift can't start from 0
+        Self { kind, rng: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// what `me` does this tick against `enemy`
+    pub fn act(&mut self, arena: &FastArena, me: &Fighter, enemy: &Fighter) -> FighterAction {
+        match self.kind {
+            OpponentKind::Rusher => rush(arena, me, enemy),
+            OpponentKind::Kiter => kite(arena, me, enemy),
+            OpponentKind::EatCamper => camp(arena, me, enemy),
+            OpponentKind::Random => self.random(),
+        }
+    }
+
+    fn random(&mut self) -> FighterAction {
+        let bits = self.next();
+        let bit = |n: u32| bits >> n & 1 == 1;
+        FighterAction {
+            forward: bit(0),
+            backward: bit(1),
+            left: bit(2),
+            right: bit(3),
+            jump: bit(4),
+            sprint: bit(5),
+            attack: bit(6),
+            eat: bit(7),
+            delta_yaw: ((bits >> 16) % 61) as f64 - 30.0,
+            delta_pitch: ((bits >> 24) % 21) as f64 - 10.0,
+            draw: bit(8),
+            block: bit(9),
+            pearl: bit(10) && bit(11) && bit(12),
+            potion: bit(13) && bit(14) && bit(15),
+            rod: bit(40),
+        }
+    }
+}
+
+/// facing the enemy, swinging if the hit would be at full strength
+fn engage(arena: &FastArena, me: &Fighter, enemy: &Fighter) -> FighterAction {
+    let in_reach = distance(me, enemy) <= arena.constants.attack_range;
+    FighterAction {
+        delta_yaw: turn_towards(me, enemy),
+        delta_pitch: -me.pitch,
+        attack: in_reach && me.attack_cooldown == 0,
+        ..FighterAction::default()
+    }
+}
+
+/// a block in the way of walking straight ahead
+fn blocked(arena: &FastArena, me: &Fighter) -> bool {
+    let yaw = me.yaw.to_radians();
+    arena.terrain.column_top(me.x - yaw.sin(), me.z + yaw.cos()) > me.y + 0.5
+}
+
+fn rush(arena: &FastArena, me: &Fighter, enemy: &Fighter) -> FighterAction {
+    let close = distance(me, enemy) < CRIT_DISTANCE;
+    FighterAction {
+        forward: true,
+        sprint: true,
+        // jumping in makes the falling hit a crit
+        jump: me.on_ground && (close || blocked(arena, me)),
+        ..engage(arena, me, enemy)
+    }
+}
+
+fn kite(arena: &FastArena, me: &Fighter, enemy: &Fighter) -> FighterAction {
+    let action = engage(arena, me, enemy);
+    let far = distance(me, enemy) > BOW_DISTANCE;
+    if me.arrows > 0 && (far || me.draw_ticks > 0) {
+        // aim a bit high for the drop, let go at full draw
+        let dy = enemy.y - me.y;
+        let level = -dy.atan2((enemy.x - me.x).hypot(enemy.z - me.z)).to_degrees();
+        return FighterAction {
+            draw: me.draw_ticks < FULL_DRAW_TICKS,
+            delta_pitch: level - distance(me, enemy) * 0.3 - me.pitch,
+            attack: false,
+            ..action
+        };
+    }
+    let strafe_left = (arena.tick / STRAFE_TICKS).is_multiple_of(2);
+    let ready = me.attack_cooldown == 0;
+    FighterAction {
+        forward: ready,
+        backward: !ready,
+        sprint: ready,
+        left: !ready && strafe_left,
+        right: !ready && !strafe_left,
+        jump: me.on_ground && blocked(arena, me) && ready,
+        ..action
+    }
+}
+
+fn camp(arena: &FastArena, me: &Fighter, enemy: &Fighter) -> FighterAction {
+    let hurt = me.health < arena.constants.max_health * EAT_HEALTH;
+    if !hurt || me.steaks == 0 {
+        return rush(arena, me, enemy);
+    }
+    let close = distance(me, enemy) < SHIELD_DISTANCE;
+    FighterAction {
+        backward: true,
+        // the shield when it covers the retreat, otherwise the steak
+        block: me.shield && close && !me.eating,
+        eat: !(me.shield && close) || me.eating,
+        attack: false,
+        ..engage(arena, me, enemy)
+    }
+}
@@ -0,0 +1,110 @@
+//! Delivering selected clientbound packets to Python
+//!
+//! packets don't implement serde, and their Debug output isn't a stable
+//! format, so every packet comes with its Debug text as is and only a few
+//! packets get typed fields: set_time, set_health, set_experience,
+//! damage_event, entity_event, game_event and explode
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use azalea::Vec3;
+use azalea_protocol::packets::ProtocolPacket;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+/// a clientbound packet captured for a listener
+#[derive(Clone, Debug)]
+pub struct CapturedPacket {
+    pub name: String,
+    pub id: u32,
+    debug: String,
+    fields: Option<Value>,
+}
+
+impl CapturedPacket {
+    pub fn new(packet: &ClientboundGamePacket) -> Self {
+        Self {
+            name: packet.name().to_string(),
+            id: packet.id(),
+            debug: format!("{:?}", packet),
+            fields: fields(packet),
+        }
+    }
+
+    /// {"name": ..., "id": ..., "debug": ..., "fields": {...} or None}
+    pub fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("name", &self.name)?;
+        dict.set_item("id", self.id)?;
+        dict.set_item("debug", &self.debug)?;
+        match &self.fields {
+            Some(fields) => dict.set_item("fields", py.import("json")?.call_method1("loads", (fields.to_string(),))?)?,
+            None => dict.set_item("fields", py.None())?,
+        }
+        Ok(dict.into_any().unbind())
+    }
+}
+
+fn vec3(v: &Vec3) -> Value {
+    json!([v.x, v.y, v.z])
+}
+
+/// typed fields of the packets listed at the top, read from the packet structs
+fn fields(packet: &ClientboundGamePacket) -> Option<Value> {
+    use ClientboundGamePacket as P;
+    Some(match packet {
+        P::SetTime(p) => json!({"game_time": p.game_time, "day_time": p.day_time, "tick_day_time": p.tick_day_time}),
+        P::SetHealth(p) => json!({"health": p.health, "food": p.food, "saturation": p.saturation}),
+        P::SetExperience(p) => json!({
+            "experience_progress": p.experience_progress,
+            "experience_level": p.experience_level,
+            "total_experience": p.total_experience,
+        }),
+        P::DamageEvent(p) => json!({
+            "entity_id": p.entity_id.0,
+            "source_type_id": p.source_type_id,
+            "source_cause_id": p.source_cause_id.0,
+            "source_direct_id": p.source_direct_id.0,
+            "source_position": p.source_position.as_ref().map(vec3),
+        }),
+        P::EntityEvent(p) => json!({"entity_id": p.entity_id.0, "event_id": p.event_id}),
+        P::GameEvent(p) => json!({"event": p.event as u8, "param": p.param}),
+        P::Explode(p) => json!({
+            "center": vec3(&p.center),
+            "radius": p.radius,
+            "block_count": p.block_count,
+            "player_knockback": p.player_knockback.as_ref().map(vec3),
+        }),
+        _ => return None,
+    })
+}
+
+/// which packets are captured, by name ("*" for all)
+#[derive(Debug, Default)]
+pub struct PacketFilter {
+    /// for poll_packets()
+    pub watched: HashSet<String>,
+    /// for on_packet() callbacks
+    pub with_callbacks: HashSet<String>,
+}
+
+impl PacketFilter {
+    pub fn is_empty(&self) -> bool {
+        self.watched.is_empty() && self.with_callbacks.is_empty()
+    }
+
+    pub fn is_watched(&self, name: &str) -> bool {
+        self.watched.contains(name) || self.watched.contains("*")
+    }
+
+    pub fn has_callback(&self, name: &str) -> bool {
+        self.with_callbacks.contains(name) || self.with_callbacks.contains("*")
+    }
+}
+
+/// packet names are accepted with or without the "minecraft:" prefix
+pub fn normalize_name(name: &str) -> String {
+    name.strip_prefix("minecraft:").unwrap_or(name).to_string()
+}
@@ -0,0 +1,77 @@
+//! Particles spawned around the bot
+
+use pyo3::prelude::*;
+use std::io::Cursor;
+
+use azalea::buf::{AzaleaRead, AzaleaWrite};
+use azalea::registry::builtin::ParticleKind;
+use azalea_entity::particle::Particle;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+use crate::events::unix_seconds;
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ParticleEvent {
+    /// e.g. "minecraft:crit" or "minecraft:explosion"
+    #[pyo3(get)]
+    pub particle: String,
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    /// spread around the position on each axis
+    #[pyo3(get)]
+    pub offset: (f32, f32, f32),
+    #[pyo3(get)]
+    pub speed: f32,
+    #[pyo3(get)]
+    pub count: u32,
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl ParticleEvent {
+    fn distance_to(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (dx, dy, dz) = (self.x - x, self.y - y, self.z - z);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ParticleEvent(particle={}, pos=({:.1}, {:.1}, {:.1}), count={})",
+            self.particle, self.x, self.y, self.z, self.count
+        )
+    }
+}
+
+/// registry name of a particle. Particle is written as its kind id followed
+/// by the data, so reading the kind back out gives us the id
+fn particle_name(particle: &Particle) -> String {
+    let mut buf = Vec::new();
+    if particle.azalea_write(&mut buf).is_err() {
+        return "unknown".to_string();
+    }
+    ParticleKind::azalea_read(&mut Cursor::new(buf.as_slice()))
+        .map(|kind| kind.to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+pub fn particle_event(packet: &ClientboundGamePacket) -> Option<ParticleEvent> {
+    let ClientboundGamePacket::LevelParticles(p) = packet else {
+        return None;
+    };
+    Some(ParticleEvent {
+        particle: particle_name(&p.particle),
+        x: p.pos.x,
+        y: p.pos.y,
+        z: p.pos.z,
+        offset: (p.x_dist, p.y_dist, p.z_dist),
+        speed: p.max_speed,
+        count: p.count,
+        timestamp: unix_seconds(),
+    })
+}
@@ -0,0 +1,212 @@
+//! Extra pathfinder costs, set from Python
+//!
+//! azalea's pathfinder takes a plain fn for its moves, so the costs ride along
+//! in the entity's CustomPathfinderState and cost_move scales whatever
+//! default_move came up with. a multiplier applies to moves that end standing
+//! on or in the block, or of that kind; infinity rules a move out
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::Deserialize;
+
+use azalea::pathfinder::custom_state::{CustomPathfinderState, CustomPathfinderStateRef};
+use azalea::pathfinder::goals::Goal;
+use azalea::pathfinder::moves::{default_move, PathfinderCtx};
+use azalea::pathfinder::rel_block_pos::RelBlockPos;
+use azalea::pathfinder::{PathfinderClientExt, PathfinderOpts};
+use azalea::registry::builtin::BlockKind;
+use azalea::registry::Registry;
+use azalea_client::Client;
+
+use crate::config;
+
+/// what PathCosts.moves can scale
+const MOVES: [&str; 5] = ["straight", "diagonal", "ascend", "descend", "parkour"];
+
+/// multipliers on the pathfinder's move costs, see PyBot.path_costs
+#[pyclass]
+#[derive(Clone, Debug, Default)]
+pub struct PathCosts {
+    /// block name -> multiplier for moves standing on it or in it, like
+    /// {"farmland": float("inf"), "water": 5.0, "dirt_path": 0.5}
+    #[pyo3(get)]
+    pub blocks: HashMap<String, f32>,
+    /// "straight", "diagonal", "ascend", "descend" or "parkour" -> multiplier
+    #[pyo3(get)]
+    pub moves: HashMap<String, f32>,
+}
+
+/// a multiplier in a config file, a number or "inf". TOML's inf arrives as
+/// null, JSON has no infinity
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FileMultiplier {
+    Number(f32),
+    Text(String),
+    Null(()),
+}
+
+impl FileMultiplier {
+    fn value(self) -> PyResult<f32> {
+        match self {
+            FileMultiplier::Number(n) => Ok(n),
+            FileMultiplier::Null(()) => Ok(f32::INFINITY),
+            FileMultiplier::Text(text) => text
+                .parse()
+                .map_err(|_| PyValueError::new_err(format!("expected a number or \"inf\", got {:?}", text))),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PathCostsSection {
+    blocks: HashMap<String, FileMultiplier>,
+    moves: HashMap<String, FileMultiplier>,
+}
+
+/// a file's `[path_costs]`
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PathCostsFile {
+    path_costs: PathCostsSection,
+}
+
+fn file_multipliers(section: HashMap<String, FileMultiplier>) -> PyResult<HashMap<String, f32>> {
+    section.into_iter().map(|(name, m)| Ok((name, m.value()?))).collect()
+}
+
+/// PathCosts looked up by BlockKind id, what cost_move reads
+struct CostTable {
+    blocks: Vec<f32>,
+    moves: [f32; 5],
+}
+
+fn check_multiplier(what: &str, multiplier: f32) -> PyResult<()> {
+    if multiplier.is_nan() || multiplier <= 0.0 {
+        return Err(PyValueError::new_err(format!(
+            "cost for {:?} must be positive (or infinity to avoid it), got {}",
+            what, multiplier
+        )));
+    }
+    Ok(())
+}
+
+impl PathCosts {
+    fn validate(blocks: HashMap<String, f32>, moves: HashMap<String, f32>) -> PyResult<Self> {
+        let blocks = blocks
+            .into_iter()
+            .map(|(name, multiplier)| -> PyResult<(String, f32)> {
+                let name = name.strip_prefix("minecraft:").unwrap_or(&name).to_string();
+                check_multiplier(&name, multiplier)?;
+                if block_kind(&name).is_none() {
+                    return Err(PyValueError::new_err(format!("unknown block {:?}", name)));
+                }
+                Ok((name, multiplier))
+            })
+            .collect::<PyResult<_>>()?;
+        for (name, multiplier) in &moves {
+            if !MOVES.contains(&name.as_str()) {
+                return Err(PyValueError::new_err(format!(
+                    "unknown move {:?}, expected one of {}",
+                    name,
+                    MOVES.join(", ")
+                )));
+            }
+            check_multiplier(name, *multiplier)?;
+        }
+        Ok(Self { blocks, moves })
+    }
+
+    fn table(&self) -> CostTable {
+        let mut blocks = vec![1.0; (0..).map_while(BlockKind::from_u32).count()];
+        for (name, multiplier) in &self.blocks {
+            if let Some(kind) = block_kind(name) {
+                blocks[kind.to_u32() as usize] = *multiplier;
+            }
+        }
+        let moves = MOVES.map(|name| self.moves.get(name).copied().unwrap_or(1.0));
+        CostTable { blocks, moves }
+    }
+}
+
+fn block_kind(name: &str) -> Option<BlockKind> {
+    let name = format!("minecraft:{}", name);
+    (0..).map_while(BlockKind::from_u32).find(|kind| kind.to_string() == name)
+}
+
+#[pymethods]
+impl PathCosts {
+    #[new]
+    #[pyo3(signature = (blocks=None, moves=None))]
+    fn new(blocks: Option<HashMap<String, f32>>, moves: Option<HashMap<String, f32>>) -> PyResult<Self> {
+        Self::validate(blocks.unwrap_or_default(), moves.unwrap_or_default())
+    }
+
+    /// the `[path_costs]` of a .toml, .yaml or .json config file, with
+    /// `blocks` and `moves` tables
+    #[staticmethod]
+    fn from_file(py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        let costs = config::load::<PathCostsFile>(py, &path)?.path_costs;
+        Self::validate(file_multipliers(costs.blocks)?, file_multipliers(costs.moves)?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PathCosts(blocks={:?}, moves={:?})", self.blocks, self.moves)
+    }
+}
+
+/// MOVES index of a move from `from` to `to`
+fn move_kind(from: RelBlockPos, to: RelBlockPos) -> usize {
+    let (dx, dz) = ((to.x - from.x).abs(), (to.z - from.z).abs());
+    if dx.max(dz) > 1 {
+        4
+    } else if to.y > from.y {
+        2
+    } else if to.y < from.y {
+        3
+    } else if dx == 1 && dz == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+/// default_move with the CostTable applied
+fn cost_move(ctx: &mut PathfinderCtx, node: RelBlockPos) {
+    let first = ctx.edges.len();
+    default_move(ctx, node);
+    let Some(table) = ctx.custom_state.get::<CostTable>() else {
+        return;
+    };
+    let world = ctx.world;
+    let cost_of = |pos: RelBlockPos| table.blocks[BlockKind::from(world.get_block_state(pos)).to_u32() as usize];
+    for edge in &mut ctx.edges[first..] {
+        let target = edge.movement.target;
+        edge.cost *= table.moves[move_kind(node, target)] * cost_of(target) * cost_of(target.down(1));
+    }
+    ctx.edges.retain(|edge| edge.cost.is_finite());
+}
+
+/// client.start_goto, with `costs` if there are any
+pub fn start_goto(client: &Client, goal: impl Goal + 'static, costs: Option<&PathCosts>, allow_mining: bool) {
+    let opts = PathfinderOpts::new().allow_mining(allow_mining);
+    let Some(costs) = costs else {
+        client.start_goto_with_opts(goal, opts);
+        return;
+    };
+    let mut state = CustomPathfinderStateRef::default();
+    state.insert(costs.table());
+    // a fresh lock, a path still being worked out holds a read on the old one
+    client
+        .ecs
+        .lock()
+        .entity_mut(client.entity)
+        .insert(CustomPathfinderState(Arc::new(RwLock::new(state))));
+    client.start_goto_with_opts(goal, opts.successors_fn(cost_move));
+}
@@ -0,0 +1,169 @@
+//! What a bot is allowed to do, for running untrusted policy code
+//!
+//! every PyBot action checks the bot's Permissions before it reaches azalea,
+//! and attached policies skip what they aren't allowed. once locked they can
+//! only be narrowed, so the code being sandboxed can't hand them back. this
+//! limits what goes out through pyzalea, it isn't a Python sandbox
+
+use pyo3::prelude::*;
+
+use crate::errors::PermissionDeniedError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    Chat,
+    Move,
+    Look,
+    AttackPlayers,
+    UseItems,
+    BreakBlocks,
+    RawPackets,
+}
+
+impl Capability {
+    fn describe(self) -> &'static str {
+        match self {
+            Capability::Chat => "chat",
+            Capability::Move => "move",
+            Capability::Look => "look around",
+            Capability::AttackPlayers => "attack players",
+            Capability::UseItems => "use items",
+            Capability::BreakBlocks => "break blocks",
+            Capability::RawPackets => "send raw packets",
+        }
+    }
+}
+
+/// the action categories a bot may use, all allowed by default. see
+/// PyBot.permissions
+#[pyclass]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permissions {
+    /// chat messages and commands
+    #[pyo3(get, set)]
+    pub chat: bool,
+    /// walking, sprinting, jumping and pathfinding
+    #[pyo3(get, set)]
+    pub movement: bool,
+    #[pyo3(get, set)]
+    pub look: bool,
+    #[pyo3(get, set)]
+    pub attack_players: bool,
+    /// eating, blocking, bows and switching hotbar slots
+    #[pyo3(get, set)]
+    pub use_items: bool,
+    /// mining, which the pathfinder does to get through walls
+    #[pyo3(get, set)]
+    pub break_blocks: bool,
+    /// send_packet() and send_raw_packet(), which can do anything
+    #[pyo3(get, set)]
+    pub raw_packets: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            chat: true,
+            movement: true,
+            look: true,
+            attack_players: true,
+            use_items: true,
+            break_blocks: true,
+            raw_packets: true,
+        }
+    }
+}
+
+impl Permissions {
+    pub fn allows(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Chat => self.chat,
+            Capability::Move => self.movement,
+            Capability::Look => self.look,
+            Capability::AttackPlayers => self.attack_players,
+            Capability::UseItems => self.use_items,
+            Capability::BreakBlocks => self.break_blocks,
+            Capability::RawPackets => self.raw_packets,
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, bool); 7] {
+        [
+            ("chat", self.chat),
+            ("movement", self.movement),
+            ("look", self.look),
+            ("attack_players", self.attack_players),
+            ("use_items", self.use_items),
+            ("break_blocks", self.break_blocks),
+            ("raw_packets", self.raw_packets),
+        ]
+    }
+
+    /// true if `self` allows nothing `other` doesn't
+    pub fn within(&self, other: &Permissions) -> bool {
+        self.fields().iter().zip(other.fields()).all(|((_, mine), (_, theirs))| !mine || theirs)
+    }
+}
+
+pub fn denied(capability: Capability) -> PyErr {
+    PermissionDeniedError::new_err(format!("this bot isn't allowed to {}", capability.describe()))
+}
+
+#[pymethods]
+impl Permissions {
+    #[new]
+    #[pyo3(signature = (chat=true, movement=true, look=true, attack_players=true, use_items=true, break_blocks=true, raw_packets=true))]
+    fn new(
+        chat: bool,
+        movement: bool,
+        look: bool,
+        attack_players: bool,
+        use_items: bool,
+        break_blocks: bool,
+        raw_packets: bool,
+    ) -> Self {
+        Self {
+            chat,
+            movement,
+            look,
+            attack_players,
+            use_items,
+            break_blocks,
+            raw_packets,
+        }
+    }
+
+    /// moving and looking around, nothing else
+    #[staticmethod]
+    fn movement_only() -> Self {
+        Self {
+            movement: true,
+            look: true,
+            ..Self::none()
+        }
+    }
+
+    /// nothing at all, the bot only watches
+    #[staticmethod]
+    fn none() -> Self {
+        Self {
+            chat: false,
+            movement: false,
+            look: false,
+            attack_players: false,
+            use_items: false,
+            break_blocks: false,
+            raw_packets: false,
+        }
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self == other
+    }
+
+    fn __repr__(&self) -> String {
+        let bool_str = |b: bool| if b { "True" } else { "False" };
+        let fields: Vec<String> = self.fields().iter().map(|(name, on)| format!("{}={}", name, bool_str(*on))).collect();
+        format!("Permissions({})", fields.join(", "))
+    }
+}
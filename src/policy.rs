@@ -0,0 +1,341 @@
+//! ONNX policies that drive a bot from its tick loop, without Python
+//!
+//! the model gets the same to_vector() observation a Python agent would, and
+//! its outputs are read as a FighterAction through an action mapping. loading
+//! models needs the "onnx" feature (tract, so there's no onnxruntime to ship)
+
+use std::path::Path;
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use azalea::SprintDirection;
+use azalea::ecs::entity::Entity;
+use azalea_client::Client;
+use azalea_world::MinecraftEntityId;
+
+use crate::arena::FighterAction;
+use crate::bot::{walk_direction, BotShared};
+use crate::errors::in_game;
+use crate::item::{hand_use, HandUse};
+use crate::logging::COMBAT;
+use crate::observation::ObservationConfig;
+use crate::recorder::ACTION_NAMES;
+use crate::state::PyGameState;
+
+#[cfg(feature = "onnx")]
+mod model {
+    use std::path::Path;
+
+    use tract_onnx::prelude::*;
+
+    pub struct Model {
+        plan: TypedRunnableModel<TypedModel>,
+        width: usize,
+    }
+
+    impl Model {
+        /// with a fixed [1, width] float input
+        pub fn load(path: &Path, width: usize) -> Result<Self, String> {
+            let plan = tract_onnx::onnx()
+                .model_for_path(path)
+                .and_then(|model| model.with_input_fact(0, f32::fact([1, width]).into()))
+                .and_then(|model| model.into_optimized())
+                .and_then(|model| model.into_runnable())
+                .map_err(|e| format!("can't load {}: {:#}", path.display(), e))?;
+            Ok(Self { plan, width })
+        }
+
+        /// the first output, flattened
+        pub fn run(&self, observation: &[f32]) -> Result<Vec<f32>, String> {
+            let input = Tensor::from_shape(&[1, self.width], observation).map_err(|e| format!("{:#}", e))?;
+            let outputs = self.plan.run(tvec!(input.into())).map_err(|e| format!("{:#}", e))?;
+            let output = outputs.first().ok_or("the model has no outputs")?;
+            Ok(output.as_slice::<f32>().map_err(|e| format!("{:#}", e))?.to_vec())
+        }
+    }
+}
+
+#[cfg(not(feature = "onnx"))]
+mod model {
+    use std::path::Path;
+
+    /// can't exist without the onnx feature
+    pub enum Model {}
+
+    impl Model {
+        pub fn load(_path: &Path, _width: usize) -> Result<Self, String> {
+            Err("pyzalea was built without the \"onnx\" feature".to_string())
+        }
+
+        pub fn run(&self, _observation: &[f32]) -> Result<Vec<f32>, String> {
+            match *self {}
+        }
+    }
+}
+
+use model::Model;
+
+/// which model output drives each FighterAction field
+#[derive(Clone, Debug)]
+pub struct ActionMapping {
+    /// output index per ACTION_NAMES entry, None for actions the model doesn't have
+    outputs: [Option<usize>; ACTION_NAMES.len()],
+}
+
+impl ActionMapping {
+    /// a dict of action name -> output index, or a list of action names in
+    /// output order
+    pub fn from_py(mapping: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut pairs = vec![];
+        if let Ok(dict) = mapping.downcast::<PyDict>() {
+            for (name, index) in dict.iter() {
+                pairs.push((name.extract::<String>()?, index.extract::<usize>()?));
+            }
+        } else {
+            let names = mapping.extract::<Vec<String>>()?;
+            pairs.extend(names.into_iter().enumerate().map(|(i, name)| (name, i)));
+        }
+        let mut outputs = [None; ACTION_NAMES.len()];
+        for (name, index) in pairs {
+            let Some(action) = ACTION_NAMES.iter().position(|a| *a == name) else {
+                return Err(PyValueError::new_err(format!(
+                    "unknown action {:?}, expected one of {}",
+                    name,
+                    ACTION_NAMES.join(", ")
+                )));
+            };
+            outputs[action] = Some(index);
+        }
+        if outputs.iter().all(Option::is_none) {
+            return Err(PyValueError::new_err("the action mapping is empty"));
+        }
+        Ok(Self { outputs })
+    }
+
+    fn max_output(&self) -> usize {
+        self.outputs.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// flags are on above `threshold`, delta_yaw/delta_pitch are degrees and
+    /// must be finite
+    fn action(&self, outputs: &[f32], threshold: f32) -> Result<FighterAction, String> {
+        let value = |action: usize| self.outputs[action].and_then(|i| outputs.get(i).copied());
+        let flag = |action: usize| value(action).is_some_and(|v| v > threshold);
+        for action in [8, 9] {
+            if let Some(v) = value(action).filter(|v| !v.is_finite()) {
+                return Err(format!("the model's {} output is {}", ACTION_NAMES[action], v));
+            }
+        }
+        Ok(FighterAction {
+            forward: flag(0),
+            backward: flag(1),
+            left: flag(2),
+            right: flag(3),
+            jump: flag(4),
+            sprint: flag(5),
+            attack: flag(6),
+            eat: flag(7),
+            delta_yaw: value(8).unwrap_or(0.0) as f64,
+            delta_pitch: value(9).unwrap_or(0.0) as f64,
+            draw: flag(10),
+            block: flag(11),
+            pearl: flag(12),
+            potion: flag(13),
+            rod: flag(14),
+        })
+    }
+}
+
+pub struct Policy {
+    model: Model,
+    mapping: ActionMapping,
+    config: ObservationConfig,
+    threshold: f32,
+    /// who to attack, None for the nearest player
+    target: Option<String>,
+    entity_radius: f64,
+    /// ticks it has acted on
+    pub ticks: u64,
+    /// why it stopped, None while it's running
+    pub error: Option<String>,
+}
+
+impl Policy {
+    /// loads the model and runs it once on a zero observation, so a model
+    /// that doesn't fit the config or the mapping fails here and not mid-game
+    pub fn load(
+        path: &Path,
+        mapping: ActionMapping,
+        config: ObservationConfig,
+        target: Option<String>,
+        threshold: f32,
+        entity_radius: f64,
+    ) -> PyResult<Self> {
+        let width = config.feature_names().len();
+        let model = Model::load(path, width).map_err(PyRuntimeError::new_err)?;
+        let outputs = model.run(&vec![0.0; width]).map_err(PyRuntimeError::new_err)?;
+        if mapping.max_output() >= outputs.len() {
+            return Err(PyValueError::new_err(format!(
+                "the model has {} outputs but the action mapping uses output {}",
+                outputs.len(),
+                mapping.max_output()
+            )));
+        }
+        Ok(Self {
+            model,
+            mapping,
+            config,
+            threshold,
+            target,
+            entity_radius,
+            ticks: 0,
+            error: None,
+        })
+    }
+
+    /// observe, run the model and press the keys, called on every game tick
+    pub fn tick(&mut self, client: &Client, shared: &BotShared) {
+        if self.error.is_some() || !in_game(client) {
+            return;
+        }
+        // the state can be missing a component for a few ticks after spawning
+        let Ok(state) = shared.game_state(client, self.entity_radius, None) else {
+            return;
+        };
+        if state.is_dead {
+            return;
+        }
+        let outputs = self.model.run(&self.config.vector(&state));
+        let action = match outputs.and_then(|outputs| self.mapping.action(&outputs, self.threshold)) {
+            Ok(action) => action,
+            Err(e) => {
+                tracing::error!(target: COMBAT, "policy stopped: {}", e);
+                self.error = Some(e);
+                release(client, shared);
+                return;
+            }
+        };
+        if let Some(entity) = apply(client, shared, &state, &action, self.target.as_deref()) {
+            *shared.target.lock() = Some(entity);
+        }
+        self.ticks += 1;
+    }
+}
+
+/// let go of every key
+pub fn release(client: &Client, shared: &BotShared) {
+    let mut sanitizer = shared.sanitizer.lock();
+    sanitizer.walk(client, azalea::WalkDirection::None);
+    sanitizer.set_jumping(client, false);
+}
+
+/// returns the entity it attacked, if any. goes through the bot's
+/// ActionLimits like everything Python asks for, and skips what its
+/// Permissions don't allow
+fn apply(
+    client: &Client,
+    shared: &BotShared,
+    state: &PyGameState,
+    action: &FighterAction,
+    target: Option<&str>,
+) -> Option<Entity> {
+    let permissions = shared.permissions.lock().clone();
+    let mut sanitizer = shared.sanitizer.lock();
+    if permissions.look {
+        let pitch = (state.pitch + action.delta_pitch as f32).clamp(-90.0, 90.0);
+        sanitizer.look(client, state.yaw + action.delta_yaw as f32, pitch);
+    }
+
+    if permissions.movement {
+        // sprinting only goes forwards
+        if action.sprint && action.forward && !action.backward {
+            sanitizer.sprint(
+                client,
+                match (action.left, action.right) {
+                    (true, false) => SprintDirection::ForwardLeft,
+                    (false, true) => SprintDirection::ForwardRight,
+                    _ => SprintDirection::Forward,
+                },
+            );
+        } else {
+            sanitizer.walk(client, walk_direction(action.forward, action.backward, action.left, action.right));
+        }
+        sanitizer.set_jumping(client, action.jump);
+    }
+
+    if action.eat && permissions.use_items {
+        sanitizer.use_item(client);
+    }
+    if action.pearl && permissions.use_items && state.held_item_id.as_deref() == Some("ender_pearl") {
+        sanitizer.use_item(client);
+    }
+    if action.potion && permissions.use_items && state.held_item_id.as_deref() == Some("splash_potion") {
+        sanitizer.use_item(client);
+    }
+    if action.rod && permissions.use_items && state.held_item_id.as_deref() == Some("fishing_rod") {
+        sanitizer.use_item(client);
+    }
+    // the bow shoots and the shield drops when they're let go
+    if permissions.use_items {
+        let main = state.held_item_id.as_deref();
+        match hand_use(main, state.offhand_id.as_deref()) {
+            HandUse::Draw if action.draw => {
+                sanitizer.use_item(client);
+            }
+            HandUse::Block if action.block && main == Some("shield") => {
+                sanitizer.use_item(client);
+            }
+            HandUse::Block if action.block => {
+                sanitizer.use_offhand(client);
+            }
+            HandUse::Draw | HandUse::Block if state.is_using_item && !action.eat => {
+                sanitizer.release_item(client);
+            }
+            _ => {}
+        }
+    }
+    if !action.attack || !permissions.attack_players {
+        return None;
+    }
+    let entity = attack_target(client, state, target);
+    match entity {
+        Some(entity) if sanitizer.click() => client.attack(entity),
+        Some(_) => {
+            tracing::trace!(target: COMBAT, "policy attack over the click limit");
+            return None;
+        }
+        None => tracing::trace!(target: COMBAT, "policy attack without a target in range"),
+    }
+    entity
+}
+
+fn attack_target(client: &Client, state: &PyGameState, target: Option<&str>) -> Option<Entity> {
+    match target {
+        Some(username) => client.player_uuid_by_username(username).and_then(|uuid| client.entity_by_uuid(uuid)),
+        None => {
+            let player = state.nearest_player()?;
+            client.ecs_entity_by_minecraft_entity(MinecraftEntityId(player.id as i32))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_rejects_non_finite_turns() {
+        let mut outputs = [None; ACTION_NAMES.len()];
+        (outputs[6], outputs[8], outputs[9]) = (Some(0), Some(1), Some(2));
+        let mapping = ActionMapping { outputs };
+        let action = mapping.action(&[0.9, 5.0, -1.0], 0.5).unwrap();
+        assert!(action.attack && !action.forward);
+        assert_eq!((action.delta_yaw, action.delta_pitch), (5.0, -1.0));
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(mapping.action(&[0.9, bad, 0.0], 0.5).is_err());
+            assert!(mapping.action(&[0.9, 0.0, bad], 0.5).is_err());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Velocity and shooter of projectiles
+//!
+//! azalea drops the velocity in add_entity, and arrows often get no motion
+//! update after that, so remember what the server told us
+
+use std::collections::HashMap;
+
+use azalea_core::position::Vec3;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+use crate::entity_kind::{category, Category};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Flight {
+    pub velocity: Vec3,
+    /// entity that shot or threw it, if the server says
+    pub owner: Option<i32>,
+}
+
+/// keyed by the protocol entity id
+#[derive(Debug, Default)]
+pub struct Projectiles {
+    flying: HashMap<i32, Flight>,
+}
+
+impl Projectiles {
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::AddEntity(p) if category(p.entity_type) == Category::Projectile => {
+                // for projectiles the object data is the owner's id, 0 for none
+                let owner = (p.data != 0).then_some(p.data);
+                self.flying.insert(
+                    p.id.0,
+                    Flight {
+                        velocity: p.movement.to_vec3(),
+                        owner,
+                    },
+                );
+            }
+            ClientboundGamePacket::SetEntityMotion(p) => {
+                if let Some(flight) = self.flying.get_mut(&p.id.0) {
+                    flight.velocity = p.delta.to_vec3();
+                }
+            }
+            ClientboundGamePacket::RemoveEntities(p) => {
+                for id in &p.entity_ids {
+                    self.flying.remove(&id.0);
+                }
+            }
+            ClientboundGamePacket::Respawn(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.flying.clear();
+    }
+
+    pub fn get(&self, id: i32) -> Option<&Flight> {
+        self.flying.get(&id)
+    }
+}
@@ -0,0 +1,136 @@
+//! Building serverbound packets from Python, for anything the high-level API
+//! doesn't cover yet
+//!
+//! packets are always parsed back into azalea's packet type before sending, so
+//! malformed data is rejected here instead of getting the bot kicked
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyString, PyTuple};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::OnceLock;
+
+use azalea::BlockPos;
+use azalea::buf::{AzaleaWrite, AzaleaWriteVar};
+use azalea_protocol::packets::ProtocolPacket;
+use azalea_protocol::packets::game::ServerboundGamePacket;
+use azalea_protocol::read::ReadPacketError;
+
+fn write_err(e: std::io::Error) -> PyErr {
+    PyValueError::new_err(format!("failed to encode field: {}", e))
+}
+
+/// encode one value. plain values map to bool/varint/double/string/raw bytes,
+/// anything else needs a (type, value) tuple
+fn encode_value(buf: &mut Vec<u8>, name: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+    // bool before int, since True is also an int in python
+    if value.is_instance_of::<PyBool>() {
+        return value.extract::<bool>()?.azalea_write(buf).map_err(write_err);
+    }
+    if value.is_instance_of::<PyInt>() {
+        return value.extract::<i32>()?.azalea_write_var(buf).map_err(write_err);
+    }
+    if value.is_instance_of::<PyFloat>() {
+        return value.extract::<f64>()?.azalea_write(buf).map_err(write_err);
+    }
+    if value.is_instance_of::<PyString>() {
+        return value.extract::<String>()?.azalea_write(buf).map_err(write_err);
+    }
+    if let Ok(bytes) = value.downcast::<PyBytes>() {
+        buf.extend_from_slice(bytes.as_bytes());
+        return Ok(());
+    }
+
+    let Ok(tagged) = value.downcast::<PyTuple>() else {
+        return Err(PyValueError::new_err(format!("field '{}' has an unsupported type", name)));
+    };
+    if tagged.len() != 2 {
+        return Err(PyValueError::new_err(format!("field '{}' must be a (type, value) tuple", name)));
+    }
+    let kind: String = tagged.get_item(0)?.extract()?;
+    let value = tagged.get_item(1)?;
+    let result = match kind.as_str() {
+        "bool" => value.extract::<bool>()?.azalea_write(buf),
+        "byte" => value.extract::<i8>()?.azalea_write(buf),
+        "ubyte" => value.extract::<u8>()?.azalea_write(buf),
+        "short" => value.extract::<i16>()?.azalea_write(buf),
+        "ushort" => value.extract::<u16>()?.azalea_write(buf),
+        "int" => value.extract::<i32>()?.azalea_write(buf),
+        "long" => value.extract::<i64>()?.azalea_write(buf),
+        "float" => value.extract::<f32>()?.azalea_write(buf),
+        "double" => value.extract::<f64>()?.azalea_write(buf),
+        "varint" => value.extract::<i32>()?.azalea_write_var(buf),
+        "varlong" => value.extract::<i64>()?.azalea_write_var(buf),
+        "string" => value.extract::<String>()?.azalea_write(buf),
+        // length-prefixed, unlike plain bytes
+        "bytes" => value.extract::<Vec<u8>>()?.azalea_write(buf),
+        "position" => {
+            let (x, y, z): (i32, i32, i32) = value.extract()?;
+            BlockPos::new(x, y, z).azalea_write(buf)
+        }
+        _ => {
+            return Err(PyValueError::new_err(format!("field '{}' has unknown type '{}'", name, kind)));
+        }
+    };
+    result.map_err(write_err)
+}
+
+/// encode a dict of fields in order (dicts keep insertion order)
+pub fn encode_fields(fields: &Bound<'_, PyDict>) -> PyResult<Vec<u8>> {
+    let mut buf = Vec::new();
+    for (key, value) in fields.iter() {
+        let name = key.str()?.to_string();
+        encode_value(&mut buf, &name, &value)?;
+    }
+    Ok(buf)
+}
+
+fn parse(id: u32, data: &[u8]) -> Result<ServerboundGamePacket, Box<ReadPacketError>> {
+    let mut cursor = Cursor::new(data);
+    let packet = ServerboundGamePacket::read(id, &mut cursor)?;
+    if cursor.position() as usize != data.len() {
+        return Err(Box::new(ReadPacketError::LeftoverData {
+            packet_name: packet.name().to_string(),
+            data: data[cursor.position() as usize..].to_vec(),
+        }));
+    }
+    Ok(packet)
+}
+
+/// serverbound game packet ids by name, found once by reading every id with
+/// an empty body: a packet without fields parses, any other names itself in
+/// the error
+fn packet_ids() -> &'static HashMap<String, u32> {
+    static IDS: OnceLock<HashMap<String, u32>> = OnceLock::new();
+    IDS.get_or_init(|| {
+        let mut ids = HashMap::new();
+        for id in 0.. {
+            let name = match parse(id, &[]) {
+                Ok(packet) => packet.name().to_string(),
+                Err(e) => match *e {
+                    ReadPacketError::Parse { packet_name, .. } | ReadPacketError::LeftoverData { packet_name, .. } => packet_name,
+                    ReadPacketError::UnknownPacketId { .. } => break,
+                    _ => continue,
+                },
+            };
+            ids.insert(name, id);
+        }
+        ids
+    })
+}
+
+/// parse a packet body given the packet's id or name (like "swing" or "chat_command")
+pub fn build_packet(packet: &Bound<'_, PyAny>, data: &[u8]) -> PyResult<ServerboundGamePacket> {
+    if let Ok(id) = packet.extract::<u32>() {
+        return parse(id, data)
+            .map_err(|e| PyValueError::new_err(format!("invalid packet {}: {}", id, e)));
+    }
+
+    let name: String = packet.extract()?;
+    let name = name.strip_prefix("minecraft:").unwrap_or(&name);
+    let Some(&id) = packet_ids().get(name) else {
+        return Err(PyValueError::new_err(format!("'{}' is not a serverbound game packet", name)));
+    };
+    parse(id, data).map_err(|e| PyValueError::new_err(format!("invalid packet '{}': {}", name, e)))
+}
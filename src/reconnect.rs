@@ -0,0 +1,249 @@
+//! Opt-in reconnect policy with exponential backoff
+//!
+//! azalea's own auto-reconnect (fixed 5s delay, forever) is turned off for
+//! bots that have a policy, and this plugin schedules the rejoins instead
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::Deserialize;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use azalea::app::{App, Plugin, Update};
+use azalea::ecs::prelude::*;
+use azalea_client::auto_reconnect::{start_rejoin_on_disconnect, InternalReconnectAfter};
+use azalea_client::disconnect::DisconnectEvent;
+use azalea_client::join::ConnectionFailedEvent;
+
+use crate::bot::BotState;
+use crate::events::{BotEvent, ReconnectEvent};
+use crate::logging::CONNECTION;
+
+#[pyclass]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReconnectPolicy {
+    /// give up after this many attempts in a row
+    #[pyo3(get, set)]
+    pub max_retries: u32,
+    /// seconds before the first attempt
+    #[pyo3(get)]
+    pub initial_delay: f64,
+    /// upper bound on the delay between attempts (seconds)
+    #[pyo3(get)]
+    pub max_delay: f64,
+    /// delay growth factor per attempt
+    #[pyo3(get)]
+    pub multiplier: f64,
+    /// rejoin after being kicked (as opposed to losing the connection)
+    #[pyo3(get, set)]
+    pub rejoin_on_kick: bool,
+    /// never rejoin if the kick message contains one of these (case-insensitive)
+    #[pyo3(get, set)]
+    pub kick_filter: Vec<String>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_delay: 1.0,
+            max_delay: 60.0,
+            multiplier: 2.0,
+            rejoin_on_kick: true,
+            kick_filter: vec!["banned".to_string()],
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// the delays become Durations, so they have to be finite and not negative.
+    /// also run on policies from config files, which skip new()
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("initial_delay", self.initial_delay),
+            ("max_delay", self.max_delay),
+            ("multiplier", self.multiplier),
+        ] {
+            check_seconds(name, value)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn check_seconds(name: &str, value: f64) -> Result<(), String> {
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!("{} must be finite and not negative, got {}", name, value));
+    }
+    Ok(())
+}
+
+/// `seconds` from now, None when that's no valid Instant
+pub fn instant_after(seconds: f64) -> Option<Instant> {
+    Duration::try_from_secs_f64(seconds).ok().and_then(|delay| Instant::now().checked_add(delay))
+}
+
+#[pymethods]
+impl ReconnectPolicy {
+    #[new]
+    #[pyo3(signature = (max_retries=5, initial_delay=1.0, max_delay=60.0, multiplier=2.0, rejoin_on_kick=true, kick_filter=vec!["banned".to_string()]))]
+    fn new(
+        max_retries: u32,
+        initial_delay: f64,
+        max_delay: f64,
+        multiplier: f64,
+        rejoin_on_kick: bool,
+        kick_filter: Vec<String>,
+    ) -> PyResult<Self> {
+        let policy = Self {
+            max_retries,
+            initial_delay,
+            max_delay,
+            multiplier,
+            rejoin_on_kick,
+            kick_filter,
+        };
+        policy.validate().map_err(PyValueError::new_err)?;
+        Ok(policy)
+    }
+
+    #[setter]
+    fn set_initial_delay(&mut self, initial_delay: f64) -> PyResult<()> {
+        check_seconds("initial_delay", initial_delay).map_err(PyValueError::new_err)?;
+        self.initial_delay = initial_delay;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_max_delay(&mut self, max_delay: f64) -> PyResult<()> {
+        check_seconds("max_delay", max_delay).map_err(PyValueError::new_err)?;
+        self.max_delay = max_delay;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_multiplier(&mut self, multiplier: f64) -> PyResult<()> {
+        check_seconds("multiplier", multiplier).map_err(PyValueError::new_err)?;
+        self.multiplier = multiplier;
+        Ok(())
+    }
+
+    /// delay in seconds before the given (1-based) attempt
+    fn delay_for(&self, attempt: u32) -> f64 {
+        let exp = attempt.saturating_sub(1) as i32;
+        (self.initial_delay * self.multiplier.powi(exp)).min(self.max_delay)
+    }
+
+    /// whether a kick with this message should be rejoined
+    fn allows_kick(&self, reason: &str) -> bool {
+        if !self.rejoin_on_kick {
+            return false;
+        }
+        let reason = reason.to_lowercase();
+        !self
+            .kick_filter
+            .iter()
+            .any(|pattern| reason.contains(&pattern.to_lowercase()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ReconnectPolicy(max_retries={}, initial_delay={}, max_delay={}, multiplier={})",
+            self.max_retries, self.initial_delay, self.max_delay, self.multiplier
+        )
+    }
+}
+
+pub struct ReconnectPlugin;
+impl Plugin for ReconnectPlugin {
+    fn build(&self, app: &mut App) {
+        // after azalea's auto-reconnect, so our delay wins when both schedule one
+        app.add_systems(Update, schedule_reconnect.after(start_rejoin_on_disconnect));
+    }
+}
+
+/// kick messages from vanilla's connection-throttle and common proxies
+const THROTTLE_MESSAGES: &[&str] = &["throttled", "logging in too fast", "wait before reconnecting"];
+
+fn is_throttled(reason: &str) -> bool {
+    let reason = reason.to_lowercase();
+    THROTTLE_MESSAGES.iter().any(|m| reason.contains(m))
+}
+
+fn schedule_reconnect(
+    mut commands: Commands,
+    mut disconnect_events: MessageReader<DisconnectEvent>,
+    mut connection_failed_events: MessageReader<ConnectionFailedEvent>,
+    query: Query<&BotState>,
+) {
+    let disconnects = disconnect_events
+        .read()
+        .map(|e| (e.entity, e.reason.as_ref().map(|r| r.to_string()), true));
+    let failures = connection_failed_events
+        .read()
+        .map(|e| (e.entity, Some(e.error.to_string()), false));
+
+    for (entity, reason, was_connected) in disconnects.chain(failures) {
+        let Ok(state) = query.get(entity) else { continue };
+        let Some(shared) = &state.shared else { continue };
+        let _span = shared.span.enter();
+        if shared.closing.load(Ordering::SeqCst) {
+            continue;
+        }
+        if shared.transferring.swap(false, Ordering::SeqCst) {
+            commands.entity(entity).insert(InternalReconnectAfter { instant: Instant::now() });
+            continue;
+        }
+
+        // throttling isn't a real failure, so it doesn't count against the policy
+        if reason.as_deref().is_some_and(is_throttled) {
+            let attempt = shared.throttle_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            if attempt <= shared.throttle_retries {
+                let delay = shared.throttle_delay * attempt as f64;
+                tracing::warn!(target: CONNECTION, attempt, delay, "throttled, rejoining");
+                if let Some(instant) = instant_after(delay) {
+                    commands.entity(entity).insert(InternalReconnectAfter { instant });
+                }
+                shared.push_event(BotEvent::Reconnect(ReconnectEvent {
+                    kind: "throttled".to_string(),
+                    attempt,
+                    delay,
+                    reason,
+                }));
+                continue;
+            }
+        }
+
+        let Some(policy) = &shared.reconnect else { continue };
+
+        let attempt = shared.reconnect_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+
+        // a kick comes with a reason, a dropped connection doesn't
+        let kicked = was_connected && reason.is_some();
+        let kick_allowed = !kicked || policy.allows_kick(reason.as_deref().unwrap_or(""));
+
+        let delay = policy.delay_for(attempt);
+        let retry = attempt <= policy.max_retries && kick_allowed;
+        let Some(instant) = instant_after(delay).filter(|_| retry) else {
+            // this disconnect isn't retried, so the attempts made are the ones before it
+            let attempts = attempt - 1;
+            tracing::warn!(target: CONNECTION, attempts, "giving up on reconnecting");
+            shared.push_event(BotEvent::Reconnect(ReconnectEvent {
+                kind: "gave_up".to_string(),
+                attempt: attempts,
+                delay: 0.0,
+                reason,
+            }));
+            continue;
+        };
+
+        tracing::info!(target: CONNECTION, attempt, delay, "reconnecting");
+        commands.entity(entity).insert(InternalReconnectAfter { instant });
+        shared.push_event(BotEvent::Reconnect(ReconnectEvent {
+            kind: "reconnecting".to_string(),
+            attempt,
+            delay,
+            reason,
+        }));
+    }
+}
@@ -0,0 +1,414 @@
+//! Imitation data from a connected bot
+//!
+//! logs an observation, the actions the bot and its opponent seem to have
+//! taken, and a few events every tick while it spectates or duels a human.
+//! nobody tells us the inputs, so actions are inferred from how the states
+//! change between ticks
+
+use std::path::{Path, PathBuf};
+
+use numpy::{PyArray1, PyArrayMethods};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::bot::PyBot;
+use crate::item::{hand_use, HandUse};
+use crate::observation::ObservationConfig;
+use crate::state::{PyEntity, PyGameState};
+
+/// FighterAction's fields, in its order
+pub const ACTION_NAMES: &[&str] = &[
+    "forward", "backward", "left", "right", "jump", "sprint", "attack", "eat", "delta_yaw", "delta_pitch",
+    "draw", "block", "pearl", "potion", "rod",
+];
+
+pub const EVENT_NAMES: &[&str] = &["hurt", "damage_taken", "opponent_hurt", "damage_dealt", "opponent_visible", "died"];
+
+/// blocks per tick along an axis before it counts as walking that way
+const MOVE_THRESHOLD: f64 = 0.02;
+/// walking is ~0.22 blocks per tick, sprinting ~0.28
+const SPRINT_SPEED: f64 = 0.25;
+const JUMP_SPEED: f64 = 0.1;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Npz,
+    Parquet,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Npz => "npz",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+/// what inference needs from a PyGameState or a PyEntity
+#[derive(Clone, Copy)]
+struct Motion {
+    x: f64,
+    y: f64,
+    z: f64,
+    yaw: f32,
+    pitch: f32,
+    on_ground: bool,
+    using_item: bool,
+    /// what using_item means
+    hand: HandUse,
+    /// the stack size while holding ender pearls, we only know this for ourselves
+    pearls: Option<i32>,
+    /// the same for splash potions
+    potions: Option<i32>,
+    /// while holding a rod, whether a bobber is out. the opponent's can't be
+    /// told apart from ours
+    bobber: Option<bool>,
+    /// we only know this for ourselves
+    sprinting: Option<bool>,
+    health: f32,
+}
+
+impl Motion {
+    fn of_state(state: &PyGameState) -> Self {
+        Self {
+            x: state.x,
+            y: state.y,
+            z: state.z,
+            yaw: state.yaw,
+            pitch: state.pitch,
+            on_ground: state.is_on_ground,
+            using_item: state.is_using_item,
+            hand: hand_use(state.held_item_id.as_deref(), state.offhand_id.as_deref()),
+            pearls: (state.held_item_id.as_deref() == Some("ender_pearl")).then_some(state.held_item_count),
+            potions: (state.held_item_id.as_deref() == Some("splash_potion")).then_some(state.held_item_count),
+            bobber: (state.held_item_id.as_deref() == Some("fishing_rod"))
+                .then(|| state.entities.iter().any(|e| e.entity_type == "fishing_bobber")),
+            sprinting: Some(state.is_sprinting),
+            health: state.health,
+        }
+    }
+
+    fn of_entity(entity: &PyEntity) -> Self {
+        Self {
+            x: entity.x,
+            y: entity.y,
+            z: entity.z,
+            yaw: entity.yaw,
+            pitch: entity.pitch,
+            on_ground: entity.is_on_ground,
+            using_item: entity.is_using_item,
+            hand: hand_use(
+                entity.held_item.as_ref().map(|item| item.id.as_str()),
+                entity.offhand.as_ref().map(|item| item.id.as_str()),
+            ),
+            pearls: None,
+            potions: None,
+            bobber: None,
+            sprinting: None,
+            health: entity.health,
+        }
+    }
+}
+
+/// wrap an angle difference to -180..180
+fn wrap_degrees(delta: f32) -> f32 {
+    (delta + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// the inputs that would take `prev` to `cur`, in ACTION_NAMES order
+fn infer(prev: &Motion, cur: &Motion, attacked: bool) -> [f32; ACTION_NAMES.len()] {
+    let (dx, dz) = (cur.x - prev.x, cur.z - prev.z);
+    // minecraft yaw: 0 faces +z, 90 faces -x
+    let (sin, cos) = (prev.yaw as f64).to_radians().sin_cos();
+    let forward = -dx * sin + dz * cos;
+    let left = dx * cos + dz * sin;
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+    let sprinting = cur.sprinting.unwrap_or_else(|| dx.hypot(dz) > SPRINT_SPEED);
+    [
+        flag(forward > MOVE_THRESHOLD),
+        flag(forward < -MOVE_THRESHOLD),
+        flag(left > MOVE_THRESHOLD),
+        flag(left < -MOVE_THRESHOLD),
+        flag(prev.on_ground && !cur.on_ground && cur.y - prev.y > JUMP_SPEED),
+        flag(sprinting),
+        flag(attacked),
+        flag(cur.using_item && cur.hand == HandUse::Eat),
+        wrap_degrees(cur.yaw - prev.yaw),
+        cur.pitch - prev.pitch,
+        flag(cur.using_item && cur.hand == HandUse::Draw),
+        flag(cur.using_item && cur.hand == HandUse::Block),
+        flag(prev.pearls.zip(cur.pearls).is_some_and(|(before, after)| after < before)),
+        flag(prev.potions.zip(cur.potions).is_some_and(|(before, after)| after < before)),
+        flag(prev.bobber.zip(cur.bobber).is_some_and(|(before, after)| after != before)),
+    ]
+}
+
+/// rows waiting to be written
+#[derive(Default)]
+struct Buffer {
+    ticks: Vec<u64>,
+    observations: Vec<f32>,
+    actions: Vec<f32>,
+    opponent_actions: Vec<f32>,
+    events: Vec<f32>,
+}
+
+impl Buffer {
+    fn len(&self) -> usize {
+        self.ticks.len()
+    }
+}
+
+#[pyclass]
+pub struct Recorder {
+    bot: Py<PyBot>,
+    /// directory the shards go in
+    #[pyo3(get)]
+    path: PathBuf,
+    /// username to follow, None for whoever's the nearest player
+    #[pyo3(get)]
+    opponent: Option<String>,
+    /// rows per shard
+    #[pyo3(get)]
+    shard_size: usize,
+    format: Format,
+    config: ObservationConfig,
+    buffer: Buffer,
+    /// rows recorded so far, written or not
+    #[pyo3(get)]
+    rows: usize,
+    /// paths of the shards written so far
+    #[pyo3(get)]
+    shards: Vec<PathBuf>,
+    prev: Option<PyGameState>,
+    /// the opponent last tick, with its swing count
+    prev_opponent: Option<(PyEntity, u32)>,
+}
+
+#[pymethods]
+impl Recorder {
+    /// format is "npz" (numpy) or "parquet" (needs pyarrow)
+    #[new]
+    #[pyo3(signature = (bot, path, opponent=None, shard_size=6000, format="npz", config=None))]
+    fn new(
+        bot: Py<PyBot>,
+        path: PathBuf,
+        opponent: Option<String>,
+        shard_size: usize,
+        format: &str,
+        config: Option<ObservationConfig>,
+    ) -> PyResult<Self> {
+        let format = match format {
+            "npz" => Format::Npz,
+            "parquet" => Format::Parquet,
+            _ => return Err(PyValueError::new_err(format!("unknown format {:?}, expected \"npz\" or \"parquet\"", format))),
+        };
+        if shard_size == 0 {
+            return Err(PyValueError::new_err("shard_size must be at least 1"));
+        }
+        std::fs::create_dir_all(&path)
+            .map_err(|e| PyIOError::new_err(format!("can't create {}: {}", path.display(), e)))?;
+        Ok(Self {
+            bot,
+            path,
+            opponent,
+            shard_size,
+            format,
+            config: config.unwrap_or_default(),
+            buffer: Buffer::default(),
+            rows: 0,
+            shards: vec![],
+            prev: None,
+            prev_opponent: None,
+        })
+    }
+
+    #[getter]
+    fn format(&self) -> &'static str {
+        self.format.extension()
+    }
+
+    #[getter]
+    fn feature_names(&self) -> Vec<&'static str> {
+        self.config.feature_names()
+    }
+
+    #[getter]
+    fn action_names(&self) -> Vec<&'static str> {
+        ACTION_NAMES.to_vec()
+    }
+
+    #[getter]
+    fn event_names(&self) -> Vec<&'static str> {
+        EVENT_NAMES.to_vec()
+    }
+
+    /// add a row for `state` (bot.get_state() if not given). the first row
+    /// after a gap has no actions, since there's nothing to compare against
+    #[pyo3(signature = (state=None))]
+    fn record(&mut self, py: Python<'_>, state: Option<PyGameState>) -> PyResult<()> {
+        let bot = self.bot.bind(py);
+        let state = match state {
+            Some(state) => state,
+            None => bot.call_method0("get_state")?.extract()?,
+        };
+        let opponent = match &self.opponent {
+            Some(name) => bot.call_method1("get_player_state", (name,))?.extract::<Option<PyEntity>>()?,
+            None => state.nearest_player(),
+        };
+        let opponent = match opponent {
+            Some(entity) => {
+                let swings = bot.call_method1("swing_count", (entity.id as i32,))?.extract::<u32>()?;
+                Some((entity, swings))
+            }
+            None => None,
+        };
+
+        let me = Motion::of_state(&state);
+        let (actions, hurt, died) = match &self.prev {
+            Some(prev) => {
+                // attacking resets the cooldown, so a drop means we swung
+                let attacked = state.attack_cooldown < prev.attack_cooldown;
+                let taken = (prev.health - state.health).max(0.0);
+                (infer(&Motion::of_state(prev), &me, attacked), taken, state.is_dead && !prev.is_dead)
+            }
+            None => ([0.0; ACTION_NAMES.len()], 0.0, false),
+        };
+        // only compare against the same entity, the nearest player can change
+        let (opponent_actions, dealt) = match (&self.prev_opponent, &opponent) {
+            (Some((prev, prev_swings)), Some((cur, swings))) if prev.id == cur.id => {
+                let prev = Motion::of_entity(prev);
+                let cur_motion = Motion::of_entity(cur);
+                let dealt = (prev.health - cur_motion.health).max(0.0);
+                (infer(&prev, &cur_motion, swings > prev_swings), dealt)
+            }
+            _ => ([0.0; ACTION_NAMES.len()], 0.0),
+        };
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+
+        self.buffer.ticks.push(state.tick);
+        self.buffer.observations.extend(self.config.vector(&state));
+        self.buffer.actions.extend(actions);
+        self.buffer.opponent_actions.extend(opponent_actions);
+        self.buffer.events.extend([
+            flag(hurt > 0.0),
+            hurt,
+            flag(dealt > 0.0),
+            dealt,
+            flag(opponent.is_some()),
+            flag(died),
+        ]);
+        self.rows += 1;
+        self.prev = Some(state);
+        self.prev_opponent = opponent;
+
+        if self.buffer.len() >= self.shard_size {
+            self.flush(py)?;
+        }
+        Ok(())
+    }
+
+    /// bot.tick() and record the state it returns
+    fn tick(&mut self, py: Python<'_>) -> PyResult<PyGameState> {
+        let state = self.bot.bind(py).call_method0("tick")?.extract::<PyGameState>()?;
+        self.record(py, Some(state.clone()))?;
+        Ok(state)
+    }
+
+    /// write the buffered rows as a shard, returns its path (None if there
+    /// was nothing to write)
+    fn flush(&mut self, py: Python<'_>) -> PyResult<Option<PathBuf>> {
+        if self.buffer.len() == 0 {
+            return Ok(None);
+        }
+        let path = self.next_shard();
+        match self.format {
+            Format::Npz => self.write_npz(py, &path)?,
+            Format::Parquet => self.write_parquet(py, &path)?,
+        }
+        self.buffer = Buffer::default();
+        self.shards.push(path.clone());
+        Ok(Some(path))
+    }
+
+    /// flush, and forget the last state so a later record() starts fresh
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.flush(py)?;
+        self.prev = None;
+        self.prev_opponent = None;
+        Ok(())
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, py: Python<'_>, _args: &Bound<'_, pyo3::types::PyTuple>) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Recorder(path={}, rows={}, shards={}, format={})",
+            self.path.display(),
+            self.rows,
+            self.shards.len(),
+            self.format.extension()
+        )
+    }
+}
+
+impl Recorder {
+    /// shard_00000.npz, shard_00001.npz, ... skipping ones already on disk
+    fn next_shard(&self) -> PathBuf {
+        (self.shards.len()..)
+            .map(|i| self.path.join(format!("shard_{:05}.{}", i, self.format.extension())))
+            .find(|path| !path.exists())
+            .expect("ran out of shard names")
+    }
+
+    fn write_npz(&self, py: Python<'_>, path: &Path) -> PyResult<()> {
+        let numpy = py.import("numpy")?;
+        let rows = self.buffer.len();
+        let table = |data: &[f32], width: usize| PyArray1::from_slice(py, data).reshape([rows, width]);
+        let arrays = PyDict::new(py);
+        arrays.set_item("ticks", PyArray1::from_slice(py, &self.buffer.ticks))?;
+        arrays.set_item("observations", table(&self.buffer.observations, self.config.feature_names().len())?)?;
+        arrays.set_item("actions", table(&self.buffer.actions, ACTION_NAMES.len())?)?;
+        arrays.set_item("opponent_actions", table(&self.buffer.opponent_actions, ACTION_NAMES.len())?)?;
+        arrays.set_item("events", table(&self.buffer.events, EVENT_NAMES.len())?)?;
+        arrays.set_item("feature_names", numpy.call_method1("array", (self.config.feature_names(),))?)?;
+        arrays.set_item("action_names", numpy.call_method1("array", (ACTION_NAMES.to_vec(),))?)?;
+        arrays.set_item("event_names", numpy.call_method1("array", (EVENT_NAMES.to_vec(),))?)?;
+        numpy.call_method("savez_compressed", (path,), Some(&arrays))?;
+        Ok(())
+    }
+
+    /// one column per value: tick, the features, action_*, opponent_action_*
+    /// and the events
+    fn write_parquet(&self, py: Python<'_>, path: &Path) -> PyResult<()> {
+        let pyarrow = py.import("pyarrow")?;
+        let parquet = py.import("pyarrow.parquet")?;
+        let columns = PyDict::new(py);
+        columns.set_item("tick", PyArray1::from_slice(py, &self.buffer.ticks))?;
+        let add = |data: &[f32], names: Vec<String>| -> PyResult<()> {
+            let width = names.len();
+            for (i, name) in names.into_iter().enumerate() {
+                let column: Vec<f32> = data.iter().skip(i).step_by(width).copied().collect();
+                columns.set_item(name, PyArray1::from_vec(py, column))?;
+            }
+            Ok(())
+        };
+        add(&self.buffer.observations, self.config.feature_names().iter().map(|n| n.to_string()).collect())?;
+        add(&self.buffer.actions, ACTION_NAMES.iter().map(|n| format!("action_{}", n)).collect())?;
+        add(&self.buffer.opponent_actions, ACTION_NAMES.iter().map(|n| format!("opponent_action_{}", n)).collect())?;
+        add(&self.buffer.events, EVENT_NAMES.iter().map(|n| n.to_string()).collect())?;
+        let table = pyarrow.call_method1("table", (columns,))?;
+        parquet.call_method1("write_table", (table, path))?;
+        Ok(())
+    }
+}
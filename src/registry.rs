@@ -0,0 +1,61 @@
+//! azalea's built-in registries as name -> id dicts
+//!
+//! the ids are the ones this build's protocol version uses on the wire, the
+//! same as BlockGrid ids and PyEntity kinds, so scripts can build one-hot
+//! encodings and check item names without a copy of the game data
+
+use std::fmt::Display;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use azalea::registry::builtin::{BlockKind, EntityKind, ItemKind, MobEffect, ParticleKind, SoundEvent};
+use azalea::registry::Registry;
+
+/// what registry() takes
+const REGISTRIES: [&str; 6] = ["block", "item", "entity_type", "mob_effect", "particle_type", "sound_event"];
+
+/// (name without "minecraft:", id) for every entry, in id order
+fn entries<R: Registry + Display>() -> Vec<(String, u32)> {
+    (0..)
+        .map_while(R::from_u32)
+        .map(|entry| {
+            let name = entry.to_string();
+            let name = name.strip_prefix("minecraft:").unwrap_or(&name).to_string();
+            (name, entry.to_u32())
+        })
+        .collect()
+}
+
+/// the names registry() knows
+#[pyfunction]
+pub fn registries() -> Vec<&'static str> {
+    REGISTRIES.to_vec()
+}
+
+/// one of registries() as {name: id}, in id order, e.g.
+/// registry("item")["diamond_sword"]. names have no "minecraft:" prefix
+#[pyfunction]
+pub fn registry<'py>(py: Python<'py>, name: &str) -> PyResult<Bound<'py, PyDict>> {
+    let entries = match name.strip_prefix("minecraft:").unwrap_or(name) {
+        "block" => entries::<BlockKind>(),
+        "item" => entries::<ItemKind>(),
+        "entity_type" => entries::<EntityKind>(),
+        "mob_effect" => entries::<MobEffect>(),
+        "particle_type" => entries::<ParticleKind>(),
+        "sound_event" => entries::<SoundEvent>(),
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "unknown registry {:?}, expected one of {}",
+                name,
+                REGISTRIES.join(", ")
+            )))
+        }
+    };
+    let dict = PyDict::new(py);
+    for (name, id) in entries {
+        dict.set_item(name, id)?;
+    }
+    Ok(dict)
+}
@@ -0,0 +1,153 @@
+//! What to do when a server pushes a resource pack
+//!
+//! replaces azalea's AcceptResourcePacksPlugin, which always accepts
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::Arc;
+
+use azalea::app::{App, Plugin, Update};
+use azalea::ecs::prelude::*;
+use azalea_client::InConfigState;
+use azalea_client::packet::config::SendConfigPacketEvent;
+use azalea_client::packet::game::{ResourcePackEvent, SendGamePacketEvent};
+use azalea_protocol::packets::config;
+use azalea_protocol::packets::game::s_resource_pack::{self, ServerboundResourcePack};
+
+use crate::bot::BotState;
+
+/// a resource pack push, passed to the `resource_packs=` callback
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ResourcePackRequest {
+    #[pyo3(get)]
+    pub url: String,
+    #[pyo3(get)]
+    pub hash: String,
+    /// the server kicks you if this is declined
+    #[pyo3(get)]
+    pub required: bool,
+    #[pyo3(get)]
+    pub prompt: Option<String>,
+}
+
+#[pymethods]
+impl ResourcePackRequest {
+    fn __repr__(&self) -> String {
+        format!("ResourcePackRequest(url={}, required={})", self.url, self.required)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum ResourcePackPolicy {
+    /// pretend to download and load it (nothing is actually downloaded)
+    #[default]
+    Accept,
+    Decline,
+    /// `callback(request) -> bool`, called on the bot's connection thread
+    Callback(Arc<PyObject>),
+}
+
+impl ResourcePackPolicy {
+    /// parse `resource_packs=` ("accept", "decline" or a callable)
+    pub fn from_py(value: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(value) = value else {
+            return Ok(Self::Accept);
+        };
+        if let Ok(mode) = value.extract::<String>() {
+            return match mode.as_str() {
+                "accept" => Ok(Self::Accept),
+                "decline" => Ok(Self::Decline),
+                _ => Err(PyValueError::new_err(format!(
+                    "unknown resource_packs mode '{}' (expected 'accept', 'decline' or a callable)",
+                    mode
+                ))),
+            };
+        }
+        if value.is_callable() {
+            return Ok(Self::Callback(Arc::new(value.clone().unbind())));
+        }
+        Err(PyValueError::new_err("resource_packs must be 'accept', 'decline' or a callable"))
+    }
+
+    fn should_accept(&self, event: &ResourcePackEvent) -> bool {
+        match self {
+            Self::Accept => true,
+            Self::Decline => false,
+            Self::Callback(callback) => {
+                let request = ResourcePackRequest {
+                    url: event.url.clone(),
+                    hash: event.hash.clone(),
+                    required: event.required,
+                    prompt: event.prompt.as_ref().map(|p| p.to_string()),
+                };
+                Python::with_gil(|py| {
+                    callback
+                        .call1(py, (request,))
+                        .and_then(|result| result.extract::<bool>(py))
+                        .unwrap_or_else(|e| {
+                            // a broken callback shouldn't get us kicked from servers that require packs
+                            e.print(py);
+                            event.required
+                        })
+                })
+            }
+        }
+    }
+}
+
+pub struct ResourcePackPlugin;
+impl Plugin for ResourcePackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, answer_resource_packs);
+    }
+}
+
+fn answer_resource_packs(
+    mut commands: Commands,
+    mut events: MessageReader<ResourcePackEvent>,
+    query: Query<(&BotState, Option<&InConfigState>)>,
+) {
+    for event in events.read() {
+        let Ok((state, in_config)) = query.get(event.entity) else {
+            continue;
+        };
+        let accept = match &state.shared {
+            Some(shared) => shared.resource_packs.should_accept(event),
+            None => true,
+        };
+
+        // accepting is "accepted" then "loaded", declining is one packet
+        let actions: &[(config::s_resource_pack::Action, s_resource_pack::Action)] = if accept {
+            &[
+                (config::s_resource_pack::Action::Accepted, s_resource_pack::Action::Accepted),
+                (
+                    config::s_resource_pack::Action::SuccessfullyLoaded,
+                    s_resource_pack::Action::SuccessfullyLoaded,
+                ),
+            ]
+        } else {
+            &[(config::s_resource_pack::Action::Declined, s_resource_pack::Action::Declined)]
+        };
+
+        for (config_action, game_action) in actions {
+            if in_config.is_some() {
+                commands.trigger(SendConfigPacketEvent::new(
+                    event.entity,
+                    config::ServerboundResourcePack {
+                        id: event.id,
+                        action: *config_action,
+                    },
+                ));
+            } else {
+                commands.trigger(SendGamePacketEvent::new(
+                    event.entity,
+                    ServerboundResourcePack {
+                        id: event.id,
+                        action: *game_action,
+                    },
+                ));
+            }
+        }
+    }
+}
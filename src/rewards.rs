@@ -0,0 +1,423 @@
+//! Reward shaping for FastArena
+//!
+//! every term of the rewards step() hands out, weighted by a RewardConfig
+//! so shaping experiments don't need a rebuild. weights are added as they
+//! are, so penalties are negative. set with FastArena(rewards=...),
+//! FastArena.rewards or `[arena.rewards]` in a config file. past weights,
+//! a Python reward function can add to the rewards or replace them
+
+use std::any::Any;
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::Deserialize;
+
+#[pyclass]
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RewardConfig {
+    /// per point of damage the fighter dealt, its own pearls don't count
+    #[pyo3(get, set)]
+    pub damage_dealt: f64,
+    /// per point of damage taken
+    #[pyo3(get, set)]
+    pub damage_taken: f64,
+    /// a melee hit landed
+    #[pyo3(get, set)]
+    pub hit: f64,
+    /// a swing that didn't land
+    #[pyo3(get, set)]
+    pub whiff: f64,
+    /// pressing jump before it's back
+    #[pyo3(get, set)]
+    pub jump_spam: f64,
+    /// per point of health healed
+    #[pyo3(get, set)]
+    pub heal: f64,
+    /// every tick spent eating
+    #[pyo3(get, set)]
+    pub eating: f64,
+    /// a steak eaten
+    #[pyo3(get, set)]
+    pub finish_eating: f64,
+    /// a hit stopping a steak
+    #[pyo3(get, set)]
+    pub interrupted_eating: f64,
+    /// per block closer than proximity_range, to both fighters
+    #[pyo3(get, set)]
+    pub proximity: f64,
+    #[pyo3(get, set)]
+    pub proximity_range: f64,
+    /// every tick
+    #[pyo3(get, set)]
+    pub time: f64,
+    /// the other fighter at 0 health or in the void
+    #[pyo3(get, set)]
+    pub win: f64,
+    #[pyo3(get, set)]
+    pub loss: f64,
+    /// on top of win, times the fraction of max_ticks left
+    #[pyo3(get, set)]
+    pub speed_bonus: f64,
+    /// more health left at max_ticks
+    #[pyo3(get, set)]
+    pub timeout_win: f64,
+    #[pyo3(get, set)]
+    pub timeout_loss: f64,
+    /// the same health at max_ticks, to both
+    #[pyo3(get, set)]
+    pub draw: f64,
+    /// in sumo these and edge_control take the place of everything above but
+    /// time and the end
+    #[pyo3(get, set)]
+    pub sumo_hit: f64,
+    #[pyo3(get, set)]
+    pub sumo_whiff: f64,
+    /// per block further from the platform's edge than the other fighter
+    #[pyo3(get, set)]
+    pub edge_control: f64,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        Self {
+            damage_dealt: 0.5,
+            damage_taken: -0.3,
+            hit: 0.2,
+            whiff: -0.05,
+            jump_spam: -0.03,
+            heal: 0.3,
+            eating: 0.02,
+            finish_eating: 0.5,
+            interrupted_eating: -0.3,
+            proximity: 0.01,
+            proximity_range: 10.0,
+            time: -0.001,
+            win: 10.0,
+            loss: -10.0,
+            speed_bonus: 5.0,
+            timeout_win: 2.0,
+            timeout_loss: -2.0,
+            draw: -3.0,
+            sumo_hit: 0.3,
+            sumo_whiff: -0.05,
+            edge_control: 0.01,
+        }
+    }
+}
+
+#[pymethods]
+impl RewardConfig {
+    /// the defaults are the arena's usual shaping
+    #[new]
+    #[pyo3(signature = (
+        damage_dealt=0.5,
+        damage_taken=-0.3,
+        hit=0.2,
+        whiff=-0.05,
+        jump_spam=-0.03,
+        heal=0.3,
+        eating=0.02,
+        finish_eating=0.5,
+        interrupted_eating=-0.3,
+        proximity=0.01,
+        proximity_range=10.0,
+        time=-0.001,
+        win=10.0,
+        loss=-10.0,
+        speed_bonus=5.0,
+        timeout_win=2.0,
+        timeout_loss=-2.0,
+        draw=-3.0,
+        sumo_hit=0.3,
+        sumo_whiff=-0.05,
+        edge_control=0.01,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        damage_dealt: f64,
+        damage_taken: f64,
+        hit: f64,
+        whiff: f64,
+        jump_spam: f64,
+        heal: f64,
+        eating: f64,
+        finish_eating: f64,
+        interrupted_eating: f64,
+        proximity: f64,
+        proximity_range: f64,
+        time: f64,
+        win: f64,
+        loss: f64,
+        speed_bonus: f64,
+        timeout_win: f64,
+        timeout_loss: f64,
+        draw: f64,
+        sumo_hit: f64,
+        sumo_whiff: f64,
+        edge_control: f64,
+    ) -> Self {
+        Self {
+            damage_dealt,
+            damage_taken,
+            hit,
+            whiff,
+            jump_spam,
+            heal,
+            eating,
+            finish_eating,
+            interrupted_eating,
+            proximity,
+            proximity_range,
+            time,
+            win,
+            loss,
+            speed_bonus,
+            timeout_win,
+            timeout_loss,
+            draw,
+            sumo_hit,
+            sumo_whiff,
+            edge_control,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RewardConfig(damage_dealt={}, damage_taken={}, hit={}, whiff={}, jump_spam={}, heal={}, eating={}, \
+             finish_eating={}, interrupted_eating={}, proximity={}, proximity_range={}, time={}, win={}, loss={}, \
+             speed_bonus={}, timeout_win={}, timeout_loss={}, draw={}, sumo_hit={}, sumo_whiff={}, edge_control={})",
+            self.damage_dealt,
+            self.damage_taken,
+            self.hit,
+            self.whiff,
+            self.jump_spam,
+            self.heal,
+            self.eating,
+            self.finish_eating,
+            self.interrupted_eating,
+            self.proximity,
+            self.proximity_range,
+            self.time,
+            self.win,
+            self.loss,
+            self.speed_bonus,
+            self.timeout_win,
+            self.timeout_loss,
+            self.draw,
+            self.sumo_hit,
+            self.sumo_whiff,
+            self.edge_control
+        )
+    }
+}
+
+/// what happened in a step, [fighter 1, fighter 2]. a reward function gets
+/// them summed over the steps since its last call
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StepEvents {
+    pub hits: [u32; 2],
+    pub whiffs: [u32; 2],
+    /// hits, arrows and harming splashes on the other fighter, see
+    /// Fighter.damage_dealt
+    pub damage_dealt: [f64; 2],
+    pub damage_taken: [f64; 2],
+    pub healed: [f64; 2],
+    pub steaks_eaten: [u32; 2],
+    pub eating_interrupted: [u32; 2],
+    /// sword sweeps, see Fighter.swept
+    pub sweeps: [u32; 2],
+}
+
+impl StepEvents {
+    fn add(&mut self, other: &StepEvents) {
+        for i in 0..2 {
+            self.hits[i] += other.hits[i];
+            self.whiffs[i] += other.whiffs[i];
+            self.damage_dealt[i] += other.damage_dealt[i];
+            self.damage_taken[i] += other.damage_taken[i];
+            self.healed[i] += other.healed[i];
+            self.steaks_eaten[i] += other.steaks_eaten[i];
+            self.eating_interrupted[i] += other.eating_interrupted[i];
+            self.sweeps[i] += other.sweeps[i];
+        }
+    }
+
+    /// (name, fighter, amount) for each above 0, healing shows up as
+    /// negative damage in the sums but not here
+    pub fn list(&self) -> Vec<(&'static str, u8, f64)> {
+        let mut list = vec![];
+        for i in 0..2 {
+            let fighter = i as u8 + 1;
+            let all = [
+                ("hit", self.hits[i] as f64),
+                ("whiff", self.whiffs[i] as f64),
+                ("damage_dealt", self.damage_dealt[i]),
+                ("damage_taken", self.damage_taken[i]),
+                ("healed", self.healed[i]),
+                ("steak_eaten", self.steaks_eaten[i] as f64),
+                ("eating_interrupted", self.eating_interrupted[i] as f64),
+                ("sweep", self.sweeps[i] as f64),
+            ];
+            list.extend(all.into_iter().filter(|&(_, amount)| amount > 0.0).map(|(name, amount)| (name, fighter, amount)));
+        }
+        list
+    }
+
+    /// `name1` and `name2` for each of them
+    pub fn fill(&self, dict: &Bound<'_, PyDict>) -> PyResult<()> {
+        for (i, n) in ["1", "2"].into_iter().enumerate() {
+            dict.set_item(format!("hits{}", n), self.hits[i])?;
+            dict.set_item(format!("whiffs{}", n), self.whiffs[i])?;
+            dict.set_item(format!("damage_dealt{}", n), self.damage_dealt[i])?;
+            dict.set_item(format!("damage_taken{}", n), self.damage_taken[i])?;
+            dict.set_item(format!("healed{}", n), self.healed[i])?;
+            dict.set_item(format!("steaks_eaten{}", n), self.steaks_eaten[i])?;
+            dict.set_item(format!("eating_interrupted{}", n), self.eating_interrupted[i])?;
+            dict.set_item(format!("sweeps{}", n), self.sweeps[i])?;
+        }
+        Ok(())
+    }
+}
+
+/// a fighter's reward from a step, term by term. they add up to what step()
+/// returned
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RewardBreakdown {
+    /// damage dealt and taken
+    pub damage: f64,
+    /// hit and sumo_hit
+    pub hit: f64,
+    /// whiff and sumo_whiff
+    pub whiff: f64,
+    pub jump_spam: f64,
+    pub heal: f64,
+    /// eating, finish_eating and interrupted_eating
+    pub eat: f64,
+    pub proximity: f64,
+    pub edge_control: f64,
+    pub time: f64,
+    /// win, loss, speed_bonus, timeout_win, timeout_loss and draw
+    pub terminal: f64,
+    /// what the Python reward function gave, in replace mode the terms above
+    /// are all 0
+    pub reward_fn: f64,
+}
+
+impl RewardBreakdown {
+    pub const NAMES: [&'static str; 11] = [
+        "damage", "hit", "whiff", "jump_spam", "heal", "eat", "proximity", "edge_control", "time", "terminal", "reward_fn",
+    ];
+
+    pub fn values(&self) -> [f64; 11] {
+        [
+            self.damage,
+            self.hit,
+            self.whiff,
+            self.jump_spam,
+            self.heal,
+            self.eat,
+            self.proximity,
+            self.edge_control,
+            self.time,
+            self.terminal,
+            self.reward_fn,
+        ]
+    }
+
+    pub fn total(&self) -> f64 {
+        self.values().iter().sum()
+    }
+
+    /// `name1` and `name2` for each term of `both`
+    pub fn fill(both: &[RewardBreakdown; 2], dict: &Bound<'_, PyDict>) -> PyResult<()> {
+        for (i, n) in ["1", "2"].into_iter().enumerate() {
+            for (name, value) in Self::NAMES.iter().zip(both[i].values()) {
+                dict.set_item(format!("{}{}", name, n), value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// a Python `reward_fn(obs1, obs2, events) -> (r1, r2)`, see
+/// FastArena.set_reward_fn()
+#[derive(Clone)]
+pub struct RewardFn {
+    /// a PyObject, behind Any so dropping a FastArena doesn't need
+    /// libpython (the bench binary doesn't link it)
+    callable: Arc<dyn Any + Send + Sync>,
+    /// on top of the built-in rewards instead of in their place
+    pub add: bool,
+    /// steps between calls, the last step of an episode always calls it
+    pub every: u32,
+    /// since the last call
+    events: StepEvents,
+    rewards: [f64; 2],
+    steps: u32,
+}
+
+impl RewardFn {
+    /// `mode` "add" or "replace"
+    pub fn new(callable: PyObject, mode: &str, every: u32) -> PyResult<Self> {
+        let add = match mode {
+            "add" => true,
+            "replace" => false,
+            _ => return Err(PyValueError::new_err(format!("unknown mode {:?}, expected \"add\" or \"replace\"", mode))),
+        };
+        if every == 0 {
+            return Err(PyValueError::new_err("every must be at least 1"));
+        }
+        Ok(Self { callable: Arc::new(callable) as Arc<dyn Any + Send + Sync>, add, every, events: StepEvents::default(), rewards: [0.0; 2], steps: 0 })
+    }
+
+    pub fn callable(&self, py: Python<'_>) -> PyObject {
+        self.function().clone_ref(py)
+    }
+
+    fn function(&self) -> &PyObject {
+        self.callable.downcast_ref().expect("a reward function is a PyObject")
+    }
+
+    /// forget a cut-short episode's steps
+    pub fn clear(&mut self) {
+        self.events = StepEvents::default();
+        self.rewards = [0.0; 2];
+        self.steps = 0;
+    }
+
+    /// what the function gives for a step the built-in shaping gave
+    /// `rewards`, with `end` (tick, done, winner, winner_reason) and the obs
+    /// after it, 0 between calls. `obs` is only called when the function is
+    pub fn apply(
+        &mut self,
+        py: Python<'_>,
+        step: &StepEvents,
+        rewards: (f64, f64),
+        end: (u32, bool, i32, Option<&str>),
+        obs: impl FnOnce() -> (Vec<f64>, Vec<f64>),
+    ) -> PyResult<(f64, f64)> {
+        self.events.add(step);
+        self.rewards[0] += rewards.0;
+        self.rewards[1] += rewards.1;
+        self.steps += 1;
+        let (tick, done, winner, winner_reason) = end;
+        if self.steps < self.every && !done {
+            return Ok((0.0, 0.0));
+        }
+        let events = PyDict::new(py);
+        self.events.fill(&events)?;
+        events.set_item("reward1", self.rewards[0])?;
+        events.set_item("reward2", self.rewards[1])?;
+        events.set_item("steps", self.steps)?;
+        events.set_item("tick", tick)?;
+        events.set_item("done", done)?;
+        events.set_item("winner", winner)?;
+        events.set_item("winner_reason", winner_reason)?;
+        self.clear();
+        let (obs1, obs2) = obs();
+        self.function().call1(py, (obs1, obs2, events))?.extract(py)
+    }
+}
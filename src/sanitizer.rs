@@ -0,0 +1,371 @@
+//! Limits on what Python and attached policies make the bot do
+//!
+//! turning, clicking and moving all go through the bot's Sanitizer instead of
+//! straight to azalea, so scripted bots stay within what a person could do:
+//! look requests turn toward their target over the next ticks, clicks past
+//! the rate limit are dropped, and movement waits while the bot eats
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use azalea::bot::direction_looking_at;
+use azalea::{SprintDirection, Vec3, WalkDirection};
+use azalea_client::interact::StartUseItemEvent;
+use azalea_client::Client;
+use azalea_core::direction::Direction;
+use azalea_core::position::BlockPos;
+use azalea_entity::dimensions::EntityDimensions;
+use azalea_entity::metadata::AbstractLivingUsingItem;
+use azalea_entity::{LookDirection, Position};
+use azalea_protocol::packets::game::s_interact::InteractionHand;
+use azalea_protocol::packets::game::s_player_action::{Action, ServerboundPlayerAction};
+
+/// what the bot may do, see PyBot.action_limits. on by default; bypass=True
+/// turns every limit off, e.g. for an offline test server
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ActionLimits {
+    /// degrees the view can turn per tick, None to turn instantly
+    #[pyo3(get)]
+    pub max_rotation_per_tick: Option<f32>,
+    /// attacks and item uses per second, clicks past it are dropped
+    #[pyo3(get)]
+    pub max_clicks_per_second: Option<f64>,
+    /// stop walking, sprinting and jumping while eating, drinking or
+    /// blocking, and carry on afterwards
+    #[pyo3(get, set)]
+    pub no_move_while_eating: bool,
+    #[pyo3(get, set)]
+    pub bypass: bool,
+}
+
+/// a limit has to be positive, or None for no limit
+fn check_limit(name: &str, max: Option<f64>) -> PyResult<()> {
+    if max.is_some_and(|max| max.is_nan() || max <= 0.0) {
+        return Err(PyValueError::new_err(format!("{} must be positive, or None for no limit", name)));
+    }
+    Ok(())
+}
+
+impl Default for ActionLimits {
+    fn default() -> Self {
+        Self {
+            max_rotation_per_tick: Some(45.0),
+            max_clicks_per_second: Some(12.0),
+            no_move_while_eating: true,
+            bypass: false,
+        }
+    }
+}
+
+#[pymethods]
+impl ActionLimits {
+    #[new]
+    #[pyo3(signature = (max_rotation_per_tick=Some(45.0), max_clicks_per_second=Some(12.0), no_move_while_eating=true, bypass=false))]
+    fn new(
+        max_rotation_per_tick: Option<f32>,
+        max_clicks_per_second: Option<f64>,
+        no_move_while_eating: bool,
+        bypass: bool,
+    ) -> PyResult<Self> {
+        check_limit("max_rotation_per_tick", max_rotation_per_tick.map(f64::from))?;
+        check_limit("max_clicks_per_second", max_clicks_per_second)?;
+        Ok(Self {
+            max_rotation_per_tick,
+            max_clicks_per_second,
+            no_move_while_eating,
+            bypass,
+        })
+    }
+
+    #[setter]
+    fn set_max_rotation_per_tick(&mut self, max_rotation_per_tick: Option<f32>) -> PyResult<()> {
+        check_limit("max_rotation_per_tick", max_rotation_per_tick.map(f64::from))?;
+        self.max_rotation_per_tick = max_rotation_per_tick;
+        Ok(())
+    }
+
+    #[setter]
+    fn set_max_clicks_per_second(&mut self, max_clicks_per_second: Option<f64>) -> PyResult<()> {
+        check_limit("max_clicks_per_second", max_clicks_per_second)?;
+        self.max_clicks_per_second = max_clicks_per_second;
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        let bool_str = |b: bool| if b { "True" } else { "False" };
+        let or_none = |v: Option<String>| v.unwrap_or_else(|| "None".to_string());
+        format!(
+            "ActionLimits(max_rotation_per_tick={}, max_clicks_per_second={}, no_move_while_eating={}, bypass={})",
+            or_none(self.max_rotation_per_tick.map(|v| v.to_string())),
+            or_none(self.max_clicks_per_second.map(|v| v.to_string())),
+            bool_str(self.no_move_while_eating),
+            bool_str(self.bypass)
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Movement {
+    Walk(WalkDirection),
+    Sprint(SprintDirection),
+}
+
+/// one bot's limits and what it's part way through, in BotShared
+pub struct Sanitizer {
+    pub limits: ActionLimits,
+    /// the look direction asked for, reached over the next ticks
+    look: Option<(f32, f32)>,
+    /// degrees left to turn this tick
+    budget: f32,
+    /// clicks in the last second
+    clicks: VecDeque<Instant>,
+    /// what was last asked for, applied unless we're eating
+    movement: Movement,
+    jumping: bool,
+    /// movement is held back until the bot stops eating
+    holding: bool,
+    /// how often each limit kicked in, for PyBot.action_limit_stats()
+    pub rotations_limited: u64,
+    pub clicks_dropped: u64,
+    pub uses_skipped: u64,
+    pub moves_held: u64,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        let limits = ActionLimits::default();
+        Self {
+            budget: limits.max_rotation_per_tick.unwrap_or(f32::INFINITY),
+            limits,
+            look: None,
+            clicks: VecDeque::new(),
+            movement: Movement::Walk(WalkDirection::None),
+            jumping: false,
+            holding: false,
+            rotations_limited: 0,
+            clicks_dropped: 0,
+            uses_skipped: 0,
+            moves_held: 0,
+        }
+    }
+}
+
+/// (-180, 180]
+fn wrap_degrees(degrees: f32) -> f32 {
+    let wrapped = degrees.rem_euclid(360.0);
+    if wrapped > 180.0 {
+        wrapped - 360.0
+    } else {
+        wrapped
+    }
+}
+
+fn using_item(client: &Client) -> bool {
+    client
+        .ecs
+        .lock()
+        .get::<AbstractLivingUsingItem>(client.entity)
+        .is_some_and(|using| using.0)
+}
+
+impl Sanitizer {
+    pub fn set_limits(&mut self, limits: ActionLimits) {
+        self.limits = limits;
+        self.budget = self.max_rotation();
+        self.clicks.clear();
+    }
+
+    /// forget what the last connection was doing
+    pub fn clear(&mut self) {
+        *self = Self {
+            limits: self.limits.clone(),
+            rotations_limited: self.rotations_limited,
+            clicks_dropped: self.clicks_dropped,
+            uses_skipped: self.uses_skipped,
+            moves_held: self.moves_held,
+            ..Self::default()
+        };
+        self.budget = self.max_rotation();
+    }
+
+    fn max_rotation(&self) -> f32 {
+        match self.limits.max_rotation_per_tick {
+            Some(max) if !self.limits.bypass => max,
+            _ => f32::INFINITY,
+        }
+    }
+
+    pub fn stats(&self) -> HashMap<&'static str, u64> {
+        HashMap::from([
+            ("rotations_limited", self.rotations_limited),
+            ("clicks_dropped", self.clicks_dropped),
+            ("uses_skipped", self.uses_skipped),
+            ("moves_held", self.moves_held),
+        ])
+    }
+
+    /// call on every game tick, before the policy
+    pub fn tick(&mut self, client: &Client) {
+        self.budget = self.max_rotation();
+        self.turn(client);
+        if self.holding != self.should_hold(client) {
+            self.apply_movement(client);
+        }
+    }
+
+    /// look toward (yaw, pitch), as fast as the limit allows
+    pub fn look(&mut self, client: &Client, yaw: f32, pitch: f32) {
+        self.look = Some((yaw, pitch.clamp(-90.0, 90.0)));
+        self.turn(client);
+    }
+
+    pub fn look_at(&mut self, client: &Client, target: Vec3) {
+        let eyes = {
+            let ecs = client.ecs.lock();
+            let Some(pos) = ecs.get::<Position>(client.entity) else {
+                return;
+            };
+            let eye_height = ecs.get::<EntityDimensions>(client.entity).map_or(1.62, |d| d.eye_height);
+            pos.up(eye_height as f64)
+        };
+        let direction = direction_looking_at(eyes, target);
+        self.look(client, direction.y_rot(), direction.x_rot());
+    }
+
+    fn turn(&mut self, client: &Client) {
+        let Some((yaw, pitch)) = self.look else {
+            return;
+        };
+        let Some(current) = client.ecs.lock().get::<LookDirection>(client.entity).copied() else {
+            return;
+        };
+        let delta_yaw = wrap_degrees(yaw - current.y_rot());
+        let delta_pitch = pitch - current.x_rot();
+        let distance = delta_yaw.hypot(delta_pitch);
+        if distance <= self.budget {
+            // the unwrapped yaw, so a 350 degree target doesn't become -10
+            client.set_direction(yaw, pitch);
+            self.budget -= distance;
+            self.look = None;
+        } else if self.budget > 0.0 {
+            let fraction = self.budget / distance;
+            client.set_direction(current.y_rot() + delta_yaw * fraction, current.x_rot() + delta_pitch * fraction);
+            self.budget = 0.0;
+            self.rotations_limited += 1;
+        }
+    }
+
+    /// false if the click would go over the limit, and is dropped
+    pub fn click(&mut self) -> bool {
+        let max = match self.limits.max_clicks_per_second {
+            Some(max) if !self.limits.bypass => max,
+            _ => return true,
+        };
+        let now = Instant::now();
+        while self.clicks.front().is_some_and(|at| now.duration_since(*at) >= Duration::from_secs(1)) {
+            self.clicks.pop_front();
+        }
+        if self.clicks.len() as f64 >= max {
+            self.clicks_dropped += 1;
+            return false;
+        }
+        self.clicks.push_back(now);
+        true
+    }
+
+    /// start using the held item. skipped while it's already in use (a second
+    /// use packet mid-eat is what anticheats look for), and a click otherwise
+    pub fn use_item(&mut self, client: &Client) -> bool {
+        self.use_hand(client, InteractionHand::MainHand)
+    }
+
+    /// use_item for the offhand, like raising a shield behind a sword
+    pub fn use_offhand(&mut self, client: &Client) -> bool {
+        self.use_hand(client, InteractionHand::OffHand)
+    }
+
+    fn use_hand(&mut self, client: &Client, hand: InteractionHand) -> bool {
+        if !self.limits.bypass && using_item(client) {
+            self.uses_skipped += 1;
+            return false;
+        }
+        if !self.click() {
+            return false;
+        }
+        client.ecs.lock().write_message(StartUseItemEvent { entity: client.entity, hand, force_block: None });
+        true
+    }
+
+    /// let go of the held item, shooting a drawn bow. false if it wasn't in use
+    pub fn release_item(&mut self, client: &Client) -> bool {
+        if !using_item(client) {
+            return false;
+        }
+        client.write_packet(ServerboundPlayerAction {
+            action: Action::ReleaseUseItem,
+            pos: BlockPos::default(),
+            direction: Direction::Down,
+            seq: 0,
+        });
+        true
+    }
+
+    pub fn walk(&mut self, client: &Client, direction: WalkDirection) {
+        self.movement = Movement::Walk(direction);
+        self.apply_movement(client);
+    }
+
+    pub fn sprint(&mut self, client: &Client, direction: SprintDirection) {
+        self.movement = Movement::Sprint(direction);
+        self.apply_movement(client);
+    }
+
+    pub fn set_jumping(&mut self, client: &Client, jumping: bool) {
+        self.jumping = jumping;
+        self.apply_movement(client);
+    }
+
+    fn should_hold(&self, client: &Client) -> bool {
+        let moving = self.jumping || !matches!(self.movement, Movement::Walk(WalkDirection::None));
+        self.limits.no_move_while_eating && !self.limits.bypass && moving && using_item(client)
+    }
+
+    fn apply_movement(&mut self, client: &Client) {
+        let hold = self.should_hold(client);
+        if hold {
+            if !self.holding {
+                self.moves_held += 1;
+            }
+            client.walk(WalkDirection::None);
+            client.set_jumping(false);
+        } else {
+            match self.movement {
+                Movement::Walk(direction) => client.walk(direction),
+                Movement::Sprint(direction) => client.sprint(direction),
+            }
+            client.set_jumping(self.jumping);
+        }
+        self.holding = hold;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_stay_positive_when_set() {
+        let mut limits = ActionLimits::default();
+        assert!(limits.set_max_rotation_per_tick(Some(0.0)).is_err());
+        assert!(limits.set_max_rotation_per_tick(Some(f32::NAN)).is_err());
+        assert!(limits.set_max_clicks_per_second(Some(-1.0)).is_err());
+        assert_eq!(limits.max_rotation_per_tick, Some(45.0));
+        assert_eq!(limits.max_clicks_per_second, Some(12.0));
+        limits.set_max_clicks_per_second(None).unwrap();
+        assert_eq!(limits.max_clicks_per_second, None);
+    }
+}
@@ -0,0 +1,271 @@
+//! Scoreboard tracking (objectives, scores and teams)
+//!
+//! azalea doesn't keep the scoreboard around, so we rebuild it from the
+//! scoreboard packets as they come in
+
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::game::c_set_display_objective::DisplaySlot;
+use azalea_protocol::packets::game::c_set_objective;
+use azalea_protocol::packets::game::c_set_player_team;
+
+/// one line of an objective
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct ScoreEntry {
+    /// the entity/player the score belongs to (often a fake name on sidebars)
+    #[pyo3(get)]
+    pub owner: String,
+    #[pyo3(get)]
+    pub score: u32,
+    /// the line as the client would render it (team prefix/suffix included)
+    #[pyo3(get)]
+    pub text: String,
+}
+
+#[pymethods]
+impl ScoreEntry {
+    fn __repr__(&self) -> String {
+        format!("ScoreEntry(text={:?}, score={})", self.text, self.score)
+    }
+}
+
+/// a scoreboard objective with its scores, highest first
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Objective {
+    #[pyo3(get)]
+    pub name: String,
+    #[pyo3(get)]
+    pub display_name: String,
+    #[pyo3(get)]
+    pub scores: Vec<ScoreEntry>,
+}
+
+#[pymethods]
+impl Objective {
+    /// score of one owner, None if it has none
+    fn score_of(&self, owner: &str) -> Option<u32> {
+        self.scores.iter().find(|s| s.owner == owner).map(|s| s.score)
+    }
+
+    /// just the rendered lines, top to bottom
+    fn lines(&self) -> Vec<String> {
+        self.scores.iter().map(|s| s.text.clone()).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Objective(name={}, display_name={:?}, scores={})", self.name, self.display_name, self.scores.len())
+    }
+}
+
+#[pyclass]
+#[derive(Clone, Debug, Default)]
+pub struct Team {
+    #[pyo3(get)]
+    pub name: String,
+    #[pyo3(get)]
+    pub display_name: String,
+    /// color name like "red" or "dark_aqua"
+    #[pyo3(get)]
+    pub color: String,
+    #[pyo3(get)]
+    pub prefix: String,
+    #[pyo3(get)]
+    pub suffix: String,
+    #[pyo3(get)]
+    pub members: Vec<String>,
+}
+
+#[pymethods]
+impl Team {
+    fn __repr__(&self) -> String {
+        format!("Team(name={}, color={}, members={})", self.name, self.color, self.members.len())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct ObjectiveState {
+    display_name: String,
+    /// owner -> (score, display override)
+    scores: HashMap<String, (u32, Option<String>)>,
+}
+
+/// everything the server told us about the scoreboard
+#[derive(Debug, Default)]
+pub struct Scoreboard {
+    objectives: HashMap<String, ObjectiveState>,
+    /// display slot name ("sidebar", "list", ...) -> objective name
+    displayed: HashMap<String, String>,
+    teams: HashMap<String, Team>,
+}
+
+/// vanilla name of a display slot, e.g. "sidebar" or "sidebar.team.red"
+fn slot_name(slot: DisplaySlot) -> &'static str {
+    match slot {
+        DisplaySlot::List => "list",
+        DisplaySlot::Sidebar => "sidebar",
+        DisplaySlot::BelowName => "below_name",
+        DisplaySlot::TeamBlack => "sidebar.team.black",
+        DisplaySlot::TeamDarkBlue => "sidebar.team.dark_blue",
+        DisplaySlot::TeamDarkGreen => "sidebar.team.dark_green",
+        DisplaySlot::TeamDarkAqua => "sidebar.team.dark_aqua",
+        DisplaySlot::TeamDarkRed => "sidebar.team.dark_red",
+        DisplaySlot::TeamDarkPurple => "sidebar.team.dark_purple",
+        DisplaySlot::TeamGold => "sidebar.team.gold",
+        DisplaySlot::TeamGray => "sidebar.team.gray",
+        DisplaySlot::TeamDarkGray => "sidebar.team.dark_gray",
+        DisplaySlot::TeamBlue => "sidebar.team.blue",
+        DisplaySlot::TeamGreen => "sidebar.team.green",
+        DisplaySlot::TeamAqua => "sidebar.team.aqua",
+        DisplaySlot::TeamRed => "sidebar.team.red",
+        DisplaySlot::TeamLightPurple => "sidebar.team.light_purple",
+        DisplaySlot::TeamYellow => "sidebar.team.yellow",
+        DisplaySlot::TeamWhite => "sidebar.team.white",
+    }
+}
+
+impl Scoreboard {
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::SetObjective(p) => match &p.method {
+                c_set_objective::Method::Add { display_name, .. }
+                | c_set_objective::Method::Change { display_name, .. } => {
+                    self.objectives
+                        .entry(p.objective_name.clone())
+                        .or_default()
+                        .display_name = display_name.to_string();
+                }
+                c_set_objective::Method::Remove => {
+                    self.objectives.remove(&p.objective_name);
+                    self.displayed.retain(|_, name| *name != p.objective_name);
+                }
+            },
+            ClientboundGamePacket::SetDisplayObjective(p) => {
+                let slot = slot_name(p.slot);
+                if p.objective_name.is_empty() {
+                    self.displayed.remove(slot);
+                } else {
+                    self.displayed.insert(slot.to_string(), p.objective_name.clone());
+                }
+            }
+            ClientboundGamePacket::SetScore(p) => {
+                let objective = self.objectives.entry(p.objective_name.clone()).or_default();
+                let display = p.display.as_ref().map(|d| d.to_string());
+                objective.scores.insert(p.owner.clone(), (p.score, display));
+            }
+            ClientboundGamePacket::ResetScore(p) => match &p.objective_name {
+                Some(name) => {
+                    if let Some(objective) = self.objectives.get_mut(name) {
+                        objective.scores.remove(&p.owner);
+                    }
+                }
+                // no objective means every score of that owner
+                None => {
+                    for objective in self.objectives.values_mut() {
+                        objective.scores.remove(&p.owner);
+                    }
+                }
+            },
+            ClientboundGamePacket::SetPlayerTeam(p) => self.handle_team(p),
+            _ => {}
+        }
+    }
+
+    fn handle_team(&mut self, p: &c_set_player_team::ClientboundSetPlayerTeam) {
+        use c_set_player_team::Method;
+
+        fn apply(team: &mut Team, params: &c_set_player_team::Parameters) {
+            team.display_name = params.display_name.to_string();
+            team.color = params.color.name().to_string();
+            team.prefix = params.player_prefix.to_string();
+            team.suffix = params.player_suffix.to_string();
+        }
+
+        match &p.method {
+            Method::Add((params, players)) => {
+                let mut team = Team {
+                    name: p.name.clone(),
+                    members: players.clone(),
+                    ..Default::default()
+                };
+                apply(&mut team, params);
+                self.teams.insert(p.name.clone(), team);
+            }
+            Method::Remove => {
+                self.teams.remove(&p.name);
+            }
+            Method::Change(params) => {
+                if let Some(team) = self.teams.get_mut(&p.name) {
+                    apply(team, params);
+                }
+            }
+            Method::Join(players) => {
+                if let Some(team) = self.teams.get_mut(&p.name) {
+                    for player in players {
+                        if !team.members.contains(player) {
+                            team.members.push(player.clone());
+                        }
+                    }
+                }
+            }
+            Method::Leave(players) => {
+                if let Some(team) = self.teams.get_mut(&p.name) {
+                    team.members.retain(|m| !players.contains(m));
+                }
+            }
+        }
+    }
+
+    pub fn team_of(&self, member: &str) -> Option<&Team> {
+        self.teams.values().find(|t| t.members.iter().any(|m| m == member))
+    }
+
+    pub fn objective(&self, name: &str) -> Option<Objective> {
+        let state = self.objectives.get(name)?;
+        let mut scores: Vec<ScoreEntry> = state
+            .scores
+            .iter()
+            .map(|(owner, (score, display))| {
+                let text = match display {
+                    Some(display) => display.clone(),
+                    None => match self.team_of(owner) {
+                        Some(team) => format!("{}{}{}", team.prefix, owner, team.suffix),
+                        None => owner.clone(),
+                    },
+                };
+                ScoreEntry {
+                    owner: owner.clone(),
+                    score: *score,
+                    text,
+                }
+            })
+            .collect();
+        // same order as the vanilla sidebar
+        scores.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.owner.cmp(&b.owner)));
+        Some(Objective {
+            name: name.to_string(),
+            display_name: state.display_name.clone(),
+            scores,
+        })
+    }
+
+    /// objective shown in a display slot
+    pub fn displayed(&self, slot: &str) -> Option<Objective> {
+        self.objective(self.displayed.get(slot)?)
+    }
+
+    pub fn objectives(&self) -> Vec<Objective> {
+        self.objectives.keys().filter_map(|name| self.objective(name)).collect()
+    }
+
+    pub fn teams(&self) -> Vec<Team> {
+        self.teams.values().cloned().collect()
+    }
+}
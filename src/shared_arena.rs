@@ -0,0 +1,496 @@
+//! ArenaVec over shared memory, for trainers in other processes
+//!
+//! serve_shared() maps two files (/dev/shm is a good place for them):
+//! `<path>.act`, which trainers write actions into, and `<path>.obs`, which
+//! the simulator writes observations, rewards, dones and winners into. every
+//! arena has a step counter in each file. a trainer bumps the one in `.act`
+//! after writing that arena's actions; the simulator steps the arena and sets
+//! the one in `.obs` to match. so processes that drive different arenas don't
+//! wait on each other
+
+use std::ffi::{c_char, c_int, c_void};
+use std::fs::OpenOptions;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::{PyBufferError, PyIOError, PyTimeoutError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+
+use crate::arena::{FastArena, FighterAction, OBS_SIZE};
+use crate::recorder::ACTION_NAMES;
+use crate::shutdown::deadline;
+
+const MAGIC: [u8; 4] = *b"PZSA";
+const VERSION: u32 = 8;
+/// bytes before the step counters
+const HEADER_SIZE: usize = 64;
+/// floats per fighter in `.act`: forward, backward, left, right, jump,
+/// sprint, attack, eat (pressed if > 0.5), delta_yaw, delta_pitch, draw, block,
+/// pearl, potion, rod. the same rows as FighterAction.from_values()
+pub const ACTION_SIZE: usize = ACTION_NAMES.len();
+/// idle polls that only yield before the simulator starts sleeping
+const SPINS: u32 = 1000;
+const SIGNAL_CHECK: Duration = Duration::from_millis(100);
+
+#[repr(C)]
+struct Header {
+    magic: [u8; 4],
+    version: u32,
+    count: u32,
+    /// set by SharedArenaVec.close(), serve_shared() returns once it sees it
+    closed: AtomicU32,
+}
+
+/// byte offsets into the two files, for `count` arenas
+#[derive(Clone, Copy)]
+struct Layout {
+    count: usize,
+}
+
+impl Layout {
+    fn data(&self) -> usize {
+        HEADER_SIZE + self.count * 8
+    }
+
+    fn act_len(&self) -> usize {
+        self.data() + self.count * 2 * ACTION_SIZE * 4
+    }
+
+    fn rewards(&self) -> usize {
+        self.data() + self.count * 2 * OBS_SIZE * 4
+    }
+
+    fn dones(&self) -> usize {
+        self.rewards() + self.count * 2 * 4
+    }
+
+    fn winners(&self) -> usize {
+        self.dones() + self.count * 4
+    }
+
+    fn obs_len(&self) -> usize {
+        self.winners() + self.count * 4
+    }
+}
+
+/// a whole file mapped read-write and shared with other processes
+struct Mapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// SAFETY: the memory is shared with other processes anyway, the counters
+// are atomics and everything else is only touched between steps
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    fn map(path: &Path, len: Option<usize>) -> std::io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).create(len.is_some()).truncate(len.is_some()).open(path)?;
+        let len = match len {
+            Some(len) => {
+                file.set_len(len as u64)?;
+                len
+            }
+            None => file.metadata()?.len() as usize,
+        };
+        if len < HEADER_SIZE {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "too short for a shared ArenaVec"));
+        }
+        // SAFETY: a fresh mapping of the whole file, checked for failure below
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self { ptr: ptr as *mut u8, len })
+    }
+
+    fn header(&self) -> &Header {
+        // SAFETY: mappings are at least HEADER_SIZE bytes and page aligned
+        unsafe { &*(self.ptr as *const Header) }
+    }
+
+    fn counter(&self, arena: usize) -> &AtomicU64 {
+        // SAFETY: callers stay below the header's count, which the length was
+        // checked against
+        unsafe { &*(self.ptr.add(HEADER_SIZE + arena * 8) as *const AtomicU64) }
+    }
+
+    /// copy `out.len()` floats from byte `offset`. the memory is never
+    /// borrowed as a slice, Python views and other processes have it too
+    fn read_floats(&self, offset: usize, out: &mut [f32]) {
+        assert!(offset + out.len() * 4 <= self.len);
+        // SAFETY: in bounds, 4-aligned by Layout, and the step counters order
+        // this against the other side's writes
+        unsafe { std::ptr::copy_nonoverlapping(self.ptr.add(offset) as *const f32, out.as_mut_ptr(), out.len()) }
+    }
+
+    /// copy `values` to byte `offset`, see read_floats()
+    fn write_floats(&self, offset: usize, values: &[f32]) {
+        assert!(offset + values.len() * 4 <= self.len);
+        // SAFETY: as in read_floats()
+        unsafe { std::ptr::copy_nonoverlapping(values.as_ptr(), self.ptr.add(offset) as *mut f32, values.len()) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: mapped in map() and not used after this
+        unsafe {
+            libc::munmap(self.ptr as *mut c_void, self.len);
+        }
+    }
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> PyErr {
+    PyIOError::new_err(format!("can't map {}: {}", path.display(), e))
+}
+
+/// make a file with its header filled in, written to a temporary name and
+/// renamed so SharedArenaVec never sees it half done
+fn create(path: &Path, len: usize, count: usize, fill: impl FnOnce(&Mapping)) -> PyResult<Mapping> {
+    let tmp = with_extension(path, ".tmp");
+    let mapping = Mapping::map(&tmp, Some(len)).map_err(|e| io_error(&tmp, e))?;
+    // SAFETY: nobody else has the file yet
+    unsafe {
+        let header = mapping.ptr as *mut Header;
+        (*header).magic = MAGIC;
+        (*header).version = VERSION;
+        (*header).count = count as u32;
+    }
+    fill(&mapping);
+    std::fs::rename(&tmp, path).map_err(|e| io_error(path, e))?;
+    Ok(mapping)
+}
+
+/// a fighter's row of `.act`, rows FighterAction.from_values() rejects are idle
+fn action(values: &[f32]) -> FighterAction {
+    let values: Vec<f64> = values.iter().map(|&v| v as f64).collect();
+    FighterAction::from_values(&values).unwrap_or_default()
+}
+
+fn write_obs(obs: &Mapping, layout: Layout, i: usize, arena: &FastArena) {
+    let row: Vec<f32> = arena.get_obs1().into_iter().chain(arena.get_obs2()).map(|v| v as f32).collect();
+    obs.write_floats(layout.data() + i * 2 * OBS_SIZE * 4, &row);
+}
+
+/// the loop behind ArenaVec.serve_shared(), returns the arena steps done
+pub fn serve(py: Python<'_>, arenas: &mut [FastArena], path: &Path, timeout: Option<f64>) -> PyResult<u64> {
+    if timeout.is_some_and(|timeout| timeout.is_nan() || timeout < 0.0) {
+        return Err(PyValueError::new_err("timeout must be positive or None"));
+    }
+    // an infinite (or too large) timeout is none at all
+    let timeout = timeout.and_then(|timeout| Duration::try_from_secs_f64(timeout).ok());
+    let layout = Layout { count: arenas.len() };
+    let act_path = with_extension(path, ".act");
+    let obs_path = with_extension(path, ".obs");
+    for arena in arenas.iter_mut() {
+        arena.reset(None, None, None);
+    }
+    // .obs last, SharedArenaVec waits for it
+    let act = create(&act_path, layout.act_len(), layout.count, |_| {})?;
+    let obs = create(&obs_path, layout.obs_len(), layout.count, |obs| {
+        for (i, arena) in arenas.iter().enumerate() {
+            write_obs(obs, layout, i, arena);
+        }
+    })?;
+
+    let served = py.allow_threads(|| {
+        let mut steps = 0;
+        let mut idle_since = Instant::now();
+        let mut checked = Instant::now();
+        let mut spins = 0;
+        while act.header().closed.load(Ordering::Acquire) == 0 {
+            let mut stepped = false;
+            for (i, arena) in arenas.iter_mut().enumerate() {
+                let wanted = act.counter(i).load(Ordering::Acquire);
+                if obs.counter(i).load(Ordering::Relaxed) >= wanted {
+                    continue;
+                }
+                let mut actions = [0.0; 2 * ACTION_SIZE];
+                act.read_floats(layout.data() + i * 2 * ACTION_SIZE * 4, &mut actions);
+                let (reward1, reward2, done) = arena.simulate(&action(&actions[..ACTION_SIZE]), &action(&actions[ACTION_SIZE..]));
+                obs.write_floats(layout.rewards() + i * 8, &[reward1 as f32, reward2 as f32]);
+                obs.write_floats(layout.dones() + i * 4, &[if done { 1.0 } else { 0.0 }]);
+                obs.write_floats(layout.winners() + i * 4, &[arena.winner as f32]);
+                // finished episodes start over, so the obs are the next one's first
+                if done {
+                    arena.reset(None, None, None);
+                }
+                write_obs(&obs, layout, i, arena);
+                obs.counter(i).store(wanted, Ordering::Release);
+                steps += 1;
+                stepped = true;
+            }
+
+            if stepped {
+                idle_since = Instant::now();
+                spins = 0;
+                continue;
+            }
+            if timeout.is_some_and(|timeout| idle_since.elapsed() >= timeout) {
+                break;
+            }
+            if checked.elapsed() >= SIGNAL_CHECK {
+                checked = Instant::now();
+                Python::with_gil(|py| py.check_signals())?;
+            }
+            if spins < SPINS {
+                spins += 1;
+                std::thread::yield_now();
+            } else {
+                std::thread::sleep(Duration::from_micros(50));
+            }
+        }
+        Ok(steps)
+    });
+    // trainers keep their mappings, the names just go away
+    std::fs::remove_file(&act_path).ok();
+    std::fs::remove_file(&obs_path).ok();
+    served
+}
+
+/// a float32 array in a shared ArenaVec's memory, numpy.asarray() and
+/// memoryview() give views of it with its shape
+#[pyclass]
+pub struct SharedArray {
+    mapping: Arc<Mapping>,
+    offset: usize,
+    shape: Vec<isize>,
+    strides: Vec<isize>,
+    readonly: bool,
+}
+
+impl SharedArray {
+    fn new(mapping: &Arc<Mapping>, offset: usize, shape: Vec<usize>, readonly: bool) -> Self {
+        let mut strides = vec![4isize; shape.len()];
+        for i in (0..shape.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * shape[i + 1] as isize;
+        }
+        Self {
+            mapping: mapping.clone(),
+            offset,
+            shape: shape.into_iter().map(|n| n as isize).collect(),
+            strides,
+            readonly,
+        }
+    }
+}
+
+#[pymethods]
+impl SharedArray {
+    #[getter]
+    fn shape(&self) -> Vec<isize> {
+        self.shape.clone()
+    }
+
+    #[getter]
+    fn readonly(&self) -> bool {
+        self.readonly
+    }
+
+    fn __len__(&self) -> usize {
+        self.shape.first().copied().unwrap_or(0) as usize
+    }
+
+    /// the buffer protocol, always the live shared memory
+    unsafe fn __getbuffer__(slf: Bound<'_, Self>, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("no buffer view to fill in"));
+        }
+        let this = slf.borrow();
+        if flags & ffi::PyBUF_WRITABLE == ffi::PyBUF_WRITABLE && this.readonly {
+            return Err(PyBufferError::new_err("this shared array is read-only, only actions can be written"));
+        }
+        let len = this.shape.iter().product::<isize>() * 4;
+        // SAFETY: view is the caller's to fill in, the pointers are into the
+        // mapping and this object, which the view keeps alive through obj
+        (*view).buf = this.mapping.ptr.add(this.offset) as *mut c_void;
+        (*view).len = len;
+        (*view).readonly = this.readonly as c_int;
+        (*view).itemsize = 4;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            c"f".as_ptr() as *mut c_char
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).ndim = this.shape.len() as c_int;
+        (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            this.shape.as_ptr() as *mut isize
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            this.strides.as_ptr() as *mut isize
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).suboffsets = std::ptr::null_mut();
+        (*view).internal = std::ptr::null_mut();
+        drop(this);
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SharedArray(shape={:?}, dtype=float32, readonly={})", self.shape, if self.readonly { "True" } else { "False" })
+    }
+}
+
+/// the trainer side of ArenaVec.serve_shared(path), in any process. write
+/// actions, step(), read obs, rewards, dones and winners; all of them are
+/// SharedArrays over the shared files, so nothing is copied or pickled
+#[pyclass]
+pub struct SharedArenaVec {
+    path: PathBuf,
+    layout: Layout,
+    act: Arc<Mapping>,
+    obs: Arc<Mapping>,
+}
+
+#[pymethods]
+impl SharedArenaVec {
+    /// wait up to `timeout` seconds for serve_shared(path) to start
+    #[new]
+    #[pyo3(signature = (path, timeout=10.0))]
+    fn new(py: Python<'_>, path: PathBuf, timeout: f64) -> PyResult<Self> {
+        let act_path = with_extension(&path, ".act");
+        let obs_path = with_extension(&path, ".obs");
+        let deadline = deadline(timeout);
+        py.allow_threads(|| {
+            while !obs_path.exists() {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(PyTimeoutError::new_err(format!(
+                        "no shared ArenaVec at {} after {}s, is serve_shared() running?",
+                        path.display(),
+                        timeout
+                    )));
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        })?;
+
+        let act = Mapping::map(&act_path, None).map_err(|e| io_error(&act_path, e))?;
+        let obs = Mapping::map(&obs_path, None).map_err(|e| io_error(&obs_path, e))?;
+        let header = obs.header();
+        if header.magic != MAGIC || header.version != VERSION || act.header().count != header.count {
+            return Err(PyValueError::new_err(format!(
+                "{} isn't a shared ArenaVec of this pyzalea version",
+                path.display()
+            )));
+        }
+        let layout = Layout { count: header.count as usize };
+        if act.len < layout.act_len() || obs.len < layout.obs_len() {
+            return Err(PyValueError::new_err(format!("{} is truncated", path.display())));
+        }
+        Ok(Self {
+            path,
+            layout,
+            act: Arc::new(act),
+            obs: Arc::new(obs),
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.layout.count
+    }
+
+    /// (len, 2, ACTION_SIZE): forward, backward, left, right, jump, sprint,
+    /// attack, eat (pressed if > 0.5), delta_yaw, delta_pitch, draw, block,
+    /// pearl, potion, rod per fighter
+    #[getter]
+    fn actions(&self) -> SharedArray {
+        SharedArray::new(&self.act, self.layout.data(), vec![self.layout.count, 2, ACTION_SIZE], false)
+    }
+
+    /// (len, 2, OBS_SIZE), after a done it's the next episode's first
+    #[getter]
+    fn obs(&self) -> SharedArray {
+        SharedArray::new(&self.obs, self.layout.data(), vec![self.layout.count, 2, OBS_SIZE], true)
+    }
+
+    /// (len, 2) of the last step
+    #[getter]
+    fn rewards(&self) -> SharedArray {
+        SharedArray::new(&self.obs, self.layout.rewards(), vec![self.layout.count, 2], true)
+    }
+
+    /// (len,), 1.0 if the last step ended the episode
+    #[getter]
+    fn dones(&self) -> SharedArray {
+        SharedArray::new(&self.obs, self.layout.dones(), vec![self.layout.count], true)
+    }
+
+    /// (len,) of the last step, like FastArena.winner: 0 none, 1 or 2, -1 a draw
+    #[getter]
+    fn winners(&self) -> SharedArray {
+        SharedArray::new(&self.obs, self.layout.winners(), vec![self.layout.count], true)
+    }
+
+    /// step arenas start..stop with the actions written for them, and wait
+    /// for the results. don't step the same arena from two processes at once
+    #[pyo3(signature = (start=0, stop=None, timeout=10.0))]
+    fn step(&self, py: Python<'_>, start: usize, stop: Option<usize>, timeout: f64) -> PyResult<()> {
+        let stop = stop.unwrap_or(self.layout.count);
+        if start > stop || stop > self.layout.count {
+            return Err(PyValueError::new_err(format!(
+                "arenas {}..{} out of range for {} arenas",
+                start, stop, self.layout.count
+            )));
+        }
+        let wanted: Vec<u64> = (start..stop)
+            .map(|i| self.act.counter(i).fetch_add(1, Ordering::Release) + 1)
+            .collect();
+        let deadline = deadline(timeout);
+        py.allow_threads(|| {
+            let mut spins = 0;
+            for (i, wanted) in (start..stop).zip(wanted) {
+                while self.obs.counter(i).load(Ordering::Acquire) < wanted {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return Err(PyTimeoutError::new_err(format!(
+                            "the shared ArenaVec at {} didn't step in {}s, is serve_shared() still running?",
+                            self.path.display(),
+                            timeout
+                        )));
+                    }
+                    if spins < SPINS {
+                        spins += 1;
+                        std::thread::yield_now();
+                    } else {
+                        std::thread::sleep(Duration::from_micros(50));
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// make serve_shared() return, for every process using it
+    fn close(&self) {
+        self.act.header().closed.store(1, Ordering::Release);
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SharedArenaVec(path={:?}, len={})", self.path.display().to_string(), self.layout.count)
+    }
+}
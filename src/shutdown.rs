@@ -0,0 +1,159 @@
+//! Tracking connection threads so they can be stopped on exit
+//!
+//! azalea runs everything as local tasks inside `start()`, so dropping that
+//! future (and the thread's runtime) is what actually stops a bot. a watchdog
+//! thread reports the ones that stop by themselves, see crash.rs
+
+use pyo3::prelude::*;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Once};
+use std::sync::atomic::Ordering;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+use crate::bot::BotShared;
+use crate::crash::{self, CrashEvent};
+use crate::events::{BotEvent, DisconnectEvent};
+
+/// one connection thread, running one bot or a whole swarm
+struct Connection {
+    bots: Vec<Arc<BotShared>>,
+    stop: Arc<Notify>,
+    thread: JoinHandle<()>,
+}
+
+lazy_static::lazy_static! {
+    static ref CONNECTIONS: Mutex<Vec<Connection>> = Mutex::new(Vec::new());
+}
+
+/// run `start` on a new thread with its own single-threaded runtime (azalea
+/// uses LocalSet), until it returns or shutdown() is called
+pub fn spawn_connection<F, Fut>(bots: Vec<Arc<BotShared>>, start: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = ()>,
+{
+    let stop = Arc::new(Notify::new());
+    let stop_clone = stop.clone();
+    let thread_bots = bots.clone();
+    let thread = std::thread::spawn(move || {
+        crash::mark_bot_thread();
+        let ran = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to create tokio runtime");
+
+            rt.block_on(async move {
+                tokio::select! {
+                    _ = start() => {}
+                    _ = stop_clone.notified() => {}
+                }
+            });
+            // don't wait on blocking tasks (like DNS lookups) that are stuck
+            rt.shutdown_timeout(Duration::from_secs(1));
+        }));
+        if let Err(payload) = ran {
+            crash::report(&thread_bots, crash::caught(payload.as_ref(), true));
+        }
+    });
+
+    let mut connections = CONNECTIONS.lock();
+    reap(&mut connections);
+    connections.push(Connection { bots, stop, thread });
+    drop(connections);
+
+    static WATCHDOG: Once = Once::new();
+    WATCHDOG.call_once(|| {
+        std::thread::Builder::new()
+            .name("pyzalea-watchdog".to_string())
+            .spawn(|| loop {
+                std::thread::sleep(Duration::from_millis(250));
+                reap(&mut CONNECTIONS.lock());
+            })
+            .expect("Failed to spawn the watchdog thread");
+    });
+}
+
+/// drop finished connection threads. a thread that panicked crashed its
+/// bots, one that returned disconnected them
+fn reap(connections: &mut Vec<Connection>) {
+    let mut i = 0;
+    while i < connections.len() {
+        if !connections[i].thread.is_finished() {
+            i += 1;
+            continue;
+        }
+        let connection = connections.swap_remove(i);
+        match connection.thread.join() {
+            Ok(()) => stopped(&connection.bots),
+            // report() skips bots that are closing or have a crash already
+            Err(_) => crash::report(&connection.bots, CrashEvent::new("the connection thread panicked".to_string(), true)),
+        }
+    }
+}
+
+/// the bots of a thread that returned by itself, told they're disconnected
+/// unless they're closing or already were
+fn stopped(bots: &[Arc<BotShared>]) {
+    for bot in bots {
+        if bot.closing.load(Ordering::SeqCst) {
+            continue;
+        }
+        bot.joined.store(false, Ordering::SeqCst);
+        if bot.connected.swap(false, Ordering::SeqCst) {
+            bot.push_event(BotEvent::Disconnect(DisconnectEvent { reason: None }));
+        }
+    }
+}
+
+/// every bot whose connection thread is still around
+pub fn bots() -> Vec<Arc<BotShared>> {
+    CONNECTIONS.lock().iter().flat_map(|c| c.bots.iter().cloned()).collect()
+}
+
+/// `timeout` seconds from now. None (wait forever) for an infinite or too
+/// large timeout, negative and NaN ones are over right away
+pub fn deadline(timeout: f64) -> Option<Instant> {
+    Instant::now().checked_add(Duration::try_from_secs_f64(timeout.max(0.0)).ok()?)
+}
+
+/// disconnect every bot, stop their connection threads and wait up to
+/// `timeout` seconds for them to finish. registered with atexit, but safe to
+/// call earlier. returns False if some thread didn't stop in time
+#[pyfunction]
+#[pyo3(signature = (timeout=5.0))]
+pub fn shutdown(py: Python<'_>, timeout: f64) -> bool {
+    let connections: Vec<Connection> = CONNECTIONS.lock().drain(..).collect();
+    py.allow_threads(|| {
+        for connection in &connections {
+            for bot in &connection.bots {
+                bot.closing.store(true, Ordering::SeqCst);
+                if let Some(client) = bot.client.lock().take() {
+                    client.disconnect();
+                }
+                bot.connected.store(false, Ordering::SeqCst);
+                bot.joined.store(false, Ordering::SeqCst);
+            }
+            // notify_one keeps the permit if the thread isn't waiting yet
+            connection.stop.notify_one();
+        }
+
+        let deadline = deadline(timeout);
+        let mut all_stopped = true;
+        for connection in connections {
+            while !connection.thread.is_finished() && deadline.is_none_or(|d| Instant::now() < d) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            if connection.thread.is_finished() {
+                connection.thread.join().ok();
+            } else {
+                all_stopped = false;
+            }
+        }
+        all_stopped
+    })
+}
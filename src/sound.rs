@@ -0,0 +1,114 @@
+//! Sounds played in the world around the bot
+
+use pyo3::prelude::*;
+
+use azalea::registry::Holder;
+use azalea::registry::builtin::SoundEvent as SoundKind;
+use azalea_core::sound::CustomSound;
+use azalea_client::Client;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::game::c_sound::SoundSource;
+
+use crate::events::unix_seconds;
+
+/// a sound the server told us to play
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct SoundEvent {
+    /// e.g. "minecraft:entity.arrow.shoot"
+    #[pyo3(get)]
+    pub sound: String,
+    /// sound category ("players", "hostile", "blocks", ...)
+    #[pyo3(get)]
+    pub category: String,
+    #[pyo3(get)]
+    pub x: f64,
+    #[pyo3(get)]
+    pub y: f64,
+    #[pyo3(get)]
+    pub z: f64,
+    #[pyo3(get)]
+    pub volume: f32,
+    #[pyo3(get)]
+    pub pitch: f32,
+    /// entity the sound is attached to, if it came from one
+    #[pyo3(get)]
+    pub entity_id: Option<i32>,
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl SoundEvent {
+    fn distance_to(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (dx, dy, dz) = (self.x - x, self.y - y, self.z - z);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SoundEvent(sound={}, pos=({:.1}, {:.1}, {:.1}), volume={:.2})",
+            self.sound, self.x, self.y, self.z, self.volume
+        )
+    }
+}
+
+fn category_name(source: SoundSource) -> &'static str {
+    match source {
+        SoundSource::Master => "master",
+        SoundSource::Music => "music",
+        SoundSource::Records => "records",
+        SoundSource::Weather => "weather",
+        SoundSource::Blocks => "blocks",
+        SoundSource::Hostile => "hostile",
+        SoundSource::Neutral => "neutral",
+        SoundSource::Players => "players",
+        SoundSource::Ambient => "ambient",
+        SoundSource::Voice => "voice",
+    }
+}
+
+fn sound_name(holder: &Holder<SoundKind, CustomSound>) -> String {
+    match holder {
+        Holder::Reference(sound) => sound.to_string(),
+        Holder::Direct(custom) => custom.sound_id.to_string(),
+    }
+}
+
+/// turn a sound packet into an event. sounds attached to an entity get the
+/// entity's current position (or 0,0,0 if we can't see it)
+pub fn sound_event(bot: &Client, packet: &ClientboundGamePacket) -> Option<SoundEvent> {
+    match packet {
+        ClientboundGamePacket::Sound(p) => Some(SoundEvent {
+            sound: sound_name(&p.sound),
+            category: category_name(p.source).to_string(),
+            // positions are sent as fixed point, 1/8 of a block
+            x: p.x as f64 / 8.0,
+            y: p.y as f64 / 8.0,
+            z: p.z as f64 / 8.0,
+            volume: p.volume,
+            pitch: p.pitch,
+            entity_id: None,
+            timestamp: unix_seconds(),
+        }),
+        ClientboundGamePacket::SoundEntity(p) => {
+            let pos = bot.ecs_entity_by_minecraft_entity(p.id).and_then(|entity| {
+                let ecs = bot.ecs.lock();
+                ecs.get::<azalea_entity::Position>(entity).map(|pos| (pos.x, pos.y, pos.z))
+            });
+            let (x, y, z) = pos.unwrap_or_default();
+            Some(SoundEvent {
+                sound: sound_name(&p.sound),
+                category: category_name(p.source).to_string(),
+                x,
+                y,
+                z,
+                volume: p.volume,
+                pitch: p.pitch,
+                entity_id: Some(p.id.0),
+                timestamp: unix_seconds(),
+            })
+        }
+        _ => None,
+    }
+}
@@ -1,13 +1,43 @@
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use azalea::ecs::prelude::*;
+use azalea::physics::collision::BlockWithShape;
+use azalea::registry::builtin::BlockKind;
+use azalea::BlockPos;
+use azalea_client::Client;
+use azalea_client::attack::AttackStrengthScale;
+use azalea_client::local_player::Hunger;
+use azalea_client::tick_counter::TicksConnected;
+use azalea_entity::inventory::Inventory;
+use azalea_entity::metadata::{AbstractLivingUsingItem, Health, ItemItem, OnFire, Sprinting};
+use azalea_entity::{Crouching, Dead, EntityKindComponent, LookDirection, Physics, Position};
+use azalea_world::{InstanceName, MinecraftEntityId};
+
+use crate::blocks::BlockGrid;
+use crate::diff::{diff, StateDiff};
+use crate::dlpack::ObservationTensor;
+use crate::effects::Effect;
+use crate::entity_kind::{category, Category};
+use crate::equipment::EntityEquipment;
+use crate::projectile::Projectiles;
+use crate::motion::Motions;
+use crate::item::Item;
+use crate::observation::ObservationConfig;
+use crate::threat::{threats, Threat};
 
 /// entity in the game (player/mob/etc.)
 #[pyclass]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PyEntity {
     #[pyo3(get)]
     pub id: u32,
     #[pyo3(get)]
     pub entity_type: String,
+    pub category: Category,
     #[pyo3(get)]
     pub x: f64,
     #[pyo3(get)]
@@ -24,10 +54,50 @@ pub struct PyEntity {
     pub velocity_y: f64,
     #[pyo3(get)]
     pub velocity_z: f64,
+    /// x/y/z carried along the estimated velocity since the server last moved
+    /// it, plus PyBot.motion_prediction.horizon. steadier to aim at. these
+    /// load as 0 from states saved before they existed
+    #[pyo3(get)]
+    #[serde(default)]
+    pub smooth_x: f64,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub smooth_y: f64,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub smooth_z: f64,
+    /// blocks per tick, from how the position has been changing
+    #[pyo3(get)]
+    #[serde(default)]
+    pub estimated_velocity_x: f64,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub estimated_velocity_y: f64,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub estimated_velocity_z: f64,
+    /// 0.0 for things that don't have health (items, projectiles)
     #[pyo3(get)]
     pub health: f32,
     #[pyo3(get)]
     pub is_on_ground: bool,
+    /// eating, drinking, blocking or drawing a bow
+    #[pyo3(get)]
+    pub is_using_item: bool,
+    /// only known once the server has sent it (usually players and armed mobs)
+    #[pyo3(get)]
+    pub held_item: Option<Item>,
+    #[pyo3(get)]
+    pub offhand: Option<Item>,
+    /// head, chest, legs, feet
+    #[pyo3(get)]
+    pub armor: Vec<Option<Item>>,
+    /// the stack a dropped item entity represents
+    #[pyo3(get)]
+    pub item: Option<Item>,
+    /// who shot or threw a projectile
+    #[pyo3(get)]
+    pub owner_id: Option<i32>,
 }
 
 #[pymethods]
@@ -47,6 +117,41 @@ impl PyEntity {
         (self.velocity_x, self.velocity_y, self.velocity_z)
     }
 
+    fn smooth_position(&self) -> (f64, f64, f64) {
+        (self.smooth_x, self.smooth_y, self.smooth_z)
+    }
+
+    fn estimated_velocity(&self) -> (f64, f64, f64) {
+        (self.estimated_velocity_x, self.estimated_velocity_y, self.estimated_velocity_z)
+    }
+
+    /// "player", "hostile", "passive", "item", "projectile" or "other"
+    #[getter]
+    fn category(&self) -> &'static str {
+        self.category.as_str()
+    }
+
+    fn is_player(&self) -> bool {
+        self.category == Category::Player
+    }
+
+    fn is_hostile(&self) -> bool {
+        self.category == Category::Hostile
+    }
+
+    fn is_passive(&self) -> bool {
+        self.category == Category::Passive
+    }
+
+    /// a dropped item stack
+    fn is_item(&self) -> bool {
+        self.category == Category::Item
+    }
+
+    fn is_projectile(&self) -> bool {
+        self.category == Category::Projectile
+    }
+
     fn distance_to(&self, other: &PyEntity) -> f64 {
         let dx = self.x - other.x;
         let dy = self.y - other.y;
@@ -59,10 +164,288 @@ impl PyEntity {
         let dz = self.z - other.z;
         (dx * dx + dz * dz).sqrt()
     }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_dict(py, self)
+    }
+
+    #[pyo3(signature = (indent=None))]
+    fn to_json(&self, indent: Option<usize>) -> PyResult<String> {
+        to_json(self, indent)
+    }
+
+    #[staticmethod]
+    fn from_dict(dict: &Bound<'_, PyAny>) -> PyResult<Self> {
+        from_dict(dict)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json)
+    }
+}
+
+fn to_json<T: Serialize>(value: &T, indent: Option<usize>) -> PyResult<String> {
+    let result = match indent {
+        None => serde_json::to_string(value),
+        Some(indent) => {
+            let spaces = " ".repeat(indent);
+            let formatter = serde_json::ser::PrettyFormatter::with_indent(spaces.as_bytes());
+            let mut out = Vec::new();
+            let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+            value.serialize(&mut serializer).map(|_| String::from_utf8(out).unwrap_or_default())
+        }
+    };
+    result.map_err(|e| PyValueError::new_err(format!("failed to serialize: {}", e)))
+}
+
+fn from_json<T: DeserializeOwned>(json: &str) -> PyResult<T> {
+    serde_json::from_str(json).map_err(|e| PyValueError::new_err(format!("invalid state: {}", e)))
+}
+
+/// plain dicts/lists, same shape as the json
+fn to_dict<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = to_json(value, None)?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+fn from_dict<T: DeserializeOwned>(dict: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json: String = dict.py().import("json")?.call_method1("dumps", (dict,))?.extract()?;
+    from_json(&json)
+}
+
+/// entity kind without the namespace, like "player" or "arrow"
+fn entity_type_name(kind: &EntityKindComponent) -> String {
+    let name = kind.0.to_string();
+    name.strip_prefix("minecraft:").unwrap_or(&name).to_string()
+}
+
+/// every other entity in the bot's world within `radius` blocks
+pub fn nearby_entities(
+    client: &Client,
+    radius: f64,
+    equipment: &EntityEquipment,
+    projectiles: &Projectiles,
+    motions: &Motions,
+) -> Vec<PyEntity> {
+    let mut ecs = client.ecs.lock();
+    let Some(own_pos) = ecs.get::<Position>(client.entity).map(|p| **p) else {
+        return vec![];
+    };
+    let Some(own_world) = ecs.get::<InstanceName>(client.entity).cloned() else {
+        return vec![];
+    };
+
+    let mut query = ecs.query::<(
+        Entity,
+        &MinecraftEntityId,
+        &EntityKindComponent,
+        &Position,
+        &InstanceName,
+        Option<&LookDirection>,
+        Option<&Physics>,
+        Option<&Health>,
+        Option<&ItemItem>,
+        Option<&AbstractLivingUsingItem>,
+    )>();
+    let mut entities: Vec<PyEntity> = query
+        .iter(&ecs)
+        .filter(|(entity, _, _, pos, world, ..)| {
+            *entity != client.entity && **world == own_world && pos.distance_to(own_pos) <= radius
+        })
+        .map(|(_, id, kind, pos, _, look, physics, health, item, using)| {
+            snapshot(*id, kind, pos, look, physics, health, item, using, equipment, projectiles, motions)
+        })
+        .collect();
+    // stable order between ticks, the ECS doesn't have one
+    entities.sort_by_key(|e| e.id);
+    entities
+}
+
+/// a player the bot can see, by username (any distance)
+pub fn player_entity(
+    client: &Client,
+    username: &str,
+    equipment: &EntityEquipment,
+    projectiles: &Projectiles,
+    motions: &Motions,
+) -> Option<PyEntity> {
+    let uuid = client.player_uuid_by_username(username)?;
+    let entity = client.entity_by_uuid(uuid)?;
+    let ecs = client.ecs.lock();
+    Some(snapshot(
+        *ecs.get::<MinecraftEntityId>(entity)?,
+        ecs.get::<EntityKindComponent>(entity)?,
+        ecs.get::<Position>(entity)?,
+        ecs.get::<LookDirection>(entity),
+        ecs.get::<Physics>(entity),
+        ecs.get::<Health>(entity),
+        ecs.get::<ItemItem>(entity),
+        ecs.get::<AbstractLivingUsingItem>(entity),
+        equipment,
+        projectiles,
+        motions,
+    ))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn snapshot(
+    id: MinecraftEntityId,
+    kind: &EntityKindComponent,
+    pos: &Position,
+    look: Option<&LookDirection>,
+    physics: Option<&Physics>,
+    health: Option<&Health>,
+    item: Option<&ItemItem>,
+    using: Option<&AbstractLivingUsingItem>,
+    equipment: &EntityEquipment,
+    projectiles: &Projectiles,
+    motions: &Motions,
+) -> PyEntity {
+    let gear = equipment.get(id.0).cloned().unwrap_or_default();
+    let flight = projectiles.get(id.0);
+    let velocity = flight
+        .map(|f| f.velocity)
+        .or(physics.map(|p| p.velocity))
+        .unwrap_or_default();
+    let (smooth, estimated) = motions.predict(id.0, **pos);
+    PyEntity {
+        id: id.0 as u32,
+        entity_type: entity_type_name(kind),
+        category: category(kind.0),
+        x: pos.x,
+        y: pos.y,
+        z: pos.z,
+        yaw: look.map(|l| l.y_rot()).unwrap_or(0.0),
+        pitch: look.map(|l| l.x_rot()).unwrap_or(0.0),
+        velocity_x: velocity.x,
+        velocity_y: velocity.y,
+        velocity_z: velocity.z,
+        smooth_x: smooth.x,
+        smooth_y: smooth.y,
+        smooth_z: smooth.z,
+        estimated_velocity_x: estimated.x,
+        estimated_velocity_y: estimated.y,
+        estimated_velocity_z: estimated.z,
+        health: health.map(|h| h.0).unwrap_or(0.0),
+        is_on_ground: physics.map(|p| p.on_ground()).unwrap_or(false),
+        is_using_item: using.is_some_and(|u| u.0),
+        held_item: gear.mainhand,
+        offhand: gear.offhand,
+        armor: gear.armor.into(),
+        item: item.and_then(|i| Item::from_stack(&i.0)),
+        owner_id: flight.and_then(|f| f.owner),
+    }
+}
+
+/// copy the bot's look, movement and combat components into `state`
+pub fn read_kinematics(client: &Client, state: &mut PyGameState) {
+    let ecs = client.ecs.lock();
+    let entity = client.entity;
+    if let Some(look) = ecs.get::<LookDirection>(entity) {
+        state.yaw = look.y_rot();
+        state.pitch = look.x_rot();
+    }
+    if let Some(physics) = ecs.get::<Physics>(entity) {
+        state.velocity_x = physics.velocity.x;
+        state.velocity_y = physics.velocity.y;
+        state.velocity_z = physics.velocity.z;
+        state.is_on_ground = physics.on_ground();
+        state.is_in_water = physics.is_in_water();
+        state.is_in_lava = physics.is_in_lava();
+    }
+    if let Some(on_fire) = ecs.get::<OnFire>(entity) {
+        state.is_on_fire = on_fire.0;
+    }
+    if let Some(sprinting) = ecs.get::<Sprinting>(entity) {
+        state.is_sprinting = sprinting.0;
+    }
+    if let Some(crouching) = ecs.get::<Crouching>(entity) {
+        state.is_sneaking = **crouching;
+    }
+    state.is_dead = ecs.get::<Dead>(entity).is_some();
+    if let Some(using) = ecs.get::<AbstractLivingUsingItem>(entity) {
+        state.is_using_item = using.0;
+    }
+    if let Some(hunger) = ecs.get::<Hunger>(entity) {
+        state.saturation = hunger.saturation;
+    }
+    if let Some(inventory) = ecs.get::<Inventory>(entity) {
+        state.selected_slot = inventory.selected_hotbar_slot;
+    }
+    if let Some(scale) = ecs.get::<AttackStrengthScale>(entity) {
+        state.attack_cooldown = scale.0.clamp(0.0, 1.0);
+    }
+}
+
+/// cobwebs and suffocation, which azalea's physics doesn't flag
+pub fn read_hazards(client: &Client, state: &mut PyGameState) {
+    let bounding_box = {
+        let ecs = client.ecs.lock();
+        let Some(physics) = ecs.get::<Physics>(client.entity) else {
+            return;
+        };
+        physics.bounding_box
+    };
+    let eyes = BlockPos::from(client.eye_position());
+    let world = client.world();
+    let world = world.read();
+
+    // every block the hitbox touches, like vanilla's stuck-in-block check
+    let shrunk = bounding_box.deflate_all(1.0e-7);
+    let min = BlockPos::from(shrunk.min);
+    let max = BlockPos::from(shrunk.max);
+    state.is_in_web = (min.x..=max.x).any(|x| {
+        (min.y..=max.y).any(|y| {
+            (min.z..=max.z).any(|z| {
+                world
+                    .get_block_state(BlockPos::new(x, y, z))
+                    .is_some_and(|block| BlockKind::from(block) == BlockKind::Cobweb)
+            })
+        })
+    });
+    // a full solid block in the head, close enough to vanilla's suffocation check
+    state.is_suffocating = world
+        .get_block_state(eyes)
+        .is_some_and(|block| block.is_collision_shape_full());
+}
+
+/// copy hotbar, armor and offhand from the bot's inventory into `state`
+pub fn read_equipment(client: &Client, state: &mut PyGameState) {
+    let ecs = client.ecs.lock();
+    let Some(inventory) = ecs.get::<Inventory>(client.entity) else {
+        return;
+    };
+    let player = inventory.inventory_menu.as_player();
+    // the hotbar is the last row of the main inventory
+    state.hotbar = player.inventory[27..].iter().map(Item::from_stack).collect();
+    state.armor = player.armor.iter().map(Item::from_stack).collect();
+    state.offhand = Item::from_stack(&player.offhand);
+
+    let held = state.hotbar.get(state.selected_slot as usize).cloned().flatten();
+    state.held_item_count = held.as_ref().map(|i| i.count).unwrap_or(0);
+    state.held_item_id = held.map(|i| i.id);
+    state.offhand_count = state.offhand.as_ref().map(|i| i.count).unwrap_or(0);
+    state.offhand_id = state.offhand.as_ref().map(|i| i.id.clone());
+}
+
+/// client ticks since the bot logged in (restarts from 0 on reconnect)
+pub fn ticks_connected(client: &Client) -> u64 {
+    let ecs = client.ecs.lock();
+    ecs.get::<TicksConnected>(client.entity).map(|t| t.0).unwrap_or(0)
+}
+
+/// from the last set_experience packet (azalea doesn't keep it)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Experience {
+    pub level: u32,
+    /// 0.0-1.0 towards the next level
+    pub progress: f32,
+    pub total: u32,
 }
 
 #[pyclass]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PyGameState {
     // Player state
     #[pyo3(get)]
@@ -95,6 +478,26 @@ pub struct PyGameState {
     pub is_sneaking: bool,
     #[pyo3(get)]
     pub is_dead: bool,
+    /// eating, drinking, blocking or drawing a bow
+    #[pyo3(get)]
+    pub is_using_item: bool,
+    /// took damage in the last 10 ticks, see bot.last_damage()
+    #[pyo3(get)]
+    pub recently_hurt: bool,
+
+    // Surroundings
+    #[pyo3(get)]
+    pub is_in_water: bool,
+    #[pyo3(get)]
+    pub is_in_lava: bool,
+    #[pyo3(get)]
+    pub is_on_fire: bool,
+    /// touching a cobweb
+    #[pyo3(get)]
+    pub is_in_web: bool,
+    /// head inside a solid block
+    #[pyo3(get)]
+    pub is_suffocating: bool,
 
     // Combat state
     #[pyo3(get)]
@@ -102,11 +505,67 @@ pub struct PyGameState {
     #[pyo3(get)]
     pub selected_slot: u8,
 
+    // Equipment (None for empty slots)
+    #[pyo3(get)]
+    pub hotbar: Vec<Option<Item>>,
+    /// head, chest, legs, feet
+    #[pyo3(get)]
+    pub armor: Vec<Option<Item>>,
+    #[pyo3(get)]
+    pub offhand: Option<Item>,
+    /// like "diamond_sword", None for an empty hand
+    #[pyo3(get)]
+    pub held_item_id: Option<String>,
+    #[pyo3(get)]
+    pub held_item_count: i32,
+    #[pyo3(get)]
+    pub offhand_id: Option<String>,
+    #[pyo3(get)]
+    pub offhand_count: i32,
+
+    // Potion effects
+    #[pyo3(get)]
+    pub effects: Vec<Effect>,
+
+    // Experience
+    #[pyo3(get)]
+    pub xp_level: u32,
+    #[pyo3(get)]
+    pub xp_progress: f32,
+    #[pyo3(get)]
+    pub xp_total: u32,
+
+    // World
+    /// 0-23999, 0 is sunrise, 6000 noon, 18000 midnight
+    #[pyo3(get)]
+    pub time_of_day: u64,
+    #[pyo3(get)]
+    pub is_raining: bool,
+    #[pyo3(get)]
+    pub is_thundering: bool,
+    /// like "minecraft:overworld"
+    #[pyo3(get)]
+    pub dimension: Option<String>,
+    /// like "minecraft:plains", None until the chunk has loaded
+    #[pyo3(get)]
+    pub biome: Option<String>,
+    /// 0-15, from torches, lava and so on
+    #[pyo3(get)]
+    pub block_light: u8,
+    /// 0-15, how much sky the bot's block sees (not how bright it is right now)
+    #[pyo3(get)]
+    pub sky_light: u8,
+
     // Nearby entities
     #[pyo3(get)]
     pub entities: Vec<PyEntity>,
 
+    // Blocks around the bot, only when bot.block_grid_radius is set
+    #[pyo3(get)]
+    pub blocks: Option<BlockGrid>,
+
     // Game tick
+    /// client ticks since login, same counter as bot.current_tick()
     #[pyo3(get)]
     pub tick: u64,
 }
@@ -128,6 +587,58 @@ impl PyGameState {
         (self.velocity_x, self.velocity_y, self.velocity_z)
     }
 
+    /// every field as plain dicts/lists, entities and items included
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_dict(py, self)
+    }
+
+    #[pyo3(signature = (indent=None))]
+    fn to_json(&self, indent: Option<usize>) -> PyResult<String> {
+        to_json(self, indent)
+    }
+
+    /// rebuild a state saved with to_dict()
+    #[staticmethod]
+    fn from_dict(dict: &Bound<'_, PyAny>) -> PyResult<Self> {
+        from_dict(dict)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json)
+    }
+
+    /// what changed since `previous` (health lost, movement, entities that
+    /// appeared or disappeared, ...)
+    fn diff(&self, previous: &PyGameState) -> StateDiff {
+        diff(self, previous)
+    }
+
+    /// item in the selected hotbar slot
+    fn held_item(&self) -> Option<Item> {
+        self.hotbar.get(self.selected_slot as usize).cloned().flatten()
+    }
+
+    /// whether the main hand holds `name`: an exact id ("diamond_sword") or
+    /// the last part of one ("sword" matches any sword)
+    fn is_holding(&self, name: &str) -> bool {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        self.held_item_id
+            .as_deref()
+            .is_some_and(|id| id == name || id.ends_with(&format!("_{name}")))
+    }
+
+    /// between dusk and dawn, when hostile mobs spawn
+    fn is_night(&self) -> bool {
+        (13000..23000).contains(&self.time_of_day)
+    }
+
+    /// the active effect with this name ("speed" or "minecraft:speed")
+    fn effect(&self, name: &str) -> Option<Effect> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        self.effects.iter().find(|e| e.name == name).cloned()
+    }
+
     #[pyo3(signature = (entity_type=None, max_distance=None))]
     fn nearest_entity(&self, entity_type: Option<&str>, max_distance: Option<f64>) -> Option<PyEntity> {
         let mut nearest: Option<(f64, &PyEntity)> = None;
@@ -162,6 +673,41 @@ impl PyGameState {
         nearest.map(|(_, e)| e.clone())
     }
 
+    /// players and hostiles within `max_distance` with distance, angle,
+    /// facing, held weapon and closing speed, nearest first
+    #[pyo3(signature = (max_distance=32.0))]
+    fn threats(&self, max_distance: f64) -> Vec<Threat> {
+        threats(self, max_distance)
+    }
+
+    /// the `k` closest matches, nearest first
+    #[pyo3(signature = (k, entity_type=None, max_distance=None))]
+    fn nearest_entities(&self, k: usize, entity_type: Option<&str>, max_distance: Option<f64>) -> Vec<PyEntity> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut matches: Vec<(f64, &PyEntity)> = self
+            .entities
+            .iter()
+            .filter(|e| entity_type.is_none_or(|t| e.entity_type == t))
+            .map(|e| {
+                let dx = self.x - e.x;
+                let dy = self.y - e.y;
+                let dz = self.z - e.z;
+                ((dx * dx + dy * dy + dz * dz).sqrt(), e)
+            })
+            .filter(|(dist, _)| max_distance.is_none_or(|max_d| *dist <= max_d))
+            .collect();
+
+        // only the k nearest need sorting
+        if k < matches.len() {
+            matches.select_nth_unstable_by(k - 1, |a, b| a.0.total_cmp(&b.0));
+            matches.truncate(k);
+        }
+        matches.sort_by(|a, b| a.0.total_cmp(&b.0));
+        matches.into_iter().map(|(_, e)| e.clone()).collect()
+    }
+
     #[pyo3(signature = (max_distance=32.0))]
     fn nearby_players(&self, max_distance: f64) -> Vec<PyEntity> {
         self.entities
@@ -180,47 +726,83 @@ impl PyGameState {
             .collect()
     }
 
-    fn to_vector(&self) -> Vec<f32> {
-        let mut v = vec![
-            self.x as f32,
-            self.y as f32,
-            self.z as f32,
-            self.yaw,
-            self.pitch,
-            self.velocity_x as f32,
-            self.velocity_y as f32,
-            self.velocity_z as f32,
-            self.health,
-            self.food as f32,
-            self.is_on_ground as u8 as f32,
-            self.is_sprinting as u8 as f32,
-            self.is_sneaking as u8 as f32,
-            self.attack_cooldown,
-        ];
-
-        // add nearest player info
-        if let Some(enemy) = self.nearest_entity(Some("player"), None) {
-            v.extend_from_slice(&[
-                enemy.x as f32,
-                enemy.y as f32,
-                enemy.z as f32,
-                enemy.yaw,
-                enemy.pitch,
-                enemy.velocity_x as f32,
-                enemy.velocity_y as f32,
-                enemy.velocity_z as f32,
-                enemy.health,
-                // relative position
-                (enemy.x - self.x) as f32,
-                (enemy.y - self.y) as f32,
-                (enemy.z - self.z) as f32,
-            ]);
-        } else {
-            // pad with zeros if no enemy
-            v.extend_from_slice(&[0.0; 12]);
+    /// arrows, pearls, potions, tridents, ... within `max_distance`, nearest
+    /// first
+    #[pyo3(signature = (max_distance=32.0))]
+    fn nearby_projectiles(&self, max_distance: f64) -> Vec<PyEntity> {
+        let dist = |e: &PyEntity| {
+            let dx = self.x - e.x;
+            let dy = self.y - e.y;
+            let dz = self.z - e.z;
+            (dx * dx + dy * dy + dz * dz).sqrt()
+        };
+        let mut projectiles: Vec<(f64, &PyEntity)> = self
+            .entities
+            .iter()
+            .filter(|e| e.category == Category::Projectile)
+            .map(|e| (dist(e), e))
+            .filter(|(d, _)| *d <= max_distance)
+            .collect();
+        projectiles.sort_by(|a, b| a.0.total_cmp(&b.0));
+        projectiles.into_iter().map(|(_, e)| e.clone()).collect()
+    }
+
+    /// dropped items within `max_distance`, nearest first
+    #[pyo3(signature = (max_distance=32.0))]
+    fn nearby_items(&self, max_distance: f64) -> Vec<PyEntity> {
+        let dist = |e: &PyEntity| {
+            let dx = self.x - e.x;
+            let dy = self.y - e.y;
+            let dz = self.z - e.z;
+            (dx * dx + dy * dy + dz * dz).sqrt()
+        };
+        let mut items: Vec<(f64, &PyEntity)> = self
+            .entities
+            .iter()
+            .filter(|e| e.item.is_some())
+            .map(|e| (dist(e), e))
+            .filter(|(d, _)| *d <= max_distance)
+            .collect();
+        items.sort_by(|a, b| a.0.total_cmp(&b.0));
+        items.into_iter().map(|(_, e)| e.clone()).collect()
+    }
+
+    /// float32 numpy array laid out by `config` (see ObservationConfig),
+    /// or the default 26 features
+    #[pyo3(signature = (config=None))]
+    fn to_vector<'py>(&self, py: Python<'py>, config: Option<PyRef<'_, ObservationConfig>>) -> Bound<'py, PyArray1<f32>> {
+        let vector = match config {
+            Some(config) => config.vector(self),
+            None => ObservationConfig::default().vector(self),
+        };
+        PyArray1::from_vec(py, vector)
+    }
+
+    /// to_vector() as an ObservationTensor, for from_dlpack()
+    #[pyo3(signature = (config=None))]
+    fn to_tensor(&self, config: Option<PyRef<'_, ObservationConfig>>) -> ObservationTensor {
+        let vector = match config {
+            Some(config) => config.vector(self),
+            None => ObservationConfig::default().vector(self),
+        };
+        let len = vector.len();
+        ObservationTensor::new(vector, vec![len])
+    }
+
+    /// name of each to_vector() entry, in order
+    #[staticmethod]
+    #[pyo3(signature = (config=None))]
+    fn feature_names(config: Option<PyRef<'_, ObservationConfig>>) -> Vec<&'static str> {
+        match config {
+            Some(config) => config.feature_names(),
+            None => ObservationConfig::default().feature_names(),
         }
+    }
+}
 
-        v
+impl PyGameState {
+    pub fn nearest_player(&self) -> Option<PyEntity> {
+        self.nearest_entity(Some("player"), None)
     }
 }
 
@@ -242,9 +824,35 @@ impl Default for PyGameState {
             is_sprinting: false,
             is_sneaking: false,
             is_dead: false,
+            is_using_item: false,
+            recently_hurt: false,
+            is_in_water: false,
+            is_in_lava: false,
+            is_on_fire: false,
+            is_in_web: false,
+            is_suffocating: false,
             attack_cooldown: 1.0,
             selected_slot: 0,
+            hotbar: vec![None; 9],
+            armor: vec![None; 4],
+            offhand: None,
+            held_item_id: None,
+            held_item_count: 0,
+            offhand_id: None,
+            offhand_count: 0,
+            effects: vec![],
+            xp_level: 0,
+            xp_progress: 0.0,
+            xp_total: 0,
+            time_of_day: 0,
+            is_raining: false,
+            is_thundering: false,
+            dimension: None,
+            biome: None,
+            block_light: 0,
+            sky_light: 0,
             entities: vec![],
+            blocks: None,
             tick: 0,
         }
     }
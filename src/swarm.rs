@@ -0,0 +1,344 @@
+//! Many bots in one azalea Swarm
+//!
+//! unlike connect_swarm (separate clients), the bots share one ECS world, so
+//! chunks and entities are only stored once
+
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use parking_lot::Mutex;
+
+use azalea::prelude::*;
+use azalea::swarm::{DefaultSwarmPlugins, Swarm, SwarmBuilder, SwarmEvent};
+
+use crate::bot::{bot_plugins, default_plugins, handle_event, BotShared, BotState, ConnectOptions, PyBot};
+use crate::chat::ChatMessage;
+use crate::events::MAX_QUEUED_EVENTS;
+use crate::logging::CONNECTION;
+use crate::permissions::Permissions;
+use crate::reconnect::ReconnectPlugin;
+use crate::resource_pack::ResourcePackPlugin;
+use crate::shutdown::spawn_connection;
+
+/// something that happened to the swarm as a whole
+/// ("init", "joined", "login", "disconnect" or "chat")
+#[pyclass(name = "SwarmEvent")]
+#[derive(Clone, Debug)]
+pub struct PySwarmEvent {
+    #[pyo3(get)]
+    pub kind: String,
+    /// bot that joined or disconnected
+    #[pyo3(get)]
+    pub username: Option<String>,
+    /// for "chat", received once even if every bot saw it
+    #[pyo3(get)]
+    pub message: Option<ChatMessage>,
+}
+
+#[pymethods]
+impl PySwarmEvent {
+    fn __repr__(&self) -> String {
+        format!("SwarmEvent(kind={}, username={:?})", self.kind, self.username)
+    }
+}
+
+/// state shared between a PySwarm and the swarm thread
+#[derive(Default)]
+pub struct SwarmShared {
+    /// every bot has joined
+    pub logged_in: AtomicBool,
+    /// bots currently in game
+    pub joined: AtomicUsize,
+    pub total: usize,
+    pub events: Mutex<VecDeque<PySwarmEvent>>,
+}
+
+impl SwarmShared {
+    pub fn bot_joined(&self, username: String) {
+        let joined = self.joined.fetch_add(1, Ordering::SeqCst) + 1;
+        tracing::info!(target: CONNECTION, "joined ({}/{})", joined, self.total);
+        self.push_event(PySwarmEvent {
+            kind: "joined".to_string(),
+            username: Some(username),
+            message: None,
+        });
+    }
+
+    pub fn bot_left(&self) {
+        self.joined.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub fn push_event(&self, event: PySwarmEvent) {
+        let mut events = self.events.lock();
+        if events.len() >= MAX_QUEUED_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
+
+#[derive(Clone, Default, Resource)]
+pub struct SwarmState {
+    pub shared: Option<Arc<SwarmShared>>,
+}
+
+async fn handle_swarm_event(_swarm: Swarm, event: SwarmEvent, state: SwarmState) -> anyhow::Result<()> {
+    let Some(shared) = state.shared else {
+        return Ok(());
+    };
+    let event = match event {
+        SwarmEvent::Init => "init",
+        SwarmEvent::Login => {
+            shared.logged_in.store(true, Ordering::SeqCst);
+            "login"
+        }
+        SwarmEvent::Disconnect(account, _) => {
+            shared.push_event(PySwarmEvent {
+                kind: "disconnect".to_string(),
+                username: Some(account.username.clone()),
+                message: None,
+            });
+            return Ok(());
+        }
+        SwarmEvent::Chat(m) => {
+            shared.push_event(PySwarmEvent {
+                kind: "chat".to_string(),
+                username: None,
+                message: Some(ChatMessage::from_packet(&m, 0)),
+            });
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    shared.push_event(PySwarmEvent {
+        kind: event.to_string(),
+        username: None,
+        message: None,
+    });
+    Ok(())
+}
+
+/// calls a method on every bot, e.g. `swarm.all.goto(0, 64, 0)`
+#[pyclass]
+pub struct SwarmBroadcast {
+    bots: Vec<Py<PyBot>>,
+}
+
+#[pymethods]
+impl SwarmBroadcast {
+    fn __getattr__(&self, py: Python<'_>, name: String) -> BroadcastMethod {
+        BroadcastMethod {
+            bots: self.bots.iter().map(|b| b.clone_ref(py)).collect(),
+            name,
+        }
+    }
+}
+
+#[pyclass]
+pub struct BroadcastMethod {
+    bots: Vec<Py<PyBot>>,
+    name: String,
+}
+
+#[pymethods]
+impl BroadcastMethod {
+    /// returns each bot's result, in the swarm's order
+    #[pyo3(signature = (*args, **kwargs))]
+    fn __call__(
+        &self,
+        py: Python<'_>,
+        args: &Bound<'_, PyTuple>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Vec<PyObject>> {
+        self.bots
+            .iter()
+            .map(|bot| {
+                bot.bind(py)
+                    .call_method(self.name.as_str(), args.clone(), kwargs)
+                    .map(|r| r.unbind())
+            })
+            .collect()
+    }
+}
+
+#[pyclass(name = "Swarm")]
+pub struct PySwarm {
+    shared: Arc<SwarmShared>,
+    bots: Vec<Py<PyBot>>,
+    usernames: Vec<String>,
+}
+
+#[pymethods]
+impl PySwarm {
+    /// per-bot handles, in the order the usernames were given
+    #[getter]
+    fn bots(&self, py: Python<'_>) -> Vec<Py<PyBot>> {
+        self.bots.iter().map(|b| b.clone_ref(py)).collect()
+    }
+
+    /// broadcast proxy: `swarm.all.chat("hi")` runs `bot.chat("hi")` on every bot
+    #[getter]
+    fn all(&self, py: Python<'_>) -> SwarmBroadcast {
+        SwarmBroadcast {
+            bots: self.bots(py),
+        }
+    }
+
+    /// the bot with this username
+    fn bot(&self, py: Python<'_>, username: &str) -> Option<Py<PyBot>> {
+        let index = self.usernames.iter().position(|u| u == username)?;
+        Some(self.bots[index].clone_ref(py))
+    }
+
+    /// (bots in game, total bots)
+    #[getter]
+    fn progress(&self) -> (usize, usize) {
+        (self.shared.joined.load(Ordering::SeqCst), self.shared.total)
+    }
+
+    /// true once every bot has joined
+    #[getter]
+    fn logged_in(&self) -> bool {
+        self.shared.logged_in.load(Ordering::SeqCst)
+    }
+
+    /// drain the swarm-level events queued since the last call
+    fn poll_events(&self) -> Vec<PySwarmEvent> {
+        self.shared.events.lock().drain(..).collect()
+    }
+
+    /// disconnect every bot
+    fn disconnect(&self, py: Python<'_>) -> PyResult<()> {
+        for bot in &self.bots {
+            bot.bind(py).call_method0("disconnect")?;
+        }
+        Ok(())
+    }
+
+    /// give every bot these permissions, and lock them if `lock`
+    #[pyo3(signature = (permissions, lock=false))]
+    fn set_permissions(&self, py: Python<'_>, permissions: Permissions, lock: bool) -> PyResult<()> {
+        for bot in &self.bots {
+            let bot = bot.bind(py);
+            bot.setattr("permissions", permissions.clone())?;
+            if lock {
+                bot.call_method0("lock_permissions")?;
+            }
+        }
+        Ok(())
+    }
+
+    #[getter]
+    fn usernames(&self) -> Vec<String> {
+        self.usernames.clone()
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    /// disconnects every bot, even if the block raised
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) -> PyResult<bool> {
+        self.disconnect(py)?;
+        Ok(false)
+    }
+
+    fn __len__(&self) -> usize {
+        self.bots.len()
+    }
+
+    fn __getitem__(&self, py: Python<'_>, index: usize) -> PyResult<Py<PyBot>> {
+        self.bots
+            .get(index)
+            .map(|b| b.clone_ref(py))
+            .ok_or_else(|| pyo3::exceptions::PyIndexError::new_err("swarm index out of range"))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Swarm(bots={}, logged_in={})", self.bots.len(), self.logged_in())
+    }
+}
+
+impl PySwarm {
+    pub fn connect(
+        py: Python<'_>,
+        host: &str,
+        port: u16,
+        accounts: Vec<Account>,
+        opts: ConnectOptions,
+        join_delay: Option<Duration>,
+    ) -> PyResult<Self> {
+        let shared = Arc::new(SwarmShared {
+            total: accounts.len(),
+            ..Default::default()
+        });
+        let bot_shared: Vec<Arc<BotShared>> = accounts.iter().map(|a| BotShared::new(&opts, &a.username)).collect();
+        let address = format!("{}:{}", host, port);
+
+        let swarm_shared = shared.clone();
+        let states = bot_shared.clone();
+        let usernames: Vec<String> = accounts.iter().map(|a| a.username.clone()).collect();
+        let chat_signing = opts.chat_signing;
+        let has_policy = opts.reconnect.is_some();
+
+        // one connection thread for the whole swarm
+        spawn_connection(bot_shared.clone(), move || async move {
+            tracing::info!(target: CONNECTION, %address, "connecting swarm of {}", accounts.len());
+
+            let mut builder = SwarmBuilder::new_without_plugins()
+                .add_plugins(default_plugins(chat_signing))
+                .add_plugins(bot_plugins())
+                .add_plugins(DefaultSwarmPlugins)
+                .add_plugins(ReconnectPlugin)
+                .add_plugins(ResourcePackPlugin)
+                .set_handler(handle_event)
+                .set_swarm_handler(handle_swarm_event)
+                .set_swarm_state(SwarmState {
+                    shared: Some(swarm_shared.clone()),
+                });
+            for (account, shared) in accounts.into_iter().zip(states) {
+                let state = BotState {
+                    shared: Some(shared),
+                    swarm: Some(swarm_shared.clone()),
+                };
+                builder = builder.add_account_with_state(account, state);
+            }
+            if let Some(delay) = join_delay {
+                // bots then join one at a time, each waiting for the previous one
+                builder = builder.join_delay(delay);
+            }
+            if has_policy {
+                builder = builder.reconnect_after(None);
+            }
+
+            match builder.start(address.as_str()).await {
+                AppExit::Success => tracing::info!(target: CONNECTION, "swarm stopped"),
+                AppExit::Error(e) => tracing::error!(target: CONNECTION, "swarm stopped with an error: {:?}", e),
+            }
+        });
+
+        py.allow_threads(|| {
+            for _ in 0..100 {
+                if bot_shared.iter().all(|s| s.connected.load(Ordering::SeqCst)) {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        });
+
+        let bots = bot_shared
+            .into_iter()
+            .zip(usernames.iter())
+            .map(|(shared, username)| Py::new(py, PyBot::from_shared(shared, username.clone())))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self {
+            shared,
+            bots,
+            usernames,
+        })
+    }
+}
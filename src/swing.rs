@@ -0,0 +1,42 @@
+//! Arm swings of other entities, the only sign we get that someone attacked
+//!
+//! the animate packet is an event azalea doesn't keep, so count them per entity
+
+use std::collections::HashMap;
+
+use azalea_protocol::packets::game::c_animate::AnimationAction;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+/// keyed by the protocol entity id
+#[derive(Debug, Default)]
+pub struct Swings {
+    counts: HashMap<i32, u32>,
+}
+
+impl Swings {
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::Animate(p) => {
+                if matches!(p.action, AnimationAction::SwingMainHand | AnimationAction::SwingOffHand) {
+                    *self.counts.entry(p.id.0).or_default() += 1;
+                }
+            }
+            ClientboundGamePacket::RemoveEntities(p) => {
+                for id in &p.entity_ids {
+                    self.counts.remove(&id.0);
+                }
+            }
+            ClientboundGamePacket::Respawn(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    /// swings since the entity came into view
+    pub fn count(&self, id: i32) -> u32 {
+        self.counts.get(&id).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,534 @@
+//! Team fights on a FastArena's floor
+//!
+//! any number of fighters on two or more teams, with a FastArena's size,
+//! constants, kits, terrain and mode (the n-th team to appear gets kit n % 2).
+//! melee, eating, shields, effects and hazards work like in a duel, and a
+//! sword's sweep reaches whoever else stands by the defender. bows, pearls,
+//! potions and rods stay duel-only, team fighters spawn without them. a
+//! fighter at 0 health or in the void is out, and the last team with anyone
+//! left wins
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::arena::{ArenaMode, FastArena, Fighter, FighterAction, Kit, OBS_SIZE};
+use crate::dlpack::ObservationTensor;
+use crate::loadout::Loadout;
+
+/// features get_obs() adds after FastArena's, about the teams
+pub const TEAM_OBS_SIZE: usize = 8;
+/// sideways between teammates at the spawn
+const SPAWN_SPACING: f64 = 1.5;
+/// Player.attack's sweep without Sweeping Edge: everyone within a block of the
+/// defender takes 1 and a light knockback
+const SWEEP_REACH: f64 = 1.0;
+const SWEEP_DAMAGE: f64 = 1.0;
+const SWEEP_KNOCKBACK: f64 = 0.4;
+/// for getting knocked out, on top of the world's RewardConfig
+const OUT_REWARD: f64 = -5.0;
+
+/// mutable references to two different fighters
+fn pair(fighters: &mut [Fighter], i: usize, j: usize) -> (&mut Fighter, &mut Fighter) {
+    if i < j {
+        let (low, high) = fighters.split_at_mut(j);
+        (&mut low[i], &mut high[0])
+    } else {
+        let (low, high) = fighters.split_at_mut(i);
+        (&mut high[0], &mut low[j])
+    }
+}
+
+fn distance(a: &Fighter, b: &Fighter) -> f64 {
+    let (dx, dy, dz) = (b.x - a.x, b.y - a.y, b.z - a.z);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// 2v2 or any NvN, see the module docs
+#[pyclass]
+#[derive(Clone)]
+pub struct TeamArena {
+    /// the floor, terrain, constants, kits and max_ticks. its own two
+    /// fighters sit out
+    world: FastArena,
+    /// each fighter's team, any labels
+    #[pyo3(get)]
+    pub teams: Vec<u32>,
+    pub fighters: Vec<Fighter>,
+    kits: Vec<Kit>,
+    /// knocked out or fallen into the void, per fighter
+    #[pyo3(get)]
+    pub out: Vec<bool>,
+    /// whether a swing (or a sweep) can hurt a teammate
+    #[pyo3(get, set)]
+    pub friendly_fire: bool,
+    /// 0 to reward each fighter for itself, 1 for its team's mean reward,
+    /// and in between a blend
+    #[pyo3(get)]
+    pub team_spirit: f64,
+    #[pyo3(get)]
+    pub tick: u32,
+    #[pyo3(get)]
+    pub done: bool,
+    /// the team left standing, None before the end and for a draw
+    #[pyo3(get)]
+    pub winner: Option<u32>,
+    /// "kill", "void" (the losers' last one out fell), "timeout" or "draw"
+    /// (no winner)
+    #[pyo3(get)]
+    pub winner_reason: Option<&'static str>,
+}
+
+impl TeamArena {
+    /// teams in the order they first appear
+    fn team_order(&self) -> Vec<u32> {
+        let mut order: Vec<u32> = vec![];
+        for &team in &self.teams {
+            if !order.contains(&team) {
+                order.push(team);
+            }
+        }
+        order
+    }
+
+    fn can_hit(&self, attacker: usize, target: usize) -> bool {
+        target != attacker && !self.out[target] && (self.friendly_fire || self.teams[target] != self.teams[attacker])
+    }
+
+    /// the closest fighter still in of another team, or of any if only the own team is left
+    fn nearest_enemy(&self, idx: usize) -> usize {
+        let others = (0..self.fighters.len()).filter(|&j| j != idx);
+        let enemies: Vec<usize> = others.clone().filter(|&j| self.teams[j] != self.teams[idx]).collect();
+        let living: Vec<usize> = enemies.iter().copied().filter(|&j| !self.out[j]).collect();
+        let pool = if !living.is_empty() { living } else if !enemies.is_empty() { enemies } else { others.collect() };
+        let me = &self.fighters[idx];
+        pool.into_iter()
+            .min_by(|&a, &b| distance(me, &self.fighters[a]).total_cmp(&distance(me, &self.fighters[b])))
+            .unwrap_or(idx)
+    }
+
+    /// a swing by fighter `idx` at the nearest fighter it can hit. returns
+    /// who it hit, the damage it did to its own team goes in `friendly`
+    fn attack(&mut self, idx: usize, friendly: &mut f64) -> Option<usize> {
+        let mut targets: Vec<usize> = (0..self.fighters.len()).filter(|&j| self.can_hit(idx, j)).collect();
+        let me = &self.fighters[idx];
+        targets.sort_by(|&a, &b| distance(me, &self.fighters[a]).total_cmp(&distance(me, &self.fighters[b])));
+        let world = &self.world;
+        for target in targets {
+            let kits = (&self.kits[idx], &self.kits[target]);
+            let (sweeps, dealt) = (self.fighters[idx].sweeps, self.fighters[idx].damage_dealt);
+            let (attacker, defender) = pair(&mut self.fighters, idx, target);
+            if FastArena::melee(&world.constants, &world.terrain, world.mode, kits, attacker, defender) {
+                if self.teams[target] == self.teams[idx] {
+                    *friendly += self.fighters[idx].damage_dealt - dealt;
+                }
+                if self.fighters[idx].sweeps > sweeps {
+                    self.sweep(idx, target, friendly);
+                }
+                return Some(target);
+            }
+            if self.fighters[idx].attack_cooldown > 0 {
+                // the swing went into a shield
+                return None;
+            }
+        }
+        None
+    }
+
+    /// the rest of a sweep from `idx`'s hit on `target`, never in sumo where
+    /// melee is empty handed
+    fn sweep(&mut self, idx: usize, target: usize, friendly: &mut f64) {
+        let c = &self.world.constants;
+        let (target_x, target_z) = (self.fighters[target].x, self.fighters[target].z);
+        for j in 0..self.fighters.len() {
+            if j == target || !self.can_hit(idx, j) {
+                continue;
+            }
+            let (attacker, other) = pair(&mut self.fighters, idx, j);
+            let by_target = (other.x - target_x).hypot(other.z - target_z) <= SWEEP_REACH;
+            if !by_target || distance(attacker, other) > c.attack_range || other.blocks_from(attacker.x, attacker.z) {
+                continue;
+            }
+            let damage = self.kits[j].after_armor(SWEEP_DAMAGE, c.combat_version);
+            other.health -= damage;
+            other.damage_taken += damage;
+            attacker.damage_dealt += damage;
+            if self.teams[j] == self.teams[idx] {
+                *friendly += damage;
+            }
+            let yaw = attacker.yaw.to_radians();
+            other.knock_back(SWEEP_KNOCKBACK, c.knockback_vertical, (yaw.sin(), -yaw.cos()), other.on_ground);
+        }
+    }
+
+    /// fighter `idx`'s observation: FastArena's against the nearest enemy,
+    /// then TEAM_OBS_SIZE about the teams
+    fn obs(&self, idx: usize) -> Vec<f64> {
+        let me = &self.fighters[idx];
+        let enemy = &self.fighters[self.nearest_enemy(idx)];
+        let mut obs = self.world.get_obs(me, enemy, 0);
+        let team = self.teams[idx];
+        let count = |mine: bool, living: bool| {
+            (0..self.fighters.len())
+                .filter(|&j| j != idx && (self.teams[j] == team) == mine && (!living || !self.out[j]))
+                .count()
+        };
+        let fraction = |mine: bool| {
+            let total = count(mine, false);
+            if total == 0 { 0.0 } else { count(mine, true) as f64 / total as f64 }
+        };
+        let by_distance = |mine: bool| {
+            let mut others: Vec<&Fighter> = (0..self.fighters.len())
+                .filter(|&j| j != idx && !self.out[j] && (self.teams[j] == team) == mine)
+                .map(|j| &self.fighters[j])
+                .collect();
+            others.sort_by(|a, b| distance(me, a).total_cmp(&distance(me, b)));
+            others
+        };
+        let mate = by_distance(true).first().map_or((0.0, 0.0, 0.0, 0.0), |m| {
+            (m.x - me.x, m.y - me.y, m.z - me.z, m.health / self.world.constants.max_health)
+        });
+        let second = by_distance(false).get(1).map_or((0.0, 0.0), |e| (e.x - me.x, e.z - me.z));
+        obs.extend([
+            // Teams (8)
+            fraction(true),  // teammates still in
+            fraction(false), // enemies still in
+            mate.0 / 32.0,   // the closest teammate, 0 without one
+            mate.1 / 16.0,
+            mate.2 / 32.0,
+            mate.3,
+            second.0 / 32.0, // the second closest enemy
+            second.1 / 32.0,
+        ]);
+        obs
+    }
+
+    /// end the episode if at most one team has anyone left, or on the last
+    /// tick. returns the winners' team and the reward each fighter gets for it
+    fn check_end(&mut self, void: &[bool]) -> Vec<f64> {
+        let n = self.fighters.len();
+        let mut standing: Vec<u32> = (0..n).filter(|&j| !self.out[j]).map(|j| self.teams[j]).collect();
+        standing.sort();
+        standing.dedup();
+        let mut rewards = vec![0.0; n];
+        if standing.len() <= 1 {
+            self.done = true;
+            self.winner = standing.first().copied();
+            self.winner_reason = Some(match self.winner {
+                // the last ones standing went out together
+                None => "draw",
+                Some(team) if (0..n).any(|j| void[j] && self.teams[j] != team) => "void",
+                Some(_) => "kill",
+            });
+            let w = &self.world.rewards;
+            let speed_bonus = w.speed_bonus * (1.0 - self.tick as f64 / self.world.max_ticks as f64);
+            for (j, reward) in rewards.iter_mut().enumerate() {
+                *reward = match self.winner {
+                    None => w.draw,
+                    Some(team) if team == self.teams[j] => w.win + speed_bonus,
+                    Some(_) => w.loss,
+                };
+            }
+        } else if self.tick >= self.world.max_ticks {
+            self.done = true;
+            self.winner_reason = Some("timeout");
+            // the team with the most health left, a tie is a draw
+            let health = |team: u32| -> f64 {
+                (0..n).filter(|&j| self.teams[j] == team && !self.out[j]).map(|j| self.fighters[j].health).sum()
+            };
+            let mut best: Vec<(u32, f64)> = standing.iter().map(|&team| (team, health(team))).collect();
+            best.sort_by(|a, b| b.1.total_cmp(&a.1));
+            self.winner = (best[0].1 > best[1].1).then_some(best[0].0);
+            if self.winner.is_none() {
+                self.winner_reason = Some("draw");
+            }
+            for (j, reward) in rewards.iter_mut().enumerate() {
+                *reward = match self.winner {
+                    None => self.world.rewards.draw,
+                    Some(team) if team == self.teams[j] => self.world.rewards.timeout_win,
+                    Some(_) => self.world.rewards.timeout_loss,
+                };
+            }
+        }
+        rewards
+    }
+}
+
+#[pymethods]
+impl TeamArena {
+    /// `teams` is each fighter's team, like [0, 0, 1, 1] for a 2v2. `arena`
+    /// is a FastArena to take the size, constants, kits, terrain and mode from
+    /// (FastArena() if None)
+    #[new]
+    #[pyo3(signature = (teams, arena=None, friendly_fire=false, team_spirit=0.0))]
+    fn new(teams: Vec<u32>, arena: Option<FastArena>, friendly_fire: bool, team_spirit: f64) -> PyResult<Self> {
+        let mut distinct = teams.clone();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() < 2 {
+            return Err(PyValueError::new_err("teams needs fighters on at least two teams"));
+        }
+        check_team_spirit(team_spirit)?;
+        let n = teams.len();
+        let mut arena = Self {
+            world: arena.unwrap_or_default(),
+            teams,
+            fighters: vec![Fighter::default(); n],
+            kits: vec![],
+            out: vec![false; n],
+            friendly_fire,
+            team_spirit,
+            tick: 0,
+            done: false,
+            winner: None,
+            winner_reason: None,
+        };
+        arena.reset(None, None)?;
+        Ok(arena)
+    }
+
+    #[setter]
+    fn set_team_spirit(&mut self, team_spirit: f64) -> PyResult<()> {
+        check_team_spirit(team_spirit)?;
+        self.team_spirit = team_spirit;
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.fighters.len()
+    }
+
+    /// teams spawn spread around the middle, spawn_distance (the arena's
+    /// by default) from the opposite one in a 1v1 layout, teammates side by
+    /// side facing the middle. `loadouts` is one per team, in the order the
+    /// teams first appear in `teams`
+    #[pyo3(signature = (spawn_distance=None, loadouts=None))]
+    fn reset(&mut self, spawn_distance: Option<f64>, loadouts: Option<Vec<Loadout>>) -> PyResult<()> {
+        let order = self.team_order();
+        if let Some(loadouts) = &loadouts {
+            if loadouts.len() != order.len() {
+                return Err(PyValueError::new_err(format!("{} teams need {} loadouts, got {}", order.len(), order.len(), loadouts.len())));
+            }
+        }
+        let radius = spawn_distance.unwrap_or(self.world.spawn_distance) / 2.0;
+        let version = self.world.constants.combat_version;
+        let mut placed = vec![0usize; order.len()];
+        self.kits.clear();
+        for (idx, &team) in self.teams.iter().enumerate() {
+            let k = order.iter().position(|&t| t == team).unwrap_or(0);
+            let kit = match &loadouts {
+                Some(loadouts) => loadouts[k].kit(version),
+                None => self.world.kits[k % 2].clone(),
+            };
+            let size = self.teams.iter().filter(|&&t| t == team).count();
+            let angle = std::f64::consts::TAU * k as f64 / order.len() as f64;
+            let offset = (placed[k] as f64 - (size as f64 - 1.0) / 2.0) * SPAWN_SPACING;
+            placed[k] += 1;
+            // MC yaw: 0=+Z, 90=-X, facing the middle from (-cos, -sin)
+            self.fighters[idx] = Fighter {
+                x: -radius * angle.cos() + offset * angle.sin(),
+                z: -radius * angle.sin() - offset * angle.cos(),
+                yaw: (-angle.cos()).atan2(angle.sin()).to_degrees(),
+                arrows: 0,
+                pearls: 0,
+                potions: 0,
+                rod: false,
+                ..self.world.spawned(&kit)
+            };
+            self.kits.push(kit);
+        }
+        self.out.fill(false);
+        self.tick = 0;
+        self.done = false;
+        self.winner = None;
+        self.winner_reason = None;
+        Ok(())
+    }
+
+    /// one action per fighter, those that are out are ignored. returns each
+    /// fighter's reward and done
+    fn step(&mut self, actions: Vec<FighterAction>) -> PyResult<(Vec<f64>, bool)> {
+        let n = self.fighters.len();
+        if actions.len() != n {
+            return Err(PyValueError::new_err(format!("{} fighters need {} actions, got {}", n, n, actions.len())));
+        }
+        if self.done {
+            return Ok((vec![0.0; n], true));
+        }
+        let health_before: Vec<f64> = self.fighters.iter().map(|f| f.health).collect();
+        let dealt_before: Vec<f64> = self.fighters.iter().map(|f| f.damage_dealt).collect();
+
+        for (idx, action) in actions.iter().enumerate() {
+            if self.out[idx] {
+                continue;
+            }
+            let mut fighter = self.fighters[idx].clone();
+            fighter.swept = false;
+            fighter.attack_cooldown = fighter.attack_cooldown.saturating_sub(1);
+            fighter.jump_cooldown = fighter.jump_cooldown.saturating_sub(1);
+            fighter.hurt_ticks = fighter.hurt_ticks.saturating_sub(1);
+            self.world.tick_effects(&mut fighter);
+            self.world.apply_movement(&mut fighter, action);
+            self.world.tick_hazards(&self.kits[idx], &mut fighter);
+            FastArena::process_shield(&mut fighter, action.block);
+            self.fighters[idx] = fighter;
+        }
+
+        // Attacks, in fighter order
+        let mut hits: Vec<Option<usize>> = vec![None; n];
+        let mut friendly = vec![0.0; n];
+        for idx in 0..n {
+            if !self.out[idx] && actions[idx].attack {
+                hits[idx] = self.attack(idx, &mut friendly[idx]);
+            }
+        }
+
+        for (idx, action) in actions.iter().enumerate() {
+            if !self.out[idx] {
+                let mut fighter = self.fighters[idx].clone();
+                self.world.process_eating(&mut fighter, action.eat);
+                self.fighters[idx] = fighter;
+            }
+        }
+        self.tick += 1;
+
+        if self.world.mode == ArenaMode::Sumo {
+            for (fighter, health) in self.fighters.iter_mut().zip(&health_before) {
+                fighter.health = *health;
+            }
+        }
+
+        // Rewards, what a fighter did to its own team counts against it
+        // with the world's weights
+        let w = &self.world.rewards;
+        let (hit_bonus, whiff) = if self.world.mode == ArenaMode::Sumo { (w.sumo_hit, w.sumo_whiff) } else { (w.hit, w.whiff) };
+        let mut rewards = vec![w.time; n];
+        for idx in 0..n {
+            if self.out[idx] {
+                rewards[idx] = 0.0;
+                continue;
+            }
+            let enemies = self.fighters[idx].damage_dealt - dealt_before[idx] - friendly[idx];
+            let taken = (health_before[idx] - self.fighters[idx].health).max(0.0);
+            rewards[idx] += (enemies - friendly[idx]) * w.damage_dealt + taken * w.damage_taken;
+            rewards[idx] += match hits[idx] {
+                Some(j) if self.teams[j] == self.teams[idx] => -hit_bonus,
+                Some(_) => hit_bonus,
+                None if actions[idx].attack => whiff,
+                None => 0.0,
+            };
+        }
+
+        // Who's out
+        let mut void = vec![false; n];
+        for idx in 0..n {
+            if self.out[idx] {
+                continue;
+            }
+            void[idx] = self.world.in_void(&self.fighters[idx]);
+            if self.fighters[idx].health <= 0.0 || void[idx] {
+                self.out[idx] = true;
+                rewards[idx] += OUT_REWARD;
+            }
+        }
+        for (reward, end) in rewards.iter_mut().zip(self.check_end(&void)) {
+            *reward += end;
+        }
+
+        // Team spirit
+        if self.team_spirit > 0.0 {
+            let own = rewards.clone();
+            for idx in 0..n {
+                let team: Vec<f64> = (0..n).filter(|&j| self.teams[j] == self.teams[idx]).map(|j| own[j]).collect();
+                let mean = team.iter().sum::<f64>() / team.len() as f64;
+                rewards[idx] = (1.0 - self.team_spirit) * own[idx] + self.team_spirit * mean;
+            }
+        }
+        Ok((rewards, self.done))
+    }
+
+    fn get_fighter(&self, idx: usize) -> PyResult<Fighter> {
+        self.fighters
+            .get(idx)
+            .cloned()
+            .ok_or_else(|| PyValueError::new_err(format!("no fighter {}, there are {}", idx, self.fighters.len())))
+    }
+
+    #[getter]
+    fn fighters(&self) -> Vec<Fighter> {
+        self.fighters.clone()
+    }
+
+    /// fighter `idx`'s observation, OBS_SIZE + TEAM_OBS_SIZE floats: a
+    /// FastArena observation against the nearest enemy, then the teams
+    fn get_obs(&self, idx: usize) -> PyResult<Vec<f64>> {
+        if idx >= self.fighters.len() {
+            return Err(PyValueError::new_err(format!("no fighter {}, there are {}", idx, self.fighters.len())));
+        }
+        Ok(self.obs(idx))
+    }
+
+    /// every fighter's observation as one (len, obs_size) float32 ObservationTensor
+    fn obs_tensor(&self) -> ObservationTensor {
+        let rows = (0..self.fighters.len()).map(|idx| self.obs(idx).into_iter().map(|v| v as f32).collect());
+        ObservationTensor::batch(rows, Self::obs_size())
+    }
+
+    /// length of get_obs()
+    #[staticmethod]
+    fn obs_size() -> usize {
+        OBS_SIZE + TEAM_OBS_SIZE
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "TeamArena(teams={:?}, friendly_fire={}, team_spirit={}, tick={}, done={})",
+            self.teams,
+            if self.friendly_fire { "True" } else { "False" },
+            self.team_spirit,
+            self.tick,
+            if self.done { "True" } else { "False" }
+        )
+    }
+}
+
+fn check_team_spirit(team_spirit: f64) -> PyResult<()> {
+    if !(0.0..=1.0).contains(&team_spirit) {
+        return Err(PyValueError::new_err(format!("team_spirit must be between 0 and 1, got {}", team_spirit)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn void_is_the_losers_fall() {
+        let mut arena = TeamArena::new(vec![0, 0, 1, 1], None, false, 0.0).unwrap();
+        // a winner fell on the tick the last loser was knocked out
+        arena.out = vec![false, true, true, true];
+        arena.check_end(&[false, true, false, false]);
+        assert_eq!((arena.winner, arena.winner_reason), (Some(0), Some("kill")));
+
+        let mut arena = TeamArena::new(vec![0, 0, 1, 1], None, false, 0.0).unwrap();
+        arena.out = vec![false, true, true, true];
+        arena.check_end(&[false, false, false, true]);
+        assert_eq!((arena.winner, arena.winner_reason), (Some(0), Some("void")));
+    }
+
+    #[test]
+    fn a_friendly_hit_keeps_its_sweep_on_enemies() {
+        let mut arena = TeamArena::new(vec![0, 0, 1], None, true, 0.0).unwrap();
+        let stand = |x: f64, z: f64| Fighter { x, z, on_ground: true, health: 20.0, ..Fighter::default() };
+        // the teammate is closest, the enemy stands next to it
+        arena.fighters = vec![stand(0.0, 0.0), stand(0.0, 2.0), stand(0.5, 2.5)];
+        let swing = FighterAction { attack: true, ..FighterAction::default() };
+        let (rewards, _) = arena.step(vec![swing, FighterAction::default(), FighterAction::default()]).unwrap();
+        let (friendly, enemy) = (arena.fighters[1].damage_taken, arena.fighters[2].damage_taken);
+        assert!(friendly > 0.0 && enemy > 0.0);
+        assert_eq!(arena.fighters[0].damage_dealt, friendly + enemy);
+        let w = &arena.world.rewards;
+        let expected = w.time + (enemy - friendly) * w.damage_dealt - w.hit;
+        assert!((rewards[0] - expected).abs() < 1e-12, "{} != {}", rewards[0], expected);
+    }
+}
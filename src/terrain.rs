@@ -0,0 +1,265 @@
+//! Blocks on FastArena's floor
+//!
+//! a grid of solid blocks over the arena, one per integer x and z and per
+//! layer up from the floor, so block (x, layer, z) fills x..x+1 and z..z+1 from
+//! floor_y + layer up. boxes move through it one axis at a time like vanilla's
+//! Entity.move, and segments (projectiles, a hit's reach) stop at the first
+//! block they enter. water and lava fill a block without stopping anything,
+//! FastArena reads them for swimming and burning. an arena without blocks
+//! doesn't allocate the grid
+
+/// layers of blocks above the floor
+pub const TERRAIN_HEIGHT: usize = 16;
+
+/// how close counts as touching a face
+const EPSILON: f64 = 1e-7;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Block {
+    #[default]
+    Air,
+    Solid,
+    Water,
+    Lava,
+}
+
+impl Block {
+    /// "water" or "lava"
+    pub fn fluid(name: &str) -> Option<Self> {
+        match name.strip_prefix("minecraft:").unwrap_or(name) {
+            "water" => Some(Self::Water),
+            "lava" => Some(Self::Lava),
+            _ => None,
+        }
+    }
+
+    pub fn fluid_name(self) -> Option<&'static str> {
+        match self {
+            Self::Water => Some("water"),
+            Self::Lava => Some("lava"),
+            _ => None,
+        }
+    }
+}
+
+/// the fluids a box is in, or near
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fluids {
+    pub water: bool,
+    pub lava: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Terrain {
+    /// world x and z of the grid's min corner
+    min_x: i32,
+    min_z: i32,
+    width: usize,
+    depth: usize,
+    /// y of layer 0's bottom, the arena's floor
+    bottom: f64,
+    /// [x][z][layer], empty until the first block goes in
+    blocks: Vec<Block>,
+    /// whether any water or lava went in since the last clear()
+    has_fluids: bool,
+}
+
+impl Terrain {
+    /// an empty grid covering min..max in x and z, standing on `bottom`
+    pub fn new(min_x: f64, max_x: f64, min_z: f64, max_z: f64, bottom: f64) -> Self {
+        let (x0, z0) = (min_x.floor() as i32, min_z.floor() as i32);
+        Self {
+            min_x: x0,
+            min_z: z0,
+            width: (max_x.ceil() as i32 - x0).max(0) as usize,
+            depth: (max_z.ceil() as i32 - z0).max(0) as usize,
+            bottom,
+            blocks: vec![],
+            has_fluids: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// (min x, min z, blocks in x, blocks in z)
+    pub fn extent(&self) -> (i32, i32, usize, usize) {
+        (self.min_x, self.min_z, self.width, self.depth)
+    }
+
+    fn index(&self, x: i32, layer: i32, z: i32) -> Option<usize> {
+        let (x, z) = (x - self.min_x, z - self.min_z);
+        let inside = (0..self.width as i32).contains(&x)
+            && (0..self.depth as i32).contains(&z)
+            && (0..TERRAIN_HEIGHT as i32).contains(&layer);
+        inside.then(|| (x as usize * self.depth + z as usize) * TERRAIN_HEIGHT + layer as usize)
+    }
+
+    /// None outside the grid
+    pub fn get(&self, x: i32, layer: i32, z: i32) -> Option<Block> {
+        let i = self.index(x, layer, z)?;
+        Some(self.blocks.get(i).copied().unwrap_or_default())
+    }
+
+    fn solid(&self, x: i32, layer: i32, z: i32) -> bool {
+        self.get(x, layer, z) == Some(Block::Solid)
+    }
+
+    /// false (and nothing changes) outside the grid
+    pub fn set(&mut self, x: i32, layer: i32, z: i32, block: Block) -> bool {
+        let Some(i) = self.index(x, layer, z) else {
+            return false;
+        };
+        if self.blocks.is_empty() {
+            if block == Block::Air {
+                return true;
+            }
+            self.blocks = vec![Block::Air; self.width * self.depth * TERRAIN_HEIGHT];
+        }
+        self.blocks[i] = block;
+        self.has_fluids |= block.fluid_name().is_some();
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks = vec![];
+        self.has_fluids = false;
+    }
+
+    /// y of the top of the highest block in the column around (x, z), the floor
+    /// if there's none
+    pub fn column_top(&self, x: f64, z: f64) -> f64 {
+        if self.is_empty() {
+            return self.bottom;
+        }
+        self.bottom + self.height(x.floor() as i32, z.floor() as i32) as f64
+    }
+
+    /// layers up to the top of the highest block in the column
+    pub fn height(&self, x: i32, z: i32) -> u32 {
+        (0..TERRAIN_HEIGHT as i32).rev().find(|&layer| self.solid(x, layer, z)).map_or(0, |layer| layer as u32 + 1)
+    }
+
+    /// how much of `delta` along `axis` (0 x, 1 y, 2 z) the box from `lo` to
+    /// `hi` can move before a block stops it, vanilla's Shapes.collide. blocks
+    /// the box is already in don't stop it, so it can always get out
+    pub fn clip(&self, lo: [f64; 3], hi: [f64; 3], axis: usize, delta: f64) -> f64 {
+        if self.is_empty() || delta == 0.0 {
+            return delta;
+        }
+        let (mut swept_lo, mut swept_hi) = (lo, hi);
+        if delta > 0.0 {
+            swept_hi[axis] += delta;
+        } else {
+            swept_lo[axis] += delta;
+        }
+        let mut delta = delta;
+        for (x, layer, z) in self.cells(swept_lo, swept_hi) {
+            if !self.solid(x, layer, z) {
+                continue;
+            }
+            let y = self.bottom + layer as f64;
+            let (block_lo, block_hi) = ([x as f64, y, z as f64], [x as f64 + 1.0, y + 1.0, z as f64 + 1.0]);
+            if delta > 0.0 && block_lo[axis] >= hi[axis] - EPSILON {
+                delta = delta.min(block_lo[axis] - hi[axis]).max(0.0);
+            } else if delta < 0.0 && block_hi[axis] <= lo[axis] + EPSILON {
+                delta = delta.max(block_hi[axis] - lo[axis]).min(0.0);
+            }
+        }
+        delta
+    }
+
+    /// (x, layer, z) of every block the box overlaps, in or out of the grid
+    fn cells(&self, lo: [f64; 3], hi: [f64; 3]) -> impl Iterator<Item = (i32, i32, i32)> {
+        let range = |axis: usize| {
+            let bottom = if axis == 1 { self.bottom } else { 0.0 };
+            (lo[axis] - bottom + EPSILON).floor() as i32..=(hi[axis] - bottom - EPSILON).floor() as i32
+        };
+        let (xs, layers, zs) = (range(0), range(1), range(2));
+        xs.flat_map(move |x| {
+            let zs = zs.clone();
+            layers.clone().flat_map(move |layer| zs.clone().map(move |z| (x, layer, z)))
+        })
+    }
+
+    /// the fluids the box is in
+    pub fn fluids_in(&self, lo: [f64; 3], hi: [f64; 3]) -> Fluids {
+        let mut fluids = Fluids::default();
+        if !self.has_fluids {
+            return fluids;
+        }
+        for (x, layer, z) in self.cells(lo, hi) {
+            match self.get(x, layer, z) {
+                Some(Block::Water) => fluids.water = true,
+                Some(Block::Lava) => fluids.lava = true,
+                _ => {}
+            }
+        }
+        fluids
+    }
+
+    /// from (x, y, z) to the nearest point of the closest `fluid` block
+    /// within `radius`, None if there's none
+    pub fn nearest(&self, fluid: Block, (x, y, z): (f64, f64, f64), radius: f64) -> Option<(f64, f64, f64)> {
+        if !self.has_fluids {
+            return None;
+        }
+        let (lo, hi) = ([x - radius, y - radius, z - radius], [x + radius, y + radius, z + radius]);
+        self.cells(lo, hi)
+            .filter(|&(bx, layer, bz)| self.get(bx, layer, bz) == Some(fluid))
+            .map(|(bx, layer, bz)| {
+                let by = self.bottom + layer as f64;
+                let towards = |v: f64, min: f64| v.clamp(min, min + 1.0) - v;
+                (towards(x, bx as f64), towards(y, by), towards(z, bz as f64))
+            })
+            .filter(|&(dx, dy, dz)| (dx * dx + dy * dy + dz * dz).sqrt() <= radius)
+            .min_by(|a, b| (a.0 * a.0 + a.1 * a.1 + a.2 * a.2).total_cmp(&(b.0 * b.0 + b.1 * b.1 + b.2 * b.2)))
+    }
+
+    /// a block right under the box
+    pub fn supports(&self, lo: [f64; 3], hi: [f64; 3]) -> bool {
+        self.clip(lo, hi, 1, -0.001) > -0.001
+    }
+
+    /// how far along `from + t * d` (t from 0 to 1) the segment gets before it
+    /// enters a block, None if it doesn't. a grid walk, Amanatides and Woo
+    pub fn raycast(&self, from: (f64, f64, f64), d: (f64, f64, f64)) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let from = [from.0, from.1 - self.bottom, from.2];
+        let d = [d.0, d.1, d.2];
+        let mut cell = from.map(|v| v.floor() as i32);
+        let solid = |cell: [i32; 3]| self.solid(cell[0], cell[1], cell[2]);
+        if solid(cell) {
+            return Some(0.0);
+        }
+        let mut step = [0; 3];
+        let mut next = [f64::INFINITY; 3];
+        let mut across = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            if d[axis] > 0.0 {
+                step[axis] = 1;
+                next[axis] = (cell[axis] as f64 + 1.0 - from[axis]) / d[axis];
+                across[axis] = 1.0 / d[axis];
+            } else if d[axis] < 0.0 {
+                step[axis] = -1;
+                next[axis] = (cell[axis] as f64 - from[axis]) / d[axis];
+                across[axis] = -1.0 / d[axis];
+            }
+        }
+        loop {
+            let axis = (0..3).min_by(|&a, &b| next[a].total_cmp(&next[b]))?;
+            let t = next[axis];
+            if t > 1.0 {
+                return None;
+            }
+            cell[axis] += step[axis];
+            if solid(cell) {
+                return Some(t);
+            }
+            next[axis] += across[axis];
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Nearby players and hostiles, with the numbers target selection needs
+
+use pyo3::prelude::*;
+
+use crate::entity_kind::Category;
+use crate::item::Item;
+use crate::state::{PyEntity, PyGameState};
+
+/// how far off (in degrees) someone's yaw can be and still count as facing us
+const FACING_CONE: f32 = 30.0;
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Threat {
+    #[pyo3(get)]
+    pub entity: PyEntity,
+    #[pyo3(get)]
+    pub distance: f64,
+    /// degrees we'd have to turn to look at them, -180 to 180 (positive is right)
+    #[pyo3(get)]
+    pub angle: f32,
+    /// they're looking our way (horizontally, within 30 degrees)
+    #[pyo3(get)]
+    pub facing_us: bool,
+    /// None if the server hasn't sent their equipment
+    #[pyo3(get)]
+    pub held_item: Option<Item>,
+    /// blocks per tick they're getting closer, negative when moving away
+    #[pyo3(get)]
+    pub closing_speed: f64,
+}
+
+#[pymethods]
+impl Threat {
+    fn __repr__(&self) -> String {
+        format!(
+            "Threat({}, distance={:.1}, angle={:.0}, facing_us={}, closing_speed={:.2})",
+            self.entity.entity_type, self.distance, self.angle, self.facing_us, self.closing_speed
+        )
+    }
+}
+
+/// yaw that looks from (dx, dz) away towards the target, minecraft style
+/// (0 is +z, 90 is -x)
+fn yaw_towards(dx: f64, dz: f64) -> f32 {
+    (-dx).atan2(dz).to_degrees() as f32
+}
+
+/// wrap to -180..180
+fn wrap_degrees(angle: f32) -> f32 {
+    let angle = angle.rem_euclid(360.0);
+    if angle > 180.0 { angle - 360.0 } else { angle }
+}
+
+/// players and hostile mobs within `max_distance`, nearest first
+pub fn threats(state: &PyGameState, max_distance: f64) -> Vec<Threat> {
+    let mut threats: Vec<Threat> = state
+        .entities
+        .iter()
+        .filter(|e| matches!(e.category, Category::Player | Category::Hostile))
+        .filter_map(|e| {
+            let (dx, dy, dz) = (e.x - state.x, e.y - state.y, e.z - state.z);
+            let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+            if distance > max_distance {
+                return None;
+            }
+            let angle = wrap_degrees(yaw_towards(dx, dz) - state.yaw);
+            let facing_us = wrap_degrees(yaw_towards(-dx, -dz) - e.yaw).abs() <= FACING_CONE;
+            // their velocity relative to ours, along the line between us
+            let closing_speed = if distance > 0.0 {
+                let (vx, vy, vz) = (
+                    e.velocity_x - state.velocity_x,
+                    e.velocity_y - state.velocity_y,
+                    e.velocity_z - state.velocity_z,
+                );
+                -(vx * dx + vy * dy + vz * dz) / distance
+            } else {
+                0.0
+            };
+            Some(Threat {
+                entity: e.clone(),
+                distance,
+                angle,
+                facing_us,
+                held_item: e.held_item.clone(),
+                closing_speed,
+            })
+        })
+        .collect();
+    threats.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    threats
+}
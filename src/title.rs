@@ -0,0 +1,65 @@
+//! Titles, subtitles and the action bar
+
+use pyo3::prelude::*;
+
+use azalea_protocol::packets::game::ClientboundGamePacket;
+
+use crate::events::unix_seconds;
+
+/// a title, subtitle or action bar text ("title", "subtitle" or "actionbar")
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct TitleEvent {
+    #[pyo3(get)]
+    pub kind: String,
+    #[pyo3(get)]
+    pub text: String,
+    /// unix seconds when it was received
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl TitleEvent {
+    fn __repr__(&self) -> String {
+        format!("TitleEvent(kind={}, text={:?})", self.kind, self.text)
+    }
+}
+
+/// the last text received for each kind
+#[derive(Debug, Default)]
+pub struct Titles {
+    pub title: Option<TitleEvent>,
+    pub subtitle: Option<TitleEvent>,
+    pub action_bar: Option<TitleEvent>,
+}
+
+impl Titles {
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// record a title/subtitle/action bar packet, returning the event to queue for it.
+    /// overlay system chat is how servers usually send action bar text, so it counts too
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) -> Option<TitleEvent> {
+        let (kind, text) = match packet {
+            ClientboundGamePacket::SetTitleText(p) => ("title", p.text.to_string()),
+            ClientboundGamePacket::SetSubtitleText(p) => ("subtitle", p.text.to_string()),
+            ClientboundGamePacket::SetActionBarText(p) => ("actionbar", p.text.to_string()),
+            ClientboundGamePacket::SystemChat(p) if p.overlay => ("actionbar", p.content.to_string()),
+            _ => return None,
+        };
+        let event = TitleEvent {
+            kind: kind.to_string(),
+            text,
+            timestamp: unix_seconds(),
+        };
+        let slot = match kind {
+            "title" => &mut self.title,
+            "subtitle" => &mut self.subtitle,
+            _ => &mut self.action_bar,
+        };
+        *slot = Some(event.clone());
+        Some(event)
+    }
+}
@@ -0,0 +1,469 @@
+//! Arena and live-bot trajectories as Arrow record batches
+//!
+//! rows are buffered in Rust and written as Parquet row groups (or Arrow IPC
+//! batches), nothing goes through Python objects. needs the "arrow" feature
+//!
+//! schema, one row per agent per tick:
+//!   env: u32, episode: u64, step: u32, agent: u8,
+//!   observation: fixed_size_list<f32>,
+//!   forward..eat: bool, delta_yaw: f32, delta_pitch: f32,
+//!   reward: f32, done: bool
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+use crate::arena::{ArenaVec, FastArena, FighterAction};
+use crate::observation::ObservationConfig;
+use crate::state::PyGameState;
+
+/// bumped when a column changes, stored in the schema metadata
+pub const SCHEMA_VERSION: u32 = 6;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Format {
+    Parquet,
+    Ipc,
+}
+
+impl Format {
+    fn parse(format: Option<&str>, path: &Path) -> PyResult<Self> {
+        let format = match format {
+            Some(format) => format.to_string(),
+            None => path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase(),
+        };
+        match format.as_str() {
+            "parquet" => Ok(Format::Parquet),
+            "arrow" | "ipc" | "feather" => Ok(Format::Ipc),
+            _ => Err(PyValueError::new_err(format!(
+                "unknown format {:?}, expected \"parquet\" or \"arrow\"",
+                format
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Parquet => "parquet",
+            Format::Ipc => "arrow",
+        }
+    }
+}
+
+/// columns of the rows not written yet
+#[derive(Default)]
+pub struct Rows {
+    pub env: Vec<u32>,
+    pub episode: Vec<u64>,
+    pub step: Vec<u32>,
+    pub agent: Vec<u8>,
+    /// observation width values per row
+    pub observation: Vec<f32>,
+    /// FighterAction's order, forward to eat
+    pub flags: [Vec<bool>; 13],
+    pub delta_yaw: Vec<f32>,
+    pub delta_pitch: Vec<f32>,
+    pub reward: Vec<f32>,
+    pub done: Vec<bool>,
+}
+
+impl Rows {
+    fn len(&self) -> usize {
+        self.env.len()
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod sink {
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, BooleanArray, FixedSizeListArray, Float32Array, UInt32Array, UInt64Array, UInt8Array};
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use arrow::ipc::writer::FileWriter;
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+
+    use super::{Format, Rows, SCHEMA_VERSION};
+
+    /// the FighterAction flags, each a bool column
+    const FLAG_NAMES: &[&str] = &["forward", "backward", "left", "right", "jump", "sprint", "attack", "eat", "draw", "block", "pearl", "potion", "rod"];
+
+    pub fn available() -> Result<(), String> {
+        Ok(())
+    }
+
+    fn item() -> Arc<Field> {
+        Arc::new(Field::new("item", DataType::Float32, false))
+    }
+
+    fn schema(width: usize) -> SchemaRef {
+        let mut fields = vec![
+            Field::new("env", DataType::UInt32, false),
+            Field::new("episode", DataType::UInt64, false),
+            Field::new("step", DataType::UInt32, false),
+            Field::new("agent", DataType::UInt8, false),
+            Field::new("observation", DataType::FixedSizeList(item(), width as i32), false),
+        ];
+        fields.extend(FLAG_NAMES.iter().map(|name| Field::new(*name, DataType::Boolean, false)));
+        fields.push(Field::new("delta_yaw", DataType::Float32, false));
+        fields.push(Field::new("delta_pitch", DataType::Float32, false));
+        fields.push(Field::new("reward", DataType::Float32, false));
+        fields.push(Field::new("done", DataType::Boolean, false));
+        let metadata = HashMap::from([("pyzalea.schema_version".to_string(), SCHEMA_VERSION.to_string())]);
+        Arc::new(Schema::new_with_metadata(fields, metadata))
+    }
+
+    enum Writer {
+        Parquet(ArrowWriter<File>),
+        Ipc(FileWriter<File>),
+    }
+
+    pub struct Sink {
+        writer: Writer,
+        schema: SchemaRef,
+        width: usize,
+    }
+
+    impl Sink {
+        pub fn create(path: &Path, format: Format, width: usize) -> Result<Self, String> {
+            let file = File::create(path).map_err(|e| format!("can't create {}: {}", path.display(), e))?;
+            let schema = schema(width);
+            let writer = match format {
+                Format::Parquet => Writer::Parquet(ArrowWriter::try_new(file, schema.clone(), None).map_err(|e| e.to_string())?),
+                Format::Ipc => Writer::Ipc(FileWriter::try_new(file, &schema).map_err(|e| e.to_string())?),
+            };
+            Ok(Self { writer, schema, width })
+        }
+
+        pub fn write(&mut self, rows: &Rows) -> Result<(), String> {
+            let observation = FixedSizeListArray::try_new(
+                item(),
+                self.width as i32,
+                Arc::new(Float32Array::from(rows.observation.clone())),
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+            let mut columns: Vec<ArrayRef> = vec![
+                Arc::new(UInt32Array::from(rows.env.clone())),
+                Arc::new(UInt64Array::from(rows.episode.clone())),
+                Arc::new(UInt32Array::from(rows.step.clone())),
+                Arc::new(UInt8Array::from(rows.agent.clone())),
+                Arc::new(observation),
+            ];
+            columns.extend(rows.flags.iter().map(|flag| Arc::new(BooleanArray::from(flag.clone())) as ArrayRef));
+            columns.push(Arc::new(Float32Array::from(rows.delta_yaw.clone())));
+            columns.push(Arc::new(Float32Array::from(rows.delta_pitch.clone())));
+            columns.push(Arc::new(Float32Array::from(rows.reward.clone())));
+            columns.push(Arc::new(BooleanArray::from(rows.done.clone())));
+            let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(|e| e.to_string())?;
+            match &mut self.writer {
+                Writer::Parquet(writer) => writer.write(&batch).map_err(|e| e.to_string()),
+                Writer::Ipc(writer) => writer.write(&batch).map_err(|e| e.to_string()),
+            }
+        }
+
+        /// write the footer, the file can't be read without it
+        pub fn finish(self) -> Result<(), String> {
+            match self.writer {
+                Writer::Parquet(writer) => writer.close().map(|_| ()).map_err(|e| e.to_string()),
+                Writer::Ipc(mut writer) => writer.finish().map_err(|e| e.to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "arrow"))]
+mod sink {
+    use std::path::Path;
+
+    use super::{Format, Rows};
+
+    const UNAVAILABLE: &str = "pyzalea was built without the \"arrow\" feature";
+
+    pub fn available() -> Result<(), String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    /// can't exist without the arrow feature
+    pub enum Sink {}
+
+    impl Sink {
+        pub fn create(_path: &Path, _format: Format, _width: usize) -> Result<Self, String> {
+            Err(UNAVAILABLE.to_string())
+        }
+
+        pub fn write(&mut self, _rows: &Rows) -> Result<(), String> {
+            match *self {}
+        }
+
+        pub fn finish(self) -> Result<(), String> {
+            match self {}
+        }
+    }
+}
+
+use sink::Sink;
+
+/// writes (observation, action, reward, done) rows to one Parquet or Arrow
+/// IPC file
+#[pyclass]
+pub struct TrajectoryWriter {
+    #[pyo3(get)]
+    path: PathBuf,
+    format: Format,
+    /// rows per record batch (parquet row group)
+    #[pyo3(get)]
+    batch_size: usize,
+    rows: Rows,
+    /// observation width, fixed by the first row
+    width: Option<usize>,
+    sink: Option<Sink>,
+    /// episode counter of each env
+    episodes: HashMap<u32, u64>,
+    /// rows added so far
+    #[pyo3(get)]
+    written: usize,
+    closed: bool,
+}
+
+#[pymethods]
+impl TrajectoryWriter {
+    /// format is "parquet" or "arrow" (IPC), by default from the extension
+    #[new]
+    #[pyo3(signature = (path, format=None, batch_size=65536))]
+    fn new(path: PathBuf, format: Option<&str>, batch_size: usize) -> PyResult<Self> {
+        sink::available().map_err(PyRuntimeError::new_err)?;
+        let format = Format::parse(format, &path)?;
+        if batch_size == 0 {
+            return Err(PyValueError::new_err("batch_size must be at least 1"));
+        }
+        Ok(Self {
+            path,
+            format,
+            batch_size,
+            rows: Rows::default(),
+            width: None,
+            sink: None,
+            episodes: HashMap::new(),
+            written: 0,
+            closed: false,
+        })
+    }
+
+    #[getter]
+    fn format(&self) -> &'static str {
+        self.format.name()
+    }
+
+    #[getter]
+    fn schema_version(&self) -> u32 {
+        SCHEMA_VERSION
+    }
+
+    /// arena.step() that also records a row per fighter, with the
+    /// observations from before the step. returns what step() does
+    fn step_arena(
+        &mut self,
+        py: Python<'_>,
+        mut arena: PyRefMut<'_, FastArena>,
+        action1: FighterAction,
+        action2: FighterAction,
+    ) -> PyResult<(f64, f64, bool)> {
+        self.record_arena(py, &mut arena, 0, &action1, &action2)
+    }
+
+    /// ArenaVec.step() that records its rows, env is the arena's index
+    fn step_arena_vec(
+        &mut self,
+        py: Python<'_>,
+        mut arenas: PyRefMut<'_, ArenaVec>,
+        idx: usize,
+        action1: FighterAction,
+        action2: FighterAction,
+    ) -> PyResult<(f64, f64, bool)> {
+        let Some(arena) = arenas.arenas.get_mut(idx) else {
+            return Err(PyValueError::new_err(format!("no arena {} in an ArenaVec of {}", idx, arenas.arenas.len())));
+        };
+        self.record_arena(py, arena, idx as u32, &action1, &action2)
+    }
+
+    /// a row for a live bot: the state it acted on (as config.vector()), what
+    /// it did and the reward it got for it
+    #[pyo3(signature = (state, action, reward, done, config=None, env=0))]
+    fn add_bot(
+        &mut self,
+        state: PyRef<'_, PyGameState>,
+        action: FighterAction,
+        reward: f64,
+        done: bool,
+        config: Option<PyRef<'_, ObservationConfig>>,
+        env: u32,
+    ) -> PyResult<()> {
+        let observation = match config {
+            Some(config) => config.vector(&state),
+            None => ObservationConfig::default().vector(&state),
+        };
+        self.push(env, state.tick as u32, 0, &observation, &action, reward, done)?;
+        if done {
+            self.end_episode(env);
+        }
+        Ok(())
+    }
+
+    /// write the buffered rows as a batch
+    fn flush(&mut self) -> PyResult<()> {
+        if self.closed {
+            return Err(PyValueError::new_err("the writer is closed"));
+        }
+        if self.rows.len() == 0 {
+            return Ok(());
+        }
+        let width = self.width.unwrap_or(0);
+        if self.sink.is_none() {
+            let sink = Sink::create(&self.path, self.format, width).map_err(PyIOError::new_err)?;
+            self.sink = Some(sink);
+        }
+        if let Some(sink) = self.sink.as_mut() {
+            sink.write(&self.rows).map_err(PyIOError::new_err)?;
+        }
+        self.rows = Rows::default();
+        Ok(())
+    }
+
+    /// flush and finish the file, which isn't readable before this (dropping
+    /// the writer also finishes it)
+    fn close(&mut self) -> PyResult<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.flush()?;
+        self.closed = true;
+        if let Some(sink) = self.sink.take() {
+            sink.finish().map_err(PyIOError::new_err)?;
+        }
+        Ok(())
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&mut self, _args: &Bound<'_, PyTuple>) -> PyResult<bool> {
+        self.close()?;
+        Ok(false)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "TrajectoryWriter(path={}, format={}, written={})",
+            self.path.display(),
+            self.format.name(),
+            self.written
+        )
+    }
+}
+
+impl TrajectoryWriter {
+    fn record_arena(
+        &mut self,
+        py: Python<'_>,
+        arena: &mut FastArena,
+        env: u32,
+        action1: &FighterAction,
+        action2: &FighterAction,
+    ) -> PyResult<(f64, f64, bool)> {
+        if arena.done {
+            return Ok((0.0, 0.0, true));
+        }
+        let step = arena.tick;
+        let obs1: Vec<f32> = arena.get_obs1().into_iter().map(|v| v as f32).collect();
+        let obs2: Vec<f32> = arena.get_obs2().into_iter().map(|v| v as f32).collect();
+        let (reward1, reward2, done) = arena.step_actions(py, action1, action2)?;
+        self.push(env, step, 1, &obs1, action1, reward1, done)?;
+        self.push(env, step, 2, &obs2, action2, reward2, done)?;
+        if done {
+            self.end_episode(env);
+        }
+        Ok((reward1, reward2, done))
+    }
+
+    fn end_episode(&mut self, env: u32) {
+        *self.episodes.entry(env).or_default() += 1;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push(
+        &mut self,
+        env: u32,
+        step: u32,
+        agent: u8,
+        observation: &[f32],
+        action: &FighterAction,
+        reward: f64,
+        done: bool,
+    ) -> PyResult<()> {
+        if self.closed {
+            return Err(PyValueError::new_err("the writer is closed"));
+        }
+        match self.width {
+            Some(width) if width != observation.len() => {
+                return Err(PyValueError::new_err(format!(
+                    "observation has {} values, earlier rows had {}",
+                    observation.len(),
+                    width
+                )));
+            }
+            Some(_) => {}
+            None => self.width = Some(observation.len()),
+        }
+        let episode = self.episodes.get(&env).copied().unwrap_or(0);
+        let rows = &mut self.rows;
+        rows.env.push(env);
+        rows.episode.push(episode);
+        rows.step.push(step);
+        rows.agent.push(agent);
+        rows.observation.extend_from_slice(observation);
+        let flags = [
+            action.forward,
+            action.backward,
+            action.left,
+            action.right,
+            action.jump,
+            action.sprint,
+            action.attack,
+            action.eat,
+            action.draw,
+            action.block,
+            action.pearl,
+            action.potion,
+            action.rod,
+        ];
+        for (column, flag) in rows.flags.iter_mut().zip(flags) {
+            column.push(flag);
+        }
+        rows.delta_yaw.push(action.delta_yaw as f32);
+        rows.delta_pitch.push(action.delta_pitch as f32);
+        rows.reward.push(reward as f32);
+        rows.done.push(done);
+        self.written += 1;
+
+        if self.rows.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TrajectoryWriter {
+    fn drop(&mut self) {
+        // best effort, close() is the way to see errors
+        let _ = self.close();
+    }
+}
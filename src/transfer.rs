@@ -0,0 +1,86 @@
+//! Following server transfers (1.20.5+), like lobby -> game server
+//!
+//! azalea ignores the transfer packet, so we point the bot's ConnectOpts at the
+//! new address and disconnect; ReconnectPlugin then rejoins straight away
+
+use pyo3::prelude::*;
+use std::sync::atomic::Ordering;
+
+use azalea_client::Client;
+use azalea_client::join::ConnectOpts;
+use azalea_protocol::address::{ResolvedAddr, ServerAddr};
+use azalea_protocol::packets::game::c_transfer::ClientboundTransfer;
+
+use crate::bot::BotShared;
+use crate::events::unix_seconds;
+use crate::logging::CONNECTION;
+
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct TransferEvent {
+    #[pyo3(get)]
+    pub host: String,
+    /// 0 if the server sent one past 65535
+    #[pyo3(get)]
+    pub port: u16,
+    /// False if follow_transfers is off, the port is out of range or the
+    /// address didn't resolve
+    #[pyo3(get)]
+    pub followed: bool,
+    #[pyo3(get)]
+    pub error: Option<String>,
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl TransferEvent {
+    fn __repr__(&self) -> String {
+        format!("TransferEvent(host={}, port={}, followed={})", self.host, self.port, self.followed)
+    }
+}
+
+/// handle a transfer packet, returning the event to queue
+pub async fn follow_transfer(bot: &Client, shared: &BotShared, packet: &ClientboundTransfer) -> TransferEvent {
+    let port = u16::try_from(packet.port);
+    let mut event = TransferEvent {
+        host: packet.host.clone(),
+        port: port.unwrap_or(0),
+        followed: false,
+        error: None,
+        timestamp: unix_seconds(),
+    };
+    if port.is_err() {
+        event.error = Some(format!("port {} is out of range", packet.port));
+        return event;
+    }
+    if !shared.follow_transfers {
+        return event;
+    }
+
+    let server = ServerAddr {
+        host: event.host.clone(),
+        port: event.port,
+    };
+    let address = match ResolvedAddr::new(server).await {
+        Ok(address) => address,
+        Err(e) => {
+            event.error = Some(e.to_string());
+            return event;
+        }
+    };
+
+    {
+        let mut ecs = bot.ecs.lock();
+        let Some(mut opts) = ecs.get_mut::<ConnectOpts>(bot.entity) else {
+            event.error = Some("bot has no connection options".to_string());
+            return event;
+        };
+        opts.address = address;
+    }
+    tracing::info!(target: CONNECTION, "transferring to {}:{}", event.host, event.port);
+    shared.transferring.store(true, Ordering::SeqCst);
+    bot.disconnect();
+    event.followed = true;
+    event
+}
@@ -0,0 +1,80 @@
+//! Minecraft protocol version checks
+//!
+//! azalea speaks exactly one protocol version, so "selecting" a version means
+//! validating the choice up front instead of failing somewhere in login
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyConnectionError, PyException};
+use pyo3::prelude::*;
+
+use azalea::protocol::packets::{PROTOCOL_VERSION, VERSION_NAME};
+use azalea_client::ping;
+
+use crate::RUNTIME;
+
+create_exception!(pyzalea, VersionMismatchError, PyException);
+
+/// the minecraft version this build speaks, like "1.21.11"
+#[pyfunction]
+pub fn minecraft_version() -> &'static str {
+    VERSION_NAME
+}
+
+/// the protocol number this build speaks, 774 for 1.21.11
+#[pyfunction]
+pub fn protocol_version() -> i32 {
+    PROTOCOL_VERSION
+}
+
+/// (minecraft version, protocol number) pairs this build can join
+#[pyfunction]
+pub fn supported_versions() -> Vec<(String, i32)> {
+    vec![(VERSION_NAME.to_string(), PROTOCOL_VERSION)]
+}
+
+/// ping a server and return its (version name, protocol number)
+#[pyfunction]
+#[pyo3(signature = (host, port=25565))]
+pub fn server_version(py: Python<'_>, host: &str, port: u16) -> PyResult<(String, i32)> {
+    py.allow_threads(|| ping_version(host, port))
+}
+
+fn ping_version(host: &str, port: u16) -> PyResult<(String, i32)> {
+    let address = format!("{}:{}", host, port);
+    let response = RUNTIME
+        .block_on(ping::ping_server(address.as_str()))
+        .map_err(|e| PyConnectionError::new_err(format!("failed to ping {}: {}", address, e)))?;
+    Ok((response.version.name, response.version.protocol))
+}
+
+/// validate the `version=` argument of connect
+///
+/// None skips the check, "auto" pings the server and compares its protocol,
+/// anything else must name a supported version (e.g. "1.21.11" or "774")
+pub fn check_version(host: &str, port: u16, version: Option<&str>) -> PyResult<()> {
+    let Some(version) = version else {
+        return Ok(());
+    };
+
+    if version == "auto" {
+        let (name, protocol) = ping_version(host, port)?;
+        if protocol != PROTOCOL_VERSION {
+            return Err(VersionMismatchError::new_err(format!(
+                "server is running {} (protocol {}), but this build only supports {} (protocol {})",
+                name, protocol, VERSION_NAME, PROTOCOL_VERSION
+            )));
+        }
+        return Ok(());
+    }
+
+    let supported = supported_versions()
+        .iter()
+        .any(|(name, protocol)| name == version || protocol.to_string() == version);
+    if !supported {
+        return Err(VersionMismatchError::new_err(format!(
+            "version {} is not supported, this build only supports {} (protocol {})",
+            version, VERSION_NAME, PROTOCOL_VERSION
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,385 @@
+//! Live bot and arena state over WebSocket, for browser dashboards
+//!
+//! start_viewer() runs a small server on its own thread. every 1/rate seconds
+//! each connected client gets one JSON text frame:
+//! `{"time": ..., "bots": [...], "arenas": [...]}`. a plain GET (no upgrade) gets
+//! the same JSON once. it speaks just enough HTTP to upgrade, and ignores
+//! whatever clients send after that
+
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use parking_lot::Mutex;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+use azalea::pathfinder::{ExecutingPath, Pathfinder};
+use azalea_client::local_player::Hunger;
+use azalea_client::player::GameProfileComponent;
+use azalea_entity::metadata::Health;
+use azalea_entity::{EntityKindComponent, LookDirection, Position};
+use azalea_world::MinecraftEntityId;
+
+use crate::arena::{ArenaVec, FastArena, Fighter};
+use crate::bot::{BotShared, PyBot};
+use crate::events::unix_seconds;
+use crate::http::{self, Stop};
+use crate::logging::CONNECTION;
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47A5-95CA-C5AB0DC11B65";
+/// a client that can't take a frame in this long is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Arcs so snapshot() can copy the list out without the GIL
+#[derive(Clone)]
+enum Source {
+    Bot(Arc<BotShared>),
+    Arena(Arc<Py<FastArena>>),
+    Arenas(Arc<Py<ArenaVec>>),
+}
+
+struct ViewerShared {
+    /// by name, in the order they were watched
+    sources: Mutex<Vec<(String, Source)>>,
+    /// upgraded clients the frame loop hasn't picked up yet
+    pending: Mutex<Vec<TcpStream>>,
+    clients: AtomicUsize,
+}
+
+fn bot_json(name: &str, shared: &BotShared) -> Value {
+    let connected = shared.connected.load(Ordering::SeqCst);
+    let client = shared.client.lock().clone();
+    let Some(client) = client else {
+        return json!({ "name": name, "connected": connected, "in_game": false });
+    };
+    let target = *shared.target.lock();
+    let ecs = client.ecs.lock();
+    let Some(pos) = ecs.get::<Position>(client.entity) else {
+        return json!({ "name": name, "connected": connected, "in_game": false });
+    };
+    let look = ecs.get::<LookDirection>(client.entity);
+    let target = target.and_then(|entity| {
+        let pos = ecs.get::<Position>(entity)?;
+        let name = ecs
+            .get::<GameProfileComponent>(entity)
+            .map(|profile| profile.0.name.clone())
+            .or_else(|| ecs.get::<EntityKindComponent>(entity).map(|kind| kind.0.to_string()));
+        Some(json!({
+            "id": ecs.get::<MinecraftEntityId>(entity).map(|id| id.0),
+            "name": name,
+            "x": pos.x,
+            "y": pos.y,
+            "z": pos.z,
+        }))
+    });
+    let goal = ecs
+        .get::<Pathfinder>(client.entity)
+        .and_then(|pathfinder| pathfinder.goal.as_ref())
+        .map(|goal| format!("{:?}", goal));
+    let path: Vec<[i32; 3]> = ecs
+        .get::<ExecutingPath>(client.entity)
+        .map(|executing| {
+            executing
+                .path
+                .iter()
+                .map(|edge| {
+                    let node = edge.movement.target;
+                    [node.x, node.y, node.z]
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({
+        "name": name,
+        "connected": connected,
+        "in_game": true,
+        "x": pos.x,
+        "y": pos.y,
+        "z": pos.z,
+        "yaw": look.map(|l| l.y_rot()),
+        "pitch": look.map(|l| l.x_rot()),
+        "health": ecs.get::<Health>(client.entity).map(|h| h.0),
+        "food": ecs.get::<Hunger>(client.entity).map(|h| h.food),
+        "target": target,
+        "goal": goal,
+        "path": path,
+    })
+}
+
+fn fighter_json(fighter: &Fighter) -> Value {
+    json!({
+        "x": fighter.x,
+        "y": fighter.y,
+        "z": fighter.z,
+        "yaw": fighter.yaw,
+        "pitch": fighter.pitch,
+        "health": fighter.health,
+        "food": fighter.food,
+        "steaks": fighter.steaks,
+        "on_ground": fighter.on_ground,
+        "sprinting": fighter.sprinting,
+        "eating": fighter.eating,
+        "arrows": fighter.arrows,
+        "draw_ticks": fighter.draw_ticks,
+        "blocking": fighter.blocking,
+        "shield_disabled": fighter.shield_disabled,
+        "pearls": fighter.pearls,
+        "potions": fighter.potions,
+        "rod_cooldown": fighter.rod_cooldown,
+        "swept": fighter.swept,
+        "in_water": fighter.in_water,
+        "in_lava": fighter.in_lava,
+        "fire_ticks": fighter.fire_ticks,
+    })
+}
+
+fn arena_json(name: &str, index: Option<usize>, arena: &FastArena) -> Value {
+    json!({
+        "name": name,
+        "index": index,
+        "tick": arena.tick,
+        "done": arena.done,
+        "winner": arena.winner,
+        "winner_reason": arena.winner_reason,
+        "platform_size": arena.platform_size,
+        "mode": arena.mode.name(),
+        "heights": if arena.terrain.is_empty() { Value::Null } else { json!(arena.heights()) },
+        "fighters": [fighter_json(&arena.fighter1), fighter_json(&arena.fighter2)],
+        "arrows": arena.arrows.iter().map(|a| json!({"x": a.x, "y": a.y, "z": a.z, "owner": a.owner})).collect::<Vec<_>>(),
+        "pearls": arena.pearls.iter().map(|p| json!({"x": p.x, "y": p.y, "z": p.z, "owner": p.owner})).collect::<Vec<_>>(),
+        "potions": arena.potions.iter().map(|p| json!({"x": p.x, "y": p.y, "z": p.z, "owner": p.owner})).collect::<Vec<_>>(),
+        "hooks": arena.hooks.iter().map(|h| json!({"x": h.x, "y": h.y, "z": h.z, "owner": h.owner, "hooked": h.hooked})).collect::<Vec<_>>(),
+    })
+}
+
+/// everything that's watched, as one frame
+fn snapshot(shared: &ViewerShared) -> String {
+    let mut bots = vec![];
+    let mut arenas = vec![];
+    // not locked while waiting on the GIL, watch() holds the GIL when it locks
+    let sources = shared.sources.lock().clone();
+    for (name, source) in &sources {
+        match source {
+            Source::Bot(bot) => bots.push(bot_json(name, bot)),
+            // arenas live in Python objects. one that's busy (serve_shared()
+            // has it) is left out of this frame
+            Source::Arena(arena) => Python::with_gil(|py| {
+                if let Ok(arena) = arena.bind(py).try_borrow() {
+                    arenas.push(arena_json(name, None, &arena));
+                }
+            }),
+            Source::Arenas(vec) => Python::with_gil(|py| {
+                if let Ok(vec) = vec.bind(py).try_borrow() {
+                    arenas.extend(vec.arenas.iter().enumerate().map(|(i, arena)| arena_json(name, Some(i), arena)));
+                }
+            }),
+        }
+    }
+    json!({ "time": unix_seconds(), "bots": bots, "arenas": arenas }).to_string()
+}
+
+/// a server-to-client frame, unmasked
+fn text_frame(text: &str) -> Vec<u8> {
+    let len = text.len();
+    let mut frame = Vec::with_capacity(len + 10);
+    // FIN + text
+    frame.push(0x81);
+    if len < 126 {
+        frame.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        frame.push(126);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+/// read the request and answer it. Some(stream) if it upgraded to a WebSocket
+fn handshake(mut stream: TcpStream, shared: &ViewerShared) -> std::io::Result<Option<TcpStream>> {
+    let Some(request) = http::read_request(&mut stream)? else {
+        return Ok(None);
+    };
+    let key = request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("sec-websocket-key").then(|| value.trim().to_string())
+    });
+
+    let Some(key) = key else {
+        let headers = "Content-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\n";
+        http::respond(&mut stream, "200 OK", headers, &snapshot(shared))?;
+        return Ok(None);
+    };
+    let accept = base64::engine::general_purpose::STANDARD.encode(Sha1::digest(format!("{}{}", key, WEBSOCKET_GUID)));
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    )?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    Ok(Some(stream))
+}
+
+fn serve(listener: TcpListener, shared: Arc<ViewerShared>, stop: Arc<AtomicBool>, interval: Duration) {
+    let mut clients: Vec<TcpStream> = vec![];
+    let mut next_frame = Instant::now();
+    while !stop.load(Ordering::SeqCst) {
+        let handshakes = shared.clone();
+        http::accept(&listener, "pyzalea-viewer-handshake", move |stream, address| {
+            if let Some(stream) = handshake(stream, &handshakes)? {
+                tracing::debug!(target: CONNECTION, %address, "viewer client connected");
+                handshakes.pending.lock().push(stream);
+            }
+            Ok(())
+        });
+        clients.append(&mut shared.pending.lock());
+
+        if Instant::now() >= next_frame {
+            next_frame += interval;
+            // don't try to catch up after a stall
+            next_frame = next_frame.max(Instant::now());
+            if !clients.is_empty() {
+                let frame = text_frame(&snapshot(&shared));
+                clients.retain_mut(|client| client.write_all(&frame).is_ok());
+            }
+            shared.clients.store(clients.len(), Ordering::SeqCst);
+        }
+        std::thread::sleep(Duration::from_millis(5).min(next_frame.saturating_duration_since(Instant::now())));
+    }
+}
+
+/// a running start_viewer() server, see stop()
+#[pyclass]
+pub struct Viewer {
+    shared: Arc<ViewerShared>,
+    address: SocketAddr,
+    stop: Stop,
+}
+
+#[pymethods]
+impl Viewer {
+    /// stream `source` too: a bot, a swarm (every bot), a FastArena or an
+    /// ArenaVec. name defaults to the username, "arena" or "arenas"; watching
+    /// a name again replaces it
+    #[pyo3(signature = (source, name=None))]
+    fn watch(&self, source: &Bound<'_, PyAny>, name: Option<String>) -> PyResult<()> {
+        let mut added = vec![];
+        if let Ok(bot) = source.downcast::<PyBot>() {
+            let username = bot.getattr("username")?.extract::<String>()?;
+            added.push((name.unwrap_or(username), Source::Bot(bot.borrow().shared())));
+        } else if let Ok(arena) = source.downcast::<FastArena>() {
+            added.push((name.unwrap_or_else(|| "arena".to_string()), Source::Arena(Arc::new(arena.clone().unbind()))));
+        } else if let Ok(vec) = source.downcast::<ArenaVec>() {
+            added.push((name.unwrap_or_else(|| "arenas".to_string()), Source::Arenas(Arc::new(vec.clone().unbind()))));
+        } else if source.hasattr("bots")? && name.is_none() {
+            for bot in source.getattr("bots")?.try_iter()? {
+                let bot = bot?;
+                let bot = bot.downcast::<PyBot>()?;
+                let username = bot.getattr("username")?.extract::<String>()?;
+                added.push((username, Source::Bot(bot.borrow().shared())));
+            }
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "can't watch {} objects, expected a PyBot, PySwarm, FastArena or ArenaVec (name= doesn't go with a swarm)",
+                source.get_type().name()?
+            )));
+        }
+        let mut sources = self.shared.sources.lock();
+        for (name, source) in added {
+            sources.retain(|(existing, _)| *existing != name);
+            sources.push((name, source));
+        }
+        Ok(())
+    }
+
+    /// stop streaming `name`, False if it wasn't watched
+    fn unwatch(&self, name: &str) -> bool {
+        let mut sources = self.shared.sources.lock();
+        let before = sources.len();
+        sources.retain(|(existing, _)| existing != name);
+        sources.len() != before
+    }
+
+    /// names of everything being streamed
+    #[getter]
+    fn watched(&self) -> Vec<String> {
+        self.shared.sources.lock().iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// "ws://host:port/", with the real port if it was 0
+    #[getter]
+    fn url(&self) -> String {
+        format!("ws://{}/", self.address)
+    }
+
+    /// WebSocket clients connected right now
+    #[getter]
+    fn clients(&self) -> usize {
+        self.shared.clients.load(Ordering::SeqCst)
+    }
+
+    /// the JSON clients get, for a look without a browser
+    fn snapshot(&self, py: Python<'_>) -> String {
+        py.allow_threads(|| snapshot(&self.shared))
+    }
+
+    /// close the server and its clients' connections, which garbage
+    /// collecting the Viewer does too
+    fn stop(&self) {
+        self.stop.stop();
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, _args: &Bound<'_, PyTuple>) -> bool {
+        self.stop();
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Viewer(url={:?}, watched={}, clients={})", self.url(), self.shared.sources.lock().len(), self.clients())
+    }
+}
+
+/// serve watched bots and arenas as JSON over WebSocket at `rate` frames a
+/// second, for a browser dashboard. `sources` go to Viewer.watch(). port 0
+/// picks a free one, see Viewer.url
+#[pyfunction]
+#[pyo3(signature = (*sources, port=8765, host="127.0.0.1", rate=10.0))]
+pub fn start_viewer(sources: &Bound<'_, PyTuple>, port: u16, host: &str, rate: f64) -> PyResult<Viewer> {
+    let interval = Duration::try_from_secs_f64(1.0 / rate)
+        .ok()
+        .filter(|_| rate.is_finite() && rate > 0.0)
+        .ok_or_else(|| PyValueError::new_err(format!("rate must be positive and finite, got {:?}", rate)))?;
+    let (listener, address) = http::listen(host, port)?;
+    let viewer = Viewer {
+        shared: Arc::new(ViewerShared {
+            sources: Mutex::new(vec![]),
+            pending: Mutex::new(vec![]),
+            clients: AtomicUsize::new(0),
+        }),
+        address,
+        stop: Stop::new(),
+    };
+    for source in sources.iter() {
+        viewer.watch(&source, None)?;
+    }
+    let (shared, stop) = (viewer.shared.clone(), viewer.stop.flag());
+    std::thread::Builder::new()
+        .name("pyzalea-viewer".to_string())
+        .spawn(move || serve(listener, shared, stop, interval))?;
+    tracing::info!(target: CONNECTION, %address, "viewer listening");
+    Ok(viewer)
+}
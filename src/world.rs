@@ -0,0 +1,105 @@
+//! Time and weather, which azalea receives but doesn't keep
+
+use std::time::Instant;
+
+use azalea::registry::data::Biome;
+use azalea::registry::DataRegistry;
+use azalea::{BlockPos, Identifier};
+use azalea_client::Client;
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::game::c_game_event::EventType;
+use azalea_world::InstanceName;
+
+#[derive(Debug)]
+pub struct WorldInfo {
+    day_time: u64,
+    /// whether the daylight cycle is running (doDaylightCycle)
+    ticking: bool,
+    received: Instant,
+    rain_level: f32,
+    thunder_level: f32,
+}
+
+impl Default for WorldInfo {
+    fn default() -> Self {
+        Self {
+            day_time: 0,
+            ticking: true,
+            received: Instant::now(),
+            rain_level: 0.0,
+            thunder_level: 0.0,
+        }
+    }
+}
+
+impl WorldInfo {
+    pub fn handle_packet(&mut self, packet: &ClientboundGamePacket) {
+        match packet {
+            ClientboundGamePacket::SetTime(p) => {
+                self.day_time = p.day_time;
+                self.ticking = p.tick_day_time;
+                self.received = Instant::now();
+            }
+            // the levels fade in and out with RainLevelChange/ThunderLevelChange
+            ClientboundGamePacket::GameEvent(p) => match p.event {
+                EventType::StartRaining => self.rain_level = 0.0,
+                EventType::StopRaining => self.rain_level = 1.0,
+                EventType::RainLevelChange => self.rain_level = p.param.clamp(0.0, 1.0),
+                EventType::ThunderLevelChange => self.thunder_level = p.param.clamp(0.0, 1.0),
+                _ => {}
+            },
+            // a new dimension starts clear, the server resends the weather
+            ClientboundGamePacket::Respawn(_) => {
+                self.rain_level = 0.0;
+                self.thunder_level = 0.0;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 0-23999, 0 is sunrise, 6000 noon, 18000 midnight
+    pub fn time_of_day(&self) -> u64 {
+        // set_time only arrives every second, so count the ticks since then
+        let elapsed = if self.ticking {
+            (self.received.elapsed().as_millis() / 50) as u64
+        } else {
+            0
+        };
+        (self.day_time + elapsed) % 24000
+    }
+
+    /// same thresholds as the vanilla client
+    pub fn is_raining(&self) -> bool {
+        self.rain_level > 0.2
+    }
+
+    pub fn is_thundering(&self) -> bool {
+        self.rain_level * self.thunder_level > 0.9
+    }
+}
+
+/// dimension the bot is in, like "minecraft:the_nether"
+pub fn dimension(bot: &Client) -> Option<String> {
+    let ecs = bot.ecs.lock();
+    ecs.get::<InstanceName>(bot.entity).map(|name| name.to_string())
+}
+
+/// biome at a block, like "minecraft:plains", None where the chunk isn't loaded
+pub fn biome(bot: &Client, pos: BlockPos) -> Option<String> {
+    let world = bot.world();
+    let world = world.read();
+    let biome = world.get_biome(pos)?;
+    world
+        .registries
+        .protocol_id_to_identifier(Identifier::from(Biome::NAME), biome.protocol_id())
+        .map(|id| id.to_string())
+}
+
+/// lowest y of the bot's world, which light sections are counted from
+pub fn min_y(bot: &Client) -> i32 {
+    bot.world().read().chunks.min_y
+}